@@ -0,0 +1,209 @@
+//! Compile-time checked GraphQL documents
+//!
+//! The macros parse a string literal with `graphql-parser` while the crate
+//! is being compiled, so a typo in an embedded operation fails the build
+//! with the parser's message instead of failing at runtime:
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! # extern crate graphql_parser_macros;
+//! use graphql_parser::query::Document;
+//! use graphql_parser_macros::graphql;
+//!
+//! let doc: &'static Document<'static, &'static str> = graphql!("
+//!     query User($id: ID!) { user(id: $id) { name } }
+//! ");
+//! let op = doc.operations().next().unwrap();
+//! assert_eq!(op.name(), Some("User"));
+//! ```
+//!
+//! ```rust,compile_fail
+//! # extern crate graphql_parser_macros;
+//! use graphql_parser_macros::graphql;
+//!
+//! let doc = graphql!("query { user(id: 1) { name }");
+//! ```
+//!
+//! The document borrows from the literal embedded in the binary and is
+//! built once, on first use, so the expression evaluates to a `&'static`
+//! reference and repeated evaluation is free. Crates using the macros
+//! need `graphql-parser` as a dependency too.
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Parses a query document at compile time
+///
+/// Evaluates to `&'static graphql_parser::query::Document<'static, &'static str>`.
+#[proc_macro]
+pub fn graphql(input: TokenStream) -> TokenStream {
+    expand(input, "graphql", Kind::Query)
+}
+
+/// Parses a schema document at compile time
+///
+/// Evaluates to `&'static graphql_parser::schema::Document<'static, &'static str>`.
+#[proc_macro]
+pub fn graphql_schema(input: TokenStream) -> TokenStream {
+    expand(input, "graphql_schema", Kind::Schema)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Query,
+    Schema,
+}
+
+fn expand(input: TokenStream, name: &str, kind: Kind) -> TokenStream {
+    let mut tokens = input.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        (Some(tree), _) => {
+            let message = format!("{}! expects a single string literal", name);
+            return compile_error(&message, tree.span());
+        }
+        (None, _) => {
+            let message = format!("{}! expects a single string literal", name);
+            return compile_error(&message, Span::call_site());
+        }
+    };
+    let source = match string_value(&literal.to_string()) {
+        Some(source) => source,
+        None => {
+            let message = format!("{}! expects a single string literal", name);
+            return compile_error(&message, literal.span());
+        }
+    };
+    let (module, function, error) = match kind {
+        Kind::Query => (
+            "query",
+            "parse_query",
+            graphql_parser::parse_query::<&str>(&source)
+                .err()
+                .map(|e| e.to_string()),
+        ),
+        Kind::Schema => (
+            "schema",
+            "parse_schema",
+            graphql_parser::parse_schema::<&str>(&source)
+                .err()
+                .map(|e| e.to_string()),
+        ),
+    };
+    if let Some(error) = error {
+        return compile_error(error.trim_end(), literal.span());
+    }
+    format!(
+        "{{
+            static DOCUMENT: ::std::sync::OnceLock<
+                ::graphql_parser::{module}::Document<'static, &'static str>,
+            > = ::std::sync::OnceLock::new();
+            DOCUMENT.get_or_init(|| {{
+                ::graphql_parser::{function}::<&'static str>({literal})
+                    .expect(\"document is checked by {name}!\")
+            }})
+        }}",
+        module = module,
+        function = function,
+        literal = literal,
+        name = name,
+    )
+    .parse()
+    .expect("generated code is valid")
+}
+
+/// Returns `compile_error!("message")` reported at `span`
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut group = Group::new(Delimiter::Parenthesis, TokenTree::Literal(literal).into());
+    group.set_span(span);
+    vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(group),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Returns the value of a Rust string literal, `None` for other tokens
+fn string_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let quoted = &raw[hashes..raw.len().checked_sub(hashes)?];
+        let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        if !raw.ends_with(&"#".repeat(hashes)) {
+            return None;
+        }
+        return Some(inner.to_string());
+    }
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    unescape(inner)
+}
+
+fn unescape(inner: &str) -> Option<String> {
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\\' => value.push('\\'),
+            '\'' => value.push('\''),
+            '"' => value.push('"'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                value.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+            }
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let code = u32::from_str_radix(&hex.replace('_', ""), 16).ok()?;
+                value.push(char::from_u32(code)?);
+            }
+            '\n' => {
+                // line continuation, skips the leading whitespace of the
+                // next line
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::string_value;
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(string_value(r#""{ a }""#).unwrap(), "{ a }");
+        assert_eq!(
+            string_value(r#""a\n\t\"b\" \\ \x41 \u{1F600}""#).unwrap(),
+            "a\n\t\"b\" \\ A \u{1F600}"
+        );
+        assert_eq!(string_value("\"a \\\n    b\"").unwrap(), "a b");
+        assert_eq!(
+            string_value(r###"r#"a "quoted" \n"#"###).unwrap(),
+            r#"a "quoted" \n"#
+        );
+        assert_eq!(string_value(r#"r"\d""#).unwrap(), "\\d");
+        assert_eq!(string_value("1"), None);
+        assert_eq!(string_value("b\"a\""), None);
+        assert_eq!(string_value("'a'"), None);
+    }
+}
@@ -0,0 +1,47 @@
+extern crate graphql_parser;
+extern crate graphql_parser_macros;
+
+use graphql_parser::query::{Definition, Document};
+use graphql_parser::{parse_query, parse_schema};
+use graphql_parser_macros::{graphql, graphql_schema};
+
+fn user_query() -> &'static Document<'static, &'static str> {
+    graphql!(
+        r#"
+        query User($id: ID!) {
+            user(id: $id) { name avatar(size: 64, format: "png") }
+        }
+        fragment F on User { id }
+        "#
+    )
+}
+
+#[test]
+fn same_as_runtime_parsing() {
+    let source = r#"
+        query User($id: ID!) {
+            user(id: $id) { name avatar(size: 64, format: "png") }
+        }
+        fragment F on User { id }
+        "#;
+    assert_eq!(user_query(), &parse_query::<&str>(source).unwrap());
+    assert!(matches!(
+        user_query().definitions[1],
+        Definition::Fragment(_)
+    ));
+    // built once, on first use
+    assert!(std::ptr::eq(user_query(), user_query()));
+
+    let schema = graphql_schema!("type Query { user(id: ID!): User }\ntype User { name: String }");
+    assert_eq!(
+        schema,
+        &parse_schema::<&str>("type Query { user(id: ID!): User }\ntype User { name: String }")
+            .unwrap()
+    );
+}
+
+#[test]
+fn escapes() {
+    let doc = graphql!("{ a(s: \"\\u00e9\") }\n");
+    assert_eq!(doc.to_string(), "{\n  a(s: \"\u{e9}\")\n}\n");
+}
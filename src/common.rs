@@ -1,17 +1,30 @@
-use std::convert::TryInto;
-use std::{collections::BTreeMap, fmt};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::num::{ParseIntError, TryFromIntError};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 use combine::combinator::{choice, many, many1, optional, position};
-use combine::easy::Error;
-use combine::error::StreamError;
-use combine::{parser, ParseResult, Parser};
+use combine::{ParseResult, Parser, Positioned};
 
-use crate::helpers::{ident, kind, name, punct};
-use crate::position::Pos;
-use crate::tokenizer::{Kind as T, Token, TokenStream};
+use crate::error::{Error, Info};
+use crate::helpers::{end_position, ident, kind, name, punct, rule};
+use crate::message::{Message, MessageKey};
+use crate::position::{Pos, Span};
+use crate::query::ParseError;
+use crate::syntax_error::ErrorCode;
+use crate::tokenizer::{Kind as T, TokenStream};
 
 /// Text abstracts over types that hold a string value.
 /// It is used to make the AST generic over the string type.
+///
+/// **Breaking change:** `Value` has to implement `Hash` now, so that the
+/// AST nodes do. Implementations of `Text` whose value type isn't `Hash`
+/// have to wrap it in a type which is.
 pub trait Text<'a>: 'a {
     type Value: 'a
         + From<&'a str>
@@ -21,112 +34,1601 @@ pub trait Text<'a>: 'a {
         + Eq
         + PartialOrd
         + Ord
+        + Hash
         + fmt::Debug
         + Clone;
 }
 
-impl<'a> Text<'a> for &'a str {
-    type Value = Self;
+impl<'a> Text<'a> for &'a str {
+    type Value = Self;
+}
+
+impl<'a> Text<'a> for String {
+    type Value = String;
+}
+
+impl<'a> Text<'a> for std::borrow::Cow<'a, str> {
+    type Value = Self;
+}
+
+/// Names are shared by reference counting, so the AST clones cheaply and,
+/// with `Arc`, can be shared between threads
+///
+/// Documents of these types convert to `'static` for free with
+/// `into_static`, like those of `String`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use std::sync::Arc;
+/// use graphql_parser::parse_query;
+///
+/// let source = String::from("{ user { name } }");
+/// let doc = Arc::new(parse_query::<Arc<str>>(&source).unwrap().into_static());
+/// drop(source);
+/// let printed = std::thread::spawn(move || doc.to_string()).join().unwrap();
+/// assert_eq!(printed, "{\n  user {\n    name\n  }\n}\n");
+/// ```
+impl<'a> Text<'a> for std::sync::Arc<str> {
+    type Value = Self;
+}
+
+impl<'a> Text<'a> for std::rc::Rc<str> {
+    type Value = Self;
+}
+
+/// Uses any string type as `Text`, like a small string type which stores
+/// short names inline
+///
+/// Only the type parameter matters, values of `Names` are never created.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_schema;
+/// use graphql_parser::schema::Names;
+///
+/// let schema = parse_schema::<Names<Box<str>>>("type User { id: ID }").unwrap();
+/// assert_eq!(schema.to_string(), "type User {\n  id: ID\n}\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Names<V>(std::marker::PhantomData<V>);
+
+impl<'a, V> Text<'a> for Names<V>
+where
+    V: 'a
+        + From<&'a str>
+        + AsRef<str>
+        + std::borrow::Borrow<str>
+        + Eq
+        + Ord
+        + Hash
+        + fmt::Debug
+        + Clone,
+{
+    type Value = V;
+}
+
+/// Keeps a single copy of each name
+///
+/// Convert documents with `map_text` to share the names between all of
+/// them, with a reference counted type like `Arc<str>` as `V`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use std::sync::Arc;
+/// use graphql_parser::parse_query;
+/// use graphql_parser::query::{Document, Interner};
+///
+/// let mut interner = Interner::<Arc<str>>::new();
+/// let docs = ["{ user { id } }", "{ users { id } }"]
+///     .iter()
+///     .map(|source| {
+///         let doc = parse_query::<&str>(source).unwrap();
+///         doc.map_text(&mut |name| interner.intern(name))
+///     })
+///     .collect::<Vec<Document<Arc<str>>>>();
+/// assert_eq!(interner.len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Interner<V> {
+    names: std::collections::HashSet<V>,
+}
+
+impl<V> Default for Interner<V> {
+    fn default() -> Self {
+        Interner {
+            names: Default::default(),
+        }
+    }
+}
+
+impl<V> Interner<V>
+where
+    V: for<'s> From<&'s str> + std::borrow::Borrow<str> + Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Returns the copy of the name, creating it the first time
+    pub fn intern(&mut self, name: &str) -> V {
+        if let Some(value) = self.names.get(name) {
+            return value.clone();
+        }
+        let value = V::from(name);
+        self.names.insert(value.clone());
+        value
+    }
+
+    /// Returns the number of distinct names
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Directive<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub name: T::Value,
+    pub arguments: Vec<(T::Value, Value<'a, T>)>,
+}
+
+/// A `#` comment
+///
+/// Comments are only collected when `Parser::preserve_comments` is
+/// enabled, in the order they appear in the source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Comment<'a, T: Text<'a>> {
+    /// Position of the `#`
+    pub position: Pos,
+    pub end: Pos,
+    /// Text following the `#`, up to the end of the line
+    pub text: T::Value,
+}
+
+impl<'a, T: Text<'a>> Comment<'a, T> {
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    pub fn into_static(&self) -> Comment<'static, String> {
+        Comment {
+            position: self.position,
+            end: self.end,
+            text: self.text.as_ref().into(),
+        }
+    }
+
+    /// Converts the text with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> Comment<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        Comment {
+            position: self.position,
+            end: self.end,
+            text: f(self.text.as_ref()),
+        }
+    }
+}
+
+/// This represents integer number
+///
+/// But since there is no definition on limit of number in spec
+/// (only in implemetation), we do a trick similar to the one
+/// in `serde_json`: encapsulate value in new-type, allowing type
+/// to be extended later.
+///
+/// **Breaking change:** the number used to be an `u64`, which can't hold
+/// negative literals. It is an `i64` now, so literals from 2^63 up to
+/// 2^64 - 1 are parsed as `Value::BigInt` instead of `Value::Int`,
+/// `as_i64` returns the number as is and `as_u64` fails for negative ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// we use i64 as a reference implementation: graphql-js thinks even 32bit
+// integers is enough. We might consider lift this limit later though
+pub struct Number(pub(crate) i64);
+
+/// An integer which doesn't fit into `Number`, parsed using the BigInt
+/// extension
+///
+/// Integers are limited to magnitudes up to `u128::MAX`, of either sign,
+/// unless the `num-bigint` feature is enabled, which lifts the limit
+/// entirely.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigNumber(pub(crate) BigRepr);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum BigRepr {
+    /// Zero is never negative, so that every number has a single
+    /// representation
+    Int { negative: bool, magnitude: u128 },
+    /// Only used for numbers whose magnitude doesn't fit into u128
+    #[cfg(feature = "num-bigint")]
+    Arbitrary(num_bigint::BigInt),
+}
+
+/// A float literal
+///
+/// Floats produced by the parser keep the literal as it is written in the
+/// source, since converting it to f64 loses trailing zeros, the exponent
+/// notation and digits beyond the precision of f64. Integers print exactly
+/// as written anyway, except for `-0`.
+#[derive(Clone)]
+pub struct FloatNumber<'a, T: Text<'a>> {
+    value: f64,
+    raw: Option<T::Value>,
+}
+
+impl<'a, T: Text<'a>> FloatNumber<'a, T> {
+    pub fn new(value: f64) -> Self {
+        FloatNumber { value, raw: None }
+    }
+
+    fn from_raw(raw: &'a str) -> Result<Self, std::num::ParseFloatError> {
+        Ok(FloatNumber {
+            value: raw.parse()?,
+            raw: Some(raw.into()),
+        })
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the literal as written in the source
+    ///
+    /// Returns `None` for floats which were not produced by the parser.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_ref().map(|raw| raw.as_ref())
+    }
+
+    pub fn into_static(&self) -> FloatNumber<'static, String> {
+        FloatNumber {
+            value: self.value,
+            raw: self.raw().map(String::from),
+        }
+    }
+
+    /// Converts the source literal with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> FloatNumber<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        FloatNumber {
+            value: self.value,
+            raw: self.raw().map(f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> fmt::Debug for FloatNumber<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+/// Prints the source literal if there is one, otherwise a literal which
+/// parses back as a float, like `1.0` rather than `1`
+impl<'a, T: Text<'a>> fmt::Display for FloatNumber<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.raw() {
+            Some(raw) => f.write_str(raw),
+            None => fmt::Debug::fmt(&self.value, f),
+        }
+    }
+}
+
+/// Floats are equal if their values are, however they are written
+///
+/// Values compare with `f64::total_cmp`, so that floats can be hashed and
+/// ordered: `NaN` equals itself and `-0.0` is less than `0.0`.
+impl<'a, T: Text<'a>> PartialEq for FloatNumber<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, T: Text<'a>> Eq for FloatNumber<'a, T> {}
+
+impl<'a, T: Text<'a>> PartialOrd for FloatNumber<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T: Text<'a>> Ord for FloatNumber<'a, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.total_cmp(&other.value)
+    }
+}
+
+impl<'a, T: Text<'a>> Hash for FloatNumber<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state)
+    }
+}
+
+impl<'a, T: Text<'a>> PartialEq<f64> for FloatNumber<'a, T> {
+    fn eq(&self, other: &f64) -> bool {
+        self.value == *other
+    }
+}
+
+impl<'a, T: Text<'a>> From<f64> for FloatNumber<'a, T> {
+    fn from(value: f64) -> Self {
+        FloatNumber::new(value)
+    }
+}
+
+/// Floats are serialized as numbers, the source literal isn't kept
+#[cfg(feature = "serde")]
+impl<'a, T: Text<'a>> serde::Serialize for FloatNumber<'a, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Text<'a>> serde::Deserialize<'de> for FloatNumber<'a, T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(FloatNumber::new)
+    }
+}
+
+/// A string literal
+///
+/// Strings produced by the parser keep the literal as it is written in the
+/// source and are unescaped lazily on first access. Literals without escape
+/// sequences are returned as slices of the source, others are decoded once
+/// and cached, so documents whose string contents are never read don't pay
+/// for decoding.
+#[derive(Clone)]
+pub struct StringValue<'a, T: Text<'a>> {
+    repr: StringRepr<'a, T>,
+}
+
+#[derive(Clone)]
+enum StringRepr<'a, T: Text<'a>> {
+    Decoded(String),
+    Raw {
+        raw: T::Value,
+        block: bool,
+        decoded: OnceLock<String>,
+    },
+}
+
+impl<'a, T: Text<'a>> StringValue<'a, T> {
+    /// Creates a string from an already unescaped value
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        StringValue {
+            repr: StringRepr::Decoded(value.into()),
+        }
+    }
+
+    /// Creates a string from a literal that has already been validated
+    fn from_raw(raw: &'a str, block: bool) -> Self {
+        StringValue::from_raw_value(raw.into(), block)
+    }
+
+    fn from_raw_value(raw: T::Value, block: bool) -> Self {
+        StringValue {
+            repr: StringRepr::Raw {
+                raw,
+                block,
+                decoded: OnceLock::new(),
+            },
+        }
+    }
+
+    /// Returns the unescaped value, decoding the literal if needed
+    pub fn as_str(&self) -> &str {
+        match self.repr {
+            StringRepr::Decoded(ref value) => value,
+            StringRepr::Raw {
+                ref raw,
+                block,
+                ref decoded,
+            } => {
+                if let Some(value) = decoded.get() {
+                    return value;
+                }
+                let raw = raw.as_ref();
+                let value = if block {
+                    unquote_block_string(raw)
+                } else {
+                    unquote_string(raw).expect("string literal is validated by the parser")
+                };
+                match value {
+                    Cow::Borrowed(value) => value,
+                    Cow::Owned(value) => decoded.get_or_init(|| value),
+                }
+            }
+        }
+    }
+
+    /// Returns the literal as written in the source, including quotes
+    ///
+    /// Returns `None` for strings which were not produced by the parser.
+    pub fn raw(&self) -> Option<&str> {
+        match self.repr {
+            StringRepr::Decoded(_) => None,
+            StringRepr::Raw { ref raw, .. } => Some(raw.as_ref()),
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        match self.repr {
+            StringRepr::Decoded(value) => value,
+            StringRepr::Raw {
+                raw,
+                block,
+                decoded,
+            } => decoded.into_inner().unwrap_or_else(|| {
+                StringValue::<T>::from_raw_value(raw, block)
+                    .as_str()
+                    .to_string()
+            }),
+        }
+    }
+
+    pub fn into_static(&self) -> StringValue<'static, String> {
+        let repr = match self.repr {
+            StringRepr::Decoded(ref value) => StringRepr::Decoded(value.clone()),
+            StringRepr::Raw {
+                ref raw,
+                block,
+                ref decoded,
+            } => StringRepr::Raw {
+                raw: raw.as_ref().to_string(),
+                block,
+                decoded: decoded.clone(),
+            },
+        };
+        StringValue { repr }
+    }
+
+    /// Converts the source literal with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> StringValue<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        let repr = match self.repr {
+            StringRepr::Decoded(ref value) => StringRepr::Decoded(value.clone()),
+            StringRepr::Raw {
+                ref raw,
+                block,
+                ref decoded,
+            } => StringRepr::Raw {
+                raw: f(raw.as_ref()),
+                block,
+                decoded: decoded.clone(),
+            },
+        };
+        StringValue { repr }
+    }
+}
+
+impl<'a, T: Text<'a>> std::ops::Deref for StringValue<'a, T> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> AsRef<str> for StringValue<'a, T> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> fmt::Debug for StringValue<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a, T: Text<'a>> fmt::Display for StringValue<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'a, T: Text<'a>> PartialEq for StringValue<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> Eq for StringValue<'a, T> {}
+
+impl<'a, T: Text<'a>> PartialOrd for StringValue<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Strings are ordered by their unescaped values
+impl<'a, T: Text<'a>> Ord for StringValue<'a, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<'a, T: Text<'a>> Hash for StringValue<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<'a, T: Text<'a>> PartialEq<str> for StringValue<'a, T> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, 'b, T: Text<'a>> PartialEq<&'b str> for StringValue<'a, T> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<'a, T: Text<'a>> From<String> for StringValue<'a, T> {
+    fn from(value: String) -> Self {
+        StringValue::new(value)
+    }
+}
+
+impl<'a, 'b, T: Text<'a>> From<&'b str> for StringValue<'a, T> {
+    fn from(value: &'b str) -> Self {
+        StringValue::new(value)
+    }
+}
+
+/// Strings are serialized unescaped, the source literal isn't kept
+#[cfg(feature = "serde")]
+impl<'a, T: Text<'a>> serde::Serialize for StringValue<'a, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Text<'a>> serde::Deserialize<'de> for StringValue<'a, T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(StringValue::new)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum Value<'a, T: Text<'a>> {
+    Variable(T::Value),
+    BigInt(BigNumber),
+    Int(Number),
+    Float(FloatNumber<'a, T>),
+    String(StringValue<'a, T>),
+    Boolean(bool),
+    Null,
+    Enum(T::Value),
+    List(Vec<Value<'a, T>>),
+    /// Fields of an input object in the order of the source
+    Object(
+        #[cfg_attr(feature = "serde", serde(with = "object_fields"))] Vec<(T::Value, Value<'a, T>)>,
+    ),
+}
+
+/// A value which can't contain variables
+///
+/// The specification only allows constant values in default values and
+/// in the directives of a schema. Converting a `Value` fails if it
+/// contains a variable anywhere, converting back always succeeds.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use std::convert::TryFrom;
+/// use graphql_parser::parse_value;
+/// use graphql_parser::query::{ConstValue, Value};
+///
+/// let value = parse_value::<&str>("{limit: 10}").unwrap();
+/// let constant = ConstValue::try_from(value.clone()).unwrap();
+/// assert_eq!(Value::from(constant), value);
+///
+/// let value = parse_value::<&str>("{limit: $limit}").unwrap();
+/// let err = ConstValue::try_from(value).unwrap_err();
+/// assert_eq!(err.to_string(), "variable `$limit` is not allowed in a constant value");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum ConstValue<'a, T: Text<'a>> {
+    BigInt(BigNumber),
+    Int(Number),
+    Float(FloatNumber<'a, T>),
+    String(StringValue<'a, T>),
+    Boolean(bool),
+    Null,
+    Enum(T::Value),
+    List(Vec<ConstValue<'a, T>>),
+    Object(
+        #[cfg_attr(feature = "serde", serde(with = "object_fields"))]
+        Vec<(T::Value, ConstValue<'a, T>)>,
+    ),
+}
+
+/// Error converting a `Value` which contains a variable to a `ConstValue`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("variable `${name}` is not allowed in a constant value")]
+pub struct VariableInConstValue {
+    /// Name of the first variable found, without the `$`
+    pub name: String,
+}
+
+impl<'a, T: Text<'a>> TryFrom<Value<'a, T>> for ConstValue<'a, T> {
+    type Error = VariableInConstValue;
+
+    fn try_from(value: Value<'a, T>) -> Result<Self, VariableInConstValue> {
+        Ok(match value {
+            Value::Variable(name) => {
+                return Err(VariableInConstValue {
+                    name: name.as_ref().into(),
+                })
+            }
+            Value::BigInt(n) => ConstValue::BigInt(n),
+            Value::Int(n) => ConstValue::Int(n),
+            Value::Float(f) => ConstValue::Float(f),
+            Value::String(s) => ConstValue::String(s),
+            Value::Boolean(b) => ConstValue::Boolean(b),
+            Value::Null => ConstValue::Null,
+            Value::Enum(name) => ConstValue::Enum(name),
+            Value::List(list) => ConstValue::List(
+                list.into_iter()
+                    .map(ConstValue::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(object) => ConstValue::Object(
+                object
+                    .into_iter()
+                    .map(|(k, v)| Ok((k, ConstValue::try_from(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+impl<'a, T: Text<'a>> From<ConstValue<'a, T>> for Value<'a, T> {
+    fn from(value: ConstValue<'a, T>) -> Self {
+        match value {
+            ConstValue::BigInt(n) => Value::BigInt(n),
+            ConstValue::Int(n) => Value::Int(n),
+            ConstValue::Float(f) => Value::Float(f),
+            ConstValue::String(s) => Value::String(s),
+            ConstValue::Boolean(b) => Value::Boolean(b),
+            ConstValue::Null => Value::Null,
+            ConstValue::Enum(name) => Value::Enum(name),
+            ConstValue::List(list) => Value::List(list.into_iter().map(Value::from).collect()),
+            ConstValue::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(k, v)| (k, Value::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> ConstValue<'a, T> {
+    pub fn into_static(&self) -> ConstValue<'static, String> {
+        match self {
+            Self::BigInt(i) => ConstValue::BigInt(i.clone()),
+            Self::Int(i) => ConstValue::Int(i.clone()),
+            Self::Float(f) => ConstValue::Float(f.into_static()),
+            Self::String(s) => ConstValue::String(s.into_static()),
+            Self::Boolean(b) => ConstValue::Boolean(*b),
+            Self::Null => ConstValue::Null,
+            Self::Enum(v) => ConstValue::Enum(v.as_ref().into()),
+            Self::List(l) => ConstValue::List(l.iter().map(|e| e.into_static()).collect()),
+            Self::Object(o) => ConstValue::Object(
+                o.iter()
+                    .map(|(k, v)| (k.as_ref().into(), v.into_static()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Serializes fields of objects as a map, keeping their order
+#[cfg(feature = "serde")]
+mod object_fields {
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+    use serde::ser::{Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(fields: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_map(fields.iter().map(|(k, v)| (k, v)))
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<Vec<(K, V)>, D::Error>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        struct FieldsVisitor<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for FieldsVisitor<K, V> {
+            type Value = Vec<(K, V)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of object fields")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(fields)
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor(PhantomData))
+    }
+}
+
+impl<'a, T: Text<'a>> Directive<'a, T> {
+    /// Returns the source range of the directive
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    /// Returns the value of an argument
+    ///
+    /// As with `Value::get`, the last argument wins if the name is
+    /// repeated.
+    pub fn argument(&self, name: &str) -> Option<&Value<'a, T>> {
+        self.arguments
+            .iter()
+            .rev()
+            .find(|(key, _)| key.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn into_static(&self) -> Directive<'static, String> {
+        Directive {
+            position: self.position,
+            end: self.end,
+            name: self.name.as_ref().into(),
+            arguments: self
+                .arguments
+                .iter()
+                .map(|(k, v)| (k.as_ref().into(), v.into_static()))
+                .collect(),
+        }
+    }
+
+    /// Converts the names with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> Directive<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        Directive {
+            position: self.position,
+            end: self.end,
+            name: f(self.name.as_ref()),
+            arguments: self
+                .arguments
+                .iter()
+                .map(|(k, v)| (f(k.as_ref()), v.map_text(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    /// Returns the source text of a scalar literal
+    ///
+    /// This lets custom scalars, like decimals or 256-bit integers, parse
+    /// literals themselves instead of going through the lossy built-in
+    /// decoding. Strings include their quotes and escape sequences.
+    /// Integers are returned as written, except that `-0` loses its sign.
+    ///
+    /// Returns `None` for variables, lists, objects, and strings and floats
+    /// which were not produced by the parser.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_value;
+    ///
+    /// let value = parse_value::<&str>("0.10000000000000000001").unwrap();
+    /// assert_eq!(value.literal().as_deref(), Some("0.10000000000000000001"));
+    /// let value = parse_value::<&str>(r#""0x\u0041""#).unwrap();
+    /// assert_eq!(value.literal().as_deref(), Some(r#""0x\u0041""#));
+    /// ```
+    pub fn literal(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Int(n) => Some(Cow::Owned(n.0.to_string())),
+            Self::BigInt(n) => Some(Cow::Owned(n.0.to_string())),
+            Self::Float(f) => f.raw().map(Cow::Borrowed),
+            Self::String(s) => s.raw().map(Cow::Borrowed),
+            Self::Boolean(true) => Some(Cow::Borrowed("true")),
+            Self::Boolean(false) => Some(Cow::Borrowed("false")),
+            Self::Null => Some(Cow::Borrowed("null")),
+            Self::Enum(name) => Some(Cow::Borrowed(name.as_ref())),
+            Self::Variable(_) | Self::List(_) | Self::Object(_) => None,
+        }
+    }
+
+    /// Returns the value of a field of an object
+    ///
+    /// Returns `None` if the value isn't an object or doesn't contain the
+    /// field. Nested fields are reached with `and_then`, or with
+    /// [`get_path`](#method.get_path).
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_value;
+    ///
+    /// let value = parse_value::<&str>("{a: {b: [1, 2]}}").unwrap();
+    /// let b = value.get("a").and_then(|a| a.get("b"));
+    /// assert_eq!(b.and_then(|b| b.as_list()).map(|l| l.len()), Some(2));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value<'a, T>> {
+        let object = self.as_object()?;
+        // a repeated field is invalid, the last one is commonly used then
+        object
+            .iter()
+            .rev()
+            .find(|(name, _)| name.as_ref() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value at a path of object fields
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_value;
+    ///
+    /// let value = parse_value::<&str>(r#"{user: {name: "Alice"}}"#).unwrap();
+    /// let name = value.get_path(&["user", "name"]).and_then(|v| v.as_str());
+    /// assert_eq!(name, Some("Alice"));
+    /// ```
+    pub fn get_path<K: AsRef<str>>(&self, path: &[K]) -> Option<&Value<'a, T>> {
+        path.iter()
+            .try_fold(self, |value, key| value.get(key.as_ref()))
+    }
+
+    /// Returns the contents of a string, with escape sequences replaced
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns an integer if it fits into i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(n) => Some(n.as_i64()),
+            Self::BigInt(n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns a float, or an integer converted to the nearest float
+    ///
+    /// Integers are accepted as in the input coercion of the `Float` type.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(f.as_f64()),
+            Self::Int(n) => Some(n.as_f64()),
+            Self::BigInt(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of an enum value
+    pub fn as_enum(&self) -> Option<&str> {
+        match self {
+            Self::Enum(name) => Some(name.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of a variable, without the `$`
+    pub fn as_variable(&self) -> Option<&str> {
+        match self {
+            Self::Variable(name) => Some(name.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value<'a, T>]> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of an object in the order of the source
+    pub fn as_object(&self) -> Option<&[(T::Value, Value<'a, T>)]> {
+        match self {
+            Self::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn into_static(&self) -> Value<'static, String> {
+        match self {
+            Self::Variable(v) => Value::Variable(v.as_ref().into()),
+            Self::BigInt(i) => Value::BigInt(i.clone()),
+            Self::Int(i) => Value::Int(i.clone()),
+            Self::Float(f) => Value::Float(f.into_static()),
+            Self::String(s) => Value::String(s.into_static()),
+            Self::Boolean(b) => Value::Boolean(*b),
+            Self::Null => Value::Null,
+            Self::Enum(v) => Value::Enum(v.as_ref().into()),
+            Self::List(l) => Value::List(l.iter().map(|e| e.into_static()).collect()),
+            Self::Object(o) => Value::Object(
+                o.iter()
+                    .map(|(k, v)| (k.as_ref().into(), v.into_static()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts the names and literals with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> Value<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        match self {
+            Self::Variable(v) => Value::Variable(f(v.as_ref())),
+            Self::BigInt(i) => Value::BigInt(i.clone()),
+            Self::Int(i) => Value::Int(i.clone()),
+            Self::Float(n) => Value::Float(n.map_text(f)),
+            Self::String(s) => Value::String(s.map_text(f)),
+            Self::Boolean(b) => Value::Boolean(*b),
+            Self::Null => Value::Null,
+            Self::Enum(v) => Value::Enum(f(v.as_ref())),
+            Self::List(l) => Value::List(l.iter().map(|e| e.map_text(f)).collect()),
+            Self::Object(o) => Value::Object(
+                o.iter()
+                    .map(|(k, v)| (f(k.as_ref()), v.map_text(f)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> From<bool> for Value<'a, T> {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl<'a, T: Text<'a>> From<i64> for Value<'a, T> {
+    fn from(value: i64) -> Self {
+        Value::Int(Number::from(value))
+    }
+}
+
+impl<'a, T: Text<'a>> From<f64> for Value<'a, T> {
+    fn from(value: f64) -> Self {
+        Value::Float(FloatNumber::from(value))
+    }
+}
+
+impl<'a, 'b, T: Text<'a>> From<&'b str> for Value<'a, T> {
+    fn from(value: &'b str) -> Self {
+        Value::String(StringValue::from(value))
+    }
+}
+
+impl<'a, T: Text<'a>> From<String> for Value<'a, T> {
+    fn from(value: String) -> Self {
+        Value::String(StringValue::from(value))
+    }
+}
+
+impl<'a, T: Text<'a>> From<Vec<Value<'a, T>>> for Value<'a, T> {
+    fn from(value: Vec<Value<'a, T>>) -> Self {
+        Value::List(value)
+    }
+}
+
+/// Builds a `Value::Object` from pairs of keys and values
+///
+/// Keys and values are converted with `Into`, so keys may be string
+/// literals and values anything `Value` implements `From` for.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::object;
+/// use graphql_parser::query::Value;
+///
+/// let value: Value<String> = object! {
+///     "name" => "Alice",
+///     "age" => 42,
+///     "tags" => vec![Value::from("admin")],
+///     "address" => object! { "city" => "Paris" },
+/// };
+/// assert_eq!(value.to_string(), r#"{name: "Alice", age: 42, tags: ["admin"], address: {city: "Paris"}}"#);
+/// ```
+#[macro_export]
+macro_rules! object {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        $crate::query::Value::Object({
+            ::std::vec![$(
+                (
+                    ::std::convert::Into::into($key),
+                    $crate::query::Value::from($value),
+                ),
+            )*]
+        })
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum Type<'a, T: Text<'a>> {
+    NamedType(T::Value),
+    ListType(Box<Type<'a, T>>),
+    NonNullType(Box<Type<'a, T>>),
+}
+
+impl<'a, T: Text<'a>> Type<'a, T> {
+    /// Returns the name of the named type inside all list and non-null
+    /// wrappers
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::query::Type;
+    ///
+    /// let ty: Type<&str> = Type::NonNullType(Box::new(Type::ListType(
+    ///     Box::new(Type::NamedType("User")),
+    /// )));
+    /// assert_eq!(ty.innermost_name(), "User");
+    /// assert!(ty.is_non_null());
+    /// assert!(ty.is_list());
+    /// assert_eq!(ty.list_depth(), 1);
+    /// assert!(ty.wraps(&Type::NamedType("User")));
+    /// ```
+    pub fn innermost_name(&self) -> &str {
+        match *self {
+            Type::NamedType(ref name) => name.as_ref(),
+            Type::ListType(ref inner) | Type::NonNullType(ref inner) => inner.innermost_name(),
+        }
+    }
+
+    /// Returns `true` for `T!` types
+    pub fn is_non_null(&self) -> bool {
+        matches!(*self, Type::NonNullType(_))
+    }
+
+    /// Returns `true` for `[T]` and `[T]!` types
+    pub fn is_list(&self) -> bool {
+        matches!(*self.nullable(), Type::ListType(_))
+    }
+
+    /// Returns the type without its outer non-null wrapper, if any
+    pub fn nullable(&self) -> &Type<'a, T> {
+        match *self {
+            Type::NonNullType(ref inner) => inner,
+            _ => self,
+        }
+    }
+
+    /// Returns the number of nested lists, `2` for `[[T!]]!`
+    pub fn list_depth(&self) -> usize {
+        match *self {
+            Type::NamedType(_) => 0,
+            Type::ListType(ref inner) => inner.list_depth() + 1,
+            Type::NonNullType(ref inner) => inner.list_depth(),
+        }
+    }
+
+    /// Returns `true` if `other` is nested inside the wrappers of this type
+    ///
+    /// `[User!]!` wraps `[User!]`, `User!` and `User`, but not itself.
+    pub fn wraps(&self, other: &Type<'a, T>) -> bool {
+        match *self {
+            Type::NamedType(_) => false,
+            Type::ListType(ref inner) | Type::NonNullType(ref inner) => {
+                same_type(inner, other) || inner.wraps(other)
+            }
+        }
+    }
+
+    pub fn into_static(&self) -> Type<'static, String> {
+        match *self {
+            Type::NamedType(ref name) => Type::NamedType(name.as_ref().into()),
+            Type::ListType(ref inner) => Type::ListType(Box::new(inner.into_static())),
+            Type::NonNullType(ref inner) => Type::NonNullType(Box::new(inner.into_static())),
+        }
+    }
+
+    /// Converts the name with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> Type<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        match *self {
+            Type::NamedType(ref name) => Type::NamedType(f(name.as_ref())),
+            Type::ListType(ref inner) => Type::ListType(Box::new(inner.map_text(f))),
+            Type::NonNullType(ref inner) => Type::NonNullType(Box::new(inner.map_text(f))),
+        }
+    }
+}
+
+/// Parses a type reference, like `[Episode!]!`
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::Type;
+///
+/// let ty: Type<String> = "[Episode!]!".parse().unwrap();
+/// assert_eq!(ty.innermost_name(), "Episode");
+/// assert!("[Episode".parse::<Type<String>>().is_err());
+/// ```
+impl FromStr for Type<'static, String> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Type<'static, String>, ParseError> {
+        crate::query::parse_type::<&str>(s).map(|ty| ty.into_static())
+    }
+}
+
+impl<'s> TryFrom<&'s str> for Type<'static, String> {
+    type Error = ParseError;
+
+    fn try_from(s: &'s str) -> Result<Type<'static, String>, ParseError> {
+        s.parse()
+    }
+}
+
+/// Compares types by the names they contain, unlike `PartialEq` this
+/// doesn't require `T: PartialEq`
+pub(crate) fn same_type<'a, T: Text<'a>>(a: &Type<'a, T>, b: &Type<'a, T>) -> bool {
+    match (a, b) {
+        (Type::NamedType(a), Type::NamedType(b)) => a.as_ref() == b.as_ref(),
+        (Type::ListType(a), Type::ListType(b)) => same_type(a, b),
+        (Type::NonNullType(a), Type::NonNullType(b)) => same_type(a, b),
+        _ => false,
+    }
+}
+
+impl BigNumber {
+    /// Returns a number as i128 if it fits the type
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.0 {
+            BigRepr::Int {
+                negative: false,
+                magnitude,
+            } => magnitude.try_into().ok(),
+            // `i128::MIN` has no positive counterpart
+            BigRepr::Int {
+                negative: true,
+                magnitude,
+            } if magnitude <= i128::MIN.unsigned_abs() => Some((magnitude as i128).wrapping_neg()),
+            _ => None,
+        }
+    }
+
+    /// Returns the absolute value of the number, use `is_negative` for the
+    /// sign
+    ///
+    /// Numbers beyond `u128::MAX`, which only the `num-bigint` feature
+    /// produces, saturate at `u128::MAX`.
+    pub fn as_u128(&self) -> u128 {
+        match self.0 {
+            BigRepr::Int { magnitude, .. } => magnitude,
+            #[cfg(feature = "num-bigint")]
+            BigRepr::Arbitrary(_) => u128::MAX,
+        }
+    }
+
+    /// Returns a number as i64 if it fits the type
+    ///
+    /// Only numbers parsed with `ParserOptions::ints_as_bigint` may fit.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|n| n.try_into().ok())
+    }
+
+    /// Returns a number as u64 if it fits the type
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128().and_then(|n| n.try_into().ok())
+    }
+
+    /// Returns the nearest f64, which may lose precision
+    pub fn as_f64(&self) -> f64 {
+        match self.0 {
+            BigRepr::Int {
+                negative,
+                magnitude,
+            } => {
+                if negative {
+                    -(magnitude as f64)
+                } else {
+                    magnitude as f64
+                }
+            }
+            // overflows to infinity instead of failing
+            #[cfg(feature = "num-bigint")]
+            BigRepr::Arbitrary(ref n) => n.to_string().parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Returns the number as an arbitrary precision integer
+    #[cfg(feature = "num-bigint")]
+    pub fn to_bigint(&self) -> num_bigint::BigInt {
+        match self.0 {
+            BigRepr::Int {
+                negative,
+                magnitude,
+            } => {
+                let n = num_bigint::BigInt::from(magnitude);
+                if negative {
+                    -n
+                } else {
+                    n
+                }
+            }
+            BigRepr::Arbitrary(ref n) => n.clone(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self.0 {
+            BigRepr::Int { negative, .. } => negative,
+            #[cfg(feature = "num-bigint")]
+            BigRepr::Arbitrary(ref n) => n.sign() == num_bigint::Sign::Minus,
+        }
+    }
+
+    fn from_parts(negative: bool, magnitude: u128) -> BigNumber {
+        BigNumber(BigRepr::Int {
+            negative: negative && magnitude != 0,
+            magnitude,
+        })
+    }
+}
+
+impl PartialOrd for BigNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Numbers are ordered by value
+impl Ord for BigNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (
+                &BigRepr::Int {
+                    negative: a_negative,
+                    magnitude: a,
+                },
+                &BigRepr::Int {
+                    negative: b_negative,
+                    magnitude: b,
+                },
+            ) => match (a_negative, b_negative) {
+                (false, false) => a.cmp(&b),
+                (true, true) => b.cmp(&a),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+            },
+            #[cfg(feature = "num-bigint")]
+            (a, b) => BigNumber(a.clone())
+                .to_bigint()
+                .cmp(&BigNumber(b.clone()).to_bigint()),
+        }
+    }
+}
+
+impl From<i128> for BigNumber {
+    fn from(n: i128) -> BigNumber {
+        BigNumber::from_parts(n < 0, n.unsigned_abs())
+    }
+}
+
+impl From<u128> for BigNumber {
+    fn from(n: u128) -> BigNumber {
+        BigNumber::from_parts(false, n)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl From<num_bigint::BigInt> for BigNumber {
+    fn from(n: num_bigint::BigInt) -> BigNumber {
+        match u128::try_from(n.magnitude()) {
+            Ok(magnitude) => BigNumber::from_parts(n.sign() == num_bigint::Sign::Minus, magnitude),
+            Err(_) => BigNumber(BigRepr::Arbitrary(n)),
+        }
+    }
+}
+
+impl FromStr for BigNumber {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<BigNumber, ParseIntError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        // u128 accepts a plus sign, which can't follow a minus
+        if negative && digits.starts_with('+') {
+            return Err(s.parse::<u128>().unwrap_err());
+        }
+        match digits.parse() {
+            Ok(magnitude) => Ok(BigNumber::from_parts(negative, magnitude)),
+            // only numbers which overflow parse as arbitrary precision ones
+            #[cfg(feature = "num-bigint")]
+            Err(e) => s
+                .parse::<num_bigint::BigInt>()
+                .map(|n| BigNumber(BigRepr::Arbitrary(n)))
+                .map_err(|_| e),
+            #[cfg(not(feature = "num-bigint"))]
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl fmt::Display for BigNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl From<i32> for BigNumber {
+    fn from(n: i32) -> BigNumber {
+        BigNumber::from(i128::from(n))
+    }
+}
+
+impl From<u32> for BigNumber {
+    fn from(n: u32) -> BigNumber {
+        BigNumber::from(i128::from(n))
+    }
+}
+
+impl From<i64> for BigNumber {
+    fn from(n: i64) -> BigNumber {
+        BigNumber::from(i128::from(n))
+    }
+}
+
+impl From<u64> for BigNumber {
+    fn from(n: u64) -> BigNumber {
+        BigNumber::from(i128::from(n))
+    }
+}
+
+impl From<Number> for BigNumber {
+    fn from(n: Number) -> BigNumber {
+        BigNumber::from(n.0)
+    }
+}
+
+impl fmt::Display for BigRepr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BigRepr::Int {
+                negative,
+                magnitude,
+            } => {
+                if negative {
+                    f.write_str("-")?;
+                }
+                write!(f, "{}", magnitude)
+            }
+            #[cfg(feature = "num-bigint")]
+            BigRepr::Arbitrary(ref n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Numbers which fit into i128 or u128 are serialized as numbers, others
+/// as strings
+#[cfg(feature = "serde")]
+impl serde::Serialize for BigNumber {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            BigRepr::Int {
+                negative: false,
+                magnitude,
+            } => serializer.serialize_u128(magnitude),
+            _ => match self.as_i128() {
+                Some(n) => serializer.serialize_i128(n),
+                None => serializer.collect_str(self),
+            },
+        }
+    }
 }
 
-impl<'a> Text<'a> for String {
-    type Value = String;
-}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BigNumber {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<BigNumber, D::Error> {
+        struct Visitor;
 
-impl<'a> Text<'a> for std::borrow::Cow<'a, str> {
-    type Value = Self;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = BigNumber;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer or a string of decimal digits")
+            }
+
+            fn visit_i64<E>(self, n: i64) -> Result<BigNumber, E> {
+                Ok(BigNumber::from(i128::from(n)))
+            }
+
+            fn visit_u64<E>(self, n: u64) -> Result<BigNumber, E> {
+                Ok(BigNumber::from(i128::from(n)))
+            }
+
+            fn visit_i128<E>(self, n: i128) -> Result<BigNumber, E> {
+                Ok(BigNumber::from(n))
+            }
+
+            fn visit_u128<E>(self, n: u128) -> Result<BigNumber, E> {
+                Ok(BigNumber::from(n))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<BigNumber, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Directive<'a, T: Text<'a>> {
-    pub position: Pos,
-    pub name: T::Value,
-    pub arguments: Vec<(T::Value, Value<'a, T>)>,
+impl Number {
+    /// Returns the number, integers which don't fit into i64 are parsed
+    /// as `BigNumber`
+    pub fn as_i64(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns a number as i32 if it fits the type, which is the range of
+    /// the `Int` type of the specification
+    pub fn as_i32(&self) -> Option<i32> {
+        self.0.try_into().ok()
+    }
+
+    /// Returns a number as u64 unless it's negative
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.try_into().ok()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    /// Returns the nearest f64, which may lose precision
+    pub fn as_f64(&self) -> f64 {
+        self.0 as f64
+    }
 }
 
-/// This represents integer number
-///
-/// But since there is no definition on limit of number in spec
-/// (only in implemetation), we do a trick similar to the one
-/// in `serde_json`: encapsulate value in new-type, allowing type
-/// to be extended later.
-#[derive(Debug, Clone, PartialEq)]
-// we use i64 as a reference implementation: graphql-js thinks even 32bit
-// integers is enough. We might consider lift this limit later though
-pub struct Number(pub(crate) u64);
+impl FromStr for Number {
+    type Err = ParseIntError;
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct BigNumber(pub(crate) u128);
+    fn from_str(s: &str) -> Result<Number, ParseIntError> {
+        s.parse().map(Number)
+    }
+}
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Value<'a, T: Text<'a>> {
-    Variable(T::Value),
-    BigInt(BigNumber),
-    Int(Number),
-    Float(f64),
-    String(String),
-    Boolean(bool),
-    Null,
-    Enum(T::Value),
-    List(Vec<Value<'a, T>>),
-    Object(BTreeMap<T::Value, Value<'a, T>>),
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
 }
 
-impl<'a, T: Text<'a>> Value<'a, T> {
-    pub fn into_static(&self) -> Value<'static, String> {
-        match self {
-            Self::Variable(v) => Value::Variable(v.as_ref().into()),
-            Self::BigInt(i) => Value::BigInt(i.clone()),
-            Self::Int(i) => Value::Int(i.clone()),
-            Self::Float(f) => Value::Float(*f),
-            Self::String(s) => Value::String(s.clone()),
-            Self::Boolean(b) => Value::Boolean(*b),
-            Self::Null => Value::Null,
-            Self::Enum(v) => Value::Enum(v.as_ref().into()),
-            Self::List(l) => Value::List(l.iter().map(|e| e.into_static()).collect()),
-            Self::Object(o) => Value::Object(
-                o.iter()
-                    .map(|(k, v)| (k.as_ref().into(), v.into_static()))
-                    .collect(),
-            ),
-        }
+impl From<i32> for Number {
+    fn from(n: i32) -> Number {
+        Number(n.into())
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Type<'a, T: Text<'a>> {
-    NamedType(T::Value),
-    ListType(Box<Type<'a, T>>),
-    NonNullType(Box<Type<'a, T>>),
+impl From<u32> for Number {
+    fn from(n: u32) -> Number {
+        Number(n.into())
+    }
 }
 
-impl BigNumber {
-    /// Returns a number as i64 if it fits the type
-    pub fn as_u64(&self) -> Option<u64> {
-        if let Ok(n) = TryInto::<u64>::try_into(self.0) {
-            Some(n)
-        } else {
-            None
-        }
+impl From<i64> for Number {
+    fn from(n: i64) -> Number {
+        Number(n)
     }
+}
 
-    pub fn as_u128(&self) -> u128 {
-        self.0
+impl TryFrom<u64> for Number {
+    type Error = TryFromIntError;
+
+    fn try_from(n: u64) -> Result<Number, TryFromIntError> {
+        n.try_into().map(Number)
     }
 }
 
-impl Number {
-    /// Returns a number as i64 if it fits the type
-    pub fn as_i64(&self) -> Option<i64> {
-        if let Ok(n) = TryInto::<i64>::try_into(self.0) {
-            Some(n)
-        } else {
-            None
-        }
+impl TryFrom<i128> for Number {
+    type Error = TryFromIntError;
+
+    fn try_from(n: i128) -> Result<Number, TryFromIntError> {
+        n.try_into().map(Number)
     }
+}
 
-    pub fn as_u64(&self) -> u64 {
-        self.0
+impl TryFrom<u128> for Number {
+    type Error = TryFromIntError;
+
+    fn try_from(n: u128) -> Result<Number, TryFromIntError> {
+        n.try_into().map(Number)
     }
 }
 
@@ -140,9 +1642,11 @@ where
         position()
             .skip(punct("@"))
             .and(name::<'a, T>())
-            .and(parser(arguments))
-            .map(|((position, name), arguments)| Directive {
+            .and(rule("arguments", arguments))
+            .and(end_position())
+            .map(|(((position, name), arguments), end)| Directive {
                 position,
+                end,
                 name,
                 arguments,
             }),
@@ -156,13 +1660,56 @@ pub fn arguments<'a, T>(
 where
     T: Text<'a>,
 {
-    optional(
+    let (arguments, consumed) = argument_nodes::<T, Value<'a, T>>(input)?;
+    let arguments = arguments
+        .into_iter()
+        .map(|(name, _, value)| (name, value))
+        .collect();
+    Ok((arguments, consumed))
+}
+
+/// Parses arguments along with the spans of their names
+pub(crate) fn argument_nodes<'a, T, V>(
+    input: &mut TokenStream<'a>,
+) -> ParseResult<Vec<(T::Value, Span, V)>, TokenStream<'a>>
+where
+    T: Text<'a>,
+    V: ValueNode<'a, T>,
+{
+    let (arguments, consumed): (Vec<_>, _) = optional(
         punct("(")
-            .with(many1(name::<'a, T>().skip(punct(":")).and(parser(value))))
+            .with(many1(
+                (position(), name::<'a, T>(), end_position())
+                    .skip(punct(":"))
+                    .and(rule("value", value_node::<T, V>))
+                    .map(|((start, name, end), value)| (name, Span::new(start, end), value)),
+            ))
             .skip(punct(")")),
     )
     .map(|opt| opt.unwrap_or_else(Vec::new))
-    .parse_stream(input)
+    .parse_stream(input)?;
+    if input.duplicate_arguments() {
+        warn_repeated(input, &arguments, MessageKey::DuplicateArgument);
+    }
+    Ok((arguments, consumed))
+}
+
+/// Warns about names given more than once, pointing at the first one
+fn warn_repeated<N: AsRef<str>, V>(
+    input: &mut TokenStream,
+    entries: &[(N, Span, V)],
+    key: MessageKey,
+) {
+    let mut first = BTreeMap::new();
+    for (name, span, _) in entries {
+        let prev = *first.entry(name.as_ref()).or_insert(span.start);
+        if prev != span.start {
+            input.warn(
+                span.start,
+                Message::with_args(key, vec![name.as_ref().to_string(), prev.to_string()]),
+            );
+        }
+    }
 }
 
 pub fn bigint_value<'a, S>(
@@ -171,35 +1718,124 @@ pub fn bigint_value<'a, S>(
 where
     S: Text<'a>,
 {
-    kind(T::BigIntValue)
-        .and_then(|tok| tok.value.parse())
-        .map(BigNumber)
+    let position = input.position();
+    let extension = input.bigint_extension();
+    let (value, consumed) = kind(T::BigIntValue)
+        .and_then(move |tok| {
+            if extension {
+                tok.value.parse().map_err(number_too_large)
+            } else {
+                Err(Error::Message(Info::text(
+                    ErrorCode::NumberTooLarge,
+                    "number too large for Int, the BigInt extension is disabled",
+                )))
+            }
+        })
         .map(Value::BigInt)
-        .parse_stream(input)
+        .parse_stream(input)?;
+    input.warn(position, Message::new(MessageKey::BigIntExtension));
+    Ok((value, consumed))
 }
 
 pub fn int_value<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<Value<'a, S>, TokenStream<'a>>
 where
     S: Text<'a>,
 {
+    let as_bigint = input.ints_as_bigint();
     kind(T::IntValue)
-        .and_then(|tok| tok.value.parse())
-        .map(Number)
-        .map(Value::Int)
+        .and_then(move |tok| {
+            let value = if as_bigint {
+                tok.value
+                    .parse()
+                    .map(|n: i64| Value::BigInt(BigNumber::from(i128::from(n))))
+            } else {
+                tok.value.parse().map(Number).map(Value::Int)
+            };
+            value.map_err(number_too_large)
+        })
         .parse_stream(input)
 }
 
+/// Integer tokens are valid numbers, so parsing them only fails on overflow
+fn number_too_large<'a>(err: ParseIntError) -> Error<'a> {
+    Error::Message(Info::text(ErrorCode::NumberTooLarge, err.to_string()))
+}
+
 pub fn float_value<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<Value<'a, S>, TokenStream<'a>>
 where
     S: Text<'a>,
 {
     kind(T::FloatValue)
-        .and_then(|tok| tok.value.parse())
+        .and_then(|tok| FloatNumber::from_raw(tok.value))
         .map(Value::Float)
         .parse_stream(input)
 }
 
-fn unquote_block_string<'a>(src: &'a str) -> Result<String, Error<Token<'a>, Token<'a>>> {
+/// Builds a string out of pieces of a source string, borrowing from the
+/// source for as long as the pieces are adjacent there
+struct SourcePieces<'a> {
+    src: &'a str,
+    borrowed: Option<(usize, usize)>,
+    owned: Option<String>,
+}
+
+impl<'a> SourcePieces<'a> {
+    fn new(src: &'a str) -> SourcePieces<'a> {
+        SourcePieces {
+            src,
+            borrowed: None,
+            owned: None,
+        }
+    }
+
+    /// Appends a slice which must be a part of the source string
+    fn push_slice(&mut self, piece: &'a str) {
+        if piece.is_empty() {
+            return;
+        }
+        let start = piece.as_ptr() as usize - self.src.as_ptr() as usize;
+        debug_assert!(start + piece.len() <= self.src.len());
+        match (&mut self.owned, &mut self.borrowed) {
+            (Some(owned), _) => owned.push_str(piece),
+            (None, Some((_, end))) if *end == start => *end += piece.len(),
+            (None, Some(_)) => self.push_owned(piece),
+            (None, borrowed @ None) => *borrowed = Some((start, start + piece.len())),
+        }
+    }
+
+    /// Appends a newline, borrowing it if the source has one at that place
+    fn push_newline(&mut self) {
+        if let (None, Some((_, end))) = (&self.owned, self.borrowed) {
+            if self.src[end..].starts_with('\n') {
+                self.push_slice(&self.src[end..end + 1]);
+                return;
+            }
+        }
+        self.push_owned("\n");
+    }
+
+    fn push_owned(&mut self, piece: &str) {
+        let (src, borrowed) = (self.src, self.borrowed);
+        let owned = self.owned.get_or_insert_with(|| {
+            let mut owned = String::new();
+            if let Some((start, end)) = borrowed {
+                owned.push_str(&src[start..end]);
+            }
+            owned
+        });
+        owned.push_str(piece);
+    }
+
+    fn finish(self) -> Cow<'a, str> {
+        match (self.owned, self.borrowed) {
+            (Some(owned), _) => Cow::Owned(owned),
+            (None, Some((start, end))) => Cow::Borrowed(&self.src[start..end]),
+            (None, None) => Cow::Borrowed(""),
+        }
+    }
+}
+
+fn unquote_block_string(src: &str) -> Cow<'_, str> {
     debug_assert!(src.starts_with("\"\"\"") && src.ends_with("\"\"\""));
     let indent = src[3..src.len() - 3]
         .lines()
@@ -214,89 +1850,116 @@ fn unquote_block_string<'a>(src: &'a str) -> Result<String, Error<Token<'a>, Tok
         })
         .min()
         .unwrap_or(0);
-    let mut result = String::with_capacity(src.len() - 6);
+    let mut result = SourcePieces::new(src);
     let mut lines = src[3..src.len() - 3].lines();
     if let Some(first) = lines.next() {
         let stripped = first.trim();
         if !stripped.is_empty() {
-            result.push_str(stripped);
-            result.push('\n');
+            result.push_slice(stripped);
+            result.push_newline();
         }
     }
-    let mut last_line = 0;
-    for line in lines {
-        last_line = result.len();
-        if line.len() > indent {
-            result.push_str(&line[indent..].replace(r#"\""""#, r#"""""#));
+    let mut lines = lines.peekable();
+    while let Some(line) = lines.next() {
+        let line = line.get(indent..).unwrap_or("");
+        if lines.peek().is_none() && line.trim().is_empty() {
+            // trailing whitespace-only line is dropped
+            break;
         }
-        result.push('\n');
-    }
-    if result[last_line..].trim().is_empty() {
-        result.truncate(last_line);
+        if line.contains(r#"\""""#) {
+            result.push_owned(&line.replace(r#"\""""#, r#"""""#));
+        } else {
+            result.push_slice(line);
+        }
+        result.push_newline();
     }
 
-    Ok(result)
+    result.finish()
 }
 
-fn unquote_string<'a>(s: &'a str) -> Result<String, Error<Token, Token>> {
+fn unquote_string(s: &str) -> Result<Cow<'_, str>, Error<'_>> {
+    let inner = &s[1..s.len() - 1];
+    if !inner.contains('\\') {
+        return Ok(Cow::Borrowed(inner));
+    }
     let mut res = String::with_capacity(s.len());
+    unescape(s, Some(&mut res))?;
+    Ok(Cow::Owned(res))
+}
+
+/// Checks escape sequences of a string literal without decoding it
+fn validate_string(s: &str) -> Result<(), Error<'_>> {
+    unescape(s, None)
+}
+
+/// Decodes escape sequences of a quoted string, pushing the characters to
+/// `res` if it is given
+fn unescape<'a>(s: &'a str, mut res: Option<&mut String>) -> Result<(), Error<'a>> {
     debug_assert!(s.starts_with('"') && s.ends_with('"'));
     let mut chars = s[1..s.len() - 1].chars();
-    let mut temp_code_point = String::with_capacity(4);
+    let mut push = |c: char| {
+        if let Some(ref mut res) = res {
+            res.push(c);
+        }
+    };
     while let Some(c) = chars.next() {
         match c {
             '\\' => {
                 match chars.next().expect("slash cant be at the end") {
-                    c @ '"' | c @ '\\' | c @ '/' => res.push(c),
-                    'b' => res.push('\u{0010}'),
-                    'f' => res.push('\u{000C}'),
-                    'n' => res.push('\n'),
-                    'r' => res.push('\r'),
-                    't' => res.push('\t'),
+                    c @ '"' | c @ '\\' | c @ '/' => push(c),
+                    'b' => push('\u{0010}'),
+                    'f' => push('\u{000C}'),
+                    'n' => push('\n'),
+                    'r' => push('\r'),
+                    't' => push('\t'),
                     'u' => {
-                        temp_code_point.clear();
-                        for _ in 0..4 {
-                            match chars.next() {
-                                Some(inner_c) => temp_code_point.push(inner_c),
-                                None => {
-                                    return Err(Error::unexpected_message(format_args!(
-                                        "\\u must have 4 characters after it, only found '{}'",
-                                        temp_code_point
-                                    )))
-                                }
-                            }
+                        let rest = chars.as_str();
+                        let len = rest
+                            .char_indices()
+                            .nth(4)
+                            .map_or(rest.len(), |(idx, _)| idx);
+                        let code_point = &rest[..len];
+                        if code_point.chars().count() < 4 {
+                            return Err(Error::Unexpected(Info::text(
+                                ErrorCode::InvalidEscape,
+                                format!(
+                                    "\\u must have 4 characters after it, only found '{}'",
+                                    code_point
+                                ),
+                            )));
                         }
+                        chars = rest[len..].chars();
 
                         // convert our hex string into a u32, then convert that into a char
-                        match u32::from_str_radix(&temp_code_point, 16).map(std::char::from_u32) {
-                            Ok(Some(unicode_char)) => res.push(unicode_char),
+                        match u32::from_str_radix(code_point, 16).map(std::char::from_u32) {
+                            Ok(Some(unicode_char)) => push(unicode_char),
                             _ => {
-                                return Err(Error::unexpected_message(format_args!(
-                                    "{} is not a valid unicode code point",
-                                    temp_code_point
+                                return Err(Error::Unexpected(Info::text(
+                                    ErrorCode::InvalidEscape,
+                                    format!("{} is not a valid unicode code point", code_point),
                                 )))
                             }
                         }
                     }
                     c => {
-                        return Err(Error::unexpected_message(format_args!(
-                            "bad escaped char {:?}",
-                            c
+                        return Err(Error::Unexpected(Info::text(
+                            ErrorCode::InvalidEscape,
+                            format!("bad escaped char {:?}", c),
                         )));
                     }
                 }
             }
-            c => res.push(c),
+            c => push(c),
         }
     }
 
-    Ok(res)
+    Ok(())
 }
 
 pub fn string<'a>(input: &mut TokenStream<'a>) -> ParseResult<String, TokenStream<'a>> {
     choice((
-        kind(T::StringValue).and_then(|tok| unquote_string(tok.value)),
-        kind(T::BlockString).and_then(|tok| unquote_block_string(tok.value)),
+        kind(T::StringValue).and_then(|tok| unquote_string(tok.value).map(Cow::into_owned)),
+        kind(T::BlockString).map(|tok| unquote_block_string(tok.value).into_owned()),
     ))
     .parse_stream(input)
 }
@@ -308,8 +1971,8 @@ where
     S: Text<'a>,
 {
     kind(T::StringValue)
-        .and_then(|tok| unquote_string(tok.value))
-        .map(Value::String)
+        .and_then(|tok| validate_string(tok.value).map(|()| tok))
+        .map(|tok| Value::String(StringValue::from_raw(tok.value, false)))
         .parse_stream(input)
 }
 
@@ -320,8 +1983,7 @@ where
     S: Text<'a>,
 {
     kind(T::BlockString)
-        .and_then(|tok| unquote_block_string(tok.value))
-        .map(Value::String)
+        .map(|tok| Value::String(StringValue::from_raw(tok.value, true)))
         .parse_stream(input)
 }
 
@@ -334,29 +1996,97 @@ where
         .or(ident("false").map(|_| Value::Boolean(false)))
         .or(ident("null").map(|_| Value::Null))
         .or(name::<'a, T>().map(Value::Enum))
-        .or(parser(int_value))
-        .or(parser(float_value))
-        .or(parser(bigint_value))
-        .or(parser(string_value))
-        .or(parser(block_string_value))
+        .or(rule("int_value", int_value))
+        .or(rule("float_value", float_value))
+        .or(rule("bigint_value", bigint_value))
+        .or(rule("string_value", string_value))
+        .or(rule("block_string_value", block_string_value))
         .parse_stream(input)
 }
 
+/// Parts of a value, which the value grammar combines with its span
+pub(crate) enum ValueParts<'a, T: Text<'a>, V> {
+    /// A value without nested values, or a variable
+    Plain(Value<'a, T>),
+    List(Vec<V>),
+    /// Fields along with the spans of their names
+    Object(Vec<(T::Value, Span, V)>),
+}
+
+/// Value built by the value grammar
+///
+/// The grammar is shared by `Value` and by values which keep the span of
+/// every nested value.
+pub(crate) trait ValueNode<'a, T: Text<'a>>: Sized {
+    fn build(parts: ValueParts<'a, T, Self>, span: Span) -> Self;
+}
+
+impl<'a, T: Text<'a>> ValueNode<'a, T> for Value<'a, T> {
+    fn build(parts: ValueParts<'a, T, Self>, _span: Span) -> Self {
+        match parts {
+            ValueParts::Plain(value) => value,
+            ValueParts::List(items) => Value::List(items),
+            ValueParts::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, _, value)| (name, value))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parts of a type reference, which the type grammar combines with its
+/// span
+pub(crate) enum TypeParts<'a, T: Text<'a>, N> {
+    Named(T::Value),
+    List(N),
+    NonNull(N),
+}
+
+/// Type reference built by the type grammar
+pub(crate) trait TypeNode<'a, T: Text<'a>>: Sized {
+    fn build(parts: TypeParts<'a, T, Self>, span: Span) -> Self;
+}
+
+impl<'a, T: Text<'a>> TypeNode<'a, T> for Type<'a, T> {
+    fn build(parts: TypeParts<'a, T, Self>, _span: Span) -> Self {
+        match parts {
+            TypeParts::Named(name) => Type::NamedType(name),
+            TypeParts::List(inner) => Type::ListType(Box::new(inner)),
+            TypeParts::NonNull(inner) => Type::NonNullType(Box::new(inner)),
+        }
+    }
+}
+
 pub fn value<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<Value<'a, T>, TokenStream<'a>>
 where
     T: Text<'a>,
 {
-    parser(plain_value)
+    value_node(input)
+}
+
+pub(crate) fn value_node<'a, T, V>(input: &mut TokenStream<'a>) -> ParseResult<V, TokenStream<'a>>
+where
+    T: Text<'a>,
+    V: ValueNode<'a, T>,
+{
+    let start = input.position();
+    let (parts, consumed) = rule("plain_value", plain_value)
         .or(punct("$").with(name::<'a, T>()).map(Value::Variable))
+        .map(ValueParts::Plain)
         .or(punct("[")
-            .with(many(parser(value)))
+            .with(many(rule("value", value_node::<T, V>)))
             .skip(punct("]"))
-            .map(Value::List))
-        .or(punct("{")
-            .with(many(name::<'a, T>().skip(punct(":")).and(parser(value))))
-            .skip(punct("}"))
-            .map(Value::Object))
-        .parse_stream(input)
+            .map(ValueParts::List))
+        .or(punct("{").with(rule("object_fields", |input| {
+            object_fields(input, "value", value_node::<T, V>)
+        })))
+        .parse_stream(input)?;
+    Ok((
+        V::build(parts, Span::new(start, input.last_end())),
+        consumed,
+    ))
 }
 
 pub fn default_value<'a, T>(
@@ -365,45 +2095,96 @@ pub fn default_value<'a, T>(
 where
     T: Text<'a>,
 {
-    parser(plain_value)
+    default_value_node(input)
+}
+
+pub(crate) fn default_value_node<'a, T, V>(
+    input: &mut TokenStream<'a>,
+) -> ParseResult<V, TokenStream<'a>>
+where
+    T: Text<'a>,
+    V: ValueNode<'a, T>,
+{
+    let start = input.position();
+    let (parts, consumed) = rule("plain_value", plain_value)
+        .map(ValueParts::Plain)
         .or(punct("[")
-            .with(many(parser(default_value)))
+            .with(many(rule("default_value", default_value_node::<T, V>)))
             .skip(punct("]"))
-            .map(Value::List))
-        .or(punct("{")
-            .with(many(
-                name::<'a, T>().skip(punct(":")).and(parser(default_value)),
-            ))
-            .skip(punct("}"))
-            .map(Value::Object))
-        .parse_stream(input)
+            .map(ValueParts::List))
+        .or(punct("{").with(rule("object_fields", |input| {
+            object_fields(input, "default_value", default_value_node::<T, V>)
+        })))
+        .parse_stream(input)?;
+    Ok((
+        V::build(parts, Span::new(start, input.last_end())),
+        consumed,
+    ))
+}
+
+/// Parses the fields of an object value after the opening brace, warning
+/// about fields which are repeated
+fn object_fields<'a, T, V>(
+    input: &mut TokenStream<'a>,
+    rule_name: &'static str,
+    field_value: fn(&mut TokenStream<'a>) -> ParseResult<V, TokenStream<'a>>,
+) -> ParseResult<ValueParts<'a, T, V>, TokenStream<'a>>
+where
+    T: Text<'a>,
+{
+    let (fields, consumed): (Vec<_>, _) = many(
+        (position(), name::<'a, T>(), end_position())
+            .skip(punct(":"))
+            .and(rule(rule_name, field_value))
+            .map(|((start, name, end), value)| (name, Span::new(start, end), value)),
+    )
+    .skip(punct("}"))
+    .parse_stream(input)?;
+    warn_repeated(input, &fields, MessageKey::DuplicateObjectField);
+    Ok((ValueParts::Object(fields), consumed))
 }
 
 pub fn parse_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<Type<'a, T>, TokenStream<'a>>
 where
     T: Text<'a>,
 {
-    name::<'a, T>()
-        .map(Type::NamedType)
-        .or(punct("[")
-            .with(parser(parse_type))
+    type_node(input)
+}
+
+/// Parses a type reference, the span of a non-null type includes the `!`
+pub(crate) fn type_node<'a, T, N>(input: &mut TokenStream<'a>) -> ParseResult<N, TokenStream<'a>>
+where
+    T: Text<'a>,
+    N: TypeNode<'a, T>,
+{
+    let start = input.position();
+    let ((parts, end, bang), consumed) = (
+        name::<'a, T>().map(TypeParts::Named).or(punct("[")
+            .with(rule("parse_type", type_node::<T, N>))
             .skip(punct("]"))
-            .map(Box::new)
-            .map(Type::ListType))
-        .and(optional(punct("!")).map(|v| v.is_some()))
-        .map(|(typ, strict)| {
-            if strict {
-                Type::NonNullType(Box::new(typ))
-            } else {
-                typ
-            }
-        })
-        .parse_stream(input)
+            .map(TypeParts::List)),
+        end_position(),
+        optional(punct("!").with(end_position())),
+    )
+        .parse_stream(input)?;
+    let typ = N::build(parts, Span::new(start, end));
+    let typ = match bang {
+        Some(bang_end) => N::build(TypeParts::NonNull(typ), Span::new(start, bang_end)),
+        None => typ,
+    };
+    Ok((typ, consumed))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::unquote_string;
+    use std::borrow::Cow;
+
+    use std::convert::TryFrom;
+
+    use super::{
+        unquote_block_string, unquote_string, BigNumber, ConstValue, FloatNumber, Number,
+        StringValue, Type, Value, VariableInConstValue,
+    };
 
     #[test]
     fn unquote_unicode_string() {
@@ -420,4 +2201,222 @@ mod tests {
             "\u{0009} hello \u{000A} there"
         );
     }
+
+    #[test]
+    fn lazy_string_value() {
+        let value = StringValue::<&str>::from_raw(r#""a\nb""#, false);
+        assert_eq!(value.raw(), Some(r#""a\nb""#));
+        assert_eq!(value.as_str(), "a\nb");
+        assert_eq!(value.into_static().into_string(), "a\nb");
+
+        let block = StringValue::<String>::from_raw("\"\"\"\n  a\n  b\n\"\"\"", true);
+        assert_eq!(block, "a\nb\n");
+        assert_eq!(StringValue::<String>::new("x").raw(), None);
+    }
+
+    #[test]
+    fn unquote_borrows_without_escapes() {
+        assert!(matches!(
+            unquote_string(r#""hello""#).unwrap(),
+            Cow::Borrowed("hello")
+        ));
+        assert!(matches!(
+            unquote_string(r#""a\nb""#).unwrap(),
+            Cow::Owned(_)
+        ));
+    }
+
+    #[test]
+    fn unquote_block_borrows_without_indent() {
+        let value = unquote_block_string("\"\"\"\nhello\nworld\n\"\"\"");
+        assert!(matches!(value, Cow::Borrowed("hello\nworld\n")));
+        let value = unquote_block_string("\"\"\"\n    hello\n    \"\"\"");
+        assert!(matches!(value, Cow::Borrowed("hello\n")));
+        let value = unquote_block_string("\"\"\"\n  hello\n  world\n\"\"\"");
+        assert!(matches!(value, Cow::Owned(ref v) if v == "hello\nworld\n"));
+        let value = unquote_block_string("\"\"\"hello\"\"\"");
+        assert!(matches!(value, Cow::Owned(ref v) if v == "hello\n"));
+        let value = unquote_block_string(
+            r##""""
+a \""" b
+""""##,
+        );
+        assert!(matches!(value, Cow::Owned(ref v) if v == "a \"\"\" b\n"));
+    }
+
+    #[test]
+    fn number_conversions() {
+        assert_eq!(Number::from(-3i32), "-3".parse().unwrap());
+        assert_eq!(Number::from(3u32).to_string(), "3");
+        assert_eq!(Number::from(i64::MIN).as_i64(), i64::MIN);
+        assert!(Number::try_from(u64::MAX).is_err());
+        assert_eq!(Number::try_from(-5i128).unwrap(), Number(-5));
+        assert!(Number::try_from(u128::MAX).is_err());
+        assert!("1.5".parse::<Number>().is_err());
+        assert_eq!(Number(-2).as_f64(), -2.0);
+
+        let big: BigNumber = "-170141183460469231731687303715884105728".parse().unwrap();
+        assert_eq!(big, BigNumber::from(i128::MIN));
+        assert_eq!(big.to_string(), "-170141183460469231731687303715884105728");
+        assert_eq!(BigNumber::from(Number(7)), BigNumber::from(7u32));
+        assert_eq!(BigNumber::from(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(BigNumber::from(-1i32).as_f64(), -1.0);
+        assert_eq!(BigNumber::from(u128::MAX).as_u128(), u128::MAX);
+        assert_eq!(BigNumber::from(u128::MAX).as_i128(), None);
+        assert_eq!(BigNumber::from(i128::MIN).as_i128(), Some(i128::MIN));
+        assert_eq!(BigNumber::from(-0i128), BigNumber::from(0u128));
+        assert!("-+1".parse::<BigNumber>().is_err());
+        assert!("x".parse::<BigNumber>().is_err());
+    }
+
+    #[test]
+    fn value_constructors() {
+        assert_eq!(Value::<&str>::from(true), Value::Boolean(true));
+        assert_eq!(Value::<&str>::from(-3), Value::Int(Number::from(-3)));
+        assert_eq!(Value::<&str>::from(0.5), Value::Float(0.5.into()));
+        assert_eq!(Value::<&str>::from("a"), Value::String("a".into()));
+        assert_eq!(
+            Value::<String>::from(String::from("a")),
+            Value::String("a".into())
+        );
+        assert_eq!(
+            Value::<&str>::from(vec![Value::Null, 1.into()]),
+            Value::List(vec![Value::Null, Value::Int(Number::from(1))])
+        );
+
+        let object: Value<&str> = object! {
+            "b" => vec![Value::from(1)],
+            "a" => object! {},
+        };
+        let expected = vec![
+            ("b", Value::List(vec![Value::Int(Number::from(1))])),
+            ("a", Value::Object(Vec::new())),
+        ];
+        assert_eq!(object, Value::Object(expected));
+    }
+
+    #[test]
+    fn value_accessors() {
+        let value = crate::parse_value::<&str>(
+            r#"{s: "a\nb", i: -2, f: 1.5, b: true, e: RED, v: $x, l: [null], o: {n: 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(value.get("s").and_then(|v| v.as_str()), Some("a\nb"));
+        assert_eq!(value.get("i").and_then(|v| v.as_i64()), Some(-2));
+        assert_eq!(value.get("i").and_then(|v| v.as_f64()), Some(-2.0));
+        assert_eq!(value.get("f").and_then(|v| v.as_f64()), Some(1.5));
+        assert_eq!(value.get("f").and_then(|v| v.as_i64()), None);
+        assert_eq!(value.get("b").and_then(|v| v.as_bool()), Some(true));
+        assert_eq!(value.get("e").and_then(|v| v.as_enum()), Some("RED"));
+        assert_eq!(value.get("e").and_then(|v| v.as_str()), None);
+        assert_eq!(value.get("v").and_then(|v| v.as_variable()), Some("x"));
+        assert!(value.get("l").and_then(|v| v.as_list()).unwrap()[0].is_null());
+        assert_eq!(
+            value.get_path(&["o", "n"]).and_then(|v| v.as_i64()),
+            Some(1)
+        );
+        assert_eq!(value.get_path(&["o", "m"]), None);
+        assert_eq!(value.get_path(&["s", "n"]), None);
+        assert_eq!(value.get_path::<&str>(&[]), Some(&value));
+        assert_eq!(value.as_object().map(|o| o.len()), Some(8));
+        assert_eq!(value.get("x"), None);
+    }
+
+    #[test]
+    fn const_values() {
+        let value = crate::parse_value::<&str>("{a: [1, RED, \"s\"], b: {c: null}}").unwrap();
+        let constant = ConstValue::try_from(value.clone()).unwrap();
+        assert_eq!(constant.to_string(), value.to_string());
+        assert_eq!(Value::from(constant), value);
+
+        let value = crate::parse_value::<&str>("{a: [1, {b: $x}], c: $y}").unwrap();
+        assert_eq!(
+            ConstValue::try_from(value),
+            Err(VariableInConstValue { name: "x".into() })
+        );
+    }
+
+    #[test]
+    fn type_helpers() {
+        let named = || Box::new(Type::<&str>::NamedType("Foo"));
+        // [[Foo!]]!
+        let ty = Type::NonNullType(Box::new(Type::ListType(Box::new(Type::ListType(
+            Box::new(Type::NonNullType(named())),
+        )))));
+        assert_eq!(ty.innermost_name(), "Foo");
+        assert!(ty.is_non_null());
+        assert!(ty.is_list());
+        assert!(ty.nullable().is_list());
+        assert!(!ty.nullable().is_non_null());
+        assert_eq!(ty.list_depth(), 2);
+        assert!(ty.wraps(&Type::NonNullType(named())));
+        assert!(ty.wraps(&Type::NamedType("Foo")));
+        assert!(ty.wraps(ty.nullable()));
+        assert!(!ty.wraps(&ty));
+        assert!(!ty.wraps(&Type::NamedType("Bar")));
+        assert!(!ty.wraps(&Type::ListType(named())));
+
+        let ty = Type::<&str>::NamedType("Foo");
+        assert!(!ty.is_list() && !ty.is_non_null());
+        assert_eq!(ty.list_depth(), 0);
+        assert!(!ty.wraps(&ty));
+        assert_eq!(ty.nullable().innermost_name(), "Foo");
+    }
+
+    #[test]
+    fn type_from_str() {
+        let ty: Type<String> = " [[Foo!]]! ".parse().unwrap();
+        assert_eq!(ty.to_string(), "[[Foo!]]!");
+        assert_eq!(ty.list_depth(), 2);
+        let ty = Type::try_from("Bar").unwrap();
+        assert_eq!(ty, Type::NamedType("Bar".to_string()));
+        for invalid in ["", "[Foo", "Foo!!", "Foo Bar", "!Foo", "[]"] {
+            assert!(invalid.parse::<Type<String>>().is_err(), "{:?}", invalid);
+        }
+        let err = Type::try_from("[Foo").unwrap_err();
+        assert_eq!(err.position().column, 5);
+    }
+
+    #[test]
+    fn hash_and_ord() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let values = [
+            "[1, 2]",
+            "{a: 1.0}",
+            "{a: 1.00}",
+            "\"x\"",
+            "\"\\u0078\"",
+            "-0.0",
+            "0.0",
+        ]
+        .iter()
+        .map(|v| crate::parse_value::<&str>(v).unwrap())
+        .collect::<Vec<_>>();
+        let unique = values.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 5);
+        let sorted = values.iter().collect::<BTreeSet<_>>();
+        assert_eq!(sorted.len(), 5);
+
+        let mut types = ["[A]", "A!", "B", "A"]
+            .iter()
+            .map(|t| crate::parse_type::<&str>(t).unwrap())
+            .collect::<Vec<_>>();
+        types.sort();
+        let types = types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(types, ["A", "B", "[A]", "A!"]);
+
+        let nan = FloatNumber::<&str>::new(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert!(FloatNumber::<&str>::new(-0.0) < FloatNumber::new(0.0));
+        assert!(BigNumber::from(-1) < BigNumber::from(i128::MAX));
+        #[cfg(feature = "num-bigint")]
+        {
+            let huge: BigNumber = "-1000000000000000000000000000000000000000000"
+                .parse()
+                .unwrap();
+            assert!(huge < BigNumber::from(i128::MIN));
+            assert!(BigNumber::from(i128::MAX) < BigNumber::from(-huge.to_bigint()));
+        }
+    }
 }
@@ -0,0 +1,146 @@
+//! Bounded collection of diagnostics
+//!
+//! Pathological inputs may trigger the same diagnostic thousands of times.
+//! [`Diagnostics`](struct.Diagnostics.html) keeps only the first few of them
+//! and counts the rest, so a summarizing "and N more" entry can be reported
+//! instead.
+
+/// Default maximum number of diagnostics reported for a single document
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// A list of diagnostics holding at most `limit` items
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics<T> {
+    items: Vec<T>,
+    limit: usize,
+    omitted: usize,
+    first_omitted: Option<T>,
+}
+
+impl<T> Diagnostics<T> {
+    pub fn new(limit: usize) -> Diagnostics<T> {
+        Diagnostics {
+            items: Vec::new(),
+            limit,
+            omitted: 0,
+            first_omitted: None,
+        }
+    }
+
+    /// Creates an empty list storing diagnostics in `buffer`
+    ///
+    /// Any items in the buffer are dropped, its allocation is kept.
+    pub(crate) fn with_buffer(mut buffer: Vec<T>, limit: usize) -> Diagnostics<T> {
+        buffer.clear();
+        Diagnostics {
+            items: buffer,
+            limit,
+            omitted: 0,
+            first_omitted: None,
+        }
+    }
+
+    /// Adds a diagnostic, returns `false` if it was omitted due to the limit
+    pub fn push(&mut self, item: T) -> bool {
+        if self.items.len() < self.limit {
+            self.items.push(item);
+            return true;
+        }
+        if self.first_omitted.is_none() {
+            self.first_omitted = Some(item);
+        }
+        self.omitted += 1;
+        false
+    }
+
+    /// Returns the maximum number of diagnostics kept
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the number of diagnostics kept
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.omitted == 0
+    }
+
+    /// Returns the number of diagnostics dropped because of the limit
+    pub fn omitted(&self) -> usize {
+        self.omitted
+    }
+
+    /// Returns the diagnostics kept
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Converts into a plain list
+    ///
+    /// If any diagnostics were omitted, `marker` is called with the first
+    /// omitted diagnostic and the total number of omitted ones, and its
+    /// result is appended to the list.
+    pub fn into_vec_with<F>(self, marker: F) -> Vec<T>
+    where
+        F: FnOnce(T, usize) -> T,
+    {
+        let mut items = self.items;
+        if let Some(first) = self.first_omitted {
+            items.push(marker(first, self.omitted));
+        }
+        items
+    }
+
+    /// Returns the state to pass to `rewind` in order to discard
+    /// diagnostics pushed after this call
+    pub(crate) fn mark(&self) -> (usize, usize) {
+        (self.items.len(), self.omitted)
+    }
+
+    pub(crate) fn rewind(&mut self, (len, omitted): (usize, usize)) {
+        self.items.truncate(len);
+        self.omitted = omitted;
+        if omitted == 0 {
+            self.first_omitted = None;
+        }
+    }
+}
+
+impl<T> Default for Diagnostics<T> {
+    fn default() -> Diagnostics<T> {
+        Diagnostics::new(DEFAULT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Diagnostics;
+
+    #[test]
+    fn limit() {
+        let mut diag = Diagnostics::new(2);
+        assert!(diag.push(1));
+        assert!(diag.push(2));
+        assert!(!diag.push(3));
+        assert!(!diag.push(4));
+        assert_eq!(diag.len(), 2);
+        assert_eq!(diag.omitted(), 2);
+        assert_eq!(
+            diag.into_vec_with(|first, n| first * 100 + n),
+            vec![1, 2, 302]
+        );
+    }
+
+    #[test]
+    fn rewind() {
+        let mut diag = Diagnostics::new(1);
+        diag.push(1);
+        let mark = diag.mark();
+        diag.push(2);
+        diag.rewind(mark);
+        assert_eq!(diag.omitted(), 0);
+        assert_eq!(diag.into_vec_with(|_, _| unreachable!()), vec![1]);
+    }
+}
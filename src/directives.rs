@@ -0,0 +1,280 @@
+//! Typed access to the directives defined by the specification
+//!
+//! Every node which accepts directives implements
+//! [`HasDirectives`](trait.HasDirectives.html), which decodes the
+//! arguments of `@skip`, `@include`, `@deprecated` and `@specifiedBy`.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::directives::HasDirectives;
+//! use graphql_parser::parse_schema;
+//! use graphql_parser::schema::{Definition, TypeDefinition};
+//!
+//! let schema = parse_schema::<&str>(r#"
+//!     type User { name: String @deprecated(reason: "Use `fullName`.") }
+//! "#).unwrap();
+//! if let Definition::TypeDefinition(TypeDefinition::Object(ref user)) = schema.definitions[0] {
+//!     let deprecated = user.fields[0].deprecated().unwrap();
+//!     assert_eq!(deprecated.reason(), "Use `fullName`.");
+//! }
+//! ```
+use crate::common::{Directive, Text, Value};
+use crate::{query, schema};
+
+/// Reason of `@deprecated` when none is given
+pub const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// The `if` argument of `@skip` or `@include`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition<'d> {
+    Literal(bool),
+    /// Name of the variable, without the `$`
+    Variable(&'d str),
+}
+
+impl<'d> Condition<'d> {
+    /// Returns the value of the condition, looking up variables with
+    /// `variable`
+    pub fn evaluate<F>(&self, variable: F) -> Option<bool>
+    where
+        F: FnOnce(&str) -> Option<bool>,
+    {
+        match *self {
+            Condition::Literal(b) => Some(b),
+            Condition::Variable(name) => variable(name),
+        }
+    }
+}
+
+/// The `@skip` directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Skip<'d> {
+    pub condition: Condition<'d>,
+}
+
+/// The `@include` directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Include<'d> {
+    pub condition: Condition<'d>,
+}
+
+/// The `@deprecated` directive
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Deprecated {
+    /// The `reason` argument, `None` if it isn't given
+    pub reason: Option<String>,
+}
+
+impl Deprecated {
+    /// Returns the reason, or the default of the argument if it isn't given
+    pub fn reason(&self) -> &str {
+        self.reason.as_deref().unwrap_or(DEFAULT_DEPRECATION_REASON)
+    }
+}
+
+/// The `@specifiedBy` directive
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpecifiedBy {
+    pub url: String,
+}
+
+fn condition<'d, 'a, T: Text<'a>>(directive: &'d Directive<'a, T>) -> Option<Condition<'d>> {
+    match directive.argument("if")? {
+        Value::Boolean(b) => Some(Condition::Literal(*b)),
+        Value::Variable(name) => Some(Condition::Variable(name.as_ref())),
+        _ => None,
+    }
+}
+
+/// A node which accepts directives
+///
+/// The typed accessors return `None` both when the directive is absent and
+/// when its arguments don't match the definition of the directive, like
+/// `@skip(if: "yes")`. Reporting such directives is left to validation.
+pub trait HasDirectives<'a, T: Text<'a>> {
+    /// Returns the directives applied to the node
+    fn directives(&self) -> &[Directive<'a, T>];
+
+    /// Returns the first directive with the name
+    fn directive(&self, name: &str) -> Option<&Directive<'a, T>> {
+        self.directives().iter().find(|d| d.name.as_ref() == name)
+    }
+
+    /// Returns the `@skip` directive
+    fn skip<'d>(&'d self) -> Option<Skip<'d>>
+    where
+        'a: 'd,
+    {
+        let condition = condition(self.directive("skip")?)?;
+        Some(Skip { condition })
+    }
+
+    /// Returns the `@include` directive
+    fn include<'d>(&'d self) -> Option<Include<'d>>
+    where
+        'a: 'd,
+    {
+        let condition = condition(self.directive("include")?)?;
+        Some(Include { condition })
+    }
+
+    /// Returns the `@deprecated` directive
+    fn deprecated(&self) -> Option<Deprecated> {
+        let reason = match self.directive("deprecated")?.argument("reason") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(reason)) => Some(reason.as_str().to_string()),
+            Some(_) => return None,
+        };
+        Some(Deprecated { reason })
+    }
+
+    /// Returns the `@specifiedBy` directive
+    fn specified_by(&self) -> Option<SpecifiedBy> {
+        match self.directive("specifiedBy")?.argument("url")? {
+            Value::String(url) => Some(SpecifiedBy {
+                url: url.as_str().to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> HasDirectives<'a, T> for [Directive<'a, T>] {
+    fn directives(&self) -> &[Directive<'a, T>] {
+        self
+    }
+}
+
+macro_rules! impl_has_directives {
+    ($($module:ident::$name:ident),* $(,)?) => {
+        $(
+            impl<'a, T: Text<'a>> HasDirectives<'a, T> for $module::$name<'a, T> {
+                fn directives(&self) -> &[Directive<'a, T>] {
+                    &self.directives
+                }
+            }
+        )*
+    };
+}
+
+impl_has_directives!(
+    query::Query,
+    query::Mutation,
+    query::Subscription,
+    query::FragmentDefinition,
+    query::Field,
+    query::FragmentSpread,
+    query::InlineFragment,
+    schema::SchemaDefinition,
+    schema::ScalarType,
+    schema::ScalarTypeExtension,
+    schema::ObjectType,
+    schema::ObjectTypeExtension,
+    schema::Field,
+    schema::InputValue,
+    schema::InterfaceType,
+    schema::InterfaceTypeExtension,
+    schema::UnionType,
+    schema::UnionTypeExtension,
+    schema::EnumType,
+    schema::EnumValue,
+    schema::EnumTypeExtension,
+    schema::InputObjectType,
+    schema::InputObjectTypeExtension,
+);
+
+#[cfg(test)]
+mod test {
+    use super::{Condition, Deprecated, HasDirectives, Include, Skip, SpecifiedBy};
+    use crate::parse_schema;
+    use crate::query::{parse_query, Definition, OperationDefinition, Selection};
+    use crate::schema::{self, TypeDefinition};
+
+    #[test]
+    fn query_directives() {
+        let doc =
+            parse_query::<&str>("{ a @skip(if: true) @include(if: $x) b @skip(if: 1) c }").unwrap();
+        let items = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set)) => &set.items,
+            _ => unreachable!(),
+        };
+        let fields = items
+            .iter()
+            .map(|item| match *item {
+                Selection::Field(ref field) => field,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields[0].skip(),
+            Some(Skip {
+                condition: Condition::Literal(true)
+            })
+        );
+        assert_eq!(
+            fields[0].include(),
+            Some(Include {
+                condition: Condition::Variable("x")
+            })
+        );
+        assert_eq!(
+            fields[0]
+                .include()
+                .unwrap()
+                .condition
+                .evaluate(|name| Some(name == "x")),
+            Some(true)
+        );
+        assert_eq!(fields[1].skip(), None);
+        assert!(fields[1].directive("skip").is_some());
+        assert_eq!(fields[2].skip(), None);
+        assert_eq!(
+            fields[0].directives[1].argument("if"),
+            Some(&crate::query::Value::Variable("x"))
+        );
+    }
+
+    #[test]
+    fn schema_directives() {
+        let doc = parse_schema::<&str>(
+            r#"
+            scalar Url @specifiedBy(url: "https://url.spec.whatwg.org/")
+            enum E { A @deprecated B @deprecated(reason: "b") C }
+            "#,
+        )
+        .unwrap();
+        let types = doc
+            .definitions
+            .iter()
+            .map(|d| match *d {
+                schema::Definition::TypeDefinition(ref t) => t,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        match *types[0] {
+            TypeDefinition::Scalar(ref scalar) => assert_eq!(
+                scalar.specified_by(),
+                Some(SpecifiedBy {
+                    url: "https://url.spec.whatwg.org/".into()
+                })
+            ),
+            _ => unreachable!(),
+        }
+        match *types[1] {
+            TypeDefinition::Enum(ref e) => {
+                assert_eq!(e.values[0].deprecated(), Some(Deprecated { reason: None }));
+                assert_eq!(
+                    e.values[0].deprecated().unwrap().reason(),
+                    "No longer supported"
+                );
+                assert_eq!(e.values[1].deprecated().unwrap().reason(), "b");
+                assert_eq!(e.values[2].deprecated(), None);
+                assert_eq!(
+                    e.values[1].directives[..].deprecated().unwrap().reason(),
+                    "b"
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+}
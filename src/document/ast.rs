@@ -0,0 +1,58 @@
+use crate::common::{Comment, Text};
+use crate::query;
+use crate::schema;
+
+/// Root of a document which may contain both executable and type system
+/// definitions
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Document<'a, T: Text<'a>> {
+    pub definitions: Vec<Definition<'a, T>>,
+    /// Comments of the document, see `Parser::preserve_comments`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<Comment<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum Definition<'a, T: Text<'a>> {
+    /// An operation or a fragment
+    Executable(query::Definition<'a, T>),
+    /// A type, directive or schema definition or an extension
+    TypeSystem(schema::Definition<'a, T>),
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the executable definitions of the document
+    pub fn executable_definitions(&self) -> impl Iterator<Item = &query::Definition<'a, T>> {
+        self.definitions.iter().filter_map(|d| match *d {
+            Definition::Executable(ref d) => Some(d),
+            Definition::TypeSystem(_) => None,
+        })
+    }
+
+    /// Returns the type system definitions and extensions of the document
+    pub fn type_system_definitions(&self) -> impl Iterator<Item = &schema::Definition<'a, T>> {
+        self.definitions.iter().filter_map(|d| match *d {
+            Definition::Executable(_) => None,
+            Definition::TypeSystem(ref d) => Some(d),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::error::Errors;
+use crate::message::MessageKey;
+use crate::syntax_error::ParseErrors;
+
+pub type InternalError<'a> = Errors<'a>;
+
+/// Error parsing a document which mixes executable and type system
+/// definitions
+///
+/// The rendered message is meant for people and may change between
+/// releases. Use `code` and `errors` to react to particular errors, the
+/// accessors are those of `ParseErrors`.
+///
+/// With the `serde` feature the error serializes as a list of errors in
+/// the format of a GraphQL response.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct ParseError(ParseErrors);
+
+impl ParseError {
+    /// Combines the first error with the ones found after resuming at the
+    /// following definitions
+    pub(crate) fn with_further(first: InternalError, further: Vec<InternalError>) -> ParseError {
+        ParseError(ParseErrors::new(
+            MessageKey::DocumentParseError,
+            &first,
+            &further,
+        ))
+    }
+}
+
+impl Deref for ParseError {
+    type Target = ParseErrors;
+
+    fn deref(&self) -> &ParseErrors {
+        &self.0
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl<'a> From<InternalError<'a>> for ParseError {
+    fn from(e: InternalError<'a>) -> ParseError {
+        ParseError::with_further(e, Vec::new())
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+use std::io;
+
+use crate::common::Text;
+use crate::format::{Displayable, Formatter, Style};
+
+use crate::document::ast::*;
+
+impl<'a, T> Document<'a, T>
+where
+    T: Text<'a>,
+{
+    /// Format a document according to style
+    pub fn format(&self, style: &Style) -> String {
+        crate::format::to_string(self, style)
+    }
+
+    /// Format a document according to style into `out`, without building
+    /// the whole text in memory first
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        crate::format::format_to(self, style, out)
+    }
+
+    /// Format a document according to style into a file, socket or other
+    /// `io::Write`
+    ///
+    /// The text is written in many small pieces, so unbuffered writers
+    /// should be wrapped in a `BufWriter`.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: W) -> io::Result<()> {
+        crate::format::write_to(self, style, out)
+    }
+
+    /// Format a document on a single line with as little whitespace as
+    /// possible, for persisted queries or sending over the network
+    pub fn format_minified(&self) -> String {
+        crate::format::to_string(self, Style::default().minify(true))
+    }
+}
+
+impl<'a, T> Displayable for Document<'a, T>
+where
+    T: Text<'a>,
+{
+    fn display(&self, f: &mut Formatter) {
+        for item in &self.definitions {
+            item.display(f);
+        }
+    }
+}
+
+impl<'a, T> Displayable for Definition<'a, T>
+where
+    T: Text<'a>,
+{
+    fn display(&self, f: &mut Formatter) {
+        match *self {
+            Definition::Executable(ref d) => d.display(f),
+            Definition::TypeSystem(ref d) => d.display(f),
+        }
+    }
+}
+
+impl_display!('a Document, Definition,);
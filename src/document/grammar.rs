@@ -0,0 +1,159 @@
+use combine::combinator::{eof, many1};
+use combine::stream::Resetable;
+use combine::{ParseResult, Parser};
+
+use crate::common::Text;
+use crate::document::ast::*;
+use crate::document::error::{InternalError, ParseError};
+use crate::helpers::rule;
+use crate::query;
+use crate::recovery;
+use crate::schema;
+use crate::tokenizer::TokenStream;
+use crate::warning::Warning;
+
+pub fn definition<'a, T>(
+    input: &mut TokenStream<'a>,
+) -> ParseResult<Definition<'a, T>, TokenStream<'a>>
+where
+    T: Text<'a>,
+{
+    rule("executable_definition", query::definition)
+        .map(Definition::Executable)
+        .or(rule("type_system_definition", schema::definition).map(Definition::TypeSystem))
+        .parse_stream(input)
+}
+
+/// Parses a document which may contain both executable and type system
+/// definitions and returns an AST
+pub fn parse_document<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    parse_document_with_warnings(s, 0).map(|(doc, _)| doc)
+}
+
+/// Parses a document which may contain both executable and type system
+/// definitions and returns an AST along with non-fatal warnings
+///
+/// At most `max_warnings` warnings are returned. If there are more, the
+/// last entry of the list says how many of them were omitted.
+pub fn parse_document_with_warnings<'a, T>(
+    s: &'a str,
+    max_warnings: usize,
+) -> Result<(Document<'a, T>, Vec<Warning>), ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    tokens.set_warning_limit(max_warnings);
+    let doc = document(&mut tokens)?;
+
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses the whole token stream as a single document
+pub(crate) fn document<'a, T>(tokens: &mut TokenStream<'a>) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let start = tokens.checkpoint();
+    let result = many1(rule("definition", definition))
+        .map(|d| Document {
+            definitions: d,
+            comments: Vec::new(),
+        })
+        .skip(eof())
+        .parse_stream(tokens);
+    let mut doc = match result {
+        Ok((doc, _)) => doc,
+        Err(e) => {
+            let first = e.into_inner().error;
+            tokens.reset(start);
+            let further = recovery::further_errors(tokens, &first, parse_definition::<T>);
+            return Err(ParseError::with_further(first, further));
+        }
+    };
+    doc.comments = tokens.take_comments();
+
+    Ok(doc)
+}
+
+fn parse_definition<'a, T>(
+    tokens: &mut TokenStream<'a>,
+) -> Result<Definition<'a, T>, InternalError<'a>>
+where
+    T: Text<'a>,
+{
+    rule("definition", definition)
+        .parse_stream(tokens)
+        .map(|(definition, _)| definition)
+        .map_err(|e| e.into_inner().error)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_document;
+    use crate::document::Definition;
+    use crate::query;
+    use crate::schema;
+
+    const MIXED: &str = "\
+type Query {
+  user(id: ID!): User
+}
+
+query Q($id: ID!) {
+  user(id: $id) {
+    ...F
+  }
+}
+
+\"A user\"
+type User {
+  name: String
+}
+
+fragment F on User {
+  name
+}
+
+extend type User @key(fields: \"name\")
+";
+
+    #[test]
+    fn mixed() {
+        let doc = parse_document::<String>(MIXED).unwrap();
+        assert_eq!(doc.definitions.len(), 5);
+        assert!(matches!(
+            doc.definitions[1],
+            Definition::Executable(query::Definition::Operation(_))
+        ));
+        assert!(matches!(
+            doc.definitions[2],
+            Definition::TypeSystem(schema::Definition::TypeDefinition(_))
+        ));
+        assert_eq!(doc.executable_definitions().count(), 2);
+        assert_eq!(doc.type_system_definitions().count(), 3);
+        assert_eq!(doc.to_string(), MIXED);
+    }
+
+    #[test]
+    fn shorthand() {
+        let doc = parse_document::<&str>("{ a } scalar Date").unwrap();
+        assert!(matches!(
+            doc.definitions[0],
+            Definition::Executable(query::Definition::Operation(_))
+        ));
+        assert_eq!(doc.type_system_definitions().count(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_document::<&str>("type A { a: }\nquery { b(: 1) }\nscalar S").unwrap_err();
+        assert_eq!(err.positions().len(), 2);
+        assert!(err
+            .to_string()
+            .starts_with("document parse error: Parse error at 1:13\n"));
+    }
+}
@@ -0,0 +1,16 @@
+//! Documents mixing executable and type system definitions
+//!
+//! The specification allows a single document to contain operations,
+//! fragments and type system definitions. Such documents are common in
+//! tooling, for example test fixtures which declare a schema along with
+//! the queries against it.
+mod ast;
+mod error;
+mod format;
+mod grammar;
+
+pub use self::ast::*;
+pub use self::error::ParseError;
+pub(crate) use self::grammar::document;
+pub use self::grammar::{parse_document, parse_document_with_warnings};
+pub use crate::owned_source::document::OwnedDocument;
@@ -0,0 +1,936 @@
+//! Compact dumps of syntax trees for snapshot tests
+//!
+//! The `Debug` output of a document is huge, includes every position and
+//! changes whenever a field is added to the AST. [`Dump`](trait.Dump.html)
+//! turns any node into a [`Tree`](struct.Tree.html) of one-line labels
+//! instead, leaving out positions and comments, which prints as an
+//! indented tree or as an S-expression.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::dump::Dump;
+//! use graphql_parser::parse_query;
+//!
+//! let doc = parse_query::<&str>("query Q($id: ID) { user(id: $id) { name } }").unwrap();
+//! assert_eq!(doc.dump().to_string(), "\
+//! Document
+//!   Query Q
+//!     Variable $id: ID
+//!     Field user
+//!       Argument id: $id
+//!       Field name
+//! ");
+//! assert_eq!(doc.dump().sexp(), "\
+//! (Document
+//!   (Query Q
+//!     (Variable $id: ID)
+//!     (Field user
+//!       (Argument id: $id)
+//!       (Field name))))
+//! ");
+//! ```
+use std::fmt;
+
+use crate::common::{Directive, Text, Type, Value};
+use crate::format::{Formatter, Style};
+use crate::{document, query, schema};
+
+/// A node of a dump: a label and the dumps of the child nodes
+///
+/// Labels are a kind of node followed by its name and other inline parts
+/// as printed in GraphQL, so they never contain a newline. Display prints
+/// one label per line, indented by two spaces per level.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tree {
+    pub label: String,
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    /// Creates a node without children
+    pub fn new<S: Into<String>>(label: S) -> Tree {
+        Tree {
+            label: label.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Prints the tree as an S-expression, one node per line
+    ///
+    /// Labels are printed as they are, so a label with parentheses in a
+    /// string value doesn't read back as the same tree.
+    pub fn sexp(&self) -> String {
+        let mut buf = String::new();
+        self.write_sexp(&mut buf, 0);
+        buf.push('\n');
+        buf
+    }
+
+    fn write_sexp(&self, buf: &mut String, level: usize) {
+        buf.push('(');
+        buf.push_str(&self.label);
+        for child in &self.children {
+            buf.push('\n');
+            buf.push_str(&"  ".repeat(level + 1));
+            child.write_sexp(buf, level + 1);
+        }
+        buf.push(')');
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter, level: usize) -> fmt::Result {
+        writeln!(f, "{:indent$}{}", "", self.label, indent = level * 2)?;
+        for child in &self.children {
+            child.write_indented(f, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+/// Conversion of a syntax tree node into a dump
+///
+/// Positions, spans and comments are left out, everything else is
+/// included in the order of the node, so two nodes have equal dumps
+/// exactly when they are `SemanticEq`, except that floats keep the text
+/// they were parsed from.
+pub trait Dump {
+    fn dump(&self) -> Tree;
+}
+
+fn node<S: Into<String>>(label: S, children: Vec<Tree>) -> Tree {
+    Tree {
+        label: label.into(),
+        children,
+    }
+}
+
+/// Appends `name` to the kind of node if there is a name
+fn label<S: AsRef<str>>(kind: &str, name: Option<S>) -> String {
+    match name {
+        Some(name) => format!("{} {}", kind, name.as_ref()),
+        None => kind.to_string(),
+    }
+}
+
+fn quoted(s: &str) -> String {
+    let style = Style::default();
+    let mut buf = String::new();
+    let mut f = Formatter::inline(&style, &mut buf);
+    f.write_quoted(s);
+    f.finish().expect("writing to a String can't fail");
+    buf
+}
+
+fn description(children: &mut Vec<Tree>, description: &Option<String>) {
+    if let Some(description) = description {
+        children.push(Tree::new(format!("Description {}", quoted(description))));
+    }
+}
+
+fn arguments<'a, T: Text<'a>>(children: &mut Vec<Tree>, arguments: &[(T::Value, Value<'a, T>)]) {
+    children.extend(
+        arguments
+            .iter()
+            .map(|(name, value)| Tree::new(format!("Argument {}: {}", name.as_ref(), value))),
+    );
+}
+
+fn dump_all<N: Dump>(children: &mut Vec<Tree>, nodes: &[N]) {
+    children.extend(nodes.iter().map(Dump::dump));
+}
+
+fn names<'a, T: Text<'a>>(children: &mut Vec<Tree>, kind: &str, names: &[T::Value]) {
+    children.extend(
+        names
+            .iter()
+            .map(|name| Tree::new(format!("{} {}", kind, name.as_ref()))),
+    );
+}
+
+impl<N: Dump + ?Sized> Dump for Box<N> {
+    fn dump(&self) -> Tree {
+        (**self).dump()
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for Type<'a, T> {
+    fn dump(&self) -> Tree {
+        Tree::new(format!("Type {}", self))
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for Value<'a, T> {
+    fn dump(&self) -> Tree {
+        Tree::new(format!("Value {}", self))
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for Directive<'a, T> {
+    fn dump(&self) -> Tree {
+        let Directive {
+            position: _,
+            end: _,
+            name,
+            arguments: args,
+        } = self;
+        let mut children = Vec::new();
+        arguments(&mut children, args);
+        node(format!("Directive @{}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::Document<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::Document {
+            definitions,
+            comments: _,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, definitions);
+        node("Document", children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::Definition<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            query::Definition::Operation(operation) => operation.dump(),
+            query::Definition::Fragment(fragment) => fragment.dump(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::OperationDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            query::OperationDefinition::SelectionSet(set) => set.dump(),
+            query::OperationDefinition::Query(query) => query.dump(),
+            query::OperationDefinition::Mutation(mutation) => mutation.dump(),
+            query::OperationDefinition::Subscription(subscription) => subscription.dump(),
+        }
+    }
+}
+
+fn operation<'a, T: Text<'a>>(
+    kind: &str,
+    name: &Option<T::Value>,
+    variable_definitions: &[query::VariableDefinition<'a, T>],
+    directives: &[Directive<'a, T>],
+    selection_set: &query::SelectionSet<'a, T>,
+) -> Tree {
+    let mut children = Vec::new();
+    dump_all(&mut children, variable_definitions);
+    dump_all(&mut children, directives);
+    dump_all(&mut children, &selection_set.items);
+    node(label(kind, name.as_ref()), children)
+}
+
+impl<'a, T: Text<'a>> Dump for query::Operation<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::Operation {
+            kind,
+            position: _,
+            end: _,
+            name,
+            variable_definitions,
+            directives,
+            selection_set,
+        } = self;
+        let kind = match kind {
+            query::OperationKind::SelectionSet => "SelectionSet",
+            query::OperationKind::Query => "Query",
+            query::OperationKind::Mutation => "Mutation",
+            query::OperationKind::Subscription => "Subscription",
+        };
+        operation(kind, name, variable_definitions, directives, selection_set)
+    }
+}
+
+/// Implements `Dump` for the named operations, which all have the same
+/// fields
+macro_rules! impl_dump_operation {
+    ($($name:ident),*) => {
+        $(
+            impl<'a, T: Text<'a>> Dump for query::$name<'a, T> {
+                fn dump(&self) -> Tree {
+                    let query::$name {
+                        position: _,
+                        end: _,
+                        name,
+                        variable_definitions,
+                        directives,
+                        selection_set,
+                    } = self;
+                    operation(
+                        stringify!($name),
+                        name,
+                        variable_definitions,
+                        directives,
+                        selection_set,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+impl_dump_operation!(Query, Mutation, Subscription);
+
+impl<'a, T: Text<'a>> Dump for query::SelectionSet<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::SelectionSet { span: _, items } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, items);
+        node("SelectionSet", children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::VariableDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::VariableDefinition {
+            position: _,
+            end: _,
+            name,
+            var_type,
+            default_value,
+        } = self;
+        let mut label = format!("Variable ${}: {}", name.as_ref(), var_type);
+        if let Some(default_value) = default_value {
+            label = format!("{} = {}", label, default_value);
+        }
+        Tree::new(label)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::Selection<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            query::Selection::Field(field) => field.dump(),
+            query::Selection::FragmentSpread(spread) => spread.dump(),
+            query::Selection::InlineFragment(fragment) => fragment.dump(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::Field<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::Field {
+            position: _,
+            end: _,
+            alias,
+            name,
+            arguments: args,
+            directives,
+            selection_set,
+        } = self;
+        let label = match alias {
+            Some(alias) => format!("Field {}: {}", alias.as_ref(), name.as_ref()),
+            None => format!("Field {}", name.as_ref()),
+        };
+        let mut children = Vec::new();
+        arguments(&mut children, args);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, &selection_set.items);
+        node(label, children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::FragmentSpread<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::FragmentSpread {
+            position: _,
+            end: _,
+            fragment_name,
+            directives,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        node(
+            format!("FragmentSpread {}", fragment_name.as_ref()),
+            children,
+        )
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::InlineFragment<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::InlineFragment {
+            position: _,
+            end: _,
+            type_condition,
+            directives,
+            selection_set,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        dump_all(&mut children, &selection_set.items);
+        let label = match type_condition {
+            Some(query::TypeCondition::On(on)) => format!("InlineFragment on {}", on.as_ref()),
+            None => "InlineFragment".to_string(),
+        };
+        node(label, children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for query::FragmentDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        let query::FragmentDefinition {
+            position: _,
+            end: _,
+            name,
+            type_condition: query::TypeCondition::On(on),
+            directives,
+            selection_set,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        dump_all(&mut children, &selection_set.items);
+        node(
+            format!("Fragment {} on {}", name.as_ref(), on.as_ref()),
+            children,
+        )
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::Document<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::Document {
+            definitions,
+            comments: _,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, definitions);
+        node("Document", children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::Definition<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            schema::Definition::SchemaDefinition(schema) => schema.dump(),
+            schema::Definition::TypeDefinition(definition) => definition.dump(),
+            schema::Definition::TypeExtension(extension) => extension.dump(),
+            schema::Definition::DirectiveDefinition(directive) => directive.dump(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::SchemaDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::SchemaDefinition {
+            position: _,
+            end: _,
+            directives,
+            query,
+            mutation,
+            subscription,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        for (operation, name) in [
+            ("query", query),
+            ("mutation", mutation),
+            ("subscription", subscription),
+        ] {
+            if let Some(name) = name {
+                children.push(Tree::new(format!("Root {}: {}", operation, name.as_ref())));
+            }
+        }
+        node("Schema", children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::TypeDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            schema::TypeDefinition::Scalar(scalar) => scalar.dump(),
+            schema::TypeDefinition::Object(object) => object.dump(),
+            schema::TypeDefinition::Interface(interface) => interface.dump(),
+            schema::TypeDefinition::Union(union) => union.dump(),
+            schema::TypeDefinition::Enum(enum_type) => enum_type.dump(),
+            schema::TypeDefinition::InputObject(input) => input.dump(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::TypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            schema::TypeExtension::Scalar(scalar) => scalar.dump(),
+            schema::TypeExtension::Object(object) => object.dump(),
+            schema::TypeExtension::Interface(interface) => interface.dump(),
+            schema::TypeExtension::Union(union) => union.dump(),
+            schema::TypeExtension::Enum(enum_type) => enum_type.dump(),
+            schema::TypeExtension::InputObject(input) => input.dump(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::ScalarType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::ScalarType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            directives,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        node(format!("Scalar {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::ScalarTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::ScalarTypeExtension {
+            position: _,
+            end: _,
+            name,
+            directives,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        node(format!("ScalarExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::ObjectType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::ObjectType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            implements_interfaces,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        names::<T>(&mut children, "Implements", implements_interfaces);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("Object {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::ObjectTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::ObjectTypeExtension {
+            position: _,
+            end: _,
+            name,
+            implements_interfaces,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        names::<T>(&mut children, "Implements", implements_interfaces);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("ObjectExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::Field<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::Field {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            arguments,
+            field_type,
+            directives,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, arguments);
+        dump_all(&mut children, directives);
+        node(format!("Field {}: {}", name.as_ref(), field_type), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::InputValue<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::InputValue {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            value_type,
+            default_value,
+            directives,
+        } = self;
+        let mut label = format!("InputValue {}: {}", name.as_ref(), value_type);
+        if let Some(default_value) = default_value {
+            label = format!("{} = {}", label, default_value);
+        }
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        node(label, children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::InterfaceType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::InterfaceType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            implements_interfaces,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        names::<T>(&mut children, "Implements", implements_interfaces);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("Interface {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::InterfaceTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::InterfaceTypeExtension {
+            position: _,
+            end: _,
+            name,
+            implements_interfaces,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        names::<T>(&mut children, "Implements", implements_interfaces);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("InterfaceExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::UnionType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::UnionType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            directives,
+            types,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        names::<T>(&mut children, "Member", types);
+        node(format!("Union {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::UnionTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::UnionTypeExtension {
+            position: _,
+            end: _,
+            name,
+            directives,
+            types,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        names::<T>(&mut children, "Member", types);
+        node(format!("UnionExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::EnumType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::EnumType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            directives,
+            values,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, values);
+        node(format!("Enum {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::EnumValue<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::EnumValue {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            directives,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        node(format!("EnumValue {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::EnumTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::EnumTypeExtension {
+            position: _,
+            end: _,
+            name,
+            directives,
+            values,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        dump_all(&mut children, values);
+        node(format!("EnumExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::InputObjectType<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::InputObjectType {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("InputObject {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::InputObjectTypeExtension<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::InputObjectTypeExtension {
+            position: _,
+            end: _,
+            name,
+            directives,
+            fields,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, directives);
+        dump_all(&mut children, fields);
+        node(format!("InputObjectExtension {}", name.as_ref()), children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for schema::DirectiveDefinition<'a, T> {
+    fn dump(&self) -> Tree {
+        let schema::DirectiveDefinition {
+            position: _,
+            end: _,
+            description: desc,
+            name,
+            arguments,
+            repeatable,
+            locations,
+        } = self;
+        let mut label = format!("DirectiveDefinition @{}", name.as_ref());
+        if *repeatable {
+            label.push_str(" repeatable");
+        }
+        let mut children = Vec::new();
+        description(&mut children, desc);
+        dump_all(&mut children, arguments);
+        children.extend(
+            locations
+                .iter()
+                .map(|location| Tree::new(format!("Location {}", location.as_str()))),
+        );
+        node(label, children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for document::Document<'a, T> {
+    fn dump(&self) -> Tree {
+        let document::Document {
+            definitions,
+            comments: _,
+        } = self;
+        let mut children = Vec::new();
+        dump_all(&mut children, definitions);
+        node("Document", children)
+    }
+}
+
+impl<'a, T: Text<'a>> Dump for document::Definition<'a, T> {
+    fn dump(&self) -> Tree {
+        match self {
+            document::Definition::Executable(definition) => definition.dump(),
+            document::Definition::TypeSystem(definition) => definition.dump(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Dump, Tree};
+    use crate::{parse_document, parse_query, parse_schema};
+
+    #[test]
+    fn dump_query() {
+        let doc = parse_query::<&str>(
+            r#"
+            query Q($id: ID = 1, $f: [Float!]) @op {
+                alias: user(id: $id, obj: {a: [1.0, "s)", E]}) @include(if: true) {
+                    ...F @d
+                    ... on User { name }
+                    ... @skip(if: $s) { id }
+                }
+            }
+            # comments are left out
+            fragment F on User { id }
+            { shorthand }
+            subscription { s }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.dump().to_string(),
+            r#"Document
+  Query Q
+    Variable $id: ID = 1
+    Variable $f: [Float!]
+    Directive @op
+    Field alias: user
+      Argument id: $id
+      Argument obj: {a: [1.0, "s)", E]}
+      Directive @include
+        Argument if: true
+      FragmentSpread F
+        Directive @d
+      InlineFragment on User
+        Field name
+      InlineFragment
+        Directive @skip
+          Argument if: $s
+        Field id
+  Fragment F on User
+    Field id
+  SelectionSet
+    Field shorthand
+  Subscription
+    Field s
+"#
+        );
+        let printed = doc.to_string();
+        let reformatted = parse_query::<&str>(&printed).unwrap();
+        assert_eq!(doc.dump(), reformatted.dump());
+        assert_ne!(
+            doc.dump(),
+            parse_query::<&str>("{ shorthand }").unwrap().dump()
+        );
+    }
+
+    #[test]
+    fn dump_schema() {
+        let doc = parse_schema::<&str>(
+            r#"
+            schema @s { query: Query mutation: Mutation }
+            "Description"
+            type Query implements I @d(x: [1]) {
+                "Field\ndescription"
+                f(a: Int = 1 @deprecated): [I!]!
+            }
+            extend type Query { g: Int }
+            interface I implements J { f(a: Int): [I!]! }
+            extend interface I @d
+            union U @d = Query | Other
+            extend union U = Third
+            enum E { A "B" B @deprecated }
+            extend enum E { C }
+            input In { a: Int = 2 }
+            extend input In @d
+            scalar S
+            extend scalar S @d
+            directive @d(x: [Int]) repeatable on OBJECT | FIELD
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.dump().sexp(),
+            r#"(Document
+  (Schema
+    (Directive @s)
+    (Root query: Query)
+    (Root mutation: Mutation))
+  (Object Query
+    (Description "Description")
+    (Implements I)
+    (Directive @d
+      (Argument x: [1]))
+    (Field f: [I!]!
+      (Description "Field\ndescription")
+      (InputValue a: Int = 1
+        (Directive @deprecated))))
+  (ObjectExtension Query
+    (Field g: Int))
+  (Interface I
+    (Implements J)
+    (Field f: [I!]!
+      (InputValue a: Int)))
+  (InterfaceExtension I
+    (Directive @d))
+  (Union U
+    (Directive @d)
+    (Member Query)
+    (Member Other))
+  (UnionExtension U
+    (Member Third))
+  (Enum E
+    (EnumValue A)
+    (EnumValue B
+      (Description "B")
+      (Directive @deprecated)))
+  (EnumExtension E
+    (EnumValue C))
+  (InputObject In
+    (InputValue a: Int = 2))
+  (InputObjectExtension In
+    (Directive @d))
+  (Scalar S)
+  (ScalarExtension S
+    (Directive @d))
+  (DirectiveDefinition @d repeatable
+    (InputValue x: [Int])
+    (Location OBJECT)
+    (Location FIELD)))
+"#
+        );
+    }
+
+    #[test]
+    fn dump_nodes() {
+        let doc = parse_document::<&str>("type A { a: Int }\n{ a }").unwrap();
+        assert_eq!(
+            doc.dump().to_string(),
+            "Document\n  Object A\n    Field a: Int\n  SelectionSet\n    Field a\n"
+        );
+        let value = crate::parse_value::<&str>("[1, {a: null}]").unwrap();
+        assert_eq!(value.dump(), Tree::new("Value [1, {a: null}]"));
+        let value_type = crate::parse_type::<&str>("[Int!]").unwrap();
+        assert_eq!(value_type.dump().sexp(), "(Type [Int!])\n");
+    }
+}
@@ -0,0 +1,349 @@
+//! Compact error type used internally by the parser
+//!
+//! Every failed alternative records what was expected, even if another
+//! alternative matches later on, so errors are built all the time while
+//! parsing perfectly valid documents. The types here only hold tokens and
+//! static strings in the common case and never box anything. They are
+//! converted into the public `ParseError` once parsing actually fails.
+
+use std::fmt;
+
+use combine::error::{ParseError, StreamError, Tracked};
+
+use crate::message::{Message, MessageKey};
+use crate::position::Pos;
+use crate::syntax_error::ErrorCode;
+use crate::tokenizer::{Kind, Token};
+
+/// Contents of a single error
+#[derive(Debug, Clone)]
+pub enum Info<'a> {
+    Token(Token<'a>),
+    /// Any token of the kind
+    Kind(Kind),
+    Static(&'static str),
+    Owned(Box<str>),
+    /// An error raised by the parser itself, identified by its code and
+    /// the key of its message
+    Raised(ErrorCode, Message),
+}
+
+impl<'a> Info<'a> {
+    /// An error with a message without arguments
+    pub(crate) fn raised(code: ErrorCode, key: MessageKey) -> Info<'a> {
+        Info::Raised(code, Message::new(key))
+    }
+
+    /// An error with a message which isn't translated
+    pub(crate) fn text<S: Into<String>>(code: ErrorCode, text: S) -> Info<'a> {
+        Info::Raised(code, Message::text(text))
+    }
+
+    fn as_text(&self) -> Option<&str> {
+        match *self {
+            Info::Static(text) => Some(text),
+            Info::Owned(ref text) => Some(text),
+            Info::Token(_) | Info::Kind(_) | Info::Raised(..) => None,
+        }
+    }
+}
+
+impl<'a> PartialEq for Info<'a> {
+    fn eq(&self, other: &Info<'a>) -> bool {
+        match (self, other) {
+            (Info::Token(l), Info::Token(r)) => l == r,
+            (Info::Kind(l), Info::Kind(r)) => l == r,
+            (Info::Raised(lc, l), Info::Raised(rc, r)) => lc == rc && l == r,
+            _ => match (self.as_text(), other.as_text()) {
+                (Some(l), Some(r)) => l == r,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl<'a> fmt::Display for Info<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Info::Token(ref token) => write!(f, "{}", token),
+            Info::Kind(kind) => write!(f, "{:?}", kind),
+            Info::Static(text) => f.write_str(text),
+            Info::Owned(ref text) => f.write_str(text),
+            Info::Raised(_, ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error<'a> {
+    Unexpected(Info<'a>),
+    Expected(Info<'a>),
+    Message(Info<'a>),
+}
+
+impl<'a> Error<'a> {
+    /// Returns the text of the error without the kind prefix
+    pub fn message(&self) -> String {
+        match *self {
+            Error::Unexpected(ref info) | Error::Expected(ref info) | Error::Message(ref info) => {
+                info.to_string()
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for Error<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Unexpected(ref info) => write!(f, "Unexpected `{}`", info),
+            Error::Expected(ref info) => write!(f, "Expected `{}`", info),
+            Error::Message(ref info) => write!(f, "{}", info),
+        }
+    }
+}
+
+impl<'a, E> From<E> for Error<'a>
+where
+    E: std::error::Error,
+{
+    fn from(err: E) -> Error<'a> {
+        Error::Message(Info::Owned(err.to_string().into()))
+    }
+}
+
+impl<'a> StreamError<Token<'a>, Token<'a>> for Error<'a> {
+    #[inline]
+    fn end_of_input() -> Self {
+        Error::Unexpected(Info::text(ErrorCode::UnexpectedEndOfInput, "end of input"))
+    }
+    #[inline]
+    fn unexpected_token(token: Token<'a>) -> Self {
+        Error::Unexpected(Info::Token(token))
+    }
+    #[inline]
+    fn unexpected_range(token: Token<'a>) -> Self {
+        Error::Unexpected(Info::Token(token))
+    }
+    #[inline]
+    fn unexpected_message<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Error::Unexpected(Info::Owned(msg.to_string().into()))
+    }
+    #[inline]
+    fn unexpected_static_message(msg: &'static str) -> Self {
+        Error::Unexpected(Info::Static(msg))
+    }
+
+    #[inline]
+    fn expected_token(token: Token<'a>) -> Self {
+        Error::Expected(Info::Token(token))
+    }
+    #[inline]
+    fn expected_range(token: Token<'a>) -> Self {
+        Error::Expected(Info::Token(token))
+    }
+    #[inline]
+    fn expected_message<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Error::Expected(Info::Owned(msg.to_string().into()))
+    }
+    #[inline]
+    fn expected_static_message(msg: &'static str) -> Self {
+        Error::Expected(Info::Static(msg))
+    }
+
+    #[inline]
+    fn message_token(token: Token<'a>) -> Self {
+        Error::Message(Info::Token(token))
+    }
+    #[inline]
+    fn message_range(token: Token<'a>) -> Self {
+        Error::Message(Info::Token(token))
+    }
+    #[inline]
+    fn message_message<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Error::Message(Info::Owned(msg.to_string().into()))
+    }
+    #[inline]
+    fn message_static_message(msg: &'static str) -> Self {
+        Error::Message(Info::Static(msg))
+    }
+
+    fn into_other<T>(self) -> T
+    where
+        T: StreamError<Token<'a>, Token<'a>>,
+    {
+        match self {
+            Error::Unexpected(info) => match info {
+                Info::Token(x) => T::unexpected_token(x),
+                Info::Kind(x) => T::unexpected_message(format_args!("{:?}", x)),
+                Info::Static(x) => T::unexpected_static_message(x),
+                Info::Owned(x) => T::unexpected_message(x),
+                Info::Raised(_, x) => T::unexpected_message(x),
+            },
+            Error::Expected(info) => match info {
+                Info::Token(x) => T::expected_token(x),
+                Info::Kind(x) => T::expected_message(format_args!("{:?}", x)),
+                Info::Static(x) => T::expected_static_message(x),
+                Info::Owned(x) => T::expected_message(x),
+                Info::Raised(_, x) => T::expected_message(x),
+            },
+            Error::Message(info) => match info {
+                Info::Token(x) => T::message_token(x),
+                Info::Kind(x) => T::message_message(format_args!("{:?}", x)),
+                Info::Static(x) => T::message_static_message(x),
+                Info::Owned(x) => T::message_message(x),
+                Info::Raised(_, x) => T::message_message(x),
+            },
+        }
+    }
+}
+
+/// All errors which occurred at the furthest position reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct Errors<'a> {
+    pub position: Pos,
+    pub errors: Vec<Error<'a>>,
+}
+
+impl<'a> Errors<'a> {
+    #[inline]
+    pub fn empty(position: Pos) -> Errors<'a> {
+        Errors {
+            position,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Adds an error unless an equal one is already there
+    pub fn add_error(&mut self, error: Error<'a>) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn retain_unexpected(&mut self, start: usize) {
+        let mut idx = 0;
+        self.errors.retain(|e| {
+            idx += 1;
+            idx <= start || !matches!(*e, Error::Expected(_))
+        });
+    }
+}
+
+impl<'a> ParseError<Token<'a>, Token<'a>, Pos> for Errors<'a> {
+    type StreamError = Error<'a>;
+
+    #[inline]
+    fn empty(position: Pos) -> Self {
+        Errors::empty(position)
+    }
+
+    #[inline]
+    fn from_error(position: Pos, err: Error<'a>) -> Self {
+        Errors {
+            position,
+            errors: vec![err],
+        }
+    }
+
+    #[inline]
+    fn set_position(&mut self, position: Pos) {
+        self.position = position;
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        use std::cmp::Ordering;
+        // Only keep the errors which occurred after consuming the most input
+        match self.position.cmp(&other.position) {
+            Ordering::Less => other,
+            Ordering::Greater => self,
+            Ordering::Equal => {
+                for error in other.errors {
+                    self.add_error(error);
+                }
+                self
+            }
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, err: Error<'a>) {
+        self.add_error(err);
+    }
+
+    fn set_expected<F>(self_: &mut Tracked<Self>, info: Error<'a>, f: F)
+    where
+        F: FnOnce(&mut Tracked<Self>),
+    {
+        let start = self_.error.errors.len();
+        f(self_);
+        // Replace expected errors added by `f` with the single one passed
+        self_.error.retain_unexpected(start);
+        self_.error.add_error(info);
+    }
+
+    fn clear_expected(&mut self) {
+        self.retain_unexpected(0);
+    }
+
+    fn is_unexpected_end_of_input(&self) -> bool {
+        self.errors.contains(&Error::end_of_input())
+    }
+
+    fn into_other<T>(mut self) -> T
+    where
+        T: ParseError<Token<'a>, Token<'a>, Pos>,
+    {
+        match self.errors.pop() {
+            Some(err) => T::from_error(self.position, StreamError::into_other(err)),
+            None => T::empty(self.position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use combine::error::{ParseError, StreamError};
+
+    use super::{Error, Errors, Info};
+    use crate::position::Pos;
+    use crate::tokenizer::Kind;
+
+    #[test]
+    fn merge_deduplicates() {
+        let pos = Pos::new(1, 2, 1);
+        let mut left = Errors::empty(pos);
+        left.add_error(Error::Expected(Info::Kind(Kind::Name)));
+        left.add_error(Error::expected_static_message("query"));
+        let mut right = Errors::empty(pos);
+        right.add_error(Error::expected_message("query"));
+        right.add_error(Error::Expected(Info::Kind(Kind::Name)));
+        right.add_error(Error::end_of_input());
+        let merged = left.merge(right);
+        assert_eq!(merged.errors.len(), 3);
+        assert!(merged.is_unexpected_end_of_input());
+
+        let further = Errors::empty(Pos::new(2, 1, 8));
+        assert_eq!(merged.merge(further.clone()), further);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Error::Expected(Info::Kind(Kind::IntValue)).to_string(),
+            "Expected `IntValue`"
+        );
+        assert_eq!(
+            Error::end_of_input().to_string(),
+            "Unexpected `end of input`"
+        );
+    }
+}
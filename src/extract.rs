@@ -0,0 +1,217 @@
+//! Extraction of GraphQL documents embedded in other languages
+//!
+//! Documents are often written inside source files of other languages,
+//! e.g. in JavaScript tagged templates like ``gql`{ user { name } }` ``.
+//! The extracted documents report positions, both in the AST and in parse
+//! errors, relative to the host file.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::extract::Extractor;
+//!
+//! let source = "const Q = gql`\n  { user { name } }\n`;";
+//! let embedded = Extractor::new().extract(source);
+//! assert_eq!(embedded.len(), 1);
+//! assert_eq!(embedded[0].position.to_string(), "1:15");
+//! let doc = embedded[0].parse_query::<&str>().unwrap();
+//! assert_eq!(doc.to_string(), "{\n  user {\n    name\n  }\n}\n");
+//! ```
+use crate::common::Text;
+use crate::position::Pos;
+use crate::tokenizer::TokenStream;
+use crate::{query, schema};
+
+/// Finds documents between configurable opening and closing markers
+///
+/// The text between the markers is taken verbatim: escape sequences and
+/// interpolations of the host language are not processed.
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    markers: Vec<(String, String)>,
+}
+
+/// A document found in a host file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Embedded<'a> {
+    /// Text of the document, without the markers
+    pub text: &'a str,
+    /// Byte offset of the document in the host file
+    pub offset: usize,
+    /// Position of the document in the host file
+    pub position: Pos,
+}
+
+impl Default for Extractor {
+    /// Recognizes ``gql`...` ``, ``graphql`...` `` and `graphql!("...")`
+    fn default() -> Extractor {
+        let mut extractor = Extractor::empty();
+        extractor
+            .marker("gql`", "`")
+            .marker("graphql`", "`")
+            .marker("graphql!(\"", "\")");
+        extractor
+    }
+}
+
+impl Extractor {
+    /// Creates an extractor recognizing the default markers
+    pub fn new() -> Extractor {
+        Extractor::default()
+    }
+
+    /// Creates an extractor without any markers
+    pub fn empty() -> Extractor {
+        Extractor {
+            markers: Vec::new(),
+        }
+    }
+
+    /// Adds a pair of markers, the document is between `open` and the
+    /// next occurrence of `close`
+    pub fn marker(&mut self, open: &str, close: &str) -> &mut Self {
+        self.markers.push((open.to_string(), close.to_string()));
+        self
+    }
+
+    /// Returns the documents of `source` in the order they appear
+    ///
+    /// An opening marker without a closing one is ignored.
+    pub fn extract<'a>(&self, source: &'a str) -> Vec<Embedded<'a>> {
+        let mut result = Vec::new();
+        let mut cursor = Cursor {
+            source,
+            offset: 0,
+            position: Pos::new(1, 1, 0),
+        };
+        let mut search = 0;
+        loop {
+            let found = self
+                .markers
+                .iter()
+                .filter_map(|(open, close)| {
+                    source[search..]
+                        .find(open.as_str())
+                        .map(|idx| (search + idx, open, close))
+                })
+                .min_by_key(|&(idx, open, _)| (idx, std::cmp::Reverse(open.len())));
+            let (idx, open, close) = match found {
+                Some(found) => found,
+                None => break,
+            };
+            let start = idx + open.len();
+            let end = match source[start..].find(close.as_str()) {
+                Some(len) => start + len,
+                None => {
+                    search = start;
+                    continue;
+                }
+            };
+            result.push(Embedded {
+                text: &source[start..end],
+                offset: start,
+                position: cursor.advance_to(start),
+            });
+            search = end + close.len();
+        }
+        result
+    }
+}
+
+/// Tracks the position while moving forward through the host file
+struct Cursor<'a> {
+    source: &'a str,
+    offset: usize,
+    position: Pos,
+}
+
+impl<'a> Cursor<'a> {
+    fn advance_to(&mut self, offset: usize) -> Pos {
+        for ch in self.source[self.offset..offset].chars() {
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.offset = offset;
+        self.position.offset = offset;
+        self.position
+    }
+}
+
+impl<'a> Embedded<'a> {
+    /// Parses the text as a query, positions are those in the host file
+    pub fn parse_query<S>(&self) -> Result<query::Document<'a, S>, query::ParseError>
+    where
+        S: Text<'a>,
+    {
+        query::document(&mut TokenStream::starting_at(self.text, self.position))
+    }
+
+    /// Parses the text as a schema, positions are those in the host file
+    pub fn parse_schema<T>(&self) -> Result<schema::Document<'a, T>, schema::ParseError>
+    where
+        T: Text<'a>,
+    {
+        schema::document(&mut TokenStream::starting_at(self.text, self.position))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Extractor;
+    use crate::position::Pos;
+    use crate::query::{Definition, OperationDefinition};
+
+    const JS: &str = r#"import gql from "graphql-tag";
+
+const USER = gql`
+  query User { user { name } }
+`;
+const SCHEMA = graphql`type User { name: String }`;
+const BROKEN = gql`{ user(`;
+const UNTERMINATED = gql`{ a }
+"#;
+
+    #[test]
+    fn positions() {
+        let docs = Extractor::new().extract(JS);
+        let texts: Vec<_> = docs.iter().map(|doc| doc.text).collect();
+        assert_eq!(
+            texts,
+            [
+                "\n  query User { user { name } }\n",
+                "type User { name: String }",
+                "{ user(",
+            ]
+        );
+        assert_eq!(&JS[docs[1].offset..][..4], "type");
+
+        let query = docs[0].parse_query::<&str>().unwrap();
+        match query.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => {
+                assert_eq!(q.position, Pos::new(4, 3, 52));
+            }
+            _ => unreachable!(),
+        }
+        assert!(docs[1].parse_schema::<String>().is_ok());
+        let err = docs[2].parse_query::<&str>().unwrap_err().to_string();
+        assert!(
+            err.starts_with("query parse error: Parse error at 7:"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn custom_markers() {
+        let source = "let q = graphql!(\"{ a }\"); /* GraphQL */ `{ b }` gql`{ c }`";
+        let mut extractor = Extractor::empty();
+        extractor
+            .marker("/* GraphQL */ `", "`")
+            .marker("graphql!(\"", "\")");
+        let texts: Vec<_> = extractor.extract(source).iter().map(|d| d.text).collect();
+        assert_eq!(texts, ["{ a }", "{ b }"]);
+    }
+}
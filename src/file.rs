@@ -0,0 +1,118 @@
+//! Parsing documents straight from memory-mapped files
+//!
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::common::Text;
+use crate::{query, schema};
+
+/// Contents of a memory-mapped file
+///
+/// Documents parsed from the file borrow from the mapping, so even very
+/// large schemas are never copied into memory as a whole.
+pub struct SourceFile {
+    map: Mmap,
+}
+
+impl SourceFile {
+    /// Maps the file at `path` into memory
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the file is not valid
+    /// UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other
+    /// process, until the `SourceFile` is dropped. The text is validated
+    /// once, so modified contents may be invalid UTF-8, and reading the
+    /// mapping of a truncated file kills the process with `SIGBUS`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<SourceFile> {
+        let file = File::open(path)?;
+        let map = Mmap::map(&file)?;
+        if let Err(e) = std::str::from_utf8(&map) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        Ok(SourceFile { map })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // validated in `open`, and unchanged as required by it
+        unsafe { std::str::from_utf8_unchecked(&self.map) }
+    }
+}
+
+impl fmt::Debug for SourceFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SourceFile")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+/// Parses a query from a mapped file, the AST borrows from the mapping
+pub fn parse_query_file<'a, S>(
+    file: &'a SourceFile,
+) -> Result<query::Document<'a, S>, query::ParseError>
+where
+    S: Text<'a>,
+{
+    query::parse_query(file.as_str())
+}
+
+/// Parses a schema from a mapped file, the AST borrows from the mapping
+pub fn parse_schema_file<'a, T>(
+    file: &'a SourceFile,
+) -> Result<schema::Document<'a, T>, schema::ParseError>
+where
+    T: Text<'a>,
+{
+    schema::parse_schema(file.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{ErrorKind, Write};
+
+    use super::{parse_query_file, parse_schema_file, SourceFile};
+
+    fn write_file(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "graphql-parser-{}-{}.graphql",
+            std::process::id(),
+            name
+        ));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_mapped() {
+        let path = write_file("schema", b"type Query { users: [User!]! }");
+        let file = unsafe { SourceFile::open(&path) }.unwrap();
+        let ast = parse_schema_file::<&str>(&file).unwrap();
+        assert_eq!(ast.to_string(), "type Query {\n  users: [User!]!\n}\n");
+        assert!(parse_query_file::<&str>(&file).is_err());
+        drop(ast);
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn empty_and_invalid() {
+        let path = write_file("empty", b"");
+        assert_eq!(unsafe { SourceFile::open(&path) }.unwrap().as_str(), "");
+        std::fs::remove_file(path).unwrap();
+
+        let path = write_file("invalid", b"{ a\xff }");
+        let err = unsafe { SourceFile::open(&path) }.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,46 +1,142 @@
 //! Formatting graphql
 use std::default::Default;
+use std::fmt::{self, Write};
+use std::io;
 
 use crate::common::Directive;
 
-#[derive(Debug, PartialEq)]
+/// Prints nodes into a sink, which is a `String`, the `fmt::Formatter` of
+/// a `Display` impl or an adapter of an `io::Write`
+///
+/// Printing doesn't fail, so the first error of the sink is kept and
+/// returned by `finish`, and everything after it is dropped.
 pub(crate) struct Formatter<'a> {
-    buf: String,
+    out: &'a mut dyn Write,
+    result: fmt::Result,
+    empty: bool,
+    /// The last character of the output, to tell where a space is needed
+    /// between tokens when minifying
+    last: char,
     style: &'a Style,
     indent: u32,
+    block_strings: bool,
 }
 
 /// A configuration of formatting style
 ///
-/// Currently we only have indentation configured, other things might be
-/// added later (such as minification).
+/// Indentation, line endings, spacing around colons and the layout of
+/// arguments are configured, or the output is minified. The default
+/// indents by two spaces and ends lines with `\n`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{parse_schema, LineEnding, Style};
+///
+/// let doc = parse_schema::<&str>("type User { name(upper: Boolean): String }").unwrap();
+/// let mut style = Style::default();
+/// style
+///     .indent(1)
+///     .indent_char('\t')
+///     .line_ending(LineEnding::CrLf)
+///     .space_before_colon(true);
+/// assert_eq!(
+///     doc.format(&style),
+///     "type User {\r\n\tname(upper : Boolean) : String\r\n}\r\n",
+/// );
+/// ```
 #[derive(Debug, PartialEq, Clone)]
 pub struct Style {
     indent: u32,
+    indent_char: char,
+    line_ending: LineEnding,
+    space_before_colon: bool,
+    space_after_colon: bool,
     multiline_arguments: bool,
+    minify: bool,
+}
+
+/// The characters ending each line of formatted output
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineEnding {
+    /// `\n`, as on Unix
+    Lf,
+    /// `\r\n`, as on Windows
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
 }
 
 impl Default for Style {
     fn default() -> Style {
         Style {
             indent: 2,
+            indent_char: ' ',
+            line_ending: LineEnding::Lf,
+            space_before_colon: false,
+            space_after_colon: true,
             multiline_arguments: false,
+            minify: false,
         }
     }
 }
 
 impl Style {
-    /// Change the number of spaces used for indentation
+    /// Change the number of characters used for each level of indentation
     pub fn indent(&mut self, indent: u32) -> &mut Self {
         self.indent = indent;
         self
     }
 
+    /// Change the character used for indentation, a space by default
+    ///
+    /// Indenting with tabs usually goes with `indent(1)`.
+    pub fn indent_char(&mut self, indent_char: char) -> &mut Self {
+        self.indent_char = indent_char;
+        self
+    }
+
+    /// Change the line endings, including those of block strings
+    pub fn line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Set whether to add a space before colons, as in `name : String`
+    pub fn space_before_colon(&mut self, space: bool) -> &mut Self {
+        self.space_before_colon = space;
+        self
+    }
+
+    /// Set whether to add a space after colons, as in `name: String`,
+    /// which is the default
+    pub fn space_after_colon(&mut self, space: bool) -> &mut Self {
+        self.space_after_colon = space;
+        self
+    }
+
     /// Set whether to add new lines between arguments
     pub fn multiline_arguments(&mut self, multiline_arguments: bool) -> &mut Self {
         self.multiline_arguments = multiline_arguments;
         self
     }
+
+    /// Set whether to print with as little whitespace as possible
+    ///
+    /// A minified document is on a single line without commas, with a
+    /// space only where a name or number would otherwise run into the
+    /// next token, and with strings never printed as block strings. The
+    /// other settings are ignored.
+    pub fn minify(&mut self, minify: bool) -> &mut Self {
+        self.minify = minify;
+        self
+    }
 }
 
 pub(crate) trait Displayable {
@@ -48,26 +144,73 @@ pub(crate) trait Displayable {
 }
 
 impl<'a> Formatter<'a> {
-    pub fn new(style: &Style) -> Formatter {
+    pub fn new(style: &'a Style, out: &'a mut dyn Write) -> Formatter<'a> {
         Formatter {
-            buf: String::with_capacity(1024),
+            out,
+            result: Ok(()),
+            empty: true,
+            last: '\n',
             style,
             indent: 0,
+            block_strings: !style.minify,
+        }
+    }
+
+    /// Returns a formatter for fragments printed on their own, like a value
+    /// in an error message, which never uses block strings so that the
+    /// output stays on a single line
+    pub fn inline(style: &'a Style, out: &'a mut dyn Write) -> Formatter<'a> {
+        Formatter {
+            block_strings: false,
+            ..Formatter::new(style, out)
+        }
+    }
+
+    /// Returns the first error of the sink, if any
+    pub fn finish(self) -> fmt::Result {
+        self.result
+    }
+
+    fn push(&mut self, c: char) {
+        self.write(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Writes `s` as is, even when minifying
+    fn emit(&mut self, s: &str) {
+        let last = match s.chars().next_back() {
+            Some(last) => last,
+            None => return,
+        };
+        self.empty = false;
+        self.last = last;
+        if self.result.is_ok() {
+            self.result = self.out.write_str(s);
         }
     }
 
     pub fn indent(&mut self) {
         for _ in 0..self.indent {
-            self.buf.push(' ');
+            self.push(self.style.indent_char);
         }
     }
 
     pub fn endline(&mut self) {
-        self.buf.push('\n');
+        self.write(self.style.line_ending.as_str());
+    }
+
+    /// Writes the colon between a name and its type or value
+    pub fn colon(&mut self) {
+        if self.style.space_before_colon {
+            self.push(' ');
+        }
+        self.push(':');
+        if self.style.space_after_colon {
+            self.push(' ');
+        }
     }
 
     pub fn start_argument_block(&mut self, open_char: char) {
-        self.buf.push(open_char);
+        self.push(open_char);
         if self.style.multiline_arguments {
             self.inc_indent();
         }
@@ -79,7 +222,7 @@ impl<'a> Formatter<'a> {
             self.dec_indent();
             self.indent();
         }
-        self.buf.push(close_char);
+        self.push(close_char);
     }
 
     pub fn start_argument(&mut self) {
@@ -90,14 +233,14 @@ impl<'a> Formatter<'a> {
     }
 
     pub fn deliniate_argument(&mut self) {
-        self.buf.push(',');
+        self.push(',');
         if !self.style.multiline_arguments {
-            self.buf.push(' ');
+            self.push(' ');
         }
     }
 
     pub fn start_block(&mut self) {
-        self.buf.push('{');
+        self.push('{');
         self.endline();
         self.inc_indent();
     }
@@ -105,22 +248,40 @@ impl<'a> Formatter<'a> {
     pub fn end_block(&mut self) {
         self.dec_indent();
         self.indent();
-        self.buf.push('}');
+        self.push('}');
         self.endline();
     }
 
     pub fn margin(&mut self) {
-        if !self.buf.is_empty() {
-            self.buf.push('\n');
+        if !self.empty {
+            self.endline();
         }
     }
 
     pub fn write(&mut self, s: &str) {
-        self.buf.push_str(s);
-    }
-
-    pub fn into_string(self) -> String {
-        self.buf
+        if !self.style.minify {
+            self.emit(s);
+            return;
+        }
+        // whitespace and commas only separate tokens, which are written
+        // with a space between them only where it's needed
+        for token in s
+            .split([' ', '\t', ',', '\r', '\n'])
+            .filter(|t| !t.is_empty())
+        {
+            let first = token.chars().next().unwrap_or_default();
+            let name_end = self.last.is_ascii_alphanumeric() || self.last == '_';
+            let separate = match first {
+                c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => name_end,
+                // `"" ""` would become the start of a block string
+                '"' => name_end || self.last == '"',
+                _ => false,
+            };
+            if separate {
+                self.emit(" ");
+            }
+            self.emit(token);
+        }
     }
 
     pub fn write_quoted(&mut self, s: &str) {
@@ -129,27 +290,29 @@ impl<'a> Formatter<'a> {
         for c in s.chars() {
             match c {
                 '\n' => has_newline = true,
-                '\r' | '\t' | '\u{0020}'..='\u{FFFF}' => {}
-                _ => has_nonprintable = true,
+                '\r' | '\t' => {}
+                c if c.is_control() => has_nonprintable = true,
+                _ => {}
             }
         }
-        if !has_newline || has_nonprintable {
-            use std::fmt::Write;
-            self.buf.push('"');
+        if !self.block_strings || !has_newline || has_nonprintable {
+            self.push('"');
+            // the contents are emitted as they are, so that minifying
+            // keeps their spaces
             for c in s.chars() {
                 match c {
-                    '\r' => self.write(r"\r"),
-                    '\n' => self.write(r"\n"),
-                    '\t' => self.write(r"\t"),
-                    '"' => self.write("\\\""),
-                    '\\' => self.write(r"\\"),
-                    '\u{0020}'..='\u{FFFF}' => self.buf.push(c),
-                    _ => write!(&mut self.buf, "\\u{:04}", c as u32).unwrap(),
+                    '\r' => self.emit(r"\r"),
+                    '\n' => self.emit(r"\n"),
+                    '\t' => self.emit(r"\t"),
+                    '"' => self.emit("\\\""),
+                    '\\' => self.emit(r"\\"),
+                    c if c.is_control() => self.emit(&format!("\\u{:04X}", c as u32)),
+                    _ => self.emit(c.encode_utf8(&mut [0; 4])),
                 }
             }
-            self.buf.push('"');
+            self.emit("\"");
         } else {
-            self.buf.push_str(r#"""""#);
+            self.write(r#"""""#);
             self.endline();
             self.indent += self.style.indent;
             for line in s.lines() {
@@ -161,7 +324,7 @@ impl<'a> Formatter<'a> {
             }
             self.indent -= self.style.indent;
             self.indent();
-            self.buf.push_str(r#"""""#);
+            self.write(r#"""""#);
         }
     }
 
@@ -187,12 +350,62 @@ where
     }
 }
 
+/// Prints `node` into a new string
+pub(crate) fn to_string<D: Displayable>(node: &D, style: &Style) -> String {
+    let mut buf = String::with_capacity(1024);
+    format_to(node, style, &mut buf).expect("writing to a String can't fail");
+    buf
+}
+
+/// Prints `node` into a `fmt::Write`, e.g. a `String` or a `fmt::Formatter`
+pub(crate) fn format_to<D: Displayable, W: Write>(
+    node: &D,
+    style: &Style,
+    out: &mut W,
+) -> fmt::Result {
+    let mut formatter = Formatter::new(style, out);
+    node.display(&mut formatter);
+    formatter.finish()
+}
+
+/// Prints `node` into an `io::Write`, returning the first error of `out`
+pub(crate) fn write_to<D: Displayable, W: io::Write>(
+    node: &D,
+    style: &Style,
+    out: W,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter { out, error: Ok(()) };
+    match format_to(node, style, &mut adapter) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => Err(adapter
+            .error
+            .err()
+            .unwrap_or_else(|| io::Error::other("formatter error"))),
+    }
+}
+
+/// Lets the formatter write into an `io::Write`, keeping the error, which
+/// `fmt::Error` can't carry
+struct IoAdapter<W> {
+    out: W,
+    error: io::Result<()>,
+}
+
+impl<W: io::Write> Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Err(e);
+            fmt::Error
+        })
+    }
+}
+
 macro_rules! impl_display {
     ($( $typ: ident, )+) => {
         $(
             impl fmt::Display for $typ {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    crate::format::format_to(self, &Style::default(), f)
                 }
             }
         )+
@@ -204,7 +417,7 @@ macro_rules! impl_display {
                 where T: Text<'a>,
             {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    crate::format::format_to(self, &Style::default(), f)
                 }
             }
         )+
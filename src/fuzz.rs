@@ -0,0 +1,619 @@
+//! Random syntax trees for fuzzing and property tests
+//!
+//! Implements `arbitrary::Arbitrary` for values, types and documents with
+//! `String` text. The trees are valid in the sense that printing them and
+//! parsing the result gives a semantically equal tree, so they can drive
+//! round trip tests as well as code consuming the AST. Nesting and list
+//! lengths are bounded, and strings never contain line breaks, which the
+//! printer would turn into block strings.
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::common::{ConstValue, Directive, FloatNumber, StringValue, Type, Value};
+use crate::position::Pos;
+use crate::{document, query, schema};
+
+const MAX_DEPTH: usize = 3;
+const MAX_ITEMS: usize = 3;
+
+fn count(u: &mut Unstructured, min: usize) -> Result<usize> {
+    u.int_in_range(min..=min.max(MAX_ITEMS))
+}
+
+fn name(u: &mut Unstructured) -> Result<String> {
+    const FIRST: &[u8] = b"_ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    const REST: &[u8] = b"_0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let mut name = String::new();
+    name.push(char::from(*u.choose(FIRST)?));
+    for _ in 0..u.int_in_range(0..=7)? {
+        name.push(char::from(*u.choose(REST)?));
+    }
+    Ok(name)
+}
+
+/// A name which isn't one of `reserved`
+fn name_except(u: &mut Unstructured, reserved: &[&str]) -> Result<String> {
+    let name = name(u)?;
+    if reserved.contains(&name.as_str()) {
+        Ok(format!("{}_", name))
+    } else {
+        Ok(name)
+    }
+}
+
+/// Distinct names, as duplicate arguments and fields are rejected
+fn names(u: &mut Unstructured, min: usize) -> Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for _ in 0..count(u, min)? {
+        let mut name = name(u)?;
+        while !seen.insert(name.clone()) {
+            name.push('_');
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+fn string(u: &mut Unstructured) -> Result<String> {
+    let s = String::arbitrary(u)?;
+    Ok(s.replace('\n', " "))
+}
+
+fn description(u: &mut Unstructured) -> Result<Option<String>> {
+    if u.ratio(1, 4)? {
+        Ok(Some(string(u)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn value<'a>(u: &mut Unstructured, depth: usize, variables: bool) -> Result<Value<'a, String>> {
+    let kinds = if depth == 0 { 8 } else { 10 };
+    Ok(match u.choose_index(kinds)? {
+        0 if variables => Value::Variable(name(u)?),
+        0 | 1 => Value::Int(i64::arbitrary(u)?.into()),
+        2 => {
+            let big = i128::arbitrary(u)?;
+            if i64::try_from(big).is_ok() {
+                Value::Int((big as i64).into())
+            } else {
+                Value::BigInt(big.into())
+            }
+        }
+        3 => {
+            let float = f64::arbitrary(u)?;
+            Value::Float(FloatNumber::new(if float.is_finite() {
+                float
+            } else {
+                0.0
+            }))
+        }
+        4 => Value::String(StringValue::from(string(u)?)),
+        5 => Value::Boolean(bool::arbitrary(u)?),
+        6 => Value::Null,
+        7 => Value::Enum(name_except(u, &["true", "false", "null"])?),
+        8 => {
+            let mut items = Vec::new();
+            for _ in 0..count(u, 0)? {
+                items.push(value(u, depth - 1, variables)?);
+            }
+            Value::List(items)
+        }
+        _ => {
+            let mut fields = Vec::new();
+            for name in names(u, 0)? {
+                fields.push((name, value(u, depth - 1, variables)?));
+            }
+            Value::Object(fields)
+        }
+    })
+}
+
+fn const_value<'a>(u: &mut Unstructured) -> Result<Value<'a, String>> {
+    value(u, MAX_DEPTH, false)
+}
+
+fn default_value<'a>(u: &mut Unstructured) -> Result<Option<Value<'a, String>>> {
+    if u.ratio(1, 3)? {
+        Ok(Some(const_value(u)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn value_type<'a>(u: &mut Unstructured, depth: usize) -> Result<Type<'a, String>> {
+    let inner = if depth == 0 || u.ratio(2, 3)? {
+        Type::NamedType(name(u)?)
+    } else {
+        Type::ListType(Box::new(value_type(u, depth - 1)?))
+    };
+    if u.ratio(1, 3)? {
+        Ok(Type::NonNullType(Box::new(inner)))
+    } else {
+        Ok(inner)
+    }
+}
+
+fn directives<'a>(
+    u: &mut Unstructured,
+    min: usize,
+    variables: bool,
+) -> Result<Vec<Directive<'a, String>>> {
+    let mut directives = Vec::new();
+    for _ in 0..min + u.int_in_range(0..=1)? {
+        directives.push(Directive {
+            position: Pos::default(),
+            end: Pos::default(),
+            name: name(u)?,
+            arguments: arguments(u, MAX_DEPTH - 1, variables)?,
+        });
+    }
+    Ok(directives)
+}
+
+fn arguments<'a>(
+    u: &mut Unstructured,
+    depth: usize,
+    variables: bool,
+) -> Result<Vec<(String, Value<'a, String>)>> {
+    let mut arguments = Vec::new();
+    for name in names(u, 0)? {
+        arguments.push((name, value(u, depth, variables)?));
+    }
+    Ok(arguments)
+}
+
+fn selection_set<'a>(
+    u: &mut Unstructured,
+    depth: usize,
+    min: usize,
+) -> Result<query::SelectionSet<'a, String>> {
+    let mut items = Vec::new();
+    for _ in 0..count(u, min)? {
+        items.push(selection(u, depth)?);
+    }
+    Ok(query::SelectionSet {
+        span: (Pos::default(), Pos::default()),
+        items,
+    })
+}
+
+fn selection<'a>(u: &mut Unstructured, depth: usize) -> Result<query::Selection<'a, String>> {
+    use crate::query::{Field, FragmentSpread, InlineFragment, Selection, TypeCondition};
+
+    let kinds = if depth == 0 { 2 } else { 4 };
+    Ok(match u.choose_index(kinds)? {
+        0 => Selection::FragmentSpread(FragmentSpread {
+            position: Pos::default(),
+            end: Pos::default(),
+            fragment_name: name_except(u, &["on"])?,
+            directives: directives(u, 0, true)?,
+        }),
+        1 | 2 => Selection::Field(Field {
+            position: Pos::default(),
+            end: Pos::default(),
+            alias: if u.ratio(1, 4)? { Some(name(u)?) } else { None },
+            name: name(u)?,
+            arguments: arguments(u, MAX_DEPTH - 1, true)?,
+            directives: directives(u, 0, true)?,
+            selection_set: if depth == 0 {
+                query::SelectionSet {
+                    span: (Pos::default(), Pos::default()),
+                    items: Vec::new(),
+                }
+            } else {
+                selection_set(u, depth - 1, 0)?
+            },
+        }),
+        _ => Selection::InlineFragment(InlineFragment {
+            position: Pos::default(),
+            end: Pos::default(),
+            type_condition: if u.ratio(2, 3)? {
+                Some(TypeCondition::On(name(u)?))
+            } else {
+                None
+            },
+            directives: directives(u, 0, true)?,
+            selection_set: selection_set(u, depth - 1, 1)?,
+        }),
+    })
+}
+
+fn query_definition<'a>(u: &mut Unstructured) -> Result<query::Definition<'a, String>> {
+    use crate::query::{
+        Definition, FragmentDefinition, Operation, OperationKind, TypeCondition, VariableDefinition,
+    };
+
+    if u.ratio(1, 4)? {
+        return Ok(Definition::Fragment(FragmentDefinition {
+            position: Pos::default(),
+            end: Pos::default(),
+            name: name_except(u, &["on"])?,
+            type_condition: TypeCondition::On(name(u)?),
+            directives: directives(u, 0, true)?,
+            selection_set: selection_set(u, MAX_DEPTH, 1)?,
+        }));
+    }
+    let kind = *u.choose(&[
+        OperationKind::SelectionSet,
+        OperationKind::Query,
+        OperationKind::Mutation,
+        OperationKind::Subscription,
+    ])?;
+    let mut variable_definitions = Vec::new();
+    for name in names(u, 0)? {
+        variable_definitions.push(VariableDefinition {
+            position: Pos::default(),
+            end: Pos::default(),
+            name,
+            var_type: value_type(u, MAX_DEPTH)?,
+            default_value: default_value(u)?,
+        });
+    }
+    let operation = Operation {
+        kind,
+        position: Pos::default(),
+        end: Pos::default(),
+        name: if u.ratio(1, 2)? { Some(name(u)?) } else { None },
+        variable_definitions,
+        directives: directives(u, 0, true)?,
+        selection_set: selection_set(u, MAX_DEPTH, 1)?,
+    };
+    Ok(Definition::Operation(operation.into()))
+}
+
+fn input_values<'a>(u: &mut Unstructured) -> Result<Vec<schema::InputValue<'a, String>>> {
+    let mut values = Vec::new();
+    for name in names(u, 0)? {
+        values.push(schema::InputValue {
+            position: Pos::default(),
+            end: Pos::default(),
+            description: description(u)?,
+            name,
+            value_type: value_type(u, MAX_DEPTH)?,
+            default_value: default_value(u)?,
+            directives: directives(u, 0, false)?,
+        });
+    }
+    Ok(values)
+}
+
+fn fields<'a>(u: &mut Unstructured) -> Result<Vec<schema::Field<'a, String>>> {
+    let mut fields = Vec::new();
+    for name in names(u, 0)? {
+        fields.push(schema::Field {
+            position: Pos::default(),
+            end: Pos::default(),
+            description: description(u)?,
+            name,
+            arguments: input_values(u)?,
+            field_type: value_type(u, MAX_DEPTH)?,
+            directives: directives(u, 0, false)?,
+        });
+    }
+    Ok(fields)
+}
+
+fn enum_values<'a>(u: &mut Unstructured) -> Result<Vec<schema::EnumValue<'a, String>>> {
+    let mut values = Vec::new();
+    for name in names(u, 0)? {
+        if ["true", "false", "null"].contains(&name.as_str()) {
+            continue;
+        }
+        values.push(schema::EnumValue {
+            position: Pos::default(),
+            end: Pos::default(),
+            description: description(u)?,
+            name,
+            directives: directives(u, 0, false)?,
+        });
+    }
+    Ok(values)
+}
+
+fn type_definition<'a>(u: &mut Unstructured) -> Result<schema::TypeDefinition<'a, String>> {
+    use crate::schema::*;
+
+    let position = Pos::default();
+    let end = Pos::default();
+    Ok(match u.choose_index(6)? {
+        0 => TypeDefinition::Scalar(ScalarType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            directives: directives(u, 0, false)?,
+        }),
+        1 => TypeDefinition::Object(ObjectType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            implements_interfaces: names(u, 0)?,
+            directives: directives(u, 0, false)?,
+            fields: fields(u)?,
+        }),
+        2 => TypeDefinition::Interface(InterfaceType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            implements_interfaces: names(u, 0)?,
+            directives: directives(u, 0, false)?,
+            fields: fields(u)?,
+        }),
+        3 => TypeDefinition::Union(UnionType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            directives: directives(u, 0, false)?,
+            types: names(u, 0)?,
+        }),
+        4 => TypeDefinition::Enum(EnumType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            directives: directives(u, 0, false)?,
+            values: enum_values(u)?,
+        }),
+        _ => TypeDefinition::InputObject(InputObjectType {
+            position,
+            end,
+            description: description(u)?,
+            name: name(u)?,
+            directives: directives(u, 0, false)?,
+            fields: input_values(u)?,
+        }),
+    })
+}
+
+/// Extensions always get a directive, as they must extend something
+fn type_extension<'a>(u: &mut Unstructured) -> Result<schema::TypeExtension<'a, String>> {
+    use crate::schema::*;
+
+    let position = Pos::default();
+    let end = Pos::default();
+    Ok(match u.choose_index(6)? {
+        0 => TypeExtension::Scalar(ScalarTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            directives: directives(u, 1, false)?,
+        }),
+        1 => TypeExtension::Object(ObjectTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            implements_interfaces: names(u, 0)?,
+            directives: directives(u, 1, false)?,
+            fields: fields(u)?,
+        }),
+        2 => TypeExtension::Interface(InterfaceTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            implements_interfaces: names(u, 0)?,
+            directives: directives(u, 1, false)?,
+            fields: fields(u)?,
+        }),
+        3 => TypeExtension::Union(UnionTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            directives: directives(u, 1, false)?,
+            types: names(u, 0)?,
+        }),
+        4 => TypeExtension::Enum(EnumTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            directives: directives(u, 1, false)?,
+            values: enum_values(u)?,
+        }),
+        _ => TypeExtension::InputObject(InputObjectTypeExtension {
+            position,
+            end,
+            name: name(u)?,
+            directives: directives(u, 1, false)?,
+            fields: input_values(u)?,
+        }),
+    })
+}
+
+fn schema_definition<'a>(u: &mut Unstructured) -> Result<schema::Definition<'a, String>> {
+    use crate::schema::*;
+
+    Ok(match u.choose_index(8)? {
+        0 => Definition::SchemaDefinition(SchemaDefinition {
+            position: Pos::default(),
+            end: Pos::default(),
+            directives: directives(u, 0, false)?,
+            query: Some(name(u)?),
+            mutation: if u.ratio(1, 2)? { Some(name(u)?) } else { None },
+            subscription: if u.ratio(1, 2)? { Some(name(u)?) } else { None },
+        }),
+        1 => {
+            use crate::schema::DirectiveLocation::*;
+            const LOCATIONS: &[DirectiveLocation] = &[
+                Query,
+                Mutation,
+                Subscription,
+                Field,
+                FragmentDefinition,
+                FragmentSpread,
+                InlineFragment,
+                Schema,
+                Scalar,
+                Object,
+                FieldDefinition,
+                ArgumentDefinition,
+                Interface,
+                Union,
+                Enum,
+                EnumValue,
+                InputObject,
+                InputFieldDefinition,
+                VariableDefinition,
+            ];
+            let mut locations = Vec::new();
+            for _ in 0..count(u, 1)? {
+                let location = u.choose(LOCATIONS)?.clone();
+                if !locations.contains(&location) {
+                    locations.push(location);
+                }
+            }
+            Definition::DirectiveDefinition(DirectiveDefinition {
+                position: Pos::default(),
+                end: Pos::default(),
+                description: description(u)?,
+                name: name(u)?,
+                arguments: input_values(u)?,
+                repeatable: bool::arbitrary(u)?,
+                locations,
+            })
+        }
+        2 => Definition::TypeExtension(type_extension(u)?),
+        _ => Definition::TypeDefinition(type_definition(u)?),
+    })
+}
+
+impl<'u, 'a> Arbitrary<'u> for Value<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        value(u, MAX_DEPTH, true)
+    }
+}
+
+impl<'u, 'a> Arbitrary<'u> for ConstValue<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        Ok(ConstValue::try_from(const_value(u)?).expect("no variables are generated"))
+    }
+}
+
+impl<'u, 'a> Arbitrary<'u> for Type<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        value_type(u, MAX_DEPTH)
+    }
+}
+
+impl<'u, 'a> Arbitrary<'u> for query::Document<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        let mut definitions = Vec::new();
+        for _ in 0..count(u, 1)? {
+            definitions.push(query_definition(u)?);
+        }
+        Ok(query::Document::new(definitions))
+    }
+}
+
+impl<'u, 'a> Arbitrary<'u> for schema::Document<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        let mut definitions = Vec::new();
+        for _ in 0..count(u, 1)? {
+            definitions.push(schema_definition(u)?);
+        }
+        Ok(schema::Document::new(definitions))
+    }
+}
+
+impl<'u, 'a> Arbitrary<'u> for document::Document<'a, String> {
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        let mut definitions = Vec::new();
+        for _ in 0..count(u, 1)? {
+            definitions.push(if bool::arbitrary(u)? {
+                let mut definition = query_definition(u)?;
+                // a shorthand `{ ... }` after a type without fields would
+                // be read as the fields of that type
+                if let query::Definition::Operation(operation) = &mut definition {
+                    operation.expand_shorthand(None);
+                }
+                document::Definition::Executable(definition)
+            } else {
+                document::Definition::TypeSystem(schema_definition(u)?)
+            });
+        }
+        Ok(document::Document {
+            definitions,
+            comments: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use crate::common::{ConstValue, Type, Value};
+    use crate::semantic::SemanticEq;
+    use crate::{document, query, schema};
+    use crate::{parse_document, parse_query, parse_schema, parse_type, parse_value};
+
+    /// Deterministic pseudo-random input, so failures are reproducible
+    fn inputs() -> impl Iterator<Item = Vec<u8>> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..300).map(move |i| {
+            (0..64 + i * 8)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn round_trips() {
+        for data in inputs() {
+            let mut u = Unstructured::new(&data);
+            let value = Value::<String>::arbitrary(&mut u).unwrap();
+            let printed = value.to_string();
+            let parsed = parse_value::<String>(&printed).unwrap();
+            assert!(value.semantic_eq(&parsed), "{}", printed);
+            let constant = ConstValue::<String>::arbitrary(&mut u).unwrap();
+            let printed = Value::from(constant).to_string();
+            let parsed = parse_value::<String>(&printed).unwrap();
+            assert!(ConstValue::try_from(parsed).is_ok(), "{}", printed);
+
+            let ty = Type::<String>::arbitrary(&mut u).unwrap();
+            assert_eq!(parse_type::<String>(&ty.to_string()).unwrap(), ty);
+
+            let doc = query::Document::<String>::arbitrary(&mut u).unwrap();
+            let printed = doc.to_string();
+            let parsed =
+                parse_query::<String>(&printed).unwrap_or_else(|e| panic!("{}\n{}", e, printed));
+            assert!(doc.semantic_eq(&parsed), "{}", printed);
+            let minified = doc.format_minified();
+            let parsed =
+                parse_query::<String>(&minified).unwrap_or_else(|e| panic!("{}\n{}", e, minified));
+            assert!(doc.semantic_eq(&parsed), "{}", minified);
+
+            let doc = schema::Document::<String>::arbitrary(&mut u).unwrap();
+            let printed = doc.to_string();
+            let parsed =
+                parse_schema::<String>(&printed).unwrap_or_else(|e| panic!("{}\n{}", e, printed));
+            assert!(doc.semantic_eq(&parsed), "{}", printed);
+            let minified = doc.format_minified();
+            let parsed =
+                parse_schema::<String>(&minified).unwrap_or_else(|e| panic!("{}\n{}", e, minified));
+            assert!(doc.semantic_eq(&parsed), "{}", minified);
+
+            let doc = document::Document::<String>::arbitrary(&mut u).unwrap();
+            let printed = doc.to_string();
+            let parsed =
+                parse_document::<String>(&printed).unwrap_or_else(|e| panic!("{}\n{}", e, printed));
+            assert!(doc.semantic_eq(&parsed), "{}", printed);
+            let minified = doc.format_minified();
+            let parsed = parse_document::<String>(&minified)
+                .unwrap_or_else(|e| panic!("{}\n{}", e, minified));
+            assert!(doc.semantic_eq(&parsed), "{}", minified);
+        }
+    }
+}
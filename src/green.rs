@@ -0,0 +1,459 @@
+//! Lossless syntax tree for editors and other tooling
+//!
+//! The tree keeps every byte of the source, including whitespace, commas
+//! and comments, so printing it reproduces the input exactly. Nodes are
+//! immutable and reference counted, so a modified tree shares every
+//! untouched subtree with the original one, which makes small edits cheap.
+//!
+//! The structure is intentionally coarse: the document is split into
+//! definitions and each definition into bracketed groups. Use
+//! [`GreenNode::to_query`](struct.GreenNode.html#method.to_query) or
+//! [`GreenNode::to_schema`](struct.GreenNode.html#method.to_schema) to get
+//! the typed AST.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::green::{self, GreenElement};
+//!
+//! let source = "query A { a } # first\nquery B { b }";
+//! let tree = green::parse(source);
+//! assert_eq!(tree.to_string(), source);
+//!
+//! let replacement = green::parse("query C { c }").children()[0].clone();
+//! let edited = tree.replace_child(0, replacement);
+//! assert_eq!(edited.to_string(), "query C { c } # first\nquery B { b }");
+//! // the second definition is shared between both trees
+//! assert!(tree.children()[3].ptr_eq(&edited.children()[3]));
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+use combine::StreamOnce;
+
+use crate::tokenizer::{Kind, Token, TokenStream};
+use crate::{lexer, query, schema};
+
+/// Kind of a node or token of the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Whitespace,
+    Comma,
+    Comment,
+    Punctuator,
+    Name,
+    IntValue,
+    FloatValue,
+    StringValue,
+    BlockString,
+    /// Text which could not be tokenized
+    Error,
+    /// Root of the tree
+    Document,
+    /// Top-level definition, with its description if any
+    Definition,
+    /// Parenthesized, bracketed or braced part of a definition
+    Group,
+}
+
+impl SyntaxKind {
+    /// Returns `true` for whitespace, commas and comments
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Whitespace | SyntaxKind::Comma | SyntaxKind::Comment
+        )
+    }
+}
+
+impl From<Kind> for SyntaxKind {
+    fn from(kind: Kind) -> SyntaxKind {
+        match kind {
+            Kind::Punctuator => SyntaxKind::Punctuator,
+            Kind::Name => SyntaxKind::Name,
+            Kind::IntValue | Kind::BigIntValue => SyntaxKind::IntValue,
+            Kind::FloatValue => SyntaxKind::FloatValue,
+            Kind::StringValue => SyntaxKind::StringValue,
+            Kind::BlockString => SyntaxKind::BlockString,
+            Kind::Error => SyntaxKind::Error,
+            Kind::Whitespace => SyntaxKind::Whitespace,
+            Kind::Comma => SyntaxKind::Comma,
+            Kind::Comment => SyntaxKind::Comment,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TokenData {
+    kind: SyntaxKind,
+    text: Box<str>,
+}
+
+/// Leaf of the tree holding a piece of source text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken(Arc<TokenData>);
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> GreenToken {
+        GreenToken(Arc::new(TokenData {
+            kind,
+            text: text.into(),
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct NodeData {
+    kind: SyntaxKind,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+/// Inner node of the tree
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode(Arc<NodeData>);
+
+/// Child of a node, either a node or a token
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match *self {
+            GreenElement::Node(ref node) => node.kind(),
+            GreenElement::Token(ref token) => token.kind(),
+        }
+    }
+
+    /// Length of the source text covered, in bytes
+    pub fn text_len(&self) -> usize {
+        match *self {
+            GreenElement::Node(ref node) => node.text_len(),
+            GreenElement::Token(ref token) => token.text().len(),
+        }
+    }
+
+    /// Returns `true` if both elements are the same shared value
+    pub fn ptr_eq(&self, other: &GreenElement) -> bool {
+        match (self, other) {
+            (GreenElement::Node(a), GreenElement::Node(b)) => Arc::ptr_eq(&a.0, &b.0),
+            (GreenElement::Token(a), GreenElement::Token(b)) => Arc::ptr_eq(&a.0, &b.0),
+            _ => false,
+        }
+    }
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        GreenNode(Arc::new(NodeData {
+            kind,
+            text_len,
+            children,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    /// Length of the source text covered, in bytes
+    pub fn text_len(&self) -> usize {
+        self.0.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+
+    /// Returns a copy of the node with the child at `index` replaced
+    ///
+    /// All other children are shared with the original node.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children = self.0.children.clone();
+        children[index] = child;
+        GreenNode::new(self.kind(), children)
+    }
+
+    /// Parses the text of the tree as a query document
+    pub fn to_query(&self) -> Result<query::Document<'static, String>, query::ParseError> {
+        query::parse_query::<String>(&self.to_string()).map(query::Document::<String>::into_static)
+    }
+
+    /// Parses the text of the tree as a schema document
+    pub fn to_schema(&self) -> Result<schema::Document<'static, String>, schema::ParseError> {
+        schema::parse_schema::<String>(&self.to_string())
+            .map(schema::Document::<String>::into_static)
+    }
+
+    fn write_text(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in self.children() {
+            match *child {
+                GreenElement::Node(ref node) => node.write_text(f)?,
+                GreenElement::Token(ref token) => f.write_str(token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> GreenElement {
+        GreenElement::Node(node)
+    }
+}
+
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> GreenElement {
+        GreenElement::Token(token)
+    }
+}
+
+/// Prints the source text covered by the node
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_text(f)
+    }
+}
+
+/// Builds a lossless tree of a query or schema document
+///
+/// Never fails: text which can't be tokenized ends up in `Error` tokens.
+pub fn parse(source: &str) -> GreenNode {
+    let mut builder = Builder {
+        stack: vec![(SyntaxKind::Document, Vec::new())],
+        trivia: Vec::new(),
+        prev: None,
+    };
+    let mut off = 0;
+    while off < source.len() {
+        let rest = &source[off..];
+        // brackets are only grouped here, so there is no reason to limit
+        // their nesting
+        let mut stream = TokenStream::with_recursion_limit(rest, usize::MAX);
+        let mut end = 0;
+        loop {
+            match stream.uncons() {
+                Ok(token) => {
+                    let start = token.value.as_ptr() as usize - rest.as_ptr() as usize;
+                    builder.trivia(&rest[end..start]);
+                    builder.token(token.kind.into(), token.value, Some(token));
+                    end = start + token.value.len();
+                }
+                Err(_) if stream.offset() == rest.len() => {
+                    builder.trivia(&rest[end..]);
+                    off = source.len();
+                    break;
+                }
+                Err(_) => {
+                    let start = stream.offset();
+                    builder.trivia(&rest[end..start]);
+                    let len = lexer::error_len(&rest[start..]);
+                    builder.token(SyntaxKind::Error, &rest[start..start + len], None);
+                    off += start + len;
+                    break;
+                }
+            }
+        }
+    }
+    builder.finish()
+}
+
+struct Builder<'a> {
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+    trivia: Vec<GreenElement>,
+    prev: Option<Token<'a>>,
+}
+
+pub(crate) const DEFINITION_KEYWORDS: &[&str] = &[
+    "query",
+    "mutation",
+    "subscription",
+    "fragment",
+    "schema",
+    "scalar",
+    "type",
+    "interface",
+    "union",
+    "enum",
+    "input",
+    "directive",
+    "extend",
+];
+
+impl<'a> Builder<'a> {
+    fn trivia(&mut self, mut text: &str) {
+        while !text.is_empty() {
+            let (kind, len) = match text.as_bytes()[0] {
+                b',' => (SyntaxKind::Comma, 1),
+                b'#' => (
+                    SyntaxKind::Comment,
+                    text.find(['\r', '\n']).unwrap_or(text.len()),
+                ),
+                _ => (
+                    SyntaxKind::Whitespace,
+                    text.find([',', '#']).unwrap_or(text.len()),
+                ),
+            };
+            self.trivia.push(GreenToken::new(kind, &text[..len]).into());
+            text = &text[len..];
+        }
+    }
+
+    fn token(&mut self, kind: SyntaxKind, text: &str, token: Option<Token<'a>>) {
+        if self.stack.len() == 1 || (self.stack.len() == 2 && self.starts_definition(token)) {
+            self.close_to(1);
+            let trivia = std::mem::take(&mut self.trivia);
+            self.stack[0].1.extend(trivia);
+            self.stack.push((SyntaxKind::Definition, Vec::new()));
+        } else {
+            let trivia = std::mem::take(&mut self.trivia);
+            self.top().extend(trivia);
+        }
+        let element = GreenToken::new(kind, text).into();
+        match token {
+            Some(Token {
+                kind: Kind::Punctuator,
+                value: "(" | "[" | "{",
+                ..
+            }) => self.stack.push((SyntaxKind::Group, vec![element])),
+            Some(Token {
+                kind: Kind::Punctuator,
+                value: ")" | "]" | "}",
+                ..
+            }) if self.stack.len() > 2 => {
+                self.top().push(element);
+                self.close_to(self.stack.len() - 1);
+            }
+            _ => self.top().push(element),
+        }
+        self.prev = token;
+    }
+
+    fn starts_definition(&self, token: Option<Token<'a>>) -> bool {
+        match (token, self.prev) {
+            (Some(token), Some(prev)) => starts_definition(token, prev),
+            _ => false,
+        }
+    }
+
+    fn top(&mut self) -> &mut Vec<GreenElement> {
+        &mut self.stack.last_mut().expect("document is never closed").1
+    }
+
+    fn close_to(&mut self, depth: usize) {
+        while self.stack.len() > depth {
+            let (kind, children) = self.stack.pop().expect("checked length");
+            self.top().push(GreenNode::new(kind, children).into());
+        }
+    }
+
+    fn finish(mut self) -> GreenNode {
+        self.close_to(1);
+        let trivia = std::mem::take(&mut self.trivia);
+        let (kind, mut children) = self.stack.pop().expect("document is never closed");
+        children.extend(trivia);
+        GreenNode::new(kind, children)
+    }
+}
+
+/// Checks whether a token on the top level of a definition starts
+/// the next definition, `prev` is the token preceding it
+pub(crate) fn starts_definition(token: Token, prev: Token) -> bool {
+    match token.kind {
+        Kind::StringValue | Kind::BlockString => true,
+        Kind::Punctuator => token.value == "{" && prev.value == "}",
+        Kind::Name if DEFINITION_KEYWORDS.contains(&token.value) => match prev.kind {
+            Kind::StringValue | Kind::BlockString => false,
+            Kind::Name => {
+                !DEFINITION_KEYWORDS.contains(&prev.value)
+                    && prev.value != "implements"
+                    && prev.value != "on"
+            }
+            Kind::Punctuator => !matches!(prev.value, ":" | "=" | "|" | "&" | "@" | "$" | "..."),
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, GreenElement, SyntaxKind};
+
+    fn kinds(elements: &[GreenElement]) -> Vec<SyntaxKind> {
+        elements.iter().map(GreenElement::kind).collect()
+    }
+
+    #[test]
+    fn lossless() {
+        for source in &[
+            "",
+            "  # only a comment",
+            "{ a(x: [1, 2]) } { b }",
+            "\u{feff}query Q($x: Int = 1) {\r\n  a(x: $x), b # c\n}\n",
+            "\"\"\"\nDescription\n\"\"\"\ntype A implements B & C { f: [D!]! }\nscalar X",
+            "{ a(x: \"unterminated) }\n{ b } ~ 01",
+        ] {
+            assert_eq!(parse(source).to_string(), *source);
+        }
+    }
+
+    #[test]
+    fn definitions() {
+        let tree = parse("\"desc\" type A { a: B } , extend type type { c: D }\n{ e }");
+        use self::SyntaxKind::*;
+        assert_eq!(
+            kinds(tree.children()),
+            [Definition, Whitespace, Comma, Whitespace, Definition, Whitespace, Definition]
+        );
+        match tree.children()[0] {
+            GreenElement::Node(ref node) => {
+                assert_eq!(
+                    kinds(node.children()),
+                    [
+                        StringValue,
+                        Whitespace,
+                        Name,
+                        Whitespace,
+                        Name,
+                        Whitespace,
+                        Group
+                    ]
+                );
+            }
+            _ => unreachable!(),
+        }
+        assert!(tree.to_schema().is_err());
+        let tree = tree.replace_child(6, parse("scalar E").children()[0].clone());
+        assert_eq!(tree.to_schema().unwrap().definitions.len(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        let tree = parse("{ a } ~ { b }");
+        match tree.children()[0] {
+            GreenElement::Node(ref node) => {
+                assert!(kinds(node.children()).contains(&SyntaxKind::Error));
+            }
+            _ => unreachable!(),
+        }
+        assert!(tree.to_query().is_err());
+        let tree = parse("{ a } { b }");
+        assert_eq!(tree.to_query().unwrap().definitions.len(), 2);
+    }
+}
@@ -1,11 +1,13 @@
 use std::marker::PhantomData;
 
+use combine::error::FastResult;
 use combine::error::Tracked;
-use combine::stream::easy::{Error, Errors, Info};
-use combine::{satisfy, ConsumedResult, Parser, StreamOnce};
+use combine::{satisfy, ConsumedResult, ParseResult, Parser, StreamOnce};
 
+use crate::error::{Error, Errors, Info};
 use crate::position::Pos;
 use crate::tokenizer::{Kind, Token, TokenStream};
+use crate::trace::TraceEvent;
 
 use super::common::Text;
 
@@ -23,6 +25,11 @@ where
     phantom: PhantomData<&'a T>,
 }
 
+#[derive(Debug, Clone)]
+pub struct EndPosition<'a> {
+    phantom: PhantomData<&'a u8>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Value<'a> {
     kind: Kind,
@@ -56,10 +63,31 @@ impl<'a> Parser for TokenMatch<'a> {
         satisfy(|c: Token<'a>| c.kind == self.kind).parse_lazy(input)
     }
 
-    fn add_error(&mut self, error: &mut Tracked<Errors<Token<'a>, Token<'a>, Pos>>) {
+    fn add_error(&mut self, error: &mut Tracked<Errors<'a>>) {
         error
             .error
-            .add_error(Error::Expected(Info::Owned(format!("{:?}", self.kind))));
+            .add_error(Error::Expected(Info::Kind(self.kind)));
+    }
+}
+
+/// Returns the position just past the last token parsed
+///
+/// Unlike `combine::position` this excludes the whitespace and comments
+/// following the token, so it is used for end positions of nodes.
+pub fn end_position<'a>() -> EndPosition<'a> {
+    EndPosition {
+        phantom: PhantomData,
+    }
+}
+
+impl<'a> Parser for EndPosition<'a> {
+    type Input = TokenStream<'a>;
+    type Output = Pos;
+    type PartialState = ();
+
+    #[inline]
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        FastResult::EmptyOk(input.last_end())
     }
 }
 
@@ -92,7 +120,7 @@ impl<'a> Parser for Value<'a> {
     fn add_error(&mut self, error: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
         error
             .error
-            .add_error(Error::Expected(Info::Borrowed(self.value)));
+            .add_error(Error::Expected(Info::Static(self.value)));
     }
 }
 
@@ -111,9 +139,64 @@ where
             .parse_lazy(input)
     }
 
-    fn add_error(&mut self, error: &mut Tracked<Errors<Token<'a>, Token<'a>, Pos>>) {
+    fn add_error(&mut self, error: &mut Tracked<Errors<'a>>) {
         error
             .error
-            .add_error(Error::Expected(Info::Borrowed("Name")));
+            .add_error(Error::Expected(Info::Kind(Kind::Name)));
+    }
+}
+
+/// Stack space left before a rule moves parsing onto a new stack segment
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// Size of the stack segments allocated for deeply nested documents
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+/// Grammar rule implemented by a function
+///
+/// Works like `combine::parser` but reports entering and leaving the rule
+/// when tracing is enabled. Every recursion of the grammar goes through a
+/// rule, so rules also grow the stack when it runs low. This way nesting is
+/// only bounded by the recursion limit, whatever the stack size of the
+/// calling thread.
+#[derive(Debug, Clone)]
+pub struct Rule<'a, F> {
+    name: &'static str,
+    func: F,
+    phantom: PhantomData<&'a u8>,
+}
+
+pub fn rule<'a, O, F>(name: &'static str, func: F) -> Rule<'a, F>
+where
+    F: FnMut(&mut TokenStream<'a>) -> ParseResult<O, TokenStream<'a>>,
+{
+    Rule {
+        name,
+        func,
+        phantom: PhantomData,
+    }
+}
+
+impl<'a, O, F> Parser for Rule<'a, F>
+where
+    F: FnMut(&mut TokenStream<'a>) -> ParseResult<O, TokenStream<'a>>,
+{
+    type Input = TokenStream<'a>;
+    type Output = O;
+    type PartialState = ();
+
+    #[inline]
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let rule = self.name;
+        input.trace(|position| TraceEvent::Enter { rule, position });
+        let func = &mut self.func;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || func(input));
+        let success = result.is_ok();
+        input.trace(|position| TraceEvent::Exit {
+            rule,
+            position,
+            success,
+        });
+        result.into()
     }
 }
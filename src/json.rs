@@ -0,0 +1,153 @@
+//! Conversion of values to and from JSON
+//!
+//! Variables of a request usually arrive as JSON, while arguments and
+//! default values of a document are GraphQL values. The conversion is
+//! lossless except for the cases listed on
+//! [`Value::to_json`](query/enum.Value.html#method.to_json).
+use thiserror::Error;
+
+use crate::common::{BigNumber, FloatNumber, Number, StringValue, Text, Value};
+
+/// Error converting a value to JSON
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ToJsonError {
+    /// The value contains a variable, which has no JSON representation
+    #[error("variable `${0}` can't be converted to JSON")]
+    Variable(String),
+    /// The value contains a float which is infinite or not a number
+    #[error("float `{0}` can't be converted to JSON")]
+    NonFiniteFloat(String),
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    /// Converts JSON to a value
+    ///
+    /// JSON strings become `Value::String`, there is no way to tell enum
+    /// values apart without a schema. Integers which don't fit into i64
+    /// become `Value::BigInt`, other numbers become `Value::Float`.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # extern crate serde_json;
+    /// use graphql_parser::query::Value;
+    ///
+    /// let json = serde_json::json!({"id": 1, "tags": ["a"]});
+    /// let value = Value::<String>::from_json(json.clone());
+    /// assert_eq!(value.to_string(), r#"{id: 1, tags: ["a"]}"#);
+    /// assert_eq!(value.to_json(), Ok(json));
+    /// ```
+    pub fn from_json(json: serde_json::Value) -> Value<'a, T>
+    where
+        T::Value: From<String>,
+    {
+        use serde_json::Value as Json;
+        match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Boolean(b),
+            Json::Number(n) => {
+                if let Some(n) = n.as_i64() {
+                    Value::Int(Number::from(n))
+                } else if let Some(n) = n.as_u64() {
+                    Value::BigInt(BigNumber::from(n))
+                } else {
+                    // without arbitrary precision, any other number fits f64
+                    Value::Float(FloatNumber::from(n.as_f64().unwrap_or(f64::NAN)))
+                }
+            }
+            Json::String(s) => Value::String(StringValue::from(s)),
+            Json::Array(list) => Value::List(list.into_iter().map(Value::from_json).collect()),
+            Json::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), Value::from_json(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts the value to JSON
+    ///
+    /// Enum values become strings. Integers which don't fit into i64 or
+    /// u64 become strings of their digits, as JSON parsers commonly lose
+    /// precision on them. Variables fail the conversion, and so do floats
+    /// which are infinite or not a number, which the parser never produces.
+    pub fn to_json(&self) -> Result<serde_json::Value, ToJsonError> {
+        use serde_json::Value as Json;
+        Ok(match self {
+            Value::Variable(name) => return Err(ToJsonError::Variable(name.as_ref().into())),
+            Value::Int(n) => Json::from(n.0),
+            Value::BigInt(n) => {
+                if let Some(n) = n.as_i64() {
+                    Json::from(n)
+                } else if let Some(n) = n.as_u64() {
+                    Json::from(n)
+                } else {
+                    Json::String(n.to_string())
+                }
+            }
+            Value::Float(f) => match serde_json::Number::from_f64(f.as_f64()) {
+                Some(n) => Json::Number(n),
+                None => return Err(ToJsonError::NonFiniteFloat(f.to_string())),
+            },
+            Value::String(s) => Json::String(s.as_str().into()),
+            Value::Boolean(b) => Json::Bool(*b),
+            Value::Null => Json::Null,
+            Value::Enum(name) => Json::String(name.as_ref().into()),
+            Value::List(list) => Json::Array(
+                list.iter()
+                    .map(|value| value.to_json())
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(object) => Json::Object(
+                object
+                    .iter()
+                    .map(|(k, v)| Ok((k.as_ref().into(), v.to_json()?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::ToJsonError;
+    use crate::common::{BigNumber, Value};
+    use crate::parse_value;
+
+    #[test]
+    fn round_trip() {
+        let json = json!({
+            "a": [1, -2, 1.5, "x", true, null],
+            "b": {"c": 18446744073709551615u64},
+        });
+        let value = Value::<String>::from_json(json.clone());
+        assert_eq!(
+            value.get_path(&["b", "c"]),
+            Some(&Value::BigInt(BigNumber::from(u64::MAX)))
+        );
+        assert_eq!(value.to_json(), Ok(json));
+    }
+
+    #[test]
+    fn to_json() {
+        let value =
+            parse_value::<&str>("{e: RED, n: 170141183460469231731687303715884105727}").unwrap();
+        assert_eq!(
+            value.to_json(),
+            Ok(json!({"e": "RED", "n": "170141183460469231731687303715884105727"}))
+        );
+        let value = parse_value::<&str>("[1, $x]").unwrap();
+        assert_eq!(value.to_json(), Err(ToJsonError::Variable("x".into())));
+        assert_eq!(
+            value.to_json().unwrap_err().to_string(),
+            "variable `$x` can't be converted to JSON"
+        );
+        let value = Value::<&str>::from(f64::INFINITY);
+        assert_eq!(
+            value.to_json(),
+            Err(ToJsonError::NonFiniteFloat("inf".into()))
+        );
+    }
+}
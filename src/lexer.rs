@@ -0,0 +1,450 @@
+//! Standalone lexer producing tokens with byte offsets
+//!
+//! [`tokenize`](fn.tokenize.html) iterates over the tokens of a text along
+//! with their lines and columns, without involving the parser.
+//! [`tokenize_reader`](fn.tokenize_reader.html) does the same for text
+//! which is read incrementally.
+//!
+//! Besides lexing whole documents, [`relex`](fn.relex.html) updates a
+//! previously lexed token list after an edit, only scanning the text near
+//! the edit. This is the basis for incremental reparsing in editors.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::lexer::{lex, relex, TextEdit};
+//!
+//! let old = "{ user(id: 1) { name } }";
+//! let tokens = lex(old).unwrap();
+//!
+//! // replace `1` with `42`
+//! let new = "{ user(id: 42) { name } }";
+//! let edit = TextEdit::new(11..12, 2);
+//! let tokens = relex(&tokens, new, &edit).unwrap();
+//! assert_eq!(tokens, lex(new).unwrap());
+//! ```
+use std::fmt;
+use std::ops::Range;
+
+use combine::StreamOnce;
+
+pub use crate::read::{tokenize_reader, OwnedToken, ReadError, ReadTokens};
+pub use crate::tokenizer::{Checkpoint, Kind, Token, TokenStream};
+
+/// A token and its location in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lexeme {
+    pub kind: Kind,
+    /// Byte offset of the first byte of the token
+    pub start: usize,
+    /// Byte offset just past the token
+    pub end: usize,
+}
+
+impl Lexeme {
+    /// Returns the source text of the token
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+/// Error lexing a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    /// Byte offset where the invalid token starts
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "lexing error at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Replacement of a range of the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Start of the replaced range
+    pub start: usize,
+    /// End of the replaced range, in the text before the edit
+    pub old_end: usize,
+    /// End of the inserted text, in the text after the edit
+    pub new_end: usize,
+}
+
+impl TextEdit {
+    /// Describes replacing `range` of the old text with `new_len` bytes
+    pub fn new(range: Range<usize>, new_len: usize) -> TextEdit {
+        TextEdit {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start + new_len,
+        }
+    }
+
+    fn shift(&self, offset: usize) -> usize {
+        offset + self.new_end - self.old_end
+    }
+}
+
+/// Iterator over the tokens of a text, see [`tokenize`](fn.tokenize.html)
+/// and [`tokenize_with_trivia`](fn.tokenize_with_trivia.html)
+#[derive(Debug)]
+pub struct Tokens<'a> {
+    stream: TokenStream<'a>,
+    done: bool,
+}
+
+/// Splits the source into tokens lazily
+///
+/// Tokens carry their line and column along with the text. After an
+/// invalid token the iterator returns its error and then ends.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::lexer::{tokenize, Kind};
+///
+/// let tokens = tokenize("{ user(id: 1) }")
+///     .map(|token| token.map(|t| (t.kind, t.value)))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(tokens[1], (Kind::Name, "user"));
+/// assert_eq!(tokens.len(), 8);
+/// ```
+pub fn tokenize(source: &str) -> Tokens<'_> {
+    Tokens {
+        // brackets aren't matched here, so their nesting isn't limited either
+        stream: TokenStream::with_recursion_limit(source, usize::MAX),
+        done: false,
+    }
+}
+
+/// Splits the source into tokens lazily, including whitespace, commas and
+/// comments
+///
+/// Concatenating the text of all tokens gives back the source, up to the
+/// first invalid token. Each run of whitespace is a single token of kind
+/// `Kind::Whitespace`, line terminators included. Comments don't include
+/// the terminator of their line.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::lexer::{tokenize_with_trivia, Kind};
+///
+/// let kinds = tokenize_with_trivia("a, # b\n")
+///     .map(|token| token.unwrap().kind)
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     kinds,
+///     [Kind::Name, Kind::Comma, Kind::Whitespace, Kind::Comment, Kind::Whitespace],
+/// );
+/// ```
+pub fn tokenize_with_trivia(source: &str) -> Tokens<'_> {
+    Tokens {
+        stream: TokenStream::with_trivia(source),
+        done: false,
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.stream.advance();
+        self.done = !matches!(item, Some(Ok(_)));
+        item
+    }
+}
+
+/// Splits the whole source into tokens
+pub fn lex(source: &str) -> Result<Vec<Lexeme>, LexError> {
+    let mut tokens = Vec::new();
+    lex_from(source, 0, |token| {
+        tokens.push(token);
+        true
+    })?;
+    Ok(tokens)
+}
+
+/// Splits the source into tokens, never failing
+///
+/// Invalid input produces a token of kind `Kind::Error` and lexing resumes
+/// after it. This suits editors and highlighters which deal with
+/// half-written documents. The errors are returned in source order, one
+/// for each error token.
+pub fn lex_lossy(source: &str) -> (Vec<Lexeme>, Vec<LexError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    let mut offset = 0;
+    while let Err(error) = lex_from(source, offset, |token| {
+        tokens.push(token);
+        true
+    }) {
+        offset = error.offset + error_len(&source[error.offset..]);
+        tokens.push(Lexeme {
+            kind: Kind::Error,
+            start: error.offset,
+            end: offset,
+        });
+        errors.push(error);
+    }
+    (tokens, errors)
+}
+
+/// Length of the invalid input starting at `rest`
+///
+/// Unterminated strings extend to the end of the line, or of the input for
+/// block strings. Malformed numbers and dots are taken as a whole, anything
+/// else is a single character.
+pub(crate) fn error_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let run = |f: fn(u8) -> bool| bytes.iter().position(|&b| !f(b)).unwrap_or(bytes.len());
+    match bytes.first() {
+        _ if rest.starts_with("\"\"\"") => rest.len(),
+        Some(b'"') => run(|b| b != b'\n' && b != b'\r'),
+        Some(b'-' | b'0'..=b'9') => {
+            run(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'+' | b'-'))
+        }
+        Some(b'.') => run(|b| b == b'.'),
+        _ => rest.chars().next().map_or(0, char::len_utf8),
+    }
+}
+
+/// Updates tokens of a document after an edit
+///
+/// `tokens` are the tokens of the text before the edit and `source` is the
+/// text after it. Tokens before the edit are kept, and the ones after it
+/// are reused, with shifted offsets, as soon as lexing of the new text
+/// reaches a token boundary which was there before the edit.
+///
+/// The result is always the same as lexing `source` from scratch.
+pub fn relex(tokens: &[Lexeme], source: &str, edit: &TextEdit) -> Result<Vec<Lexeme>, LexError> {
+    // a token which ends right at the edit might continue into the
+    // inserted text, so it has to be scanned again
+    let keep = tokens.partition_point(|t| t.end < edit.start);
+    let restart = keep.checked_sub(1).map_or(0, |idx| tokens[idx].end);
+    let mut old = tokens[keep..]
+        .iter()
+        .skip_while(|t| t.start < edit.old_end)
+        .peekable();
+
+    let mut result = tokens[..keep].to_vec();
+    let mut synced = None;
+    lex_from(source, restart, |token| {
+        if token.start >= edit.new_end {
+            while let Some(&&old_token) = old.peek() {
+                let start = edit.shift(old_token.start);
+                if start > token.start {
+                    break;
+                }
+                old.next();
+                if start == token.start
+                    && edit.shift(old_token.end) == token.end
+                    && old_token.kind == token.kind
+                {
+                    synced = Some(token);
+                    return false;
+                }
+            }
+        }
+        result.push(token);
+        true
+    })?;
+    if let Some(token) = synced {
+        result.push(token);
+        result.extend(old.map(|t| Lexeme {
+            kind: t.kind,
+            start: edit.shift(t.start),
+            end: edit.shift(t.end),
+        }));
+    }
+    Ok(result)
+}
+
+/// Lexes `source` starting at `offset`, until the end or until `f`
+/// returns false
+fn lex_from<F>(source: &str, offset: usize, mut f: F) -> Result<(), LexError>
+where
+    F: FnMut(Lexeme) -> bool,
+{
+    let rest = &source[offset..];
+    // brackets aren't matched here, so their nesting isn't limited either
+    let mut stream = TokenStream::with_recursion_limit(rest, usize::MAX);
+    loop {
+        match stream.uncons() {
+            Ok(token) => {
+                let start = offset + (token.value.as_ptr() as usize - rest.as_ptr() as usize);
+                let lexeme = Lexeme {
+                    kind: token.kind,
+                    start,
+                    end: start + token.value.len(),
+                };
+                if !f(lexeme) {
+                    return Ok(());
+                }
+            }
+            Err(_) if stream.offset() == rest.len() => return Ok(()),
+            Err(e) => {
+                return Err(LexError {
+                    offset: offset + stream.offset(),
+                    message: e.message(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lex, lex_lossy, relex, tokenize, tokenize_with_trivia, Kind, LexError, TextEdit};
+    use crate::position::Pos;
+
+    fn check_edit(old: &str, range: std::ops::Range<usize>, text: &str) {
+        let new = format!("{}{}{}", &old[..range.start], text, &old[range.end..]);
+        let edit = TextEdit::new(range, text.len());
+        let tokens = lex(old).unwrap();
+        assert_eq!(relex(&tokens, &new, &edit), lex(&new), "{:?}", new);
+    }
+
+    #[test]
+    fn lex_offsets() {
+        let source = "query Q { a(x: \"é\") }";
+        let tokens = lex(source).unwrap();
+        let texts: Vec<_> = tokens.iter().map(|t| t.text(source)).collect();
+        assert_eq!(
+            texts,
+            ["query", "Q", "{", "a", "(", "x", ":", "\"é\"", ")", "}"]
+        );
+        assert_eq!(tokens[7].kind, Kind::StringValue);
+        assert_eq!(
+            lex("{ a ~ }"),
+            Err(LexError {
+                offset: 4,
+                message: "unexpected character '~'".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn lossy() {
+        let source = "{ a\u{e9} 1.x .. \"bc\n d } \"\"\"e";
+        let (tokens, errors) = lex_lossy(source);
+        let tokens: Vec<_> = tokens.iter().map(|t| (t.kind, t.text(source))).collect();
+        assert_eq!(
+            tokens,
+            [
+                (Kind::Punctuator, "{"),
+                (Kind::Name, "a"),
+                (Kind::Error, "\u{e9}"),
+                (Kind::Error, "1.x"),
+                (Kind::Error, ".."),
+                (Kind::Error, "\"bc"),
+                (Kind::Name, "d"),
+                (Kind::Punctuator, "}"),
+                (Kind::Error, "\"\"\"e"),
+            ]
+        );
+        let offsets: Vec<_> = errors.iter().map(|e| e.offset).collect();
+        assert_eq!(offsets, [3, 6, 10, 13, 22]);
+        assert_eq!(lex_lossy("{ a }"), (lex("{ a }").unwrap(), Vec::new()));
+    }
+
+    #[test]
+    fn edits() {
+        let old = "query Q { a(x: 1) b # c\n d }";
+        check_edit(old, 0..0, " ");
+        check_edit(old, 6..7, "Query");
+        check_edit(old, 7..7, "2");
+        check_edit(old, 15..16, "\"x\"");
+        check_edit(old, 16..16, "23");
+        check_edit(old, 19..21, "#");
+        check_edit(old, 20..22, "");
+        check_edit(old, 25..25, "\n");
+        check_edit(old, old.len()..old.len(), " e");
+        check_edit(old, 0..old.len(), "{ x }");
+        // opens a string which swallows the rest of the line
+        check_edit("{ a(x: 1, y: 2) b }", 7..7, "\"");
+    }
+
+    #[test]
+    fn reuses_tail() {
+        let old = "{ a b c d e f }";
+        let tokens = lex(old).unwrap();
+        let new = "{ a bb c d e f }";
+        let relexed = relex(&tokens, new, &TextEdit::new(4..5, 2)).unwrap();
+        assert_eq!(relexed, lex(new).unwrap());
+    }
+
+    #[test]
+    fn tokens_iterator() {
+        let tokens = tokenize("query {\n  a(x: \"s\")\n}  ").collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 9);
+        let token = tokens[6].as_ref().unwrap();
+        assert_eq!(token.kind, Kind::StringValue);
+        assert_eq!(token.raw_contents(), "s");
+        assert_eq!(token.start, Pos::new(2, 8, 15));
+        assert_eq!(token.end, Pos::new(2, 11, 18));
+
+        let mut tokens = tokenize("a \u{e9} b");
+        assert_eq!(tokens.next().unwrap().unwrap().value, "a");
+        assert_eq!(
+            tokens.next().unwrap(),
+            Err(LexError {
+                offset: 2,
+                message: "unexpected character '\u{e9}'".into(),
+            })
+        );
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokenize(" # only a comment").count(), 0);
+    }
+
+    #[test]
+    fn trivia() {
+        let source = "\u{feff}query {\r\n\ta, b # c\r\n}\n# end";
+        let tokens = tokenize_with_trivia(source)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let text = tokens.iter().map(|t| t.value).collect::<String>();
+        assert_eq!(text, source);
+        let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                Kind::Whitespace,
+                Kind::Name,
+                Kind::Whitespace,
+                Kind::Punctuator,
+                Kind::Whitespace,
+                Kind::Name,
+                Kind::Comma,
+                Kind::Whitespace,
+                Kind::Name,
+                Kind::Whitespace,
+                Kind::Comment,
+                Kind::Whitespace,
+                Kind::Punctuator,
+                Kind::Whitespace,
+                Kind::Comment,
+            ]
+        );
+        assert_eq!(tokens[10].value, "# c");
+        assert_eq!(tokens[10].start, Pos::new(2, 14, 18));
+        assert_eq!(tokens[10].end, Pos::new(2, 17, 21));
+        assert_eq!(tokens[12].start, Pos::new(3, 1, 23));
+        assert_eq!(tokens[14].end, Pos::new(4, 6, 30));
+
+        // positions of tokens are the same as without trivia
+        let significant = tokens
+            .iter()
+            .filter(|t| !matches!(t.kind, Kind::Whitespace | Kind::Comma | Kind::Comment));
+        assert!(significant.eq(tokenize(source)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>()
+            .iter()));
+    }
+}
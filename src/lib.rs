@@ -96,16 +96,56 @@
 extern crate pretty_assertions;
 
 mod common;
+pub mod diagnostic;
+pub mod directives;
+mod error;
+pub mod extract;
+#[cfg(feature = "mmap")]
+mod file;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
 #[macro_use]
 mod format;
+pub mod document;
+pub mod dump;
+pub mod green;
 mod helpers;
+#[cfg(feature = "serde")]
+mod json;
+pub mod lexer;
+mod limits;
+pub mod message;
+mod owned;
+mod owned_source;
+mod parallel;
+mod parser;
+#[macro_use]
 mod position;
 pub mod query;
+mod read;
+mod recovery;
 pub mod schema;
+pub mod semantic;
+pub mod snippet;
+pub mod spanned;
+mod syntax_error;
 mod tokenizer;
+pub mod trace;
+mod warning;
 
-pub use crate::format::Style;
-pub use crate::position::Pos;
+pub use crate::document::parse_document;
+#[cfg(feature = "mmap")]
+pub use crate::file::{parse_query_file, parse_schema_file, SourceFile};
+pub use crate::format::{LineEnding, Style};
+#[cfg(feature = "serde")]
+pub use crate::json::ToJsonError;
+pub use crate::limits::Limits;
+pub use crate::parser::{Parser, ParserOptions};
+pub use crate::position::{ColumnUnit, Pos, SourceId, SourceMap, Span};
 pub use crate::query::minify_query;
 pub use crate::query::parse_query;
+pub use crate::query::parse_type;
+pub use crate::query::parse_value;
 pub use crate::schema::parse_schema;
+pub use crate::syntax_error::{ErrorCode, Expected, ParseErrors, SyntaxError};
+pub use crate::warning::Warning;
@@ -0,0 +1,245 @@
+//! Limits on the size of parsed documents
+//!
+//! A server parsing documents sent by untrusted clients should bound the
+//! work spent on each of them before validating or executing anything.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::{ErrorCode, Limits};
+//! use graphql_parser::query::parse_query_with_limits;
+//!
+//! let limits = Limits::new().max_depth(10).max_selections(2);
+//! let err = parse_query_with_limits::<&str>("{ a b c }", &limits).unwrap_err();
+//! assert_eq!(err.code(), ErrorCode::LimitExceeded);
+//! ```
+
+/// Recursion limit used when none is configured
+pub(crate) const DEFAULT_DEPTH: usize = 50;
+
+/// Maximum size of a document accepted by the parser
+///
+/// Exceeding the nesting depth fails with
+/// `ErrorCode::RecursionLimitExceeded`, exceeding any other limit with
+/// `ErrorCode::LimitExceeded`. Parsing stops at the first exceeded limit,
+/// no further errors are reported after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub(crate) depth: usize,
+    pub(crate) tokens: usize,
+    pub(crate) selections: usize,
+    pub(crate) input: usize,
+    pub(crate) string: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            depth: DEFAULT_DEPTH,
+            tokens: usize::MAX,
+            selections: usize::MAX,
+            input: usize::MAX,
+            string: usize::MAX,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns the default limits, which only bound the nesting depth
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    /// Sets the maximum number of brackets open at the same time, 50 by
+    /// default
+    ///
+    /// The parser grows its stack as needed, so deeper documents don't
+    /// overflow the stack of the calling thread. Yet both parsing and
+    /// dropping the AST recurse for every level of nesting, so the limit
+    /// bounds the memory and time spent on them.
+    pub fn max_depth(mut self, depth: usize) -> Limits {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets the maximum number of tokens in a document
+    pub fn max_tokens(mut self, tokens: usize) -> Limits {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Sets the maximum number of fields, fragment spreads and inline
+    /// fragments in a document
+    ///
+    /// Selections are counted in all operations and fragments together,
+    /// without expanding fragment spreads.
+    pub fn max_selections(mut self, selections: usize) -> Limits {
+        self.selections = selections;
+        self
+    }
+
+    /// Sets the maximum length of a document in bytes
+    ///
+    /// Longer documents are rejected before parsing any definition.
+    pub fn max_input_len(mut self, bytes: usize) -> Limits {
+        self.input = bytes;
+        self
+    }
+
+    /// Sets the maximum length in bytes of a string or block string, not
+    /// counting the quotes
+    ///
+    /// The length is measured in the source, before escape sequences are
+    /// replaced.
+    pub fn max_string_len(mut self, bytes: usize) -> Limits {
+        self.string = bytes;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Limits;
+    use crate::query::parse_query_with_limits;
+    use crate::schema::parse_schema_with_limits;
+    use crate::syntax_error::ErrorCode;
+    use crate::Parser;
+
+    #[test]
+    fn tokens() {
+        let query = "query Q { a b(x: 1) }";
+        let limits = Limits::new().max_tokens(11);
+        assert!(parse_query_with_limits::<&str>(query, &limits).is_ok());
+        let limits = Limits::new().max_tokens(10);
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LimitExceeded);
+
+        let schema = "type A { a: Int } type B { b: Int }";
+        let limits = Limits::new().max_tokens(10);
+        let err = parse_schema_with_limits::<&str>(schema, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "schema parse error: Parse error at 1:28\nExpected `Name`\nToken limit exceeded\n"
+        );
+    }
+
+    #[test]
+    fn selections() {
+        let query = "{ a { b ...F } } fragment F on T { c ... on U { d } }";
+        let limits = Limits::new().max_selections(6);
+        assert!(parse_query_with_limits::<&str>(query, &limits).is_ok());
+        let limits = Limits::new().max_selections(5);
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LimitExceeded);
+        assert_eq!(
+            err.to_string(),
+            "query parse error: Parse error at 1:38\nSelection limit exceeded\n"
+        );
+    }
+
+    #[test]
+    fn input_len() {
+        let query = "{ a b }";
+        let limits = Limits::new().max_input_len(7);
+        assert!(parse_query_with_limits::<&str>(query, &limits).is_ok());
+        let limits = Limits::new().max_input_len(6);
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LimitExceeded);
+        assert_eq!(
+            err.to_string(),
+            "query parse error: Parse error at 1:1\n\
+             Expected `{`, `query`, `mutation`, `subscription` or `fragment`\n\
+             Input limit exceeded\n"
+        );
+    }
+
+    #[test]
+    fn string_len() {
+        let schema = "\"\"\"Ab\\\"\"\"\"\"\" scalar A @d(x: \"\\u00e9\")";
+        let limits = Limits::new().max_string_len(6);
+        assert!(parse_schema_with_limits::<&str>(schema, &limits).is_ok());
+        let limits = Limits::new().max_string_len(5);
+        let err = parse_schema_with_limits::<&str>(schema, &limits).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LimitExceeded);
+        assert_eq!(
+            err.to_string(),
+            "schema parse error: Parse error at 1:1\n\
+             Expected `schema`, `extend`, `scalar`, `type`, `interface`, `union`, \
+             `enum`, `input` or `directive`\n\
+             String limit exceeded\n"
+        );
+
+        let query = "{ a(x: \"\\u00e9\") }";
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("query parse error: Parse error at 1:8\n"));
+        assert!(err.to_string().ends_with("\nString limit exceeded\n"));
+    }
+
+    #[test]
+    fn depth() {
+        let query = "{ a { b(x: [1]) } }";
+        let limits = Limits::new().max_depth(4);
+        assert!(parse_query_with_limits::<&str>(query, &limits).is_ok());
+        let limits = Limits::new().max_depth(3);
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::RecursionLimitExceeded);
+    }
+
+    #[test]
+    fn deep_nesting() {
+        const DEPTH: usize = 5000;
+        let query = format!(
+            "{{ a(x: {}1{}) {}b{} }}",
+            "[".repeat(DEPTH),
+            "]".repeat(DEPTH),
+            "{ a ".repeat(DEPTH),
+            " }".repeat(DEPTH)
+        );
+        let schema = format!(
+            "type T {{ f: {}Int{} }} input I {{ f: Int = {}1{} }}",
+            "[".repeat(DEPTH),
+            "!]".repeat(DEPTH),
+            "[".repeat(DEPTH),
+            "]".repeat(DEPTH)
+        );
+        let limits = Limits::new().max_depth(3 * DEPTH);
+        // without growing the stack, this overflows a much bigger one
+        let (query, schema) = std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .stack_size(256 * 1024)
+                .spawn_scoped(scope, || {
+                    (
+                        parse_query_with_limits::<&str>(&query, &limits),
+                        parse_schema_with_limits::<&str>(&schema, &limits),
+                    )
+                })
+                .unwrap()
+                .join()
+                .unwrap()
+        });
+        // dropping is recursive as well, so the documents are returned to
+        // this thread with its bigger stack
+        assert!(query.is_ok());
+        assert!(schema.is_ok());
+    }
+
+    #[test]
+    fn no_further_errors() {
+        let query = "{ a b c } { d } { e( }";
+        let limits = Limits::new().max_selections(2);
+        let err = parse_query_with_limits::<&str>(query, &limits).unwrap_err();
+        assert_eq!(err.errors().count(), 1);
+
+        let mut parser = Parser::new();
+        let (doc, errors) = parser
+            .limits(limits)
+            .parse_query_with_recovery::<&str>(query);
+        assert!(doc.definitions.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), ErrorCode::LimitExceeded);
+
+        parser.limits(Limits::new());
+        assert!(parser.parse_query::<&str>("{ a b c }").is_ok());
+    }
+}
@@ -0,0 +1,585 @@
+//! Diagnostic messages and their translation
+//!
+//! Every diagnostic produced by the parser is identified by a
+//! [`MessageKey`](enum.MessageKey.html) and a list of positional arguments.
+//! The actual wording is looked up in a [`Catalog`](trait.Catalog.html), so
+//! applications can render errors and warnings in other languages by
+//! providing their own templates.
+//!
+//! Only syntax errors and parser warnings can be localized this way. The
+//! errors of the passes over parsed documents, like `coerce_value`,
+//! `response_key_conflicts`, `check_implementations`, `Schema::new` and
+//! `suggest_fixes`, are rendered in English only. Their structured fields
+//! can be used to build messages in other languages.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::{Error, Errors, Info};
+use crate::position::Pos;
+use crate::snippet::{Annotation, Severity};
+use crate::syntax_error::SyntaxError;
+
+/// Identifies a diagnostic message independently of its wording
+///
+/// Templates reference arguments positionally: `{0}`, `{1}` and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKey {
+    /// Arbitrary text which is not translated: `{0}`
+    Text,
+    /// Prefix of query parse errors: `query parse error: {0}`
+    QueryParseError,
+    /// Prefix of schema parse errors: `schema parse error: {0}`
+    SchemaParseError,
+    /// Prefix of errors parsing mixed documents: `document parse error: {0}`
+    DocumentParseError,
+    /// Header of a parse error: `Parse error at {0}`
+    ParseErrorAt,
+    /// `Unexpected `{0}``
+    Unexpected,
+    /// `Expected {0}`, where the argument is a list of alternatives
+    Expected,
+    /// Separator between alternatives in a list: `, `
+    ListSeparator,
+    /// Separator before the last alternative in a list: ` or `
+    ListLastSeparator,
+    /// Summary of diagnostics omitted due to the limit: `and {0} more`
+    MoreDiagnostics,
+    /// Hint pointing to the specification: ` (see {0})`
+    SpecHint,
+    /// `Recursion limit exceeded`
+    RecursionLimitExceeded,
+    /// `Token limit exceeded`
+    TokenLimitExceeded,
+    /// `Selection limit exceeded`
+    SelectionLimitExceeded,
+    /// `Input limit exceeded`
+    InputLimitExceeded,
+    /// `String limit exceeded`
+    StringLimitExceeded,
+    /// `unterminated string value`
+    UnterminatedString,
+    /// `unterminated block string value`
+    UnterminatedBlockString,
+    /// `duplicate `{0}` operation`
+    DuplicateOperation,
+    /// Scalar type extension without directives
+    EmptyScalarExtension,
+    /// Object type extension without interfaces, directives and fields
+    EmptyObjectExtension,
+    /// Interface type extension without directives and fields
+    EmptyInterfaceExtension,
+    /// Union type extension without directives and types
+    EmptyUnionExtension,
+    /// Enum type extension without directives and values
+    EmptyEnumExtension,
+    /// Input object type extension without directives and fields
+    EmptyInputObjectExtension,
+    /// Header of a warning: `warning at {0}: {1}`
+    WarningAt,
+    /// `interface `{0}` is listed more than once`
+    DuplicateInterface,
+    /// `schema definition declares no root operation types`
+    EmptySchemaDefinition,
+    /// Integer literal which only fits into the BigInt extension
+    BigIntExtension,
+    /// `field `{0}` of the object is already given at {1}`
+    DuplicateObjectField,
+    /// `argument `{0}` is already given at {1}`
+    DuplicateArgument,
+}
+
+impl MessageKey {
+    /// Returns a stable identifier of the message suitable for use in
+    /// translation files
+    pub fn as_str(&self) -> &'static str {
+        use self::MessageKey::*;
+        match *self {
+            Text => "text",
+            QueryParseError => "query-parse-error",
+            SchemaParseError => "schema-parse-error",
+            DocumentParseError => "document-parse-error",
+            ParseErrorAt => "parse-error-at",
+            Unexpected => "unexpected",
+            Expected => "expected",
+            ListSeparator => "list-separator",
+            ListLastSeparator => "list-last-separator",
+            MoreDiagnostics => "more-diagnostics",
+            SpecHint => "spec-hint",
+            RecursionLimitExceeded => "recursion-limit-exceeded",
+            TokenLimitExceeded => "token-limit-exceeded",
+            SelectionLimitExceeded => "selection-limit-exceeded",
+            InputLimitExceeded => "input-limit-exceeded",
+            StringLimitExceeded => "string-limit-exceeded",
+            UnterminatedString => "unterminated-string",
+            UnterminatedBlockString => "unterminated-block-string",
+            DuplicateOperation => "duplicate-operation",
+            EmptyScalarExtension => "empty-scalar-extension",
+            EmptyObjectExtension => "empty-object-extension",
+            EmptyInterfaceExtension => "empty-interface-extension",
+            EmptyUnionExtension => "empty-union-extension",
+            EmptyEnumExtension => "empty-enum-extension",
+            EmptyInputObjectExtension => "empty-input-object-extension",
+            WarningAt => "warning-at",
+            DuplicateInterface => "duplicate-interface",
+            EmptySchemaDefinition => "empty-schema-definition",
+            BigIntExtension => "bigint-extension",
+            DuplicateObjectField => "duplicate-object-field",
+            DuplicateArgument => "duplicate-argument",
+        }
+    }
+
+    /// Returns the English template of the message
+    pub fn default_template(&self) -> &'static str {
+        use self::MessageKey::*;
+        match *self {
+            Text => "{0}",
+            QueryParseError => "query parse error: {0}",
+            SchemaParseError => "schema parse error: {0}",
+            DocumentParseError => "document parse error: {0}",
+            ParseErrorAt => "Parse error at {0}",
+            Unexpected => "Unexpected `{0}`",
+            Expected => "Expected {0}",
+            ListSeparator => ", ",
+            ListLastSeparator => " or ",
+            MoreDiagnostics => "and {0} more",
+            SpecHint => " (see {0})",
+            RecursionLimitExceeded => "Recursion limit exceeded",
+            TokenLimitExceeded => "Token limit exceeded",
+            SelectionLimitExceeded => "Selection limit exceeded",
+            InputLimitExceeded => "Input limit exceeded",
+            StringLimitExceeded => "String limit exceeded",
+            UnterminatedString => "unterminated string value",
+            UnterminatedBlockString => "unterminated block string value",
+            DuplicateOperation => "duplicate `{0}` operation",
+            EmptyScalarExtension => "Scalar type extension should contain at least one directive.",
+            EmptyObjectExtension => {
+                "Object type extension should contain at least \
+                 one interface, directive or field."
+            }
+            EmptyInterfaceExtension => {
+                "Interface type extension should contain at least \
+                 one directive or field."
+            }
+            EmptyUnionExtension => {
+                "Union type extension should contain at least one directive or type."
+            }
+            EmptyEnumExtension => {
+                "Enum type extension should contain at least one directive or value."
+            }
+            EmptyInputObjectExtension => {
+                "Input object type extension should contain at least \
+                 one directive or field."
+            }
+            WarningAt => "warning at {0}: {1}",
+            DuplicateInterface => "interface `{0}` is listed more than once",
+            EmptySchemaDefinition => "schema definition declares no root operation types",
+            BigIntExtension => {
+                "integer does not fit into i64 and is parsed using the \
+                 non-standard BigInt extension"
+            }
+            DuplicateObjectField => "field `{0}` of the object is already given at {1}",
+            DuplicateArgument => "argument `{0}` is already given at {1}",
+        }
+    }
+
+    /// Returns the section of the specification defining the rule which
+    /// the message is about, if any
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        use self::MessageKey::*;
+        let (section, title) = match *self {
+            DuplicateOperation | EmptySchemaDefinition => ("3.3.1", "Root Operation Types"),
+            EmptyScalarExtension => ("3.5.6", "Scalar Extensions"),
+            BigIntExtension => ("3.5.1", "Int"),
+            DuplicateInterface => ("3.6", "Objects"),
+            DuplicateObjectField => ("5.6.3", "Input Object Field Uniqueness"),
+            DuplicateArgument => ("5.4.2", "Argument Uniqueness"),
+            EmptyObjectExtension => ("3.6.3", "Object Extensions"),
+            EmptyInterfaceExtension => ("3.7.1", "Interface Extensions"),
+            EmptyUnionExtension => ("3.8.1", "Union Extensions"),
+            EmptyEnumExtension => ("3.9.1", "Enum Extensions"),
+            EmptyInputObjectExtension => ("3.10.1", "Input Object Extensions"),
+            _ => return None,
+        };
+        Some(SpecReference { section, title })
+    }
+}
+
+/// A reference to a section of the GraphQL specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpecReference {
+    /// Section number, e.g. `3.3.1`
+    pub section: &'static str,
+    /// Section title, e.g. `Root Operation Types`
+    pub title: &'static str,
+}
+
+impl SpecReference {
+    /// Returns a link to the section in the published specification
+    pub fn url(&self) -> String {
+        format!(
+            "https://spec.graphql.org/October2021/#sec-{}",
+            self.title.replace(' ', "-")
+        )
+    }
+}
+
+impl fmt::Display for SpecReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "§{} {}", self.section, self.title)
+    }
+}
+
+/// A source of message templates
+///
+/// Keys for which `template` returns `None` are rendered using the built-in
+/// English wording. Catalogs apply to syntax errors and parser warnings
+/// only, see the module documentation.
+pub trait Catalog {
+    fn template(&self, key: MessageKey) -> Option<Cow<'_, str>>;
+}
+
+/// The built-in English catalog
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultCatalog;
+
+impl Catalog for DefaultCatalog {
+    fn template(&self, _key: MessageKey) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+impl Catalog for HashMap<MessageKey, String> {
+    fn template(&self, key: MessageKey) -> Option<Cow<'_, str>> {
+        self.get(&key).map(|t| Cow::Borrowed(t.as_str()))
+    }
+}
+
+/// A diagnostic message: a key with its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub key: MessageKey,
+    pub args: Vec<String>,
+}
+
+impl Message {
+    pub fn new(key: MessageKey) -> Message {
+        Message {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn with_args<I, S>(key: MessageKey, args: I) -> Message
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Message {
+            key,
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Wraps a text which has no message key
+    pub fn text<S: Into<String>>(text: S) -> Message {
+        Message::with_args(MessageKey::Text, Some(text))
+    }
+
+    /// Returns the section of the specification the message refers to
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        self.key.spec_reference()
+    }
+
+    /// Renders the message using templates from the catalog
+    pub fn render(&self, catalog: &dyn Catalog) -> String {
+        let template = catalog
+            .template(self.key)
+            .unwrap_or_else(|| Cow::Borrowed(self.key.default_template()));
+        substitute(&template, &self.args)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render(&DefaultCatalog))
+    }
+}
+
+fn substitute(template: &str, args: &[String]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        let arg = tail
+            .find('}')
+            .and_then(|end| tail[..end].parse::<usize>().ok().map(|idx| (idx, end)));
+        match arg {
+            Some((idx, end)) => {
+                if let Some(value) = args.get(idx) {
+                    result.push_str(value);
+                }
+                rest = &tail[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = tail;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Part of a parse error which is either a translatable message or a
+/// fragment of source text
+#[derive(Debug, Clone, PartialEq)]
+enum Detail {
+    Message(Message),
+    Text(String),
+}
+
+impl Detail {
+    fn from_info(info: &Info) -> Detail {
+        match *info {
+            Info::Raised(_, ref message) => Detail::Message(message.clone()),
+            ref info => Detail::Text(info.to_string()),
+        }
+    }
+
+    fn render(&self, catalog: &dyn Catalog) -> String {
+        match *self {
+            Detail::Message(ref msg) => msg.render(catalog),
+            Detail::Text(ref text) => text.clone(),
+        }
+    }
+}
+
+/// Structured contents of a parse error, kept around so that the error
+/// can be rendered with any catalog
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ErrorMessages {
+    position: Pos,
+    error: SyntaxError,
+    unexpected: Vec<Detail>,
+    expected: Vec<Detail>,
+    messages: Vec<Detail>,
+}
+
+/// Serializes in the shape of errors in a GraphQL response, the
+/// structured details going into `extensions`
+#[cfg(feature = "serde")]
+impl serde::Serialize for ErrorMessages {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        #[derive(serde::Serialize)]
+        struct Location {
+            line: usize,
+            column: usize,
+        }
+
+        let location = Location {
+            line: self.position.line,
+            column: self.position.column,
+        };
+        let mut state = serializer.serialize_struct("Error", 3)?;
+        state.serialize_field("message", &self.annotation(&DefaultCatalog).title)?;
+        state.serialize_field("locations", &[location])?;
+        state.serialize_field("extensions", &self.error)?;
+        state.end()
+    }
+}
+
+impl ErrorMessages {
+    pub(crate) fn from_errors(errors: &Errors) -> ErrorMessages {
+        let mut result = ErrorMessages {
+            position: errors.position,
+            error: SyntaxError::from_errors(errors),
+            unexpected: Vec::new(),
+            expected: Vec::new(),
+            messages: Vec::new(),
+        };
+        for error in &errors.errors {
+            match *error {
+                Error::Unexpected(ref info) => result.unexpected.push(Detail::from_info(info)),
+                Error::Expected(ref info) => result.expected.push(Detail::from_info(info)),
+                Error::Message(ref info) => result.messages.push(Detail::from_info(info)),
+            }
+        }
+        result
+    }
+
+    pub(crate) fn position(&self) -> Pos {
+        self.position
+    }
+
+    pub(crate) fn error(&self) -> &SyntaxError {
+        &self.error
+    }
+
+    /// Returns the section of the specification the first message which
+    /// refers to one is about
+    pub(crate) fn spec_reference(&self) -> Option<SpecReference> {
+        self.unexpected
+            .iter()
+            .chain(&self.expected)
+            .chain(&self.messages)
+            .find_map(|detail| match *detail {
+                Detail::Message(ref message) => message.spec_reference(),
+                Detail::Text(_) => None,
+            })
+    }
+
+    /// Returns the error as an annotation of the offending token
+    ///
+    /// The unexpected token and messages form the title, the expected
+    /// tokens are the label.
+    pub(crate) fn annotation(&self, catalog: &dyn Catalog) -> Annotation {
+        let mut title = Vec::new();
+        let unexpected = match self.error.token {
+            Some(ref token) => vec![token.clone()],
+            None => self
+                .unexpected
+                .iter()
+                .map(|item| item.render(catalog))
+                .collect(),
+        };
+        for item in unexpected {
+            title.push(Message::with_args(MessageKey::Unexpected, Some(item)).render(catalog));
+        }
+        title.extend(self.messages.iter().map(|item| item.render(catalog)));
+        if title.is_empty() {
+            title.push(
+                Message::with_args(MessageKey::ParseErrorAt, Some(self.position.to_string()))
+                    .render(catalog),
+            );
+        }
+        Annotation {
+            severity: Severity::Error,
+            code: Some(self.error.code.as_str()),
+            title: title.join("; "),
+            span: self.error.span,
+            label: self.expected_line(catalog).unwrap_or_default(),
+        }
+    }
+
+    fn expected_line(&self, catalog: &dyn Catalog) -> Option<String> {
+        if self.expected.is_empty() {
+            return None;
+        }
+        let mut list = String::new();
+        for (idx, item) in self.expected.iter().enumerate() {
+            if idx > 0 {
+                let key = if idx == self.expected.len() - 1 {
+                    MessageKey::ListLastSeparator
+                } else {
+                    MessageKey::ListSeparator
+                };
+                list.push_str(&Message::new(key).render(catalog));
+            }
+            list.push('`');
+            list.push_str(&item.render(catalog));
+            list.push('`');
+        }
+        Some(Message::with_args(MessageKey::Expected, Some(list)).render(catalog))
+    }
+
+    /// Renders all lines of the error, mirroring the layout used by combine
+    pub(crate) fn render(&self, catalog: &dyn Catalog) -> String {
+        let mut out = Message::with_args(MessageKey::ParseErrorAt, Some(self.position.to_string()))
+            .render(catalog);
+        out.push('\n');
+        for item in &self.unexpected {
+            out.push_str(
+                &Message::with_args(MessageKey::Unexpected, Some(item.render(catalog)))
+                    .render(catalog),
+            );
+            out.push('\n');
+        }
+        if let Some(expected) = self.expected_line(catalog) {
+            out.push_str(&expected);
+            out.push('\n');
+        }
+        for item in &self.messages {
+            out.push_str(&item.render(catalog));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{Message, MessageKey};
+    use crate::query::parse_query;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn default_rendering() {
+        let msg = Message::with_args(MessageKey::DuplicateInterface, Some("Node"));
+        assert_eq!(msg.to_string(), "interface `Node` is listed more than once");
+    }
+
+    #[test]
+    fn spec_reference() {
+        let spec = MessageKey::EmptySchemaDefinition.spec_reference().unwrap();
+        assert_eq!(spec.to_string(), "§3.3.1 Root Operation Types");
+        assert_eq!(
+            spec.url(),
+            "https://spec.graphql.org/October2021/#sec-Root-Operation-Types"
+        );
+        assert_eq!(MessageKey::Unexpected.spec_reference(), None);
+    }
+
+    #[test]
+    fn custom_catalog() {
+        let mut catalog = HashMap::new();
+        catalog.insert(
+            MessageKey::QueryParseError,
+            "erreur d'analyse de requête : {0}".to_string(),
+        );
+        catalog.insert(MessageKey::ParseErrorAt, "Erreur à {0}".to_string());
+        catalog.insert(MessageKey::Unexpected, "Inattendu « {0} »".to_string());
+        catalog.insert(MessageKey::Expected, "Attendu {0}".to_string());
+        catalog.insert(MessageKey::ListLastSeparator, " ou ".to_string());
+        let err = parse_query::<&str>("where").unwrap_err();
+        assert_eq!(
+            err.localize(&catalog),
+            "erreur d'analyse de requête : Erreur à 1:1\n\
+             Inattendu « where[Name] »\n\
+             Attendu `{`, `query`, `mutation`, `subscription` ou `fragment`\n"
+        );
+    }
+
+    #[test]
+    fn keyed_parser_message() {
+        let mut catalog = HashMap::new();
+        catalog.insert(
+            MessageKey::RecursionLimitExceeded,
+            "Profondeur maximale atteinte".to_string(),
+        );
+        let err = parse_query::<&str>(&"{ a ".repeat(60)).unwrap_err();
+        assert!(err
+            .localize(&catalog)
+            .ends_with("Profondeur maximale atteinte\n"));
+
+        catalog.insert(
+            MessageKey::DuplicateOperation,
+            "opération `{0}` en double".to_string(),
+        );
+        catalog.insert(
+            MessageKey::EmptyUnionExtension,
+            "extension d'union vide".to_string(),
+        );
+        let err = parse_schema::<&str>("schema { query: Q query: R }").unwrap_err();
+        assert!(err
+            .localize(&catalog)
+            .ends_with("Unexpected `opération `query` en double`\n"));
+        let err = parse_schema::<&str>("extend union U").unwrap_err();
+        assert!(err.localize(&catalog).contains("`extension d'union vide`"));
+    }
+}
@@ -0,0 +1,384 @@
+//! Conversion of syntax trees to other `Text` types
+//!
+//! Every node has a `map_text` method converting it to another `Text`
+//! type, and an `into_static` method copying it into a node of
+//! `'static` lifetime with `String` text, which can be kept after the
+//! source is dropped. Documents have `to_owned_document` instead, since
+//! `into_static` already converts documents of `String`, `Arc<str>` and
+//! `Rc<str>` without copying.
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::common::Text;
+use crate::{document, query, schema};
+
+/// Implements `map_text` and `into_static` for structs, converting each
+/// field as listed
+///
+/// The struct is destructured so that adding a field fails to compile
+/// until it is listed here.
+macro_rules! impl_map_text {
+    (@convert copy, $v:ident, $f:ident) => { $v.clone() };
+    (@convert text, $v:ident, $f:ident) => { $f($v.as_ref()) };
+    (@convert opt_text, $v:ident, $f:ident) => { $v.as_ref().map(|v| $f(v.as_ref())) };
+    (@convert texts, $v:ident, $f:ident) => { $v.iter().map(|v| $f(v.as_ref())).collect() };
+    (@convert node, $v:ident, $f:ident) => { $v.map_text($f) };
+    (@convert opt_node, $v:ident, $f:ident) => { $v.as_ref().map(|v| v.map_text($f)) };
+    (@convert nodes, $v:ident, $f:ident) => { $v.iter().map(|v| v.map_text($f)).collect() };
+    (@convert pairs, $v:ident, $f:ident) => {
+        $v.iter().map(|(k, v)| ($f(k.as_ref()), v.map_text($f))).collect()
+    };
+    ($($module:ident::$name:ident { $($field:ident: $convert:ident),* $(,)? })*) => {
+        $(
+            impl<'a, T: Text<'a>> $module::$name<'a, T> {
+                /// Converts the names and literals with `f`, see
+                /// `Document::map_text`
+                pub fn map_text<'b, U, F>(&self, f: &mut F) -> $module::$name<'b, U>
+                where
+                    U: Text<'b>,
+                    F: FnMut(&str) -> U::Value,
+                {
+                    let $module::$name { $($field),* } = self;
+                    $module::$name {
+                        $($field: impl_map_text!(@convert $convert, $field, f)),*
+                    }
+                }
+
+                pub fn into_static(&self) -> $module::$name<'static, String> {
+                    self.map_text(&mut |text: &str| text.to_string())
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `map_text` and `into_static` for enums whose variants all
+/// wrap a single node
+macro_rules! impl_map_text_enum {
+    ($($module:ident::$name:ident { $($variant:ident),* $(,)? })*) => {
+        $(
+            impl<'a, T: Text<'a>> $module::$name<'a, T> {
+                /// Converts the names and literals with `f`, see
+                /// `Document::map_text`
+                pub fn map_text<'b, U, F>(&self, f: &mut F) -> $module::$name<'b, U>
+                where
+                    U: Text<'b>,
+                    F: FnMut(&str) -> U::Value,
+                {
+                    match self {
+                        $(
+                            $module::$name::$variant(v) => {
+                                $module::$name::$variant(v.map_text(f))
+                            }
+                        )*
+                    }
+                }
+
+                pub fn into_static(&self) -> $module::$name<'static, String> {
+                    self.map_text(&mut |text: &str| text.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_map_text! {
+    query::FragmentDefinition {
+        position: copy, end: copy, name: text, type_condition: node, directives: nodes,
+        selection_set: node,
+    }
+    query::Operation {
+        kind: copy, position: copy, end: copy, name: opt_text, variable_definitions: nodes,
+        directives: nodes, selection_set: node,
+    }
+    query::Query {
+        position: copy, end: copy, name: opt_text, variable_definitions: nodes,
+        directives: nodes, selection_set: node,
+    }
+    query::Mutation {
+        position: copy, end: copy, name: opt_text, variable_definitions: nodes,
+        directives: nodes, selection_set: node,
+    }
+    query::Subscription {
+        position: copy, end: copy, name: opt_text, variable_definitions: nodes,
+        directives: nodes, selection_set: node,
+    }
+    query::SelectionSet { span: copy, items: nodes }
+    query::VariableDefinition {
+        position: copy, end: copy, name: text, var_type: node, default_value: opt_node,
+    }
+    query::Field {
+        position: copy, end: copy, alias: opt_text, name: text, arguments: pairs,
+        directives: nodes, selection_set: node,
+    }
+    query::FragmentSpread { position: copy, end: copy, fragment_name: text, directives: nodes }
+    query::InlineFragment {
+        position: copy, end: copy, type_condition: opt_node, directives: nodes,
+        selection_set: node,
+    }
+
+    schema::SchemaDefinition {
+        position: copy, end: copy, directives: nodes, query: opt_text, mutation: opt_text,
+        subscription: opt_text,
+    }
+    schema::ScalarType {
+        position: copy, end: copy, description: copy, name: text, directives: nodes,
+    }
+    schema::ScalarTypeExtension { position: copy, end: copy, name: text, directives: nodes }
+    schema::ObjectType {
+        position: copy, end: copy, description: copy, name: text,
+        implements_interfaces: texts, directives: nodes, fields: nodes,
+    }
+    schema::ObjectTypeExtension {
+        position: copy, end: copy, name: text, implements_interfaces: texts,
+        directives: nodes, fields: nodes,
+    }
+    schema::Field {
+        position: copy, end: copy, description: copy, name: text, arguments: nodes,
+        field_type: node, directives: nodes,
+    }
+    schema::InputValue {
+        position: copy, end: copy, description: copy, name: text, value_type: node,
+        default_value: opt_node, directives: nodes,
+    }
+    schema::InterfaceType {
+        position: copy, end: copy, description: copy, name: text,
+        implements_interfaces: texts, directives: nodes, fields: nodes,
+    }
+    schema::InterfaceTypeExtension {
+        position: copy, end: copy, name: text, implements_interfaces: texts,
+        directives: nodes, fields: nodes,
+    }
+    schema::UnionType {
+        position: copy, end: copy, description: copy, name: text, directives: nodes,
+        types: texts,
+    }
+    schema::UnionTypeExtension {
+        position: copy, end: copy, name: text, directives: nodes, types: texts,
+    }
+    schema::EnumType {
+        position: copy, end: copy, description: copy, name: text, directives: nodes,
+        values: nodes,
+    }
+    schema::EnumValue {
+        position: copy, end: copy, description: copy, name: text, directives: nodes,
+    }
+    schema::EnumTypeExtension {
+        position: copy, end: copy, name: text, directives: nodes, values: nodes,
+    }
+    schema::InputObjectType {
+        position: copy, end: copy, description: copy, name: text, directives: nodes,
+        fields: nodes,
+    }
+    schema::InputObjectTypeExtension {
+        position: copy, end: copy, name: text, directives: nodes, fields: nodes,
+    }
+    schema::DirectiveDefinition {
+        position: copy, end: copy, description: copy, name: text, arguments: nodes,
+        repeatable: copy, locations: copy,
+    }
+}
+
+impl_map_text_enum! {
+    query::Definition { Operation, Fragment }
+    query::OperationDefinition { SelectionSet, Query, Mutation, Subscription }
+    query::Selection { Field, FragmentSpread, InlineFragment }
+    schema::Definition { SchemaDefinition, TypeDefinition, TypeExtension, DirectiveDefinition }
+    schema::TypeDefinition { Scalar, Object, Interface, Union, Enum, InputObject }
+    schema::TypeExtension { Scalar, Object, Interface, Union, Enum, InputObject }
+    document::Definition { Executable, TypeSystem }
+}
+
+impl<'a, T: Text<'a>> query::TypeCondition<'a, T> {
+    /// Converts the type name with `f`, see `Document::map_text`
+    pub fn map_text<'b, U, F>(&self, f: &mut F) -> query::TypeCondition<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(&str) -> U::Value,
+    {
+        match self {
+            query::TypeCondition::On(name) => query::TypeCondition::On(f(name.as_ref())),
+        }
+    }
+
+    pub fn into_static(&self) -> query::TypeCondition<'static, String> {
+        self.map_text(&mut |text: &str| text.to_string())
+    }
+}
+
+macro_rules! impl_to_owned_document {
+    ($($module:ident),*) => {
+        $(
+            impl<'a, T: Text<'a>> $module::Document<'a, T> {
+                /// Converts the document to another `Text` type, producing
+                /// every piece of text with `f`
+                ///
+                /// `f` receives names, comments and the source literals of
+                /// strings and floats. Use it to share names with an
+                /// `Interner`, or to keep a document borrowing the source
+                /// in a different string type.
+                pub fn map_text<'b, U, F>(&self, f: &mut F) -> $module::Document<'b, U>
+                where
+                    U: Text<'b>,
+                    F: FnMut(&str) -> U::Value,
+                {
+                    $module::Document {
+                        definitions: self.definitions.iter().map(|d| d.map_text(f)).collect(),
+                        comments: self.comments.iter().map(|c| c.map_text(f)).collect(),
+                    }
+                }
+
+                /// Copies the document into one which owns its strings, so
+                /// that it can be kept after the source is dropped
+                ///
+                /// Documents parsed as `String` convert for free with
+                /// `into_static` instead.
+                pub fn to_owned_document(&self) -> $module::Document<'static, String> {
+                    self.map_text(&mut |text: &str| text.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_to_owned_document!(query, schema, document);
+
+/// Implements `into_static` for documents of text types which own their
+/// strings, like `Document<String>::into_static`
+macro_rules! impl_shared_into_static {
+    ($($module:ident::Document<$text:ty>),* $(,)?) => {
+        $(
+            impl<'a> $module::Document<'a, $text> {
+                /// Changes the lifetime of the document to `'static`
+                ///
+                /// This is free: the names are reference counted, so the
+                /// document borrows nothing from the source.
+                pub fn into_static(self) -> $module::Document<'static, $text> {
+                    // safe for the same reason as `Document<String>::into_static`,
+                    // the lifetime is only carried by the `Text` type
+                    unsafe { std::mem::transmute::<_, $module::Document<'static, $text>>(self) }
+                }
+            }
+        )*
+    };
+}
+
+impl_shared_into_static!(
+    query::Document<Arc<str>>,
+    query::Document<Rc<str>>,
+    schema::Document<Arc<str>>,
+    schema::Document<Rc<str>>,
+);
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use crate::query::Document;
+    use crate::{parse_document, parse_query, parse_schema, Parser};
+
+    fn owned_query(source: &str) -> Document<'static, String> {
+        let source = source.to_string();
+        let doc = Parser::new()
+            .preserve_comments(true)
+            .parse_query::<&str>(&source)
+            .unwrap();
+        doc.to_owned_document()
+    }
+
+    #[test]
+    fn shared_documents() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let source = String::from("query Q($a: Int = 1) { a(s: \"x\") b }");
+        let doc = parse_query::<Arc<str>>(&source).unwrap().into_static();
+        let rc = parse_query::<Rc<str>>(&source).unwrap().into_static();
+        drop(source);
+        assert_send_sync(&doc);
+        let shared = Arc::new(doc);
+        let printed = {
+            let shared = shared.clone();
+            std::thread::spawn(move || shared.to_string())
+                .join()
+                .unwrap()
+        };
+        assert_eq!(printed, rc.to_string());
+        assert_eq!(shared.operations().next().unwrap().name(), Some("Q"));
+        let schema = parse_schema::<Rc<str>>("type A { a: Int }")
+            .unwrap()
+            .into_static();
+        assert_eq!(schema.definitions.len(), 1);
+    }
+
+    #[test]
+    fn interned_documents() {
+        use crate::query::{Interner, Names};
+
+        let mut interner = Interner::<Rc<str>>::new();
+        let source = "query Q($id: ID) { user(id: $id) { id name } }";
+        let first = parse_query::<&str>(source).unwrap();
+        let first: Document<Rc<str>> = first.map_text(&mut |name| interner.intern(name));
+        let schema = parse_schema::<&str>("type User { id: ID name: String }").unwrap();
+        let schema: crate::schema::Document<Rc<str>> =
+            schema.map_text(&mut |name| interner.intern(name));
+        assert_eq!(
+            first.to_string(),
+            parse_query::<&str>(source).unwrap().to_string()
+        );
+        assert_eq!(
+            schema.to_string(),
+            "type User {\n  id: ID\n  name: String\n}\n"
+        );
+        // Q id ID user name User String
+        assert_eq!(interner.len(), 7);
+        let id = interner.intern("id");
+        assert!(Rc::ptr_eq(&id, &interner.intern("id")));
+        // the interner, four uses in the query, one in the schema and `id`
+        assert_eq!(Rc::strong_count(&id), 7);
+
+        let boxed = parse_query::<Names<Box<str>>>(source).unwrap();
+        assert_eq!(boxed.to_owned_document(), first.to_owned_document());
+    }
+
+    #[test]
+    fn owned_documents() {
+        let source = r#"
+            # comment
+            query Q($id: ID = 1) @op { a: user(id: $id, s: "x\n") { ...F ... on U @d { b } } }
+            fragment F on User { id }
+            { shorthand }
+        "#;
+        let owned = owned_query(source);
+        let doc = Parser::new()
+            .preserve_comments(true)
+            .parse_query::<String>(source)
+            .unwrap()
+            .into_static();
+        assert_eq!(owned, doc);
+        assert_eq!(owned.comments[0].text, " comment");
+
+        let source = r#"
+            schema { query: Q }
+            "D" type Q implements I @d(a: [1]) { f(a: Int = 1): [I!]! }
+            extend type Q { g: Int }
+            interface I { f(a: Int): [I!]! }
+            union U = Q
+            enum E { A B @deprecated }
+            input In { a: Int = 2 }
+            scalar S
+            directive @d(a: [Int]) repeatable on OBJECT
+        "#;
+        let schema = parse_schema::<&str>(source).unwrap();
+        assert_eq!(
+            schema.to_owned_document(),
+            parse_schema::<String>(source).unwrap().into_static()
+        );
+        let document = parse_document::<&str>(source).unwrap();
+        assert_eq!(document.to_owned_document().definitions.len(), 9);
+
+        let query = parse_query::<&str>("{ a }").unwrap();
+        let field = query.definitions[0].into_static();
+        assert_eq!(field.to_string(), "{\n  a\n}\n");
+    }
+}
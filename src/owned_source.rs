@@ -0,0 +1,148 @@
+//! Documents which keep their source next to the syntax tree
+//!
+//! Parsing as `&str` is the cheapest way to get an AST, but the result
+//! borrows the source, so it can't easily be stored in long-lived
+//! structures. `OwnedDocument` holds both the source and the borrowed
+//! document, so it can be moved around freely while still being parsed
+//! without copying any strings.
+use std::fmt;
+
+use crate::Parser;
+
+/// Defines `OwnedDocument` for each of the document kinds, parsed with the
+/// given `Parser` method
+macro_rules! impl_owned_document {
+    ($($module:ident: $parse:ident),* $(,)?) => {
+        $(
+            pub mod $module {
+                use super::*;
+                use crate::$module::{Document, ParseError};
+
+                /// A document together with the source it borrows from
+                ///
+                /// The document is parsed as `&str`, so none of its strings
+                /// are copied.
+                pub struct OwnedDocument {
+                    // Declared first so it is dropped before the source.
+                    // The `'static` lifetime is a lie: the strings point into
+                    // `source`, whose heap buffer never moves or changes
+                    // while the document exists, and they are only handed
+                    // out with lifetimes bound to `&self`.
+                    document: Document<'static, &'static str>,
+                    source: String,
+                }
+
+                impl OwnedDocument {
+                    /// Parses the source with default settings
+                    pub fn parse<S: Into<String>>(source: S) -> Result<OwnedDocument, ParseError> {
+                        OwnedDocument::parse_with(&mut Parser::new(), source)
+                    }
+
+                    /// Parses the source with the given parser, whose
+                    /// warnings are updated as usual
+                    pub fn parse_with<S: Into<String>>(
+                        parser: &mut Parser,
+                        source: S,
+                    ) -> Result<OwnedDocument, ParseError> {
+                        let source = source.into();
+                        // SAFETY: see the comment on the `document` field
+                        let text: &'static str =
+                            unsafe { &*(source.as_str() as *const str) };
+                        let document = parser.$parse::<&'static str>(text)?;
+                        Ok(OwnedDocument { document, source })
+                    }
+
+                    /// Returns the parsed document, borrowing from `self`
+                    pub fn document<'s>(&'s self) -> &'s Document<'s, &'s str> {
+                        // SAFETY: the strings live as long as `self`, and
+                        // the document can't be modified through a shared
+                        // reference. The cast is needed because documents
+                        // are invariant in their lifetime.
+                        unsafe {
+                            &*(&self.document as *const Document<'static, &'static str>
+                                as *const Document<'s, &'s str>)
+                        }
+                    }
+
+                    /// Returns the source the document was parsed from
+                    pub fn source(&self) -> &str {
+                        &self.source
+                    }
+
+                    /// Drops the document and returns its source
+                    pub fn into_source(self) -> String {
+                        let OwnedDocument { document, source } = self;
+                        drop(document);
+                        source
+                    }
+                }
+
+                impl fmt::Debug for OwnedDocument {
+                    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.debug_struct("OwnedDocument")
+                            .field("document", self.document())
+                            .finish()
+                    }
+                }
+
+                impl fmt::Display for OwnedDocument {
+                    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        fmt::Display::fmt(self.document(), f)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_owned_document! {
+    query: parse_query,
+    schema: parse_schema,
+    document: parse_document,
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{query, schema};
+    use crate::query::{Definition, OperationDefinition};
+    use crate::Parser;
+
+    #[test]
+    fn kept_in_long_lived_structures() {
+        let mut store = HashMap::new();
+        for name in ["A", "B"] {
+            let source = format!("query {} {{ user {{ name }} }}", name);
+            let owned = query::OwnedDocument::parse(source).unwrap();
+            store.insert(name, owned);
+        }
+        let owned = store.remove("B").unwrap();
+        match &owned.document().definitions[0] {
+            Definition::Operation(OperationDefinition::Query(q)) => {
+                assert_eq!(q.name, Some("B"));
+                // the name borrows from the source instead of being copied
+                let range = owned.source().as_bytes().as_ptr_range();
+                assert!(range.contains(&q.name.unwrap().as_ptr()));
+            }
+            other => panic!("unexpected definition {:?}", other),
+        }
+        assert_eq!(owned.to_string(), "query B {\n  user {\n    name\n  }\n}\n");
+        assert_eq!(owned.into_source(), "query B { user { name } }");
+
+        assert!(query::OwnedDocument::parse("{ a").is_err());
+    }
+
+    #[test]
+    fn parse_with_options() {
+        let mut parser = Parser::new();
+        parser.preserve_comments(true);
+        let owned = schema::OwnedDocument::parse_with(&mut parser, "# hello\nscalar Date").unwrap();
+        assert_eq!(owned.document().comments[0].text, " hello");
+        assert_eq!(owned.document().definitions.len(), 1);
+
+        let both = super::document::OwnedDocument::parse("scalar Date\n{ a }").unwrap();
+        assert_eq!(both.document().definitions.len(), 2);
+        assert!(format!("{:?}", both).starts_with("OwnedDocument { document: Document {"));
+    }
+}
@@ -0,0 +1,31 @@
+//! Passes over independent definitions, run on all threads by the `par_*`
+//! functions of the `rayon` feature
+
+/// Maps the items along with their indices, keeping their order
+pub(crate) fn map<'i, I, R, F>(items: &'i [I], f: F) -> Vec<R>
+where
+    F: Fn(usize, &'i I) -> R,
+{
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| f(i, item))
+        .collect()
+}
+
+/// Same as `map`, on all threads
+#[cfg(feature = "rayon")]
+pub(crate) fn par_map<'i, I, R, F>(items: &'i [I], f: F) -> Vec<R>
+where
+    I: Sync,
+    R: Send,
+    F: Fn(usize, &'i I) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    items
+        .par_iter()
+        .enumerate()
+        .map(|(i, item)| f(i, item))
+        .collect()
+}
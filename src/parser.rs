@@ -0,0 +1,545 @@
+//! Parser which can be reused for many documents
+//!
+use crate::common::Text;
+use crate::diagnostic::DEFAULT_LIMIT;
+use crate::limits::Limits;
+use crate::position::{ColumnUnit, Pos, SourceId};
+use crate::tokenizer::{CommentBuffer, TokenStream};
+use crate::warning::Warning;
+use crate::{document, query, schema};
+
+/// A parser keeping its settings and scratch buffers between documents
+///
+/// Tokens are streamed rather than collected. The buffers filled while
+/// parsing, the list of warnings and, if comments are preserved, the list
+/// of comments, keep their allocations for the following documents. This
+/// helps when parsing many small documents, as a server does for incoming
+/// queries. The AST itself is allocated anew for every document.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::Parser;
+///
+/// let mut parser = Parser::new();
+/// for query in &["{ a }", "{ b(x: 1) }"] {
+///     let ast = parser.parse_query::<&str>(query)?;
+///     assert!(parser.warnings().is_empty());
+///     # let _ = ast;
+/// }
+/// # Ok::<(), graphql_parser::query::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    options: ParserOptions,
+    warnings: Vec<Warning>,
+    /// Comments of the last document, converted to `Comment` when it ends
+    comments: CommentBuffer,
+}
+
+/// Settings of the parser
+///
+/// Options are accepted by `Parser::with_options` and by the
+/// `parse_*_with_options` functions, so that new settings don't need new
+/// entry points.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{ColumnUnit, Limits, ParserOptions};
+/// use graphql_parser::query::parse_query_with_options;
+///
+/// let options = ParserOptions::new()
+///     .column_unit(ColumnUnit::Utf16)
+///     .limits(Limits::new().max_tokens(1000));
+/// let (doc, warnings) = parse_query_with_options::<&str>("{ a }", &options)?;
+/// assert!(warnings.is_empty());
+/// # let _ = doc;
+/// # Ok::<(), graphql_parser::query::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    warning_limit: usize,
+    error_limit: usize,
+    column_unit: ColumnUnit,
+    source: SourceId,
+    preserve_comments: bool,
+    limits: Limits,
+    bigint_extension: bool,
+    ints_as_bigint: bool,
+    duplicate_arguments: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        ParserOptions {
+            warning_limit: DEFAULT_LIMIT,
+            error_limit: DEFAULT_LIMIT,
+            column_unit: ColumnUnit::default(),
+            source: SourceId::default(),
+            preserve_comments: false,
+            limits: Limits::default(),
+            bigint_extension: true,
+            ints_as_bigint: false,
+            duplicate_arguments: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> ParserOptions {
+        ParserOptions::default()
+    }
+
+    /// Sets the maximum number of warnings kept for each document
+    pub fn warning_limit(mut self, limit: usize) -> ParserOptions {
+        self.warning_limit = limit;
+        self
+    }
+
+    /// Sets the maximum number of errors reported for each document
+    ///
+    /// Applies to the errors of a `ParseError` and to the errors returned
+    /// by the `parse_*_with_recovery` functions. If there are more, the
+    /// last error says how many of them were omitted.
+    pub fn error_limit(mut self, limit: usize) -> ParserOptions {
+        self.error_limit = limit;
+        self
+    }
+
+    /// Sets the unit in which columns of reported positions are counted
+    pub fn column_unit(mut self, unit: ColumnUnit) -> ParserOptions {
+        self.column_unit = unit;
+        self
+    }
+
+    /// Sets the source identifier stored in positions
+    pub fn source(mut self, source: SourceId) -> ParserOptions {
+        self.source = source;
+        self
+    }
+
+    /// Collects the `#` comments into the `comments` field of documents
+    pub fn preserve_comments(mut self, preserve: bool) -> ParserOptions {
+        self.preserve_comments = preserve;
+        self
+    }
+
+    /// Sets the limits on the size of documents
+    pub fn limits(mut self, limits: Limits) -> ParserOptions {
+        self.limits = limits;
+        self
+    }
+
+    /// Accepts integers which don't fit into i64 as `Value::BigInt`,
+    /// enabled by default
+    ///
+    /// BigInt is an extension of the GraphQL specification. If disabled,
+    /// larger integers are rejected with `ErrorCode::NumberTooLarge`, like
+    /// other GraphQL implementations do.
+    pub fn bigint_extension(mut self, enabled: bool) -> ParserOptions {
+        self.bigint_extension = enabled;
+        self
+    }
+
+    /// Parses every integer literal as `Value::BigInt`, whatever its size
+    ///
+    /// Code consuming the AST then has a single path for integers. Small
+    /// integers parsed this way don't produce a BigInt warning.
+    pub fn ints_as_bigint(mut self, enabled: bool) -> ParserOptions {
+        self.ints_as_bigint = enabled;
+        self
+    }
+
+    /// Reports arguments of a field or directive which are given more than
+    /// once as warnings, disabled by default
+    ///
+    /// Such documents are invalid, see the Argument Uniqueness rule of
+    /// the specification. The warning points at the repeated argument and
+    /// names the position of the first one.
+    pub fn warn_duplicate_arguments(mut self, enabled: bool) -> ParserOptions {
+        self.duplicate_arguments = enabled;
+        self
+    }
+
+    /// Returns a token stream over `s` configured by the options
+    pub(crate) fn tokens<'a>(&self, s: &'a str) -> TokenStream<'a> {
+        self.tokens_at(s, Pos::start_of(self.source))
+    }
+
+    /// Returns a token stream over `s` configured by the options, which
+    /// reports positions as if `s` started at `position`
+    pub(crate) fn tokens_at<'a>(&self, s: &'a str, position: Pos) -> TokenStream<'a> {
+        let mut tokens =
+            TokenStream::with_options(s, position, self.column_unit, self.preserve_comments);
+        tokens.set_limits(&self.limits);
+        tokens.set_warning_limit(self.warning_limit);
+        tokens.set_error_limit(self.error_limit);
+        tokens.set_bigint_extension(self.bigint_extension);
+        tokens.set_ints_as_bigint(self.ints_as_bigint);
+        tokens.set_duplicate_arguments(self.duplicate_arguments);
+        tokens
+    }
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    /// Creates a parser with the given settings
+    pub fn with_options(options: ParserOptions) -> Parser {
+        Parser {
+            options,
+            warnings: Vec::new(),
+            comments: Vec::new(),
+        }
+    }
+
+    /// Returns the settings of the parser
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Sets the maximum number of warnings kept for each document
+    pub fn warning_limit(&mut self, limit: usize) -> &mut Self {
+        self.options.warning_limit = limit;
+        self
+    }
+
+    /// Sets the maximum number of errors reported for each document
+    pub fn error_limit(&mut self, limit: usize) -> &mut Self {
+        self.options.error_limit = limit;
+        self
+    }
+
+    /// Sets the unit in which columns of reported positions are counted
+    ///
+    /// Use `ColumnUnit::Utf16` when positions are sent to a language
+    /// server client.
+    pub fn column_unit(&mut self, unit: ColumnUnit) -> &mut Self {
+        self.options.column_unit = unit;
+        self
+    }
+
+    /// Sets the source identifier stored in positions of further documents
+    ///
+    /// See `SourceMap` for naming the sources.
+    pub fn source(&mut self, source: SourceId) -> &mut Self {
+        self.options.source = source;
+        self
+    }
+
+    /// Collects the `#` comments of further documents into their
+    /// `comments` field
+    pub fn preserve_comments(&mut self, preserve: bool) -> &mut Self {
+        self.options.preserve_comments = preserve;
+        self
+    }
+
+    /// Sets the limits on the size of further documents
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Accepts integers which don't fit into i64 as `Value::BigInt`, see
+    /// `ParserOptions::bigint_extension`
+    pub fn bigint_extension(&mut self, enabled: bool) -> &mut Self {
+        self.options.bigint_extension = enabled;
+        self
+    }
+
+    /// Parses every integer literal as `Value::BigInt`, see
+    /// `ParserOptions::ints_as_bigint`
+    pub fn ints_as_bigint(&mut self, enabled: bool) -> &mut Self {
+        self.options.ints_as_bigint = enabled;
+        self
+    }
+
+    /// Reports repeated arguments as warnings, see
+    /// `ParserOptions::warn_duplicate_arguments`
+    pub fn warn_duplicate_arguments(&mut self, enabled: bool) -> &mut Self {
+        self.options.duplicate_arguments = enabled;
+        self
+    }
+
+    /// Parses a piece of query language and returns an AST
+    pub fn parse_query<'a, S>(
+        &mut self,
+        s: &'a str,
+    ) -> Result<query::Document<'a, S>, query::ParseError>
+    where
+        S: Text<'a>,
+    {
+        let mut tokens = self.tokens(s);
+        let result = query::document(&mut tokens);
+        self.finish(&mut tokens);
+        result
+    }
+
+    /// Parses a piece of schema language and returns an AST
+    pub fn parse_schema<'a, T>(
+        &mut self,
+        s: &'a str,
+    ) -> Result<schema::Document<'a, T>, schema::ParseError>
+    where
+        T: Text<'a>,
+    {
+        let mut tokens = self.tokens(s);
+        let result = schema::document(&mut tokens);
+        self.finish(&mut tokens);
+        result
+    }
+
+    /// Parses a document which may contain both executable and type system
+    /// definitions and returns an AST
+    pub fn parse_document<'a, T>(
+        &mut self,
+        s: &'a str,
+    ) -> Result<document::Document<'a, T>, document::ParseError>
+    where
+        T: Text<'a>,
+    {
+        let mut tokens = self.tokens(s);
+        let result = document::document(&mut tokens);
+        self.finish(&mut tokens);
+        result
+    }
+
+    /// Parses a piece of query language, skipping broken definitions
+    ///
+    /// See `query::parse_query_with_recovery`.
+    pub fn parse_query_with_recovery<'a, S>(
+        &mut self,
+        s: &'a str,
+    ) -> (query::Document<'a, S>, Vec<query::ParseError>)
+    where
+        S: Text<'a>,
+    {
+        let mut tokens = self.tokens(s);
+        let result = query::document_with_recovery(&mut tokens);
+        self.finish(&mut tokens);
+        result
+    }
+
+    /// Parses a piece of schema language, skipping broken definitions
+    ///
+    /// See `schema::parse_schema_with_recovery`.
+    pub fn parse_schema_with_recovery<'a, T>(
+        &mut self,
+        s: &'a str,
+    ) -> (schema::Document<'a, T>, Vec<schema::ParseError>)
+    where
+        T: Text<'a>,
+    {
+        let mut tokens = self.tokens(s);
+        let result = schema::document_with_recovery(&mut tokens);
+        self.finish(&mut tokens);
+        result
+    }
+
+    /// Returns the warnings reported for the last parsed document
+    ///
+    /// If more than the limit of warnings were found, the last entry says
+    /// how many of them were omitted.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    fn tokens<'a>(&mut self, s: &'a str) -> TokenStream<'a> {
+        let mut tokens = self.options.tokens(s);
+        tokens.reuse_warning_buffer(std::mem::take(&mut self.warnings));
+        if self.options.preserve_comments {
+            tokens.reuse_comment_buffer(std::mem::take(&mut self.comments));
+        }
+        tokens
+    }
+
+    /// Takes the buffers back from the token stream of a parsed document
+    fn finish(&mut self, tokens: &mut TokenStream) {
+        self.warnings = tokens.take_warnings();
+        if self.options.preserve_comments {
+            self.comments = tokens.take_comment_buffer();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Parser, ParserOptions};
+    use crate::limits::Limits;
+    use crate::message::MessageKey;
+    use crate::position::{ColumnUnit, SourceMap};
+    use crate::query::{Comment, Definition, OperationDefinition, Selection};
+
+    #[test]
+    fn reuse_warning_buffer() {
+        let mut parser = Parser::new();
+        parser.parse_query::<String>("{ a(x: 1, y: 2) }").unwrap();
+        assert!(parser.warnings().is_empty());
+
+        let doc = "{ a(x: 18446744073709551616) b(x: 18446744073709551616) }";
+        parser.parse_query::<String>(doc).unwrap();
+        assert_eq!(parser.warnings().len(), 2);
+        let capacity = parser.warnings.capacity();
+
+        parser.warning_limit(1).parse_query::<String>(doc).unwrap();
+        assert_eq!(parser.warnings().len(), 2);
+        assert_eq!(
+            parser.warnings()[1].message.key,
+            MessageKey::MoreDiagnostics
+        );
+        assert_eq!(parser.warnings.capacity(), capacity);
+
+        parser.parse_schema::<String>("type A { b: Int }").unwrap();
+        assert!(parser.warnings().is_empty());
+        assert_eq!(parser.warnings.capacity(), capacity);
+    }
+
+    #[test]
+    fn reuse_comment_buffer() {
+        let mut parser = Parser::new();
+        parser.preserve_comments(true);
+        let doc = parser.parse_query::<&str>("# a\n{ a } # b\n# c").unwrap();
+        assert_eq!(doc.comments.len(), 3);
+        let capacity = parser.comments.capacity();
+        assert!(capacity >= 3);
+
+        let doc = parser.parse_query::<&str>("{ a } # d").unwrap();
+        assert_eq!(doc.comments[0].text, " d");
+        assert_eq!(parser.comments.capacity(), capacity);
+        assert!(parser.parse_schema::<&str>("# e\ntype A {").is_err());
+        let doc = parser.parse_schema::<&str>("scalar S # f").unwrap();
+        assert_eq!(doc.comments.len(), 1);
+        assert_eq!(parser.comments.capacity(), capacity);
+    }
+
+    #[test]
+    fn error() {
+        let mut parser = Parser::new();
+        assert!(parser.parse_query::<String>("{ a").is_err());
+        assert!(parser.parse_query::<String>("{ a }").is_ok());
+    }
+
+    #[test]
+    fn utf16_columns() {
+        let source = "{ a(x: \"\u{1f600}\u{e9}\") b }\n\t{ c }";
+        let columns = |parser: &mut Parser| {
+            let doc = parser.parse_query::<&str>(source).unwrap();
+            let mut columns = Vec::new();
+            for def in &doc.definitions {
+                let set = match def {
+                    Definition::Operation(OperationDefinition::SelectionSet(set)) => set,
+                    _ => unreachable!(),
+                };
+                columns.push(set.span.0.column);
+                for item in &set.items {
+                    match item {
+                        Selection::Field(field) => columns.push(field.position.column),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            columns
+        };
+        assert_eq!(columns(&mut Parser::new()), [1, 3, 14, 9, 11]);
+        let mut parser = Parser::new();
+        parser.column_unit(ColumnUnit::Utf16);
+        assert_eq!(columns(&mut parser), [1, 3, 15, 2, 4]);
+    }
+
+    #[test]
+    fn source_ids() {
+        let mut sources = SourceMap::new();
+        let first = sources.add("a.graphql");
+        let second = sources.add("b.graphql");
+        let mut parser = Parser::new();
+        let doc = parser.source(first).parse_query::<&str>("{ a }").unwrap();
+        assert_eq!(doc.span().start.source, first);
+        assert_eq!(sources.locate(doc.span().end), "a.graphql:1:6");
+        let err = parser
+            .source(second)
+            .parse_query::<&str>("{ a")
+            .unwrap_err();
+        assert_eq!(err.position().source, second);
+        assert_eq!(sources.locate(err.position()), "b.graphql:1:4");
+        let doc = crate::parse_query::<&str>("{ a }").unwrap();
+        assert!(doc.span().start.source.is_unnamed());
+        assert_eq!(sources.locate(doc.span().start), "1:1");
+    }
+
+    #[test]
+    fn comments() {
+        let source = "# leading\nquery Q(# in variables\n$a: Int) {\n  a # after a\r\n  b(x: 1) #\n}\n# trailing";
+        let mut parser = Parser::new();
+        let doc = parser.parse_query::<&str>(source).unwrap();
+        assert!(doc.comments.is_empty());
+        let doc = parser
+            .preserve_comments(true)
+            .parse_query::<&str>(source)
+            .unwrap();
+        let comments: Vec<_> = doc
+            .comments
+            .iter()
+            .map(|c: &Comment<&str>| format!("{} {:?}", c.span(), c.text))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                "1:1-1:10 \" leading\"",
+                "2:9-2:23 \" in variables\"",
+                "4:5-4:14 \" after a\"",
+                "5:11-5:12 \"\"",
+                "7:1-7:11 \" trailing\"",
+            ]
+        );
+        for comment in &doc.comments {
+            let raw = &source[comment.position.offset..comment.end.offset];
+            assert_eq!(&raw[1..], comment.text);
+        }
+        let schema = parser
+            .parse_schema::<String>("type A { # fields\n  b: Int }")
+            .unwrap();
+        assert_eq!(schema.comments[0].text, " fields");
+    }
+
+    #[test]
+    fn options() {
+        let mut sources = SourceMap::new();
+        let source = sources.add("a.graphql");
+        let options = ParserOptions::new()
+            .source(source)
+            .preserve_comments(true)
+            .warning_limit(0)
+            .limits(Limits::new().max_tokens(5));
+        let text = "# c\n{ a(x: 18446744073709551616) }";
+        let err = crate::query::parse_query_with_options::<&str>(text, &options).unwrap_err();
+        assert_eq!(sources.locate(err.position()), "a.graphql:2:8");
+
+        let options = options.limits(Limits::new());
+        let (doc, warnings) =
+            crate::query::parse_query_with_options::<&str>(text, &options).unwrap();
+        assert_eq!(doc.comments[0].text, " c");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message.key, MessageKey::MoreDiagnostics);
+        let (doc, _) =
+            crate::schema::parse_schema_with_options::<&str>("scalar S # c", &options).unwrap();
+        assert_eq!(doc.comments.len(), 1);
+
+        let mut parser = Parser::with_options(options.clone());
+        assert_eq!(parser.options(), &options);
+        let doc = parser.parse_query::<&str>(text).unwrap();
+        assert_eq!(doc.span().start.source, source);
+        assert_eq!(parser.warnings().len(), 1);
+        parser
+            .column_unit(ColumnUnit::Utf16)
+            .bigint_extension(false)
+            .ints_as_bigint(true)
+            .warn_duplicate_arguments(true);
+        let expected = options
+            .column_unit(ColumnUnit::Utf16)
+            .bigint_extension(false)
+            .ints_as_bigint(true)
+            .warn_duplicate_arguments(true);
+        assert_eq!(parser.options(), &expected);
+    }
+}
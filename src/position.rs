@@ -2,16 +2,52 @@ use std::fmt;
 
 /// Original position of element in source code
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pos {
     /// One-based line number
     pub line: usize,
     /// One-based column number
     pub column: usize,
+    /// Zero-based byte offset from the start of the source
+    pub offset: usize,
+    /// Source text the position belongs to
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "SourceId::is_unnamed")
+    )]
+    pub source: SourceId,
+}
+
+impl Pos {
+    pub fn new(line: usize, column: usize, offset: usize) -> Pos {
+        Pos {
+            line,
+            column,
+            offset,
+            source: SourceId::default(),
+        }
+    }
+
+    /// Returns the position of the first character of `source`
+    pub fn start_of(source: SourceId) -> Pos {
+        Pos {
+            source,
+            ..Pos::new(1, 1, 0)
+        }
+    }
 }
 
 impl fmt::Debug for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Pos({}:{})", self.line, self.column)
+        if self.source.is_unnamed() {
+            write!(f, "Pos({}:{}@{})", self.line, self.column, self.offset)
+        } else {
+            write!(
+                f,
+                "Pos({:?} {}:{}@{})",
+                self.source, self.line, self.column, self.offset
+            )
+        }
     }
 }
 
@@ -20,3 +56,152 @@ impl fmt::Display for Pos {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
+
+/// Identifier of a source text registered in a `SourceMap`
+///
+/// The default identifier stands for an unnamed source, it is used when
+/// no identifier is given to the parser.
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SourceId(u32);
+
+impl SourceId {
+    pub fn is_unnamed(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl fmt::Debug for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Names of the sources a set of documents was parsed from
+///
+/// Schemas are often split across many files. Registering each file here
+/// gives an identifier to pass to `Parser::source`, so that positions in
+/// the resulting documents and errors can be traced back to their file.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{Parser, SourceMap};
+///
+/// let mut sources = SourceMap::new();
+/// let mut parser = Parser::new();
+/// let user = sources.add("user.graphql");
+/// parser.source(user).parse_schema::<String>("type User { name: String }")?;
+/// let err = parser
+///     .source(sources.add("query.graphql"))
+///     .parse_schema::<String>("type Query {")
+///     .unwrap_err();
+/// assert_eq!(sources.locate(err.position()), "query.graphql:1:13");
+/// # Ok::<(), graphql_parser::schema::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    names: Vec<String>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap::default()
+    }
+
+    /// Registers a source, usually by its file path
+    pub fn add(&mut self, name: impl Into<String>) -> SourceId {
+        self.names.push(name.into());
+        SourceId(self.names.len() as u32)
+    }
+
+    /// Returns the name of the source, `None` for unnamed sources
+    pub fn name(&self, id: SourceId) -> Option<&str> {
+        let index = (id.0 as usize).checked_sub(1)?;
+        self.names.get(index).map(String::as_str)
+    }
+
+    /// Formats the position as `name:line:column`
+    ///
+    /// Positions in unnamed sources are formatted as `line:column`.
+    pub fn locate(&self, pos: Pos) -> String {
+        match self.name(pos.source) {
+            Some(name) => format!("{}:{}", name, pos),
+            None => pos.to_string(),
+        }
+    }
+}
+
+/// Unit in which columns of `Pos` are counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColumnUnit {
+    /// Unicode scalar values, a tab advances by eight columns
+    #[default]
+    Chars,
+    /// UTF-16 code units, as counted by the Language Server Protocol
+    ///
+    /// Characters outside of the Basic Multilingual Plane take two columns
+    /// and a tab is a single column.
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// Returns the number of columns taken by `text`
+    pub(crate) fn width(self, text: &str) -> usize {
+        match self {
+            ColumnUnit::Chars => text.chars().count(),
+            ColumnUnit::Utf16 => text.chars().map(char::len_utf16).sum(),
+        }
+    }
+
+    pub(crate) fn tab_width(self) -> usize {
+        match self {
+            ColumnUnit::Chars => 8,
+            ColumnUnit::Utf16 => 1,
+        }
+    }
+}
+
+/// Source range of an element, from the start of its first token to just
+/// past its last token
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Pos,
+    pub end: Pos,
+}
+
+impl Span {
+    pub fn new(start: Pos, end: Pos) -> Span {
+        Span { start, end }
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Span({}-{})", self.start, self.end)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Implements `span` for AST nodes storing `position` and `end`
+macro_rules! impl_span {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<'a, T: Text<'a>> $name<'a, T> {
+                /// Returns the source range of the node
+                pub fn span(&self) -> Span {
+                    Span::new(self.position, self.end)
+                }
+            }
+        )*
+    };
+}
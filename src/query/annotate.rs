@@ -0,0 +1,208 @@
+//! Attaching user data to nodes of a query document
+//!
+//! Rather than adding a data slot to every AST node, `Annotations` keeps
+//! the data in a side table next to a borrowed document. Later passes such
+//! as type checking or planning can record resolved types or other
+//! metadata for any `NodeRef` without wrapping the nodes in their own
+//! structs. Nodes are looked up by identity, which the borrow keeps stable.
+//!
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::common::Text;
+use crate::query::ast::Document;
+use crate::query::path::{NodePath, NodeRef};
+
+/// Identity of a node: its kind and address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct NodeKey(u8, usize);
+
+impl NodeKey {
+    fn of<'a, T: Text<'a>>(node: &NodeRef<'_, 'a, T>) -> NodeKey {
+        match *node {
+            NodeRef::Document(n) => NodeKey(0, n as *const _ as usize),
+            NodeRef::Definition(n) => NodeKey(1, n as *const _ as usize),
+            NodeRef::Variable(n) => NodeKey(2, n as *const _ as usize),
+            NodeRef::Selection(n) => NodeKey(3, n as *const _ as usize),
+            NodeRef::Directive(n) => NodeKey(4, n as *const _ as usize),
+            NodeRef::Argument(n) => NodeKey(5, n as *const _ as usize),
+        }
+    }
+}
+
+/// User data of type `A` attached to nodes of a query document
+///
+/// Nodes of other documents, even equal ones or clones, never match. Use
+/// `into_paths` and `from_paths` to carry annotations over to a clone or
+/// to keep them after the document is dropped.
+pub struct Annotations<'d, 'a, T: Text<'a>, A> {
+    document: &'d Document<'a, T>,
+    data: HashMap<NodeKey, (NodeRef<'d, 'a, T>, A)>,
+}
+
+impl<'d, 'a, T: Text<'a>, A> Annotations<'d, 'a, T, A> {
+    /// Creates an empty table for the nodes of `document`
+    pub fn new(document: &'d Document<'a, T>) -> Annotations<'d, 'a, T, A> {
+        Annotations {
+            document,
+            data: HashMap::new(),
+        }
+    }
+
+    /// Creates a table from data stored by path, skipping paths which
+    /// have no node in `document`
+    pub fn from_paths<I>(document: &'d Document<'a, T>, paths: I) -> Annotations<'d, 'a, T, A>
+    where
+        I: IntoIterator<Item = (NodePath, A)>,
+    {
+        let mut annotations = Annotations::new(document);
+        for (path, value) in paths {
+            if let Some(node) = document.node_at_path(&path) {
+                annotations.insert(node, value);
+            }
+        }
+        annotations
+    }
+
+    /// Returns the annotated document
+    pub fn document(&self) -> &'d Document<'a, T> {
+        self.document
+    }
+
+    /// Attaches `value` to the node, returning the previous value if any
+    pub fn insert(&mut self, node: NodeRef<'d, 'a, T>, value: A) -> Option<A> {
+        self.data
+            .insert(NodeKey::of(&node), (node, value))
+            .map(|(_, old)| old)
+    }
+
+    pub fn get(&self, node: NodeRef<'d, 'a, T>) -> Option<&A> {
+        self.data.get(&NodeKey::of(&node)).map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, node: NodeRef<'d, 'a, T>) -> Option<&mut A> {
+        self.data
+            .get_mut(&NodeKey::of(&node))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the data of the node at `path`
+    pub fn get_path(&self, path: &NodePath) -> Option<&A> {
+        self.get(self.document.node_at_path(path)?)
+    }
+
+    pub fn remove(&mut self, node: NodeRef<'d, 'a, T>) -> Option<A> {
+        self.data
+            .remove(&NodeKey::of(&node))
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, node: NodeRef<'d, 'a, T>) -> bool {
+        self.data.contains_key(&NodeKey::of(&node))
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Iterates over annotated nodes, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (NodeRef<'d, 'a, T>, &A)> + '_ {
+        self.data.values().map(|(node, value)| (*node, value))
+    }
+
+    /// Converts the table into data stored by path, in no particular order
+    ///
+    /// Nodes which don't belong to the document are dropped.
+    pub fn into_paths(self) -> Vec<(NodePath, A)> {
+        let document = self.document;
+        self.data
+            .into_values()
+            .filter_map(|(node, value)| Some((document.path_of(node)?, value)))
+            .collect()
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Creates an empty table of annotations for the nodes of the document
+    pub fn annotations<A>(&self) -> Annotations<'_, 'a, T, A> {
+        Annotations::new(self)
+    }
+}
+
+impl<'d, 'a, T: Text<'a> + fmt::Debug, A: fmt::Debug> fmt::Debug for Annotations<'d, 'a, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.data.values().map(|(node, value)| (node, value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Annotations;
+    use crate::parse_query;
+    use crate::query::{Definition, NodeRef, OperationDefinition, Selection};
+
+    const QUERY: &str = "query Q($v: Int) { user(id: $v) { name @a } }";
+
+    #[test]
+    fn attach_data() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let user = match &doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(q)) => &q.selection_set.items[0],
+            _ => unreachable!(),
+        };
+        let name = match user {
+            Selection::Field(f) => &f.selection_set.items[0],
+            _ => unreachable!(),
+        };
+        let mut types = doc.annotations();
+        assert!(types.is_empty());
+        assert_eq!(types.insert(NodeRef::Selection(user), "User"), None);
+        assert_eq!(types.insert(NodeRef::Selection(name), "String"), None);
+        assert_eq!(
+            types.insert(NodeRef::Selection(name), "String!"),
+            Some("String")
+        );
+        assert_eq!(types.len(), 2);
+        assert_eq!(types.get(NodeRef::Selection(name)), Some(&"String!"));
+        assert_eq!(types.get(NodeRef::Document(&doc)), None);
+        assert_eq!(
+            types.get_path(&"/definitions/0/selections/0".parse().unwrap()),
+            Some(&"User")
+        );
+        *types.get_mut(NodeRef::Selection(user)).unwrap() = "User!";
+        assert!(types
+            .iter()
+            .any(|(n, t)| n.ptr_eq(&NodeRef::Selection(user)) && *t == "User!"));
+
+        // an equal node of a clone is a different node
+        let copy = doc.clone();
+        assert!(!types.contains(copy.node_at_path(&"/".parse().unwrap()).unwrap()));
+
+        let paths = types.into_paths();
+        let mut printed = paths
+            .iter()
+            .map(|(p, t)| format!("{} {}", p, t))
+            .collect::<Vec<_>>();
+        printed.sort();
+        assert_eq!(
+            printed,
+            [
+                "/definitions/0/selections/0 User!",
+                "/definitions/0/selections/0/selections/0 String!",
+            ]
+        );
+        let mut restored = Annotations::from_paths(&copy, paths);
+        assert_eq!(restored.len(), 2);
+        let node = copy
+            .node_at_path(&"/definitions/0/selections/0/selections/0".parse().unwrap())
+            .unwrap();
+        assert_eq!(restored.remove(node), Some("String!"));
+        assert_eq!(restored.len(), 1);
+    }
+}
@@ -5,13 +5,34 @@
 //!
 //! [graphql grammar]: http://facebook.github.io/graphql/October2016/#sec-Appendix-Grammar-Summary
 //!
-pub use crate::common::{Directive, Number, Text, Type, Value};
-use crate::position::Pos;
+use std::mem;
+
+pub use crate::common::{
+    Comment, ConstValue, Directive, FloatNumber, Interner, Names, Number, StringValue, Text, Type,
+    Value, VariableInConstValue,
+};
+use thiserror::Error;
+
+use crate::position::{Pos, Span};
 
 /// Root of query data
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Document<'a, T: Text<'a>> {
     pub definitions: Vec<Definition<'a, T>>,
+    /// Comments of the document, see `Parser::preserve_comments`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<Comment<'a, T>>,
 }
 
 impl<'a> Document<'a, String> {
@@ -33,22 +54,47 @@ impl<'a> Document<'a, String> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum Definition<'a, T: Text<'a>> {
     Operation(OperationDefinition<'a, T>),
     Fragment(FragmentDefinition<'a, T>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct FragmentDefinition<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub type_condition: TypeCondition<'a, T>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum OperationDefinition<'a, T: Text<'a>> {
     SelectionSet(SelectionSet<'a, T>),
     Query(Query<'a, T>),
@@ -56,57 +102,167 @@ pub enum OperationDefinition<'a, T: Text<'a>> {
     Subscription(Subscription<'a, T>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Kind of an operation, `SelectionSet` is the query shorthand `{ ... }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperationKind {
+    SelectionSet,
+    Query,
+    Mutation,
+    Subscription,
+}
+
+/// An operation of any kind
+///
+/// This holds the same data as `OperationDefinition`, for code which
+/// treats all kinds of operations alike. The shorthand form has no name,
+/// variables or directives.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_query;
+/// use graphql_parser::query::{Definition, Operation, OperationDefinition, OperationKind};
+///
+/// let doc = parse_query::<&str>("mutation M($id: ID!) { delete(id: $id) }").unwrap();
+/// if let Some(Definition::Operation(op)) = doc.definitions.into_iter().next() {
+///     let mut op = Operation::from(op);
+///     assert_eq!(op.kind, OperationKind::Mutation);
+///     op.name = Some("Renamed");
+///     let op = OperationDefinition::from(op);
+///     assert_eq!(op.to_string(), "mutation Renamed($id: ID!) {\n  delete(id: $id)\n}\n");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Operation<'a, T: Text<'a>> {
+    pub kind: OperationKind,
+    pub position: Pos,
+    pub end: Pos,
+    pub name: Option<T::Value>,
+    pub variable_definitions: Vec<VariableDefinition<'a, T>>,
+    pub directives: Vec<Directive<'a, T>>,
+    pub selection_set: SelectionSet<'a, T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Query<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: Option<T::Value>,
     pub variable_definitions: Vec<VariableDefinition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Mutation<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: Option<T::Value>,
     pub variable_definitions: Vec<VariableDefinition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Subscription<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: Option<T::Value>,
     pub variable_definitions: Vec<VariableDefinition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct SelectionSet<'a, T: Text<'a>> {
     pub span: (Pos, Pos),
     pub items: Vec<Selection<'a, T>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct VariableDefinition<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub var_type: Type<'a, T>,
     pub default_value: Option<Value<'a, T>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum Selection<'a, T: Text<'a>> {
     Field(Field<'a, T>),
     FragmentSpread(FragmentSpread<'a, T>),
     InlineFragment(InlineFragment<'a, T>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub alias: Option<T::Value>,
     pub name: T::Value,
     pub arguments: Vec<(T::Value, Value<'a, T>)>,
@@ -114,22 +270,484 @@ pub struct Field<'a, T: Text<'a>> {
     pub selection_set: SelectionSet<'a, T>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct FragmentSpread<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub fragment_name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum TypeCondition<'a, T: Text<'a>> {
     On(T::Value),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InlineFragment<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub type_condition: Option<TypeCondition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
+
+impl_span!(
+    FragmentDefinition,
+    Query,
+    Mutation,
+    Subscription,
+    VariableDefinition,
+    Field,
+    FragmentSpread,
+    InlineFragment,
+);
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the source range from the first to the last definition
+    pub fn span(&self) -> Span {
+        match (self.definitions.first(), self.definitions.last()) {
+            (Some(first), Some(last)) => Span::new(first.span().start, last.span().end),
+            _ => Span::default(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the operations in the order of the document
+    pub fn operations(&self) -> impl Iterator<Item = &OperationDefinition<'a, T>> {
+        self.definitions.iter().filter_map(|def| match *def {
+            Definition::Operation(ref op) => Some(op),
+            Definition::Fragment(_) => None,
+        })
+    }
+
+    /// Returns the fragment definitions in the order of the document
+    pub fn fragments(&self) -> impl Iterator<Item = &FragmentDefinition<'a, T>> {
+        self.definitions.iter().filter_map(|def| match *def {
+            Definition::Fragment(ref frag) => Some(frag),
+            Definition::Operation(_) => None,
+        })
+    }
+
+    /// Returns the first operation with the name
+    pub fn operation_by_name(&self, name: &str) -> Option<&OperationDefinition<'a, T>> {
+        self.operations().find(|op| op.name() == Some(name))
+    }
+
+    /// Returns the first fragment definition with the name
+    pub fn fragment(&self, name: &str) -> Option<&FragmentDefinition<'a, T>> {
+        self.fragments().find(|frag| frag.name.as_ref() == name)
+    }
+
+    /// Rewrites every shorthand operation `{ ... }` into an anonymous
+    /// `query`, so that all operations have the same shape
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_query;
+    ///
+    /// let mut doc = parse_query::<&str>("{ user { name } }").unwrap();
+    /// doc.expand_shorthand();
+    /// assert_eq!(doc.to_string(), "query {\n  user {\n    name\n  }\n}\n");
+    /// ```
+    pub fn expand_shorthand(&mut self) {
+        for definition in &mut self.definitions {
+            if let Definition::Operation(ref mut op) = *definition {
+                op.expand_shorthand(None);
+            }
+        }
+    }
+
+    /// Returns the operation to execute for the `operationName` of a
+    /// request
+    ///
+    /// As specified for `GetOperation`, the name may only be omitted if the
+    /// document contains a single operation.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_query;
+    /// use graphql_parser::query::OperationError;
+    ///
+    /// let doc = parse_query::<&str>("query A { a } query B { b }").unwrap();
+    /// assert_eq!(doc.select_operation(Some("B")).unwrap().name(), Some("B"));
+    /// assert_eq!(doc.select_operation(None), Err(OperationError::Ambiguous));
+    /// ```
+    pub fn select_operation(
+        &self,
+        name: Option<&str>,
+    ) -> Result<&OperationDefinition<'a, T>, OperationError> {
+        match name {
+            Some(name) => self
+                .operation_by_name(name)
+                .ok_or_else(|| OperationError::Unknown(name.to_string())),
+            None => {
+                let mut operations = self.operations();
+                let first = operations.next().ok_or(OperationError::NoOperation)?;
+                if operations.next().is_some() {
+                    return Err(OperationError::Ambiguous);
+                }
+                Ok(first)
+            }
+        }
+    }
+}
+
+/// Error selecting the operation to execute
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OperationError {
+    #[error("document contains no operation")]
+    NoOperation,
+    /// No operation name is given, but the document contains several
+    #[error("operation name is required, the document contains several operations")]
+    Ambiguous,
+    #[error("unknown operation `{}`", _0)]
+    Unknown(String),
+}
+
+impl<'a, T: Text<'a>> Definition<'a, T> {
+    pub fn span(&self) -> Span {
+        match *self {
+            Definition::Operation(ref op) => op.span(),
+            Definition::Fragment(ref frag) => frag.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> OperationDefinition<'a, T> {
+    pub fn span(&self) -> Span {
+        match *self {
+            OperationDefinition::SelectionSet(ref set) => Span::new(set.span.0, set.span.1),
+            OperationDefinition::Query(ref q) => q.span(),
+            OperationDefinition::Mutation(ref m) => m.span(),
+            OperationDefinition::Subscription(ref s) => s.span(),
+        }
+    }
+
+    pub fn kind(&self) -> OperationKind {
+        match *self {
+            OperationDefinition::SelectionSet(_) => OperationKind::SelectionSet,
+            OperationDefinition::Query(_) => OperationKind::Query,
+            OperationDefinition::Mutation(_) => OperationKind::Mutation,
+            OperationDefinition::Subscription(_) => OperationKind::Subscription,
+        }
+    }
+
+    /// Returns the variable definitions, empty for the shorthand form
+    pub fn variable_definitions(&self) -> &[VariableDefinition<'a, T>] {
+        match *self {
+            OperationDefinition::SelectionSet(_) => &[],
+            OperationDefinition::Query(ref q) => &q.variable_definitions,
+            OperationDefinition::Mutation(ref m) => &m.variable_definitions,
+            OperationDefinition::Subscription(ref s) => &s.variable_definitions,
+        }
+    }
+
+    /// Returns the directives, empty for the shorthand form
+    pub fn directives(&self) -> &[Directive<'a, T>] {
+        match *self {
+            OperationDefinition::SelectionSet(_) => &[],
+            OperationDefinition::Query(ref q) => &q.directives,
+            OperationDefinition::Mutation(ref m) => &m.directives,
+            OperationDefinition::Subscription(ref s) => &s.directives,
+        }
+    }
+
+    pub fn selection_set(&self) -> &SelectionSet<'a, T> {
+        match *self {
+            OperationDefinition::SelectionSet(ref set) => set,
+            OperationDefinition::Query(ref q) => &q.selection_set,
+            OperationDefinition::Mutation(ref m) => &m.selection_set,
+            OperationDefinition::Subscription(ref s) => &s.selection_set,
+        }
+    }
+
+    pub fn selection_set_mut(&mut self) -> &mut SelectionSet<'a, T> {
+        match *self {
+            OperationDefinition::SelectionSet(ref mut set) => set,
+            OperationDefinition::Query(ref mut q) => &mut q.selection_set,
+            OperationDefinition::Mutation(ref mut m) => &mut m.selection_set,
+            OperationDefinition::Subscription(ref mut s) => &mut s.selection_set,
+        }
+    }
+
+    /// Rewrites the shorthand form `{ ... }` into a `query` operation with
+    /// the name
+    ///
+    /// Other operations are left unchanged.
+    pub fn expand_shorthand(&mut self, name: Option<T::Value>) {
+        if let OperationDefinition::SelectionSet(ref mut set) = *self {
+            let (position, end) = set.span;
+            let selection_set = mem::replace(
+                set,
+                SelectionSet {
+                    span: set.span,
+                    items: Vec::new(),
+                },
+            );
+            *self = OperationDefinition::Query(Query {
+                position,
+                end,
+                name,
+                variable_definitions: Vec::new(),
+                directives: Vec::new(),
+                selection_set,
+            });
+        }
+    }
+
+    /// Returns the name of the operation, `None` for anonymous operations
+    pub fn name(&self) -> Option<&str> {
+        let name = match *self {
+            OperationDefinition::SelectionSet(_) => None,
+            OperationDefinition::Query(ref q) => q.name.as_ref(),
+            OperationDefinition::Mutation(ref m) => m.name.as_ref(),
+            OperationDefinition::Subscription(ref s) => s.name.as_ref(),
+        };
+        name.map(|name| name.as_ref())
+    }
+}
+
+impl<'a, T: Text<'a>> From<OperationDefinition<'a, T>> for Operation<'a, T> {
+    fn from(operation: OperationDefinition<'a, T>) -> Operation<'a, T> {
+        let kind = operation.kind();
+        match operation {
+            OperationDefinition::SelectionSet(selection_set) => Operation {
+                kind,
+                position: selection_set.span.0,
+                end: selection_set.span.1,
+                name: None,
+                variable_definitions: Vec::new(),
+                directives: Vec::new(),
+                selection_set,
+            },
+            OperationDefinition::Query(Query {
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            })
+            | OperationDefinition::Mutation(Mutation {
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            })
+            | OperationDefinition::Subscription(Subscription {
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }) => Operation {
+                kind,
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            },
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> From<Operation<'a, T>> for OperationDefinition<'a, T> {
+    /// Converts the operation back, a shorthand operation with a name,
+    /// variables or directives becomes a query as the shorthand form can't
+    /// hold them
+    fn from(operation: Operation<'a, T>) -> OperationDefinition<'a, T> {
+        let Operation {
+            kind,
+            position,
+            end,
+            name,
+            variable_definitions,
+            directives,
+            selection_set,
+        } = operation;
+        let shorthand = name.is_none() && variable_definitions.is_empty() && directives.is_empty();
+        match kind {
+            OperationKind::SelectionSet if shorthand => {
+                OperationDefinition::SelectionSet(selection_set)
+            }
+            OperationKind::SelectionSet | OperationKind::Query => {
+                OperationDefinition::Query(Query {
+                    position,
+                    end,
+                    name,
+                    variable_definitions,
+                    directives,
+                    selection_set,
+                })
+            }
+            OperationKind::Mutation => OperationDefinition::Mutation(Mutation {
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            OperationKind::Subscription => OperationDefinition::Subscription(Subscription {
+                position,
+                end,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Field<'a, T> {
+    /// Returns the key of the field in the response, the alias if there is
+    /// one and the name otherwise
+    pub fn response_key(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name).as_ref()
+    }
+
+    /// Returns the value of an argument
+    ///
+    /// As with `Value::get`, the last argument wins if the name is
+    /// repeated.
+    pub fn argument(&self, name: &str) -> Option<&Value<'a, T>> {
+        self.arguments
+            .iter()
+            .rev()
+            .find(|(key, _)| key.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl<'a, T: Text<'a>> SelectionSet<'a, T> {
+    /// Returns the selections of the set
+    pub fn iter(&self) -> Selections<'_, 'a, T> {
+        Selections {
+            stack: vec![self.items.iter()],
+            flatten: false,
+        }
+    }
+
+    /// Returns the selections of the set, with inline fragments which
+    /// don't affect the result replaced by their selections
+    ///
+    /// This flattens inline fragments without a type condition and without
+    /// directives, like `... { name }`, at any depth. Fragments with a
+    /// type condition or a directive such as `@include` are returned as
+    /// they are.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::query::{parse_selection_set, Selection};
+    ///
+    /// let set = parse_selection_set::<&str>("{ a ... { b ... @skip(if: $x) { c } } }").unwrap();
+    /// let keys = set
+    ///     .flattened()
+    ///     .map(|selection| match selection {
+    ///         Selection::Field(field) => field.response_key(),
+    ///         _ => "fragment",
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(keys, ["a", "b", "fragment"]);
+    /// ```
+    pub fn flattened(&self) -> Selections<'_, 'a, T> {
+        Selections {
+            stack: vec![self.items.iter()],
+            flatten: true,
+        }
+    }
+
+    /// Returns the fields selected directly by the set
+    pub fn fields(&self) -> impl Iterator<Item = &Field<'a, T>> {
+        self.items.iter().filter_map(|item| match *item {
+            Selection::Field(ref field) => Some(field),
+            _ => None,
+        })
+    }
+}
+
+impl<'s, 'a, T: Text<'a>> IntoIterator for &'s SelectionSet<'a, T> {
+    type Item = &'s Selection<'a, T>;
+    type IntoIter = Selections<'s, 'a, T>;
+
+    fn into_iter(self) -> Selections<'s, 'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the selections of a `SelectionSet`
+///
+/// Returned by `SelectionSet::iter` and `SelectionSet::flattened`.
+#[derive(Debug, Clone)]
+pub struct Selections<'s, 'a, T: Text<'a>> {
+    stack: Vec<std::slice::Iter<'s, Selection<'a, T>>>,
+    flatten: bool,
+}
+
+impl<'s, 'a, T: Text<'a>> Iterator for Selections<'s, 'a, T> {
+    type Item = &'s Selection<'a, T>;
+
+    fn next(&mut self) -> Option<&'s Selection<'a, T>> {
+        loop {
+            let selection = match self.stack.last_mut()?.next() {
+                Some(selection) => selection,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            match *selection {
+                Selection::InlineFragment(ref frag)
+                    if self.flatten
+                        && frag.type_condition.is_none()
+                        && frag.directives.is_empty() =>
+                {
+                    self.stack.push(frag.selection_set.items.iter());
+                }
+                _ => return Some(selection),
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Selection<'a, T> {
+    pub fn span(&self) -> Span {
+        match *self {
+            Selection::Field(ref field) => field.span(),
+            Selection::FragmentSpread(ref spread) => spread.span(),
+            Selection::InlineFragment(ref frag) => frag.span(),
+        }
+    }
+}
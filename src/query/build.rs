@@ -0,0 +1,364 @@
+//! Builders for constructing query documents in code
+//!
+//! Positions are left at their defaults and optional parts are empty
+//! unless set, so only the parts that matter have to be spelled out.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::query::{FieldBuilder, FragmentBuilder, OperationBuilder, Type, Value};
+//!
+//! let op = OperationBuilder::<&str>::query()
+//!     .name("User")
+//!     .variable("id", Type::NonNullType(Box::new(Type::NamedType("ID"))))
+//!     .field(
+//!         FieldBuilder::new("user")
+//!             .argument("id", Value::Variable("id"))
+//!             .spread("UserFields"),
+//!     )
+//!     .build();
+//! assert_eq!(
+//!     op.to_string(),
+//!     "query User($id: ID!) {\n  user(id: $id) {\n    ...UserFields\n  }\n}\n",
+//! );
+//!
+//! let fragment = FragmentBuilder::<&str>::new("UserFields", "User")
+//!     .field(FieldBuilder::new("name"))
+//!     .field(FieldBuilder::new("avatar").alias("picture"))
+//!     .build();
+//! assert_eq!(
+//!     fragment.to_string(),
+//!     "fragment UserFields on User {\n  name\n  picture: avatar\n}\n",
+//! );
+//! ```
+use crate::common::{Directive, Text, Type, Value};
+use crate::position::Pos;
+use crate::query::ast::*;
+
+fn empty_set<'a, T: Text<'a>>() -> SelectionSet<'a, T> {
+    SelectionSet {
+        span: (Pos::default(), Pos::default()),
+        items: Vec::new(),
+    }
+}
+
+/// Adds the methods shared by builders of nodes with directives and a
+/// selection set
+macro_rules! impl_selection_methods {
+    ($($name:ident),*) => {
+        $(
+            impl<'a, T: Text<'a>> $name<'a, T> {
+                /// Adds a directive with the given arguments
+                pub fn directive<I>(mut self, name: T::Value, arguments: I) -> Self
+                where
+                    I: IntoIterator<Item = (T::Value, Value<'a, T>)>,
+                {
+                    self.node.directives.push(Directive {
+                        position: Pos::default(),
+                        end: Pos::default(),
+                        name,
+                        arguments: arguments.into_iter().collect(),
+                    });
+                    self
+                }
+
+                /// Adds a field to the selection set
+                pub fn field(self, field: FieldBuilder<'a, T>) -> Self {
+                    self.selection(field.build())
+                }
+
+                /// Adds a fragment spread `...name` to the selection set
+                pub fn spread(self, name: T::Value) -> Self {
+                    self.selection(FragmentSpread {
+                        position: Pos::default(),
+                        end: Pos::default(),
+                        fragment_name: name,
+                        directives: Vec::new(),
+                    })
+                }
+
+                /// Adds any selection, e.g. a built `InlineFragment`
+                pub fn selection<S: Into<Selection<'a, T>>>(mut self, selection: S) -> Self {
+                    self.node.selection_set.items.push(selection.into());
+                    self
+                }
+            }
+        )*
+    };
+}
+
+/// Builder of an operation definition
+#[derive(Debug, Clone)]
+pub struct OperationBuilder<'a, T: Text<'a>> {
+    node: Operation<'a, T>,
+}
+
+impl<'a, T: Text<'a>> OperationBuilder<'a, T> {
+    /// Starts an operation of the given kind
+    ///
+    /// A `SelectionSet` operation is built as a query if it gets a name,
+    /// variables or directives.
+    pub fn new(kind: OperationKind) -> OperationBuilder<'a, T> {
+        OperationBuilder {
+            node: Operation {
+                kind,
+                position: Pos::default(),
+                end: Pos::default(),
+                name: None,
+                variable_definitions: Vec::new(),
+                directives: Vec::new(),
+                selection_set: empty_set(),
+            },
+        }
+    }
+
+    pub fn query() -> OperationBuilder<'a, T> {
+        OperationBuilder::new(OperationKind::Query)
+    }
+
+    pub fn mutation() -> OperationBuilder<'a, T> {
+        OperationBuilder::new(OperationKind::Mutation)
+    }
+
+    pub fn subscription() -> OperationBuilder<'a, T> {
+        OperationBuilder::new(OperationKind::Subscription)
+    }
+
+    pub fn name(mut self, name: T::Value) -> Self {
+        self.node.name = Some(name);
+        self
+    }
+
+    /// Adds a variable definition without a default value
+    pub fn variable(mut self, name: T::Value, var_type: Type<'a, T>) -> Self {
+        self.node.variable_definitions.push(VariableDefinition {
+            position: Pos::default(),
+            end: Pos::default(),
+            name,
+            var_type,
+            default_value: None,
+        });
+        self
+    }
+
+    /// Adds a variable definition with a default value
+    pub fn variable_with_default(
+        self,
+        name: T::Value,
+        var_type: Type<'a, T>,
+        default_value: Value<'a, T>,
+    ) -> Self {
+        let mut builder = self.variable(name, var_type);
+        if let Some(var) = builder.node.variable_definitions.last_mut() {
+            var.default_value = Some(default_value);
+        }
+        builder
+    }
+
+    pub fn build(self) -> OperationDefinition<'a, T> {
+        self.node.into()
+    }
+}
+
+/// Builder of a field selection
+#[derive(Debug, Clone)]
+pub struct FieldBuilder<'a, T: Text<'a>> {
+    node: Field<'a, T>,
+}
+
+impl<'a, T: Text<'a>> FieldBuilder<'a, T> {
+    pub fn new(name: T::Value) -> FieldBuilder<'a, T> {
+        FieldBuilder {
+            node: Field {
+                position: Pos::default(),
+                end: Pos::default(),
+                alias: None,
+                name,
+                arguments: Vec::new(),
+                directives: Vec::new(),
+                selection_set: empty_set(),
+            },
+        }
+    }
+
+    pub fn alias(mut self, alias: T::Value) -> Self {
+        self.node.alias = Some(alias);
+        self
+    }
+
+    /// Adds an argument, arguments are kept in the order they are added
+    pub fn argument<V: Into<Value<'a, T>>>(mut self, name: T::Value, value: V) -> Self {
+        self.node.arguments.push((name, value.into()));
+        self
+    }
+
+    pub fn build(self) -> Field<'a, T> {
+        self.node
+    }
+}
+
+/// Builder of a fragment definition
+#[derive(Debug, Clone)]
+pub struct FragmentBuilder<'a, T: Text<'a>> {
+    node: FragmentDefinition<'a, T>,
+}
+
+impl<'a, T: Text<'a>> FragmentBuilder<'a, T> {
+    /// Starts the fragment `name` on the type `on`
+    pub fn new(name: T::Value, on: T::Value) -> FragmentBuilder<'a, T> {
+        FragmentBuilder {
+            node: FragmentDefinition {
+                position: Pos::default(),
+                end: Pos::default(),
+                name,
+                type_condition: TypeCondition::On(on),
+                directives: Vec::new(),
+                selection_set: empty_set(),
+            },
+        }
+    }
+
+    pub fn build(self) -> FragmentDefinition<'a, T> {
+        self.node
+    }
+}
+
+/// Builder of an inline fragment `... on Type { ... }`
+#[derive(Debug, Clone)]
+pub struct InlineFragmentBuilder<'a, T: Text<'a>> {
+    node: InlineFragment<'a, T>,
+}
+
+impl<'a, T: Text<'a>> InlineFragmentBuilder<'a, T> {
+    /// Starts an inline fragment, `None` leaves out the type condition
+    pub fn new(on: Option<T::Value>) -> InlineFragmentBuilder<'a, T> {
+        InlineFragmentBuilder {
+            node: InlineFragment {
+                position: Pos::default(),
+                end: Pos::default(),
+                type_condition: on.map(TypeCondition::On),
+                directives: Vec::new(),
+                selection_set: empty_set(),
+            },
+        }
+    }
+
+    pub fn build(self) -> InlineFragment<'a, T> {
+        self.node
+    }
+}
+
+impl_selection_methods!(
+    OperationBuilder,
+    FieldBuilder,
+    FragmentBuilder,
+    InlineFragmentBuilder
+);
+
+impl<'a, T: Text<'a>> From<Field<'a, T>> for Selection<'a, T> {
+    fn from(field: Field<'a, T>) -> Selection<'a, T> {
+        Selection::Field(field)
+    }
+}
+
+impl<'a, T: Text<'a>> From<FragmentSpread<'a, T>> for Selection<'a, T> {
+    fn from(spread: FragmentSpread<'a, T>) -> Selection<'a, T> {
+        Selection::FragmentSpread(spread)
+    }
+}
+
+impl<'a, T: Text<'a>> From<InlineFragment<'a, T>> for Selection<'a, T> {
+    fn from(fragment: InlineFragment<'a, T>) -> Selection<'a, T> {
+        Selection::InlineFragment(fragment)
+    }
+}
+
+impl<'a, T: Text<'a>> From<OperationDefinition<'a, T>> for Definition<'a, T> {
+    fn from(operation: OperationDefinition<'a, T>) -> Definition<'a, T> {
+        Definition::Operation(operation)
+    }
+}
+
+impl<'a, T: Text<'a>> From<FragmentDefinition<'a, T>> for Definition<'a, T> {
+    fn from(fragment: FragmentDefinition<'a, T>) -> Definition<'a, T> {
+        Definition::Fragment(fragment)
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Creates a document of the given definitions, without comments
+    pub fn new<I, D>(definitions: I) -> Document<'a, T>
+    where
+        I: IntoIterator<Item = D>,
+        D: Into<Definition<'a, T>>,
+    {
+        Document {
+            definitions: definitions.into_iter().map(Into::into).collect(),
+            comments: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FieldBuilder, FragmentBuilder, InlineFragmentBuilder, OperationBuilder};
+    use crate::parse_query;
+    use crate::query::{Definition, Document, OperationKind, Type, Value};
+    use crate::semantic::SemanticEq;
+
+    #[test]
+    fn build_document() {
+        let op = OperationBuilder::mutation()
+            .name("Update")
+            .variable("id", Type::NamedType("ID"))
+            .variable_with_default("n", Type::NamedType("Int"), Value::Int(3.into()))
+            .directive("trace", vec![])
+            .field(
+                FieldBuilder::new("update")
+                    .alias("u")
+                    .argument("id", Value::Variable("id"))
+                    .argument("tags", vec![Value::from("a"), Value::from(true)])
+                    .directive("include", vec![("if", Value::Boolean(true))])
+                    .field(FieldBuilder::new("id"))
+                    .selection(InlineFragmentBuilder::new(Some("User")).spread("F").build())
+                    .selection(
+                        InlineFragmentBuilder::new(None)
+                            .field(FieldBuilder::new("x"))
+                            .build(),
+                    ),
+            )
+            .build();
+        let fragment = FragmentBuilder::new("F", "User")
+            .directive("d", vec![])
+            .field(FieldBuilder::new("name"))
+            .build();
+        let doc = Document::new(vec![Definition::from(op), Definition::from(fragment)]);
+        let expected = parse_query::<&str>(
+            r#"
+            mutation Update($id: ID, $n: Int = 3) @trace {
+                u: update(id: $id, tags: ["a", true]) @include(if: true) {
+                    id
+                    ... on User { ...F }
+                    ... { x }
+                }
+            }
+            fragment F on User @d { name }
+            "#,
+        )
+        .unwrap();
+        assert!(doc.semantic_eq(&expected), "{}", doc);
+        assert_eq!(doc.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn shorthand() {
+        let op = OperationBuilder::<&str>::new(OperationKind::SelectionSet)
+            .field(FieldBuilder::new("a"))
+            .build();
+        assert_eq!(op.to_string(), "{\n  a\n}\n");
+        let named = OperationBuilder::<&str>::new(OperationKind::SelectionSet)
+            .name("Q")
+            .field(FieldBuilder::new("a"))
+            .build();
+        assert_eq!(named.kind(), OperationKind::Query);
+    }
+}
@@ -0,0 +1,384 @@
+//! Detection of conflicting response keys
+//!
+//! Fields which end up under the same key in the response must select the
+//! same field with the same arguments, otherwise the server can't tell
+//! which of them to return. This is a reduced form of the "field selection
+//! merging" validation rule which works without a schema: fields are
+//! compared regardless of their parent types.
+//!
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::common::{Text, Value};
+use crate::message::SpecReference;
+use crate::parallel;
+use crate::position::Pos;
+use crate::query::ast::{Definition, Document, Field, FragmentDefinition};
+use crate::query::ast::{OperationDefinition, Selection, SelectionSet};
+
+/// Two fields selected under the same response key which can't be merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Alias or, for fields without one, name of the fields
+    pub response_key: String,
+    /// Position of the field selected first
+    pub first: Pos,
+    /// Position of the field which conflicts with the first one
+    pub second: Pos,
+    pub reason: ConflictReason,
+}
+
+impl Conflict {
+    /// Returns the section of the specification defining the rule
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        Some(SpecReference {
+            section: "5.3.2",
+            title: "Field Selection Merging",
+        })
+    }
+}
+
+/// Why the fields of a `Conflict` can't be merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictReason {
+    /// Different fields are selected, the names of both are stored
+    DifferentFields(String, String),
+    /// The same field is selected with different arguments
+    DifferentArguments(String),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reason {
+            ConflictReason::DifferentFields(ref first, ref second) => write!(
+                f,
+                "fields `{}` at {} and `{}` at {} are both selected as `{}`, \
+                 use different aliases",
+                first, self.first, second, self.second, self.response_key
+            ),
+            ConflictReason::DifferentArguments(ref name) => write!(
+                f,
+                "field `{}` is selected as `{}` with different arguments at {} and {}",
+                name, self.response_key, self.first, self.second
+            ),
+        }
+    }
+}
+
+/// Finds fields of a query document selected under the same response key
+/// which can't be merged
+///
+/// Fragment spreads are expanded, so fields of different fragments used
+/// in the same selection set are compared too. Conflicts are reported once
+/// per pair of fields, in the order of the first field of the pair.
+pub fn response_key_conflicts<'a, T>(doc: &Document<'a, T>) -> Vec<Conflict>
+where
+    T: Text<'a> + PartialEq,
+{
+    let checker = Checker::new(doc);
+    merge(parallel::map(&doc.definitions, |_, def| {
+        checker.definition(def)
+    }))
+}
+
+/// Same as `response_key_conflicts`, checking the definitions on all
+/// threads
+#[cfg(feature = "rayon")]
+pub fn par_response_key_conflicts<'a, T>(doc: &Document<'a, T>) -> Vec<Conflict>
+where
+    T: Text<'a> + PartialEq,
+    T::Value: Sync,
+{
+    let checker = Checker::new(doc);
+    merge(parallel::par_map(&doc.definitions, |_, def| {
+        checker.definition(def)
+    }))
+}
+
+/// Merges the conflicts found in each definition
+///
+/// Fragments are checked on their own and where they are spread, so the
+/// same conflict can be found several times.
+fn merge(found: Vec<Vec<Conflict>>) -> Vec<Conflict> {
+    let mut reported = HashSet::new();
+    found
+        .into_iter()
+        .flatten()
+        .filter(|conflict| reported.insert((conflict.first, conflict.second)))
+        .collect()
+}
+
+/// A field along with the fragments expanded to reach it
+type Collected<'s, 'a, T> = (&'s Field<'a, T>, Vec<&'s str>);
+
+/// Fields collected from each fragment, along with the fragments expanded
+/// to reach them from it
+type Cache<'s, 'a, T> = HashMap<&'s str, Vec<Collected<'s, 'a, T>>>;
+
+/// Fields of a merged selection set
+///
+/// A field reached through several spreads of the same fragment is only
+/// kept once, as it can't conflict with itself.
+struct Fields<'s, 'a, T: Text<'a>> {
+    items: Vec<Collected<'s, 'a, T>>,
+    seen: HashSet<*const Field<'a, T>>,
+}
+
+impl<'s, 'a, T: Text<'a>> Fields<'s, 'a, T> {
+    fn new() -> Fields<'s, 'a, T> {
+        Fields {
+            items: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    fn push(&mut self, field: &'s Field<'a, T>, path: Vec<&'s str>) {
+        if self.seen.insert(field) {
+            self.items.push((field, path));
+        }
+    }
+}
+
+struct Checker<'s, 'a, T: Text<'a>> {
+    fragments: HashMap<&'s str, &'s FragmentDefinition<'a, T>>,
+}
+
+impl<'s, 'a, T: Text<'a> + PartialEq> Checker<'s, 'a, T> {
+    fn new(doc: &'s Document<'a, T>) -> Checker<'s, 'a, T> {
+        let mut fragments = HashMap::new();
+        for def in &doc.definitions {
+            if let Definition::Fragment(ref frag) = *def {
+                fragments.insert(frag.name.as_ref(), frag);
+            }
+        }
+        Checker { fragments }
+    }
+
+    /// Finds the conflicts in the selections of a definition
+    fn definition(&self, def: &'s Definition<'a, T>) -> Vec<Conflict> {
+        let mut cache = HashMap::new();
+        let mut fields = Fields::new();
+        match *def {
+            Definition::Operation(ref op) => {
+                let set = match *op {
+                    OperationDefinition::SelectionSet(ref set) => set,
+                    OperationDefinition::Query(ref q) => &q.selection_set,
+                    OperationDefinition::Mutation(ref m) => &m.selection_set,
+                    OperationDefinition::Subscription(ref s) => &s.selection_set,
+                };
+                self.collect(set, &[], &mut cache, &mut fields);
+            }
+            Definition::Fragment(ref frag) => {
+                let path = [frag.name.as_ref()];
+                self.collect(&frag.selection_set, &path, &mut cache, &mut fields);
+            }
+        }
+        let mut conflicts = Vec::new();
+        self.check(fields, &mut cache, &mut conflicts);
+        conflicts
+    }
+
+    /// Collects the fields of the selection set, expanding fragments
+    ///
+    /// The fields of each fragment are collected once and cached, so that
+    /// fragments spread many times don't take exponential time. Fragments
+    /// already expanded on the way to the selection set, or being collected,
+    /// are skipped, so that cyclic fragments don't recurse forever.
+    fn collect(
+        &self,
+        set: &'s SelectionSet<'a, T>,
+        path: &[&'s str],
+        cache: &mut Cache<'s, 'a, T>,
+        out: &mut Fields<'s, 'a, T>,
+    ) {
+        for item in &set.items {
+            match *item {
+                Selection::Field(ref field) => out.push(field, path.to_vec()),
+                Selection::InlineFragment(ref frag) => {
+                    self.collect(&frag.selection_set, path, cache, out);
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    let name = spread.fragment_name.as_ref();
+                    if path.contains(&name) {
+                        continue;
+                    }
+                    let frag = match self.fragments.get(name) {
+                        Some(frag) => frag,
+                        None => continue,
+                    };
+                    if !cache.contains_key(name) {
+                        // empty while collected, which cuts cycles short
+                        cache.insert(name, Vec::new());
+                        let mut fields = Fields::new();
+                        self.collect(&frag.selection_set, &[name], cache, &mut fields);
+                        cache.insert(name, fields.items);
+                    }
+                    for &(field, ref rest) in &cache[name] {
+                        let mut full = path.to_vec();
+                        full.extend(rest);
+                        out.push(field, full);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compares the fields of a merged selection set, then the merged
+    /// selection sets of the fields under each response key
+    fn check(
+        &self,
+        fields: Fields<'s, 'a, T>,
+        cache: &mut Cache<'s, 'a, T>,
+        out: &mut Vec<Conflict>,
+    ) {
+        let mut keys = HashMap::new();
+        let mut groups: Vec<Vec<Collected<'s, 'a, T>>> = Vec::new();
+        for (field, path) in fields.items {
+            let key = field.response_key();
+            let idx = *keys.entry(key).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[idx].push((field, path));
+        }
+        for group in groups {
+            let (first, ref path) = group[0];
+            let mut children = Fields::new();
+            self.collect(&first.selection_set, path, cache, &mut children);
+            for &(other, ref path) in &group[1..] {
+                match compare(first, other) {
+                    Some(reason) => out.push(Conflict {
+                        response_key: first.response_key().to_string(),
+                        first: first.position,
+                        second: other.position,
+                        reason,
+                    }),
+                    None => self.collect(&other.selection_set, path, cache, &mut children),
+                }
+            }
+            self.check(children, cache, out);
+        }
+    }
+}
+
+fn compare<'a, T: Text<'a> + PartialEq>(
+    first: &Field<'a, T>,
+    second: &Field<'a, T>,
+) -> Option<ConflictReason> {
+    if first.name != second.name {
+        return Some(ConflictReason::DifferentFields(
+            first.name.as_ref().to_string(),
+            second.name.as_ref().to_string(),
+        ));
+    }
+    if !same_arguments(&first.arguments, &second.arguments) {
+        return Some(ConflictReason::DifferentArguments(
+            first.name.as_ref().to_string(),
+        ));
+    }
+    None
+}
+
+/// Compares arguments regardless of their order
+fn same_arguments<'a, T: Text<'a> + PartialEq>(
+    first: &[(T::Value, Value<'a, T>)],
+    second: &[(T::Value, Value<'a, T>)],
+) -> bool {
+    first.len() == second.len()
+        && first
+            .iter()
+            .all(|(name, value)| second.iter().any(|(n, v)| n == name && v == value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{response_key_conflicts, Conflict, ConflictReason};
+    use crate::parse_query;
+    use crate::position::Pos;
+
+    fn conflicts(query: &str) -> Vec<Conflict> {
+        let doc = parse_query::<&str>(query).unwrap();
+        let found = response_key_conflicts(&doc);
+        #[cfg(feature = "rayon")]
+        assert_eq!(super::par_response_key_conflicts(&doc), found);
+        found
+    }
+
+    #[test]
+    fn mergeable() {
+        assert_eq!(conflicts("{ a a(x: 1) }").len(), 1);
+        assert_eq!(
+            conflicts(
+                "{ a(x: 1, y: 2) b: c ...F ... on T { a(y: 2, x: 1) } }
+                 fragment F on T { b: c { d } a(y: 2, x: 1) ...F }"
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn different_fields() {
+        assert_eq!(
+            conflicts("{ x: a ...F } fragment F on T { x: b }"),
+            [Conflict {
+                response_key: "x".into(),
+                first: Pos::new(1, 3, 2),
+                second: Pos::new(1, 33, 32),
+                reason: ConflictReason::DifferentFields("a".into(), "b".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let found = conflicts("{ a { b(x: 1) } a { b(x: $v) } }");
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].reason,
+            ConflictReason::DifferentArguments("b".into())
+        );
+        assert_eq!(
+            found[0].to_string(),
+            "field `b` is selected as `b` with different arguments at 1:7 and 1:21"
+        );
+        assert_eq!(
+            found[0].spec_reference().unwrap().url(),
+            "https://spec.graphql.org/October2021/#sec-Field-Selection-Merging"
+        );
+    }
+
+    #[test]
+    fn reported_once() {
+        let found = conflicts(
+            "query A { ...F } query B { y: a y: b ...F }
+             fragment F on T { x: a x: b }",
+        );
+        let keys: Vec<_> = found.iter().map(|c| c.response_key.as_str()).collect();
+        assert_eq!(keys, ["x", "y"]);
+    }
+
+    #[test]
+    fn fragment_dag() {
+        // each fragment spreads the next one twice, 2^40 expansions in all
+        let mut query = String::from("{ ...F0 }");
+        for i in 0..40 {
+            query += &format!(" fragment F{} on T {{ a ...F{} ...F{} }}", i, i + 1, i + 1);
+        }
+        query += " fragment F40 on T { x: a x: b }";
+        let found = conflicts(&query);
+        let keys: Vec<_> = found.iter().map(|c| c.response_key.as_str()).collect();
+        assert_eq!(keys, ["x"]);
+
+        let mut query = String::from("{ ...F0 }");
+        for i in 0..40 {
+            query += &format!(
+                " fragment F{} on T {{ a {{ ...F{} }} a {{ ...F{} }} }}",
+                i,
+                i + 1,
+                i + 1
+            );
+        }
+        query += " fragment F40 on T { x: a x: b }";
+        assert_eq!(conflicts(&query).len(), 1);
+    }
+}
@@ -1,21 +1,54 @@
-use combine::easy::Errors;
-use thiserror::Error;
+use std::fmt;
+use std::ops::Deref;
 
-use crate::position::Pos;
-use crate::tokenizer::Token;
+use crate::error::Errors;
+use crate::message::MessageKey;
+use crate::syntax_error::ParseErrors;
 
-pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
+pub type InternalError<'a> = Errors<'a>;
 
 /// Error parsing query
 ///
-/// This structure is opaque for forward compatibility. We are exploring a
-/// way to improve both error message and API.
-#[derive(Error, Debug)]
-#[error("query parse error: {}", _0)]
-pub struct ParseError(String);
+/// The rendered message is meant for people and may change between
+/// releases. Use `code` and `errors` to react to particular errors, the
+/// accessors are those of `ParseErrors`.
+///
+/// With the `serde` feature the error serializes as a list of errors in
+/// the format of a GraphQL response.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct ParseError(ParseErrors);
+
+impl ParseError {
+    /// Combines the first error with the ones found after resuming at the
+    /// following definitions
+    pub(crate) fn with_further(first: InternalError, further: Vec<InternalError>) -> ParseError {
+        ParseError(ParseErrors::new(
+            MessageKey::QueryParseError,
+            &first,
+            &further,
+        ))
+    }
+}
+
+impl Deref for ParseError {
+    type Target = ParseErrors;
+
+    fn deref(&self) -> &ParseErrors {
+        &self.0
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 impl<'a> From<InternalError<'a>> for ParseError {
     fn from(e: InternalError<'a>) -> ParseError {
-        ParseError(format!("{}", e))
+        ParseError::with_further(e, Vec::new())
     }
 }
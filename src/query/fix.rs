@@ -0,0 +1,909 @@
+//! Suggested fixes for common mistakes in query documents
+//!
+//! Fixes are patches of the AST, which editors can offer as code actions
+//! and tools can apply automatically. Without a schema only unused
+//! fragments are found. With one, misspelled fields, undefined variables
+//! and abstract selections missing `__typename` are fixed too.
+//!
+//! With the `rayon` feature, `par_suggest_fixes` and
+//! `par_suggest_fixes_with_schema` scan the definitions on all threads.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::{parse_query, parse_schema};
+//! use graphql_parser::query::{apply_fixes, suggest_fixes_with_schema};
+//!
+//! let schema = parse_schema::<&str>("type Query { user(id: ID!): User }
+//!                                    type User { name: String }").unwrap();
+//! let mut doc = parse_query::<String>("query Q { user(id: $id) { nmae } }").unwrap();
+//! let fixes = suggest_fixes_with_schema(&doc, &schema);
+//! assert_eq!(fixes[0].message, "type `User` has no field `nmae`, did you mean `name`?");
+//! assert_eq!(fixes[1].message, "variable `$id` is not defined");
+//! assert_eq!(apply_fixes(&fixes, &mut doc), 2);
+//! assert_eq!(doc.to_string(), "query Q($id: ID!) {\n  user(id: $id) {\n    name\n  }\n}\n");
+//! ```
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::common::{Directive, Text, Type, Value};
+use crate::parallel;
+use crate::position::Pos;
+use crate::query::ast::*;
+use crate::query::path::{definition_parts, node_mut, selection_parts};
+use crate::query::path::{NodeMut, NodePath, PathSegment};
+use crate::schema;
+
+/// A suggested change to a query document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    /// Description of the problem
+    pub message: String,
+    /// Position of the node where the problem is
+    pub position: Pos,
+    pub patch: Patch,
+}
+
+/// Change of the AST, paths refer to the document the fix was found in
+#[derive(Debug, Clone, PartialEq)]
+pub enum Patch {
+    /// Removes the node, e.g. an unused fragment
+    Remove(NodePath),
+    /// Renames the field at the path
+    RenameField { field: NodePath, name: String },
+    /// Defines a variable in the operation at the path
+    AddVariable {
+        operation: NodePath,
+        name: String,
+        var_type: Type<'static, String>,
+    },
+    /// Selects `__typename` first in the selection set of the node at the
+    /// path
+    AddTypename { parent: NodePath },
+}
+
+impl Fix {
+    /// Applies the fix, returns false if the document has no node at its
+    /// path
+    ///
+    /// Removing or inserting nodes changes paths of their following
+    /// siblings, use `apply_fixes` to apply several fixes at once.
+    pub fn apply<'a, T>(&self, doc: &mut Document<'a, T>) -> bool
+    where
+        T: Text<'a>,
+        T::Value: From<String>,
+    {
+        match self.patch {
+            Patch::Remove(ref path) => doc.remove_at_path(path),
+            Patch::RenameField {
+                ref field,
+                ref name,
+            } => match node_mut(doc, &field.segments) {
+                Some(NodeMut::Selection(Selection::Field(field))) => {
+                    field.name = T::Value::from(name.clone());
+                    true
+                }
+                _ => false,
+            },
+            Patch::AddVariable {
+                ref operation,
+                ref name,
+                ref var_type,
+            } => {
+                let variables = match node_mut(doc, &operation.segments) {
+                    Some(NodeMut::Definition(def)) => definition_parts(def).0,
+                    _ => None,
+                };
+                match variables {
+                    Some(variables) => {
+                        variables.push(VariableDefinition {
+                            position: Pos::default(),
+                            end: Pos::default(),
+                            name: T::Value::from(name.clone()),
+                            var_type: owned_type(var_type),
+                            default_value: None,
+                        });
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Patch::AddTypename { ref parent } => {
+                let set = match node_mut(doc, &parent.segments) {
+                    Some(NodeMut::Definition(def)) => Some(definition_parts(def).2),
+                    Some(NodeMut::Selection(sel)) => selection_parts(sel).2,
+                    _ => None,
+                };
+                match set {
+                    Some(set) => {
+                        let position = set.span.0;
+                        set.items.insert(
+                            0,
+                            Selection::Field(Field {
+                                position,
+                                end: position,
+                                alias: None,
+                                name: T::Value::from("__typename".to_string()),
+                                arguments: Vec::new(),
+                                directives: Vec::new(),
+                                selection_set: SelectionSet {
+                                    span: (position, position),
+                                    items: Vec::new(),
+                                },
+                            }),
+                        );
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+impl Patch {
+    fn path_mut(&mut self) -> &mut NodePath {
+        match *self {
+            Patch::Remove(ref mut path) => path,
+            Patch::RenameField { ref mut field, .. } => field,
+            Patch::AddVariable {
+                ref mut operation, ..
+            } => operation,
+            Patch::AddTypename { ref mut parent } => parent,
+        }
+    }
+}
+
+/// Applies fixes in order, returns the number of fixes applied
+///
+/// Paths of the remaining fixes are adjusted after each removal or
+/// insertion. Fixes of nodes which were removed by an earlier fix are
+/// skipped.
+pub fn apply_fixes<'a, T>(fixes: &[Fix], doc: &mut Document<'a, T>) -> usize
+where
+    T: Text<'a>,
+    T::Value: From<String>,
+{
+    let mut pending: Vec<Option<Fix>> = fixes.iter().cloned().map(Some).collect();
+    let mut applied = 0;
+    for i in 0..pending.len() {
+        let fix = match pending[i].take() {
+            Some(fix) => fix,
+            None => continue,
+        };
+        if !fix.apply(doc) {
+            continue;
+        }
+        applied += 1;
+        for slot in &mut pending[i + 1..] {
+            let keep = match *slot {
+                Some(ref mut other) => rebase(other.patch.path_mut(), &fix.patch),
+                None => continue,
+            };
+            if !keep {
+                *slot = None;
+            }
+        }
+    }
+    applied
+}
+
+/// Adjusts the path for a patch applied before, returns false if the node
+/// at the path was removed by it
+fn rebase(path: &mut NodePath, applied: &Patch) -> bool {
+    // a removed node, or the `__typename` field inserted first
+    let (at, removed) = match *applied {
+        Patch::Remove(ref removed) => (removed.clone(), true),
+        Patch::AddTypename { ref parent } => {
+            let mut at = parent.clone();
+            at.segments.push(PathSegment::Selection(0));
+            (at, false)
+        }
+        Patch::RenameField { .. } | Patch::AddVariable { .. } => return true,
+    };
+    if removed && path.segments.starts_with(&at.segments) {
+        return false;
+    }
+    let (last, parents) = match at.segments.split_last() {
+        Some(split) => split,
+        None => return true,
+    };
+    if path.segments.len() <= parents.len() || !path.segments.starts_with(parents) {
+        return true;
+    }
+    let at_index = match segment_index(last) {
+        Some(index) => index,
+        None => return true,
+    };
+    let segment = &mut path.segments[parents.len()];
+    if std::mem::discriminant(segment) != std::mem::discriminant(last) {
+        return true;
+    }
+    if let PathSegment::Definition(ref mut index)
+    | PathSegment::Variable(ref mut index)
+    | PathSegment::Selection(ref mut index)
+    | PathSegment::Directive(ref mut index) = *segment
+    {
+        if removed && *index > at_index {
+            *index -= 1;
+        } else if !removed && *index >= at_index {
+            *index += 1;
+        }
+    }
+    true
+}
+
+fn segment_index(segment: &PathSegment) -> Option<usize> {
+    match *segment {
+        PathSegment::Definition(index)
+        | PathSegment::Variable(index)
+        | PathSegment::Selection(index)
+        | PathSegment::Directive(index) => Some(index),
+        PathSegment::Argument(_) => None,
+    }
+}
+
+/// Finds fixes which don't need a schema, i.e. removal of unused fragments
+pub fn suggest_fixes<'a, T: Text<'a>>(doc: &Document<'a, T>) -> Vec<Fix> {
+    Suggester::new(None).run(doc)
+}
+
+/// Finds fixes, using the schema to check fields and infer variable types
+pub fn suggest_fixes_with_schema<'a, 's, T, U>(
+    doc: &Document<'a, T>,
+    schema: &'s schema::Document<'s, U>,
+) -> Vec<Fix>
+where
+    T: Text<'a>,
+    U: Text<'s>,
+{
+    let index = SchemaIndex::new(schema);
+    Suggester::new(Some(&index)).run(doc)
+}
+
+/// Same as `suggest_fixes`, scanning the definitions on all threads
+#[cfg(feature = "rayon")]
+pub fn par_suggest_fixes<'a, T>(doc: &Document<'a, T>) -> Vec<Fix>
+where
+    T: Text<'a>,
+    T::Value: Sync,
+{
+    Suggester::new(None).par_run(doc)
+}
+
+/// Same as `suggest_fixes_with_schema`, scanning the definitions on all
+/// threads
+#[cfg(feature = "rayon")]
+pub fn par_suggest_fixes_with_schema<'a, 's, T, U>(
+    doc: &Document<'a, T>,
+    schema: &'s schema::Document<'s, U>,
+) -> Vec<Fix>
+where
+    T: Text<'a>,
+    T::Value: Sync,
+    U: Text<'s>,
+{
+    let index = SchemaIndex::new(schema);
+    Suggester::new(Some(&index)).par_run(doc)
+}
+
+struct FieldInfo<'s> {
+    name: &'s str,
+    type_name: &'s str,
+    arguments: Vec<(&'s str, Type<'static, String>)>,
+}
+
+#[derive(Default)]
+struct TypeInfo<'s> {
+    is_abstract: bool,
+    fields: Vec<FieldInfo<'s>>,
+}
+
+/// Composite types of a schema and their fields
+struct SchemaIndex<'s> {
+    types: HashMap<&'s str, TypeInfo<'s>>,
+    query: &'s str,
+    mutation: &'s str,
+    subscription: &'s str,
+}
+
+impl<'s> SchemaIndex<'s> {
+    fn new<U: Text<'s>>(doc: &'s schema::Document<'s, U>) -> SchemaIndex<'s> {
+        use crate::schema::{Definition as D, TypeDefinition as TD, TypeExtension as TE};
+
+        let mut index = SchemaIndex {
+            types: HashMap::new(),
+            query: "Query",
+            mutation: "Mutation",
+            subscription: "Subscription",
+        };
+        for def in &doc.definitions {
+            let (name, is_abstract, fields) = match *def {
+                D::SchemaDefinition(ref schema) => {
+                    if let Some(ref query) = schema.query {
+                        index.query = query.as_ref();
+                    }
+                    if let Some(ref mutation) = schema.mutation {
+                        index.mutation = mutation.as_ref();
+                    }
+                    if let Some(ref subscription) = schema.subscription {
+                        index.subscription = subscription.as_ref();
+                    }
+                    continue;
+                }
+                D::TypeDefinition(TD::Object(ref obj)) => (&obj.name, false, &obj.fields[..]),
+                D::TypeDefinition(TD::Interface(ref i)) => (&i.name, true, &i.fields[..]),
+                D::TypeDefinition(TD::Union(ref union)) => (&union.name, true, &[][..]),
+                D::TypeExtension(TE::Object(ref obj)) => (&obj.name, false, &obj.fields[..]),
+                D::TypeExtension(TE::Interface(ref i)) => (&i.name, true, &i.fields[..]),
+                _ => continue,
+            };
+            let info = index.types.entry(name.as_ref()).or_default();
+            info.is_abstract |= is_abstract;
+            info.fields.extend(fields.iter().map(|field| {
+                FieldInfo {
+                    name: field.name.as_ref(),
+                    type_name: field.field_type.innermost_name(),
+                    arguments: field
+                        .arguments
+                        .iter()
+                        .map(|arg| (arg.name.as_ref(), arg.value_type.into_static()))
+                        .collect(),
+                }
+            }));
+        }
+        index
+    }
+}
+
+/// Variables used and fragments spread by a definition
+#[derive(Default)]
+struct Usage {
+    variables: Vec<(String, Option<Type<'static, String>>)>,
+    spreads: Vec<String>,
+}
+
+struct Suggester<'i, 's> {
+    schema: Option<&'i SchemaIndex<'s>>,
+    fixes: Vec<Fix>,
+}
+
+impl<'i, 's> Suggester<'i, 's> {
+    fn new(schema: Option<&'i SchemaIndex<'s>>) -> Suggester<'i, 's> {
+        Suggester {
+            schema,
+            fixes: Vec::new(),
+        }
+    }
+
+    fn run<'a, T: Text<'a>>(self, doc: &Document<'a, T>) -> Vec<Fix> {
+        let schema = self.schema;
+        let scanned = parallel::map(&doc.definitions, |i, def| scan(schema, i, def));
+        let (mut fixes, usages) = self.merge(scanned);
+        let fragments = fragment_indices(doc);
+        let checked = parallel::map(&doc.definitions, |i, def| {
+            check_operation(i, def, &usages, &fragments)
+        });
+        unused_fragments(doc, checked, &mut fixes);
+        fixes
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_run<'a, T>(self, doc: &Document<'a, T>) -> Vec<Fix>
+    where
+        T: Text<'a>,
+        T::Value: Sync,
+    {
+        let schema = self.schema;
+        let scanned = parallel::par_map(&doc.definitions, |i, def| scan(schema, i, def));
+        let (mut fixes, usages) = self.merge(scanned);
+        let fragments = fragment_indices(doc);
+        let checked = parallel::par_map(&doc.definitions, |i, def| {
+            check_operation(i, def, &usages, &fragments)
+        });
+        unused_fragments(doc, checked, &mut fixes);
+        fixes
+    }
+
+    /// Collects the fixes and usages found by `scan`
+    fn merge(self, scanned: Vec<(Usage, Vec<Fix>)>) -> (Vec<Fix>, Vec<Usage>) {
+        let mut fixes = self.fixes;
+        let mut usages = Vec::new();
+        for (usage, found) in scanned {
+            usages.push(usage);
+            fixes.extend(found);
+        }
+        (fixes, usages)
+    }
+
+    /// Checks the selections of a definition, returning what it uses
+    fn definition<'a, T: Text<'a>>(&mut self, i: usize, def: &Definition<'a, T>) -> Usage {
+        let path = NodePath {
+            segments: vec![PathSegment::Definition(i)],
+        };
+        let mut usage = Usage::default();
+        match *def {
+            Definition::Operation(ref op) => {
+                let (root, directives, set) = match *op {
+                    OperationDefinition::SelectionSet(ref set) => {
+                        (self.schema.map(|s| s.query), &[][..], set)
+                    }
+                    OperationDefinition::Query(ref q) => (
+                        self.schema.map(|s| s.query),
+                        &q.directives[..],
+                        &q.selection_set,
+                    ),
+                    OperationDefinition::Mutation(ref m) => (
+                        self.schema.map(|s| s.mutation),
+                        &m.directives[..],
+                        &m.selection_set,
+                    ),
+                    OperationDefinition::Subscription(ref s) => (
+                        self.schema.map(|s| s.subscription),
+                        &s.directives[..],
+                        &s.selection_set,
+                    ),
+                };
+                directive_variables(directives, &mut usage);
+                self.selection_set(set, &path, root, &mut usage);
+            }
+            Definition::Fragment(ref frag) => {
+                let TypeCondition::On(ref cond) = frag.type_condition;
+                directive_variables(&frag.directives, &mut usage);
+                self.selection_set(&frag.selection_set, &path, Some(cond.as_ref()), &mut usage);
+            }
+        }
+        usage
+    }
+
+    fn selection_set<'a, T: Text<'a>>(
+        &mut self,
+        set: &SelectionSet<'a, T>,
+        owner: &NodePath,
+        parent: Option<&str>,
+        usage: &mut Usage,
+    ) {
+        let parent_info = parent.and_then(|name| Some((name, self.schema?.types.get(name)?)));
+        if let Some((name, info)) = parent_info {
+            let has_typename = set.items.iter().any(|item| match *item {
+                Selection::Field(ref field) => field.name.as_ref() == "__typename",
+                _ => false,
+            });
+            if info.is_abstract && !has_typename && !set.items.is_empty() {
+                self.fixes.push(Fix {
+                    message: format!(
+                        "selection of abstract type `{}` doesn't include `__typename`",
+                        name
+                    ),
+                    position: set.span.0,
+                    patch: Patch::AddTypename {
+                        parent: owner.clone(),
+                    },
+                });
+            }
+        }
+        for (i, item) in set.items.iter().enumerate() {
+            let mut path = owner.clone();
+            path.segments.push(PathSegment::Selection(i));
+            match *item {
+                Selection::Field(ref field) => {
+                    let name = field.name.as_ref();
+                    let info = match parent_info {
+                        Some(_) if name.starts_with("__") => None,
+                        Some((parent, info)) => {
+                            let found = info.fields.iter().find(|f| f.name == name);
+                            if found.is_none() {
+                                self.misspelled(field, &path, parent, info);
+                            }
+                            found
+                        }
+                        None => None,
+                    };
+                    for (arg, value) in &field.arguments {
+                        let arg_type = info
+                            .and_then(|info| info.arguments.iter().find(|a| a.0 == arg.as_ref()));
+                        value_variables(value, arg_type.map(|a| &a.1), usage);
+                    }
+                    directive_variables(&field.directives, usage);
+                    let child = info.map(|info| info.type_name);
+                    self.selection_set(&field.selection_set, &path, child, usage);
+                }
+                Selection::InlineFragment(ref frag) => {
+                    let cond = match frag.type_condition {
+                        Some(TypeCondition::On(ref cond)) => Some(cond.as_ref()),
+                        None => parent,
+                    };
+                    directive_variables(&frag.directives, usage);
+                    self.selection_set(&frag.selection_set, &path, cond, usage);
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    directive_variables(&spread.directives, usage);
+                    usage
+                        .spreads
+                        .push(spread.fragment_name.as_ref().to_string());
+                }
+            }
+        }
+    }
+
+    fn misspelled<'a, T: Text<'a>>(
+        &mut self,
+        field: &Field<'a, T>,
+        path: &NodePath,
+        parent: &str,
+        info: &TypeInfo,
+    ) {
+        let name = field.name.as_ref();
+        let limit = std::cmp::max(1, name.chars().count() / 3);
+        let closest = info
+            .fields
+            .iter()
+            .map(|f| (edit_distance(name, f.name), f.name))
+            .filter(|&(distance, _)| distance <= limit)
+            .min_by_key(|&(distance, _)| distance);
+        if let Some((_, suggestion)) = closest {
+            self.fixes.push(Fix {
+                message: format!(
+                    "type `{}` has no field `{}`, did you mean `{}`?",
+                    parent, name, suggestion
+                ),
+                position: field.position,
+                patch: Patch::RenameField {
+                    field: path.clone(),
+                    name: suggestion.to_string(),
+                },
+            });
+        }
+    }
+}
+
+/// Checks the selections of a definition, returning what it uses and the
+/// fixes found
+fn scan<'a, T: Text<'a>>(
+    schema: Option<&SchemaIndex>,
+    i: usize,
+    def: &Definition<'a, T>,
+) -> (Usage, Vec<Fix>) {
+    let mut suggester = Suggester::new(schema);
+    let usage = suggester.definition(i, def);
+    (usage, suggester.fixes)
+}
+
+/// Returns the indices of the fragment definitions by name
+fn fragment_indices<'d, 'a, T: Text<'a>>(doc: &'d Document<'a, T>) -> HashMap<&'d str, usize> {
+    doc.definitions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, def)| match *def {
+            Definition::Fragment(ref frag) => Some((frag.name.as_ref(), i)),
+            Definition::Operation(_) => None,
+        })
+        .collect()
+}
+
+/// Finds the variables an operation is missing, see `missing_variables`
+fn check_operation<'a, T: Text<'a>>(
+    i: usize,
+    def: &Definition<'a, T>,
+    usages: &[Usage],
+    fragments: &HashMap<&str, usize>,
+) -> (Vec<Fix>, Vec<usize>) {
+    match *def {
+        Definition::Operation(ref op) => missing_variables(i, op, usages, fragments),
+        Definition::Fragment(_) => (Vec::new(), Vec::new()),
+    }
+}
+
+/// Suggests removing the fragments no operation reaches
+fn unused_fragments<'a, T: Text<'a>>(
+    doc: &Document<'a, T>,
+    checked: Vec<(Vec<Fix>, Vec<usize>)>,
+    fixes: &mut Vec<Fix>,
+) {
+    let mut used_fragments = HashSet::new();
+    for (found, reachable) in checked {
+        fixes.extend(found);
+        used_fragments.extend(reachable);
+    }
+    for (i, def) in doc.definitions.iter().enumerate() {
+        if let Definition::Fragment(ref frag) = *def {
+            if !used_fragments.contains(&i) {
+                fixes.push(Fix {
+                    message: format!("fragment `{}` is never used", frag.name.as_ref()),
+                    position: frag.position,
+                    patch: Patch::Remove(NodePath {
+                        segments: vec![PathSegment::Definition(i)],
+                    }),
+                });
+            }
+        }
+    }
+}
+
+/// Returns fixes defining the variables which the operation uses but
+/// doesn't define, along with the fragments it spreads
+fn missing_variables<'a, T: Text<'a>>(
+    i: usize,
+    op: &OperationDefinition<'a, T>,
+    usages: &[Usage],
+    fragments: &HashMap<&str, usize>,
+) -> (Vec<Fix>, Vec<usize>) {
+    let mut fixes = Vec::new();
+    let reachable = reachable_fragments(i, usages, fragments);
+    let (position, defined) = match *op {
+        OperationDefinition::SelectionSet(_) => (None, &[][..]),
+        OperationDefinition::Query(ref q) => (Some(q.position), &q.variable_definitions[..]),
+        OperationDefinition::Mutation(ref m) => (Some(m.position), &m.variable_definitions[..]),
+        OperationDefinition::Subscription(ref s) => (Some(s.position), &s.variable_definitions[..]),
+    };
+    let mut missing: Vec<(&str, &Option<Type<'static, String>>)> = Vec::new();
+    for idx in Some(i).into_iter().chain(reachable.iter().cloned()) {
+        for (name, var_type) in &usages[idx].variables {
+            if defined.iter().any(|var| var.name.as_ref() == name) {
+                continue;
+            }
+            match missing.iter_mut().find(|(n, _)| n == name) {
+                Some(entry) if entry.1.is_none() => entry.1 = var_type,
+                Some(_) => {}
+                None => missing.push((name, var_type)),
+            }
+        }
+    }
+    // shorthand operations can't define variables
+    if let Some(position) = position {
+        for (name, var_type) in missing {
+            if let Some(ref var_type) = *var_type {
+                fixes.push(Fix {
+                    message: format!("variable `${}` is not defined", name),
+                    position,
+                    patch: Patch::AddVariable {
+                        operation: NodePath {
+                            segments: vec![PathSegment::Definition(i)],
+                        },
+                        name: name.to_string(),
+                        var_type: var_type.clone(),
+                    },
+                });
+            }
+        }
+    }
+    (fixes, reachable)
+}
+
+/// Returns indices of fragment definitions spread by the definition,
+/// directly or not
+fn reachable_fragments(
+    def: usize,
+    usages: &[Usage],
+    fragments: &HashMap<&str, usize>,
+) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(def);
+    while let Some(idx) = queue.pop_front() {
+        for spread in &usages[idx].spreads {
+            if let Some(&frag) = fragments.get(spread.as_str()) {
+                if seen.insert(frag) {
+                    result.push(frag);
+                    queue.push_back(frag);
+                }
+            }
+        }
+    }
+    result
+}
+
+fn directive_variables<'a, T: Text<'a>>(directives: &[Directive<'a, T>], usage: &mut Usage) {
+    for dir in directives {
+        let builtin = matches!(dir.name.as_ref(), "skip" | "include");
+        for (arg, value) in &dir.arguments {
+            let arg_type = if builtin && arg.as_ref() == "if" {
+                Some(Type::NonNullType(Box::new(Type::NamedType(
+                    "Boolean".into(),
+                ))))
+            } else {
+                None
+            };
+            value_variables(value, arg_type.as_ref(), usage);
+        }
+    }
+}
+
+/// Records variables used in the value, the type is only known for
+/// variables used directly as an argument
+fn value_variables<'a, T: Text<'a>>(
+    value: &Value<'a, T>,
+    value_type: Option<&Type<'static, String>>,
+    usage: &mut Usage,
+) {
+    match *value {
+        Value::Variable(ref name) => usage
+            .variables
+            .push((name.as_ref().to_string(), value_type.cloned())),
+        Value::List(ref items) => {
+            for item in items {
+                value_variables(item, None, usage);
+            }
+        }
+        Value::Object(ref fields) => {
+            for (_, item) in fields {
+                value_variables(item, None, usage);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn owned_type<'a, T>(ty: &Type<'static, String>) -> Type<'a, T>
+where
+    T: Text<'a>,
+    T::Value: From<String>,
+{
+    match *ty {
+        Type::NamedType(ref name) => Type::NamedType(T::Value::from(name.clone())),
+        Type::ListType(ref inner) => Type::ListType(Box::new(owned_type(inner))),
+        Type::NonNullType(ref inner) => Type::NonNullType(Box::new(owned_type(inner))),
+    }
+}
+
+/// Edit distance in characters, counting swapped neighbours as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j - 1] + cost)
+                .min(rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_fixes, edit_distance, suggest_fixes, suggest_fixes_with_schema, Fix};
+    use crate::{parse_query, parse_schema};
+
+    const SCHEMA: &str = "
+        type Query { node(id: ID!): Node search(text: String!, limit: Int): [Result] }
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name: String friends: [User] }
+        union Result = User
+    ";
+
+    fn messages(fixes: &[Fix]) -> Vec<&str> {
+        fixes.iter().map(|fix| fix.message.as_str()).collect()
+    }
+
+    fn apply_all(mut source: String) -> String {
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        // fixes are found again after each one, as paths change
+        loop {
+            let doc = parse_query::<String>(&source).unwrap();
+            let fix = match suggest_fixes_with_schema(&doc, &schema).into_iter().next() {
+                Some(fix) => fix,
+                None => return source,
+            };
+            let mut doc = doc.clone();
+            assert!(fix.apply(&mut doc));
+            source = doc.to_string();
+        }
+    }
+
+    #[test]
+    fn without_schema() {
+        let doc = parse_query::<&str>(
+            "{ ...A } fragment A on T { ...B } fragment B on T { a } fragment C on T { ...C }",
+        )
+        .unwrap();
+        assert_eq!(
+            messages(&suggest_fixes(&doc)),
+            ["fragment `C` is never used"]
+        );
+    }
+
+    #[test]
+    fn with_schema() {
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        let doc = parse_query::<&str>(
+            "query Q { node(id: $id) { id ...F } search(text: $text, limit: $n) @skip(if: $skip) { ... on User { nmae frends { id } } } }
+             fragment F on User { name(format: $format) }",
+        )
+        .unwrap();
+        assert_eq!(
+            messages(&suggest_fixes_with_schema(&doc, &schema)),
+            [
+                "selection of abstract type `Node` doesn't include `__typename`",
+                "selection of abstract type `Result` doesn't include `__typename`",
+                "type `User` has no field `nmae`, did you mean `name`?",
+                "type `User` has no field `frends`, did you mean `friends`?",
+                "variable `$id` is not defined",
+                "variable `$text` is not defined",
+                "variable `$n` is not defined",
+                "variable `$skip` is not defined",
+            ]
+        );
+        assert_eq!(
+            apply_all(doc.to_string()),
+            "query Q($id: ID!, $text: String!, $n: Int, $skip: Boolean!) {
+  node(id: $id) {
+    __typename
+    id
+    ...F
+  }
+  search(text: $text, limit: $n) @skip(if: $skip) {
+    __typename
+    ... on User {
+      name
+      friends {
+        id
+      }
+    }
+  }
+}
+
+fragment F on User {
+  name(format: $format)
+}
+"
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        use super::{par_suggest_fixes, par_suggest_fixes_with_schema};
+
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        let doc = parse_query::<&str>(
+            "query Q { search(text: $text) { ... on User { nmae } } } fragment F on User { id }",
+        )
+        .unwrap();
+        assert_eq!(par_suggest_fixes(&doc), suggest_fixes(&doc));
+        assert_eq!(
+            par_suggest_fixes_with_schema(&doc, &schema),
+            suggest_fixes_with_schema(&doc, &schema)
+        );
+    }
+
+    #[test]
+    fn apply_in_order() {
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        let source = "fragment Unused on User { name } fragment Other on User { name }
+                      query Q { search(text: \"a\") { ... on User { nmae } } }";
+        let mut doc = parse_query::<String>(source).unwrap();
+        let fixes = suggest_fixes_with_schema(&doc, &schema);
+        assert_eq!(fixes.len(), 4);
+        assert_eq!(apply_fixes(&fixes, &mut doc), 4);
+        assert_eq!(
+            doc.to_string(),
+            "query Q {
+  search(text: \"a\") {
+    __typename
+    ... on User {
+      name
+    }
+  }
+}
+"
+        );
+        // nothing is left to fix
+        assert!(suggest_fixes_with_schema(&doc, &schema).is_empty());
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("nmae", "name"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,638 @@
+//! Compact query AST stored in arenas
+//!
+//! Every kind of node lives in its own contiguous vector inside the
+//! [`Document`](struct.Document.html) and nodes refer to each other with
+//! typed indices instead of boxes and vectors. Children of a node are always
+//! stored next to each other, so a list of them is just a range of indices.
+//! This keeps traversals of very large documents cache-friendly.
+//!
+//! Leaf data (values, types and variable definitions) is kept in the same
+//! form as in the classic AST.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::query::{parse_query, flat};
+//!
+//! let ast = parse_query::<&str>("{ user(id: 1) { name } }").unwrap();
+//! let doc = flat::Document::from_ast(&ast);
+//! // nested fields are stored before their parents
+//! let names: Vec<_> = doc.fields().iter().map(|f| f.name).collect();
+//! assert_eq!(names, ["name", "user"]);
+//! assert_eq!(doc.to_ast(), ast);
+//! ```
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Index;
+
+use super::ast as classic;
+pub use super::ast::OperationKind;
+use crate::common::{Text, Value};
+use crate::position::Pos;
+
+/// Index of a node of type `N` in a [`Document`](struct.Document.html)
+pub struct Id<N> {
+    index: u32,
+    marker: PhantomData<fn() -> N>,
+}
+
+impl<N> Id<N> {
+    fn new(index: usize) -> Id<N> {
+        Id {
+            index: index as u32,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.index as usize
+    }
+}
+
+impl<N> Clone for Id<N> {
+    fn clone(&self) -> Id<N> {
+        *self
+    }
+}
+
+impl<N> Copy for Id<N> {}
+
+impl<N> PartialEq for Id<N> {
+    fn eq(&self, other: &Id<N>) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<N> Eq for Id<N> {}
+
+impl<N> Hash for Id<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state)
+    }
+}
+
+impl<N> fmt::Debug for Id<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Id({})", self.index)
+    }
+}
+
+/// Contiguous list of nodes of type `N` in a [`Document`](struct.Document.html)
+pub struct IdRange<N> {
+    start: u32,
+    end: u32,
+    marker: PhantomData<fn() -> N>,
+}
+
+impl<N> IdRange<N> {
+    fn new(start: usize, end: usize) -> IdRange<N> {
+        IdRange {
+            start: start as u32,
+            end: end as u32,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Id<N>> {
+        (self.start as usize..self.end as usize).map(Id::new)
+    }
+}
+
+impl<N> Clone for IdRange<N> {
+    fn clone(&self) -> IdRange<N> {
+        *self
+    }
+}
+
+impl<N> Copy for IdRange<N> {}
+
+impl<N> PartialEq for IdRange<N> {
+    fn eq(&self, other: &IdRange<N>) -> bool {
+        self.start == other.start && self.end == other.end
+    }
+}
+
+impl<N> Eq for IdRange<N> {}
+
+impl<N> fmt::Debug for IdRange<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IdRange({}..{})", self.start, self.end)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Definition<'a, T: Text<'a>> {
+    Operation(Id<Operation<'a, T>>),
+    Fragment(Id<FragmentDefinition<'a, T>>),
+}
+
+impl<'a, T: Text<'a>> Clone for Definition<'a, T> {
+    fn clone(&self) -> Definition<'a, T> {
+        *self
+    }
+}
+
+impl<'a, T: Text<'a>> Copy for Definition<'a, T> {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation<'a, T: Text<'a>> {
+    pub kind: OperationKind,
+    pub position: Pos,
+    pub end: Pos,
+    pub name: Option<T::Value>,
+    pub variable_definitions: IdRange<classic::VariableDefinition<'a, T>>,
+    pub directives: IdRange<Directive<'a, T>>,
+    pub selection_set: Id<SelectionSet<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentDefinition<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub name: T::Value,
+    pub type_condition: T::Value,
+    pub directives: IdRange<Directive<'a, T>>,
+    pub selection_set: Id<SelectionSet<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionSet<'a, T: Text<'a>> {
+    pub span: (Pos, Pos),
+    pub items: IdRange<Selection<'a, T>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selection<'a, T: Text<'a>> {
+    Field(Id<Field<'a, T>>),
+    FragmentSpread(Id<FragmentSpread<'a, T>>),
+    InlineFragment(Id<InlineFragment<'a, T>>),
+}
+
+impl<'a, T: Text<'a>> Clone for Selection<'a, T> {
+    fn clone(&self) -> Selection<'a, T> {
+        *self
+    }
+}
+
+impl<'a, T: Text<'a>> Copy for Selection<'a, T> {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub alias: Option<T::Value>,
+    pub name: T::Value,
+    pub arguments: IdRange<Argument<'a, T>>,
+    pub directives: IdRange<Directive<'a, T>>,
+    pub selection_set: Id<SelectionSet<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentSpread<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub fragment_name: T::Value,
+    pub directives: IdRange<Directive<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineFragment<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub type_condition: Option<T::Value>,
+    pub directives: IdRange<Directive<'a, T>>,
+    pub selection_set: Id<SelectionSet<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub end: Pos,
+    pub name: T::Value,
+    pub arguments: IdRange<Argument<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument<'a, T: Text<'a>> {
+    pub name: T::Value,
+    pub value: Value<'a, T>,
+}
+
+/// Query document with all nodes stored in arenas
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document<'a, T: Text<'a>> {
+    definitions: Vec<Definition<'a, T>>,
+    operations: Vec<Operation<'a, T>>,
+    fragments: Vec<FragmentDefinition<'a, T>>,
+    variable_definitions: Vec<classic::VariableDefinition<'a, T>>,
+    selection_sets: Vec<SelectionSet<'a, T>>,
+    selections: Vec<Selection<'a, T>>,
+    fields: Vec<Field<'a, T>>,
+    fragment_spreads: Vec<FragmentSpread<'a, T>>,
+    inline_fragments: Vec<InlineFragment<'a, T>>,
+    directives: Vec<Directive<'a, T>>,
+    arguments: Vec<Argument<'a, T>>,
+    comments: Vec<classic::Comment<'a, T>>,
+}
+
+impl<'a, T: Text<'a> + Clone> Document<'a, T> {
+    /// Converts the classic AST into the arena representation
+    pub fn from_ast(ast: &classic::Document<'a, T>) -> Document<'a, T> {
+        let mut doc = Document {
+            definitions: Vec::with_capacity(ast.definitions.len()),
+            operations: Vec::new(),
+            fragments: Vec::new(),
+            variable_definitions: Vec::new(),
+            selection_sets: Vec::new(),
+            selections: Vec::new(),
+            fields: Vec::new(),
+            fragment_spreads: Vec::new(),
+            inline_fragments: Vec::new(),
+            directives: Vec::new(),
+            arguments: Vec::new(),
+            comments: ast.comments.clone(),
+        };
+        for definition in &ast.definitions {
+            let definition = doc.add_definition(definition);
+            doc.definitions.push(definition);
+        }
+        doc
+    }
+
+    /// Converts the document back into the classic AST
+    pub fn to_ast(&self) -> classic::Document<'a, T> {
+        classic::Document {
+            definitions: self
+                .definitions
+                .iter()
+                .map(|&definition| match definition {
+                    Definition::Operation(id) => {
+                        classic::Definition::Operation(self.operation_to_ast(&self[id]))
+                    }
+                    Definition::Fragment(id) => {
+                        let fragment = &self[id];
+                        classic::Definition::Fragment(classic::FragmentDefinition {
+                            position: fragment.position,
+                            end: fragment.end,
+                            name: fragment.name.clone(),
+                            type_condition: classic::TypeCondition::On(
+                                fragment.type_condition.clone(),
+                            ),
+                            directives: self.directives_to_ast(fragment.directives),
+                            selection_set: self.selection_set_to_ast(fragment.selection_set),
+                        })
+                    }
+                })
+                .collect(),
+            comments: self.comments.clone(),
+        }
+    }
+
+    pub fn definitions(&self) -> &[Definition<'a, T>] {
+        &self.definitions
+    }
+
+    pub fn operations(&self) -> &[Operation<'a, T>] {
+        &self.operations
+    }
+
+    pub fn fragments(&self) -> &[FragmentDefinition<'a, T>] {
+        &self.fragments
+    }
+
+    pub fn selection_sets(&self) -> &[SelectionSet<'a, T>] {
+        &self.selection_sets
+    }
+
+    pub fn fields(&self) -> &[Field<'a, T>] {
+        &self.fields
+    }
+
+    pub fn fragment_spreads(&self) -> &[FragmentSpread<'a, T>] {
+        &self.fragment_spreads
+    }
+
+    pub fn inline_fragments(&self) -> &[InlineFragment<'a, T>] {
+        &self.inline_fragments
+    }
+
+    pub fn directives(&self) -> &[Directive<'a, T>] {
+        &self.directives
+    }
+
+    pub fn arguments(&self) -> &[Argument<'a, T>] {
+        &self.arguments
+    }
+
+    fn add_definition(&mut self, definition: &classic::Definition<'a, T>) -> Definition<'a, T> {
+        match *definition {
+            classic::Definition::Operation(ref operation) => {
+                let variables = operation.variable_definitions();
+                let directives = operation.directives();
+                let selection_set = operation.selection_set();
+                let span = operation.span();
+                let (kind, position, end) = (operation.kind(), span.start, span.end);
+                let name = match *operation {
+                    classic::OperationDefinition::SelectionSet(_) => None,
+                    classic::OperationDefinition::Query(ref q) => q.name.clone(),
+                    classic::OperationDefinition::Mutation(ref m) => m.name.clone(),
+                    classic::OperationDefinition::Subscription(ref s) => s.name.clone(),
+                };
+                let start = self.variable_definitions.len();
+                self.variable_definitions.extend(variables.iter().cloned());
+                let variable_definitions = IdRange::new(start, self.variable_definitions.len());
+                let operation = Operation {
+                    kind,
+                    position,
+                    end,
+                    name,
+                    variable_definitions,
+                    directives: self.add_directives(directives),
+                    selection_set: self.add_selection_set(selection_set),
+                };
+                self.operations.push(operation);
+                Definition::Operation(Id::new(self.operations.len() - 1))
+            }
+            classic::Definition::Fragment(ref fragment) => {
+                let classic::TypeCondition::On(ref type_condition) = fragment.type_condition;
+                let fragment = FragmentDefinition {
+                    position: fragment.position,
+                    end: fragment.end,
+                    name: fragment.name.clone(),
+                    type_condition: type_condition.clone(),
+                    directives: self.add_directives(&fragment.directives),
+                    selection_set: self.add_selection_set(&fragment.selection_set),
+                };
+                self.fragments.push(fragment);
+                Definition::Fragment(Id::new(self.fragments.len() - 1))
+            }
+        }
+    }
+
+    fn add_selection_set(&mut self, set: &classic::SelectionSet<'a, T>) -> Id<SelectionSet<'a, T>> {
+        // reserve a contiguous block for the items before descending into
+        // nested selection sets
+        let start = self.selections.len();
+        let end = start + set.items.len();
+        // placeholders, overwritten below
+        self.selections.resize(end, Selection::Field(Id::new(0)));
+        for (slot, item) in (start..end).zip(&set.items) {
+            self.selections[slot] = self.add_selection(item);
+        }
+        self.selection_sets.push(SelectionSet {
+            span: set.span,
+            items: IdRange::new(start, end),
+        });
+        Id::new(self.selection_sets.len() - 1)
+    }
+
+    fn add_selection(&mut self, selection: &classic::Selection<'a, T>) -> Selection<'a, T> {
+        match *selection {
+            classic::Selection::Field(ref field) => {
+                let field = Field {
+                    position: field.position,
+                    end: field.end,
+                    alias: field.alias.clone(),
+                    name: field.name.clone(),
+                    arguments: self.add_arguments(&field.arguments),
+                    directives: self.add_directives(&field.directives),
+                    selection_set: self.add_selection_set(&field.selection_set),
+                };
+                self.fields.push(field);
+                Selection::Field(Id::new(self.fields.len() - 1))
+            }
+            classic::Selection::FragmentSpread(ref spread) => {
+                let spread = FragmentSpread {
+                    position: spread.position,
+                    end: spread.end,
+                    fragment_name: spread.fragment_name.clone(),
+                    directives: self.add_directives(&spread.directives),
+                };
+                self.fragment_spreads.push(spread);
+                Selection::FragmentSpread(Id::new(self.fragment_spreads.len() - 1))
+            }
+            classic::Selection::InlineFragment(ref fragment) => {
+                let fragment = InlineFragment {
+                    position: fragment.position,
+                    end: fragment.end,
+                    type_condition: fragment
+                        .type_condition
+                        .as_ref()
+                        .map(|classic::TypeCondition::On(name)| name.clone()),
+                    directives: self.add_directives(&fragment.directives),
+                    selection_set: self.add_selection_set(&fragment.selection_set),
+                };
+                self.inline_fragments.push(fragment);
+                Selection::InlineFragment(Id::new(self.inline_fragments.len() - 1))
+            }
+        }
+    }
+
+    fn add_directives(
+        &mut self,
+        directives: &[crate::common::Directive<'a, T>],
+    ) -> IdRange<Directive<'a, T>> {
+        // arguments are added first, so directives stay contiguous
+        let arguments: Vec<_> = directives
+            .iter()
+            .map(|d| self.add_arguments(&d.arguments))
+            .collect();
+        let start = self.directives.len();
+        self.directives.extend(
+            directives
+                .iter()
+                .zip(arguments)
+                .map(|(d, arguments)| Directive {
+                    position: d.position,
+                    end: d.end,
+                    name: d.name.clone(),
+                    arguments,
+                }),
+        );
+        IdRange::new(start, self.directives.len())
+    }
+
+    fn add_arguments(
+        &mut self,
+        arguments: &[(T::Value, Value<'a, T>)],
+    ) -> IdRange<Argument<'a, T>> {
+        let start = self.arguments.len();
+        self.arguments
+            .extend(arguments.iter().map(|(name, value)| Argument {
+                name: name.clone(),
+                value: value.clone(),
+            }));
+        IdRange::new(start, self.arguments.len())
+    }
+
+    fn operation_to_ast(
+        &self,
+        operation: &Operation<'a, T>,
+    ) -> classic::OperationDefinition<'a, T> {
+        classic::Operation {
+            kind: operation.kind,
+            position: operation.position,
+            end: operation.end,
+            name: operation.name.clone(),
+            variable_definitions: self[operation.variable_definitions].to_vec(),
+            directives: self.directives_to_ast(operation.directives),
+            selection_set: self.selection_set_to_ast(operation.selection_set),
+        }
+        .into()
+    }
+
+    fn selection_set_to_ast(&self, id: Id<SelectionSet<'a, T>>) -> classic::SelectionSet<'a, T> {
+        let set = &self[id];
+        classic::SelectionSet {
+            span: set.span,
+            items: self[set.items]
+                .iter()
+                .map(|&selection| match selection {
+                    Selection::Field(id) => {
+                        let field = &self[id];
+                        classic::Selection::Field(classic::Field {
+                            position: field.position,
+                            end: field.end,
+                            alias: field.alias.clone(),
+                            name: field.name.clone(),
+                            arguments: self.arguments_to_ast(field.arguments),
+                            directives: self.directives_to_ast(field.directives),
+                            selection_set: self.selection_set_to_ast(field.selection_set),
+                        })
+                    }
+                    Selection::FragmentSpread(id) => {
+                        let spread = &self[id];
+                        classic::Selection::FragmentSpread(classic::FragmentSpread {
+                            position: spread.position,
+                            end: spread.end,
+                            fragment_name: spread.fragment_name.clone(),
+                            directives: self.directives_to_ast(spread.directives),
+                        })
+                    }
+                    Selection::InlineFragment(id) => {
+                        let fragment = &self[id];
+                        classic::Selection::InlineFragment(classic::InlineFragment {
+                            position: fragment.position,
+                            end: fragment.end,
+                            type_condition: fragment
+                                .type_condition
+                                .clone()
+                                .map(classic::TypeCondition::On),
+                            directives: self.directives_to_ast(fragment.directives),
+                            selection_set: self.selection_set_to_ast(fragment.selection_set),
+                        })
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn directives_to_ast(
+        &self,
+        range: IdRange<Directive<'a, T>>,
+    ) -> Vec<crate::common::Directive<'a, T>> {
+        self[range]
+            .iter()
+            .map(|d| crate::common::Directive {
+                position: d.position,
+                end: d.end,
+                name: d.name.clone(),
+                arguments: self.arguments_to_ast(d.arguments),
+            })
+            .collect()
+    }
+
+    fn arguments_to_ast(&self, range: IdRange<Argument<'a, T>>) -> Vec<(T::Value, Value<'a, T>)> {
+        self[range]
+            .iter()
+            .map(|a| (a.name.clone(), a.value.clone()))
+            .collect()
+    }
+}
+
+macro_rules! impl_index {
+    ($($node:ident => $arena:ident,)*) => {
+        $(
+            impl<'a, T: Text<'a>> Index<Id<$node<'a, T>>> for Document<'a, T> {
+                type Output = $node<'a, T>;
+                fn index(&self, id: Id<$node<'a, T>>) -> &$node<'a, T> {
+                    &self.$arena[id.index()]
+                }
+            }
+
+            impl<'a, T: Text<'a>> Index<IdRange<$node<'a, T>>> for Document<'a, T> {
+                type Output = [$node<'a, T>];
+                fn index(&self, range: IdRange<$node<'a, T>>) -> &[$node<'a, T>] {
+                    &self.$arena[range.start as usize..range.end as usize]
+                }
+            }
+        )*
+    };
+}
+
+impl_index! {
+    Operation => operations,
+    FragmentDefinition => fragments,
+    SelectionSet => selection_sets,
+    Selection => selections,
+    Field => fields,
+    FragmentSpread => fragment_spreads,
+    InlineFragment => inline_fragments,
+    Directive => directives,
+    Argument => arguments,
+}
+
+impl<'a, T: Text<'a>> Index<IdRange<classic::VariableDefinition<'a, T>>> for Document<'a, T> {
+    type Output = [classic::VariableDefinition<'a, T>];
+    fn index(
+        &self,
+        range: IdRange<classic::VariableDefinition<'a, T>>,
+    ) -> &[classic::VariableDefinition<'a, T>] {
+        &self.variable_definitions[range.start as usize..range.end as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Document, Selection};
+    use crate::query::parse_query;
+
+    #[test]
+    fn roundtrip() {
+        let source = include_str!("../../tests/queries/kitchen-sink.graphql");
+        let ast = parse_query::<String>(source).unwrap();
+        let doc = Document::from_ast(&ast);
+        assert_eq!(doc.to_ast(), ast);
+    }
+
+    #[test]
+    fn children_are_contiguous() {
+        let ast =
+            parse_query::<&str>("query Q @a(x: 1) @b { a { b c } ... on T { d } ...F e(y: 2) }")
+                .unwrap();
+        let doc = Document::from_ast(&ast);
+        let op = &doc.operations()[0];
+        let names: Vec<_> = doc[op.directives].iter().map(|d| d.name).collect();
+        assert_eq!(names, ["a", "b"]);
+        let set = &doc[op.selection_set];
+        let kinds: Vec<_> = doc[set.items]
+            .iter()
+            .map(|s| match *s {
+                Selection::Field(id) => doc[id].name,
+                Selection::FragmentSpread(id) => doc[id].fragment_name,
+                Selection::InlineFragment(_) => "...",
+            })
+            .collect();
+        assert_eq!(kinds, ["a", "...", "F", "e"]);
+        assert_eq!(doc.fields().len(), 5);
+        assert_eq!(doc.arguments().len(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::io;
 
 use crate::format::{format_directives, Displayable, Formatter, Style};
 
@@ -10,17 +11,29 @@ where
 {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        crate::format::to_string(self, style)
+    }
+
+    /// Format a document according to style into `out`, without building
+    /// the whole text in memory first
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        crate::format::format_to(self, style, out)
+    }
+
+    /// Format a document according to style into a file, socket or other
+    /// `io::Write`
+    ///
+    /// The text is written in many small pieces, so unbuffered writers
+    /// should be wrapped in a `BufWriter`.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: W) -> io::Result<()> {
+        crate::format::write_to(self, style, out)
     }
-}
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+    /// Format a document on a single line with as little whitespace as
+    /// possible, for persisted queries or sending over the network
+    pub fn format_minified(&self) -> String {
+        crate::format::to_string(self, Style::default().minify(true))
+    }
 }
 
 impl<'a, T: Text<'a>> Displayable for Document<'a, T>
@@ -117,13 +130,13 @@ where
         f.start_argument_block('(');
         f.start_argument();
         f.write(&arguments[0].0.as_ref());
-        f.write(": ");
+        f.colon();
         arguments[0].1.display(f);
         for arg in &arguments[1..] {
             f.deliniate_argument();
             f.start_argument();
             f.write(&arg.0.as_ref());
-            f.write(": ");
+            f.colon();
             arg.1.display(f);
         }
         f.end_argument_block(')');
@@ -138,7 +151,7 @@ where
         f.indent();
         if let Some(ref alias) = self.alias {
             f.write(alias.as_ref());
-            f.write(": ");
+            f.colon();
         }
         f.write(self.name.as_ref());
         format_arguments(&self.arguments, f);
@@ -229,13 +242,15 @@ where
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
-            if !self.variable_definitions.is_empty() {
-                f.write("(");
-                for var in &self.variable_definitions {
-                    var.display(f);
-                }
-                f.write(")");
+        }
+        if !self.variable_definitions.is_empty() {
+            f.write("(");
+            self.variable_definitions[0].display(f);
+            for var in &self.variable_definitions[1..] {
+                f.write(", ");
+                var.display(f);
             }
+            f.write(")");
         }
         format_directives(&self.directives, f);
         f.write(" ");
@@ -254,7 +269,7 @@ where
     fn display(&self, f: &mut Formatter) {
         f.write("$");
         f.write(self.name.as_ref());
-        f.write(": ");
+        f.colon();
         self.var_type.display(f);
         if let Some(ref default) = self.default_value {
             f.write(" = ");
@@ -293,47 +308,71 @@ where
                 f.write("$");
                 f.write(name.as_ref());
             }
-            Value::BigInt(ref num) => f.write(&format!("{}", num.0)),
-            Value::Int(ref num) => f.write(&format!("{}", num.0)),
-            Value::Float(val) => f.write(&format!("{}", val)),
-            Value::String(ref val) => f.write_quoted(val),
+            Value::BigInt(ref num) => f.write(&format!("{}", num)),
+            Value::Int(ref num) => f.write(&format!("{}", num)),
+            Value::Float(ref val) => f.write(&format!("{}", val)),
+            Value::String(ref val) => f.write_quoted(val.as_str()),
             Value::Boolean(true) => f.write("true"),
             Value::Boolean(false) => f.write("false"),
             Value::Null => f.write("null"),
             Value::Enum(ref name) => f.write(name.as_ref()),
-            Value::List(ref items) => {
-                f.start_argument_block('[');
-                if !items.is_empty() {
-                    f.start_argument();
-                    items[0].display(f);
-                    for item in &items[1..] {
-                        f.deliniate_argument();
-                        f.start_argument();
-                        item.display(f);
-                    }
-                }
-                f.end_argument_block(']');
-            }
-            Value::Object(ref items) => {
-                f.start_argument_block('{');
-                let mut first = true;
-                for (name, value) in items.iter() {
-                    if first {
-                        first = false;
-                    } else {
-                        f.deliniate_argument();
-                    }
-                    f.start_argument();
-                    f.write(name.as_ref());
-                    f.write(": ");
-                    value.display(f);
-                }
-                f.end_argument_block('}');
-            }
+            Value::List(ref items) => format_list(items, f),
+            Value::Object(ref items) => format_object(items, f),
         }
     }
 }
 
+impl<'a, T: Text<'a>> Displayable for ConstValue<'a, T>
+where
+    T: Text<'a>,
+{
+    fn display(&self, f: &mut Formatter) {
+        match *self {
+            ConstValue::BigInt(ref num) => f.write(&format!("{}", num)),
+            ConstValue::Int(ref num) => f.write(&format!("{}", num)),
+            ConstValue::Float(ref val) => f.write(&format!("{}", val)),
+            ConstValue::String(ref val) => f.write_quoted(val.as_str()),
+            ConstValue::Boolean(true) => f.write("true"),
+            ConstValue::Boolean(false) => f.write("false"),
+            ConstValue::Null => f.write("null"),
+            ConstValue::Enum(ref name) => f.write(name.as_ref()),
+            ConstValue::List(ref items) => format_list(items, f),
+            ConstValue::Object(ref items) => format_object(items, f),
+        }
+    }
+}
+
+fn format_list<V: Displayable>(items: &[V], f: &mut Formatter) {
+    f.start_argument_block('[');
+    if !items.is_empty() {
+        f.start_argument();
+        items[0].display(f);
+        for item in &items[1..] {
+            f.deliniate_argument();
+            f.start_argument();
+            item.display(f);
+        }
+    }
+    f.end_argument_block(']');
+}
+
+fn format_object<K: AsRef<str>, V: Displayable>(items: &[(K, V)], f: &mut Formatter) {
+    f.start_argument_block('{');
+    let mut first = true;
+    for (name, value) in items.iter() {
+        if first {
+            first = false;
+        } else {
+            f.deliniate_argument();
+        }
+        f.start_argument();
+        f.write(name.as_ref());
+        f.colon();
+        value.display(f);
+    }
+    f.end_argument_block('}');
+}
+
 impl<'a, T: Text<'a>> Displayable for InlineFragment<'a, T>
 where
     T: Text<'a>,
@@ -405,10 +444,184 @@ impl_display!(
     Mutation,
     Subscription,
     VariableDefinition,
-    Type,
-    Value,
     InlineFragment,
     TypeCondition,
     FragmentSpread,
-    Directive,
 );
+
+macro_rules! impl_inline_display {
+    ($( $typ: ident, )+) => {
+        $(
+            /// Prints valid GraphQL on a single line
+            impl<'a, T> fmt::Display for $typ<'a, T>
+                where T: Text<'a>,
+            {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    let style = Style::default();
+                    let mut formatter = Formatter::inline(&style, f);
+                    self.display(&mut formatter);
+                    formatter.finish()
+                }
+            }
+        )+
+    };
+}
+
+impl_inline_display!(Type, Value, ConstValue, Directive,);
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::query::{parse_query, Definition, OperationDefinition, Selection, Value};
+    use crate::{parse_type, parse_value};
+    use crate::{LineEnding, Style};
+
+    #[test]
+    fn standalone_display() {
+        let value = parse_value::<&str>(
+            r#"{s: "multi\nline \"quoted\" \u0007 😀", b: """block
+            string""", l: [1, 2.5, ENUM, null, $v]}"#,
+        )
+        .unwrap();
+        let printed = value.to_string();
+        assert_eq!(
+            printed,
+            r#"{s: "multi\nline \"quoted\" \u0007 😀", b: "block\nstring\n", l: [1, 2.5, ENUM, null, $v]}"#
+        );
+        assert_eq!(parse_value::<&str>(&printed).unwrap(), value);
+        assert_eq!(Value::<&str>::from(2.0).to_string(), "2.0");
+        assert_eq!(Value::<&str>::from(1e100).to_string(), "1e100");
+        assert_eq!(Value::<&str>::from(1e-7).to_string(), "1e-7");
+
+        assert_eq!(
+            parse_type::<&str>("[[A!]]!").unwrap().to_string(),
+            "[[A!]]!"
+        );
+
+        let doc = parse_query::<&str>("{ a @dir(x: {y: \"a\\nb\"}) }").unwrap();
+        let field = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set)) => {
+                match set.items[0] {
+                    Selection::Field(ref field) => field,
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        };
+        assert_eq!(field.directives[0].to_string(), r#"@dir(x: {y: "a\nb"})"#);
+    }
+
+    #[test]
+    fn minified() {
+        let doc = parse_query::<&str>(
+            r#"
+            query Q($id: ID = -1, $f: [Float!] = [1.5, -2]) @op {
+                alias: user(id: $id, obj: {a: [1, "s  t", E, true]}, n: 2) @include(if: $x) {
+                    ...F @d
+                    ... on User { name }
+                    ... @skip(if: false) { id }
+                }
+            }
+            fragment F on User { id desc(text: """block
+              string""") }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.format_minified(),
+            concat!(
+                r#"query Q($id:ID=-1$f:[Float!]=[1.5 -2])@op{alias:user(id:$id obj:{a:[1 "s  t"E true]}n:2)"#,
+                r#"@include(if:$x){...F@d...on User{name}...@skip(if:false){id}}}"#,
+                r#"fragment F on User{id desc(text:"block\nstring\n")}"#,
+            )
+        );
+        let minified = doc.format_minified();
+        assert_eq!(
+            parse_query::<&str>(&minified).unwrap().to_string(),
+            doc.to_string()
+        );
+        let mut style = Style::default();
+        style.minify(true);
+        assert_eq!(doc.format(&style), doc.format_minified());
+    }
+
+    #[test]
+    fn configured_style() {
+        let doc = parse_query::<&str>("query Q($v: Int = 1) { a: b(x: $v, y: {z: \"s\"}) { c } }")
+            .unwrap();
+        let mut style = Style::default();
+        style
+            .indent(1)
+            .indent_char('\t')
+            .line_ending(LineEnding::CrLf)
+            .space_after_colon(false);
+        let printed = doc.format(&style);
+        assert_eq!(
+            printed,
+            "query Q($v:Int = 1) {\r\n\ta:b(x:$v, y:{z:\"s\"}) {\r\n\t\tc\r\n\t}\r\n}\r\n"
+        );
+        let reparsed = parse_query::<&str>(&printed).unwrap();
+        assert_eq!(reparsed.to_string(), doc.to_string());
+
+        style
+            .indent(4)
+            .indent_char(' ')
+            .space_before_colon(true)
+            .space_after_colon(true)
+            .multiline_arguments(true);
+        assert_eq!(
+            doc.format(&style),
+            "query Q($v : Int = 1) {\r\n    a : b(\r\n        x : $v,\r\n        \
+             y : {\r\n            z : \"s\"\r\n        }\r\n    ) {\r\n        \
+             c\r\n    }\r\n}\r\n"
+        );
+        // minifying ignores the other settings
+        style.minify(true);
+        assert_eq!(doc.format(&style), doc.format_minified());
+    }
+
+    /// Accepts `limit` bytes, then fails
+    struct Full {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl io::Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.limit - self.written.len());
+            if len == 0 {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "full"));
+            }
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn format_into_writers() {
+        let doc = parse_query::<&str>("query Q($v: Int) { a(x: $v) { b } } { c }").unwrap();
+        let style = Style::default();
+        let expected = doc.format(&style);
+
+        let mut out = String::from("# header\n");
+        doc.format_to(&style, &mut out).unwrap();
+        assert_eq!(out, format!("# header\n{}", expected));
+
+        let mut bytes = Vec::new();
+        doc.write_to(&style, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+
+        let mut full = Full {
+            written: Vec::new(),
+            limit: 10,
+        };
+        let err = doc.write_to(&style, &mut full).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(full.written, &expected.as_bytes()[..10]);
+    }
+}
@@ -1,12 +1,19 @@
 use combine::combinator::{eof, many1, optional, position};
-use combine::{parser, ParseResult, Parser};
+use combine::error::Consumed;
+use combine::stream::Resetable;
+use combine::{ParseResult, Parser, Positioned};
 
 use crate::common::Directive;
-use crate::common::{arguments, default_value, directives, parse_type};
-use crate::helpers::{ident, name, punct};
+use crate::common::{self, arguments, default_value, directives, value};
+use crate::error::Errors;
+use crate::helpers::{end_position, ident, name, punct, rule};
+use crate::limits::Limits;
+use crate::parser::ParserOptions;
 use crate::query::ast::*;
-use crate::query::error::ParseError;
+use crate::query::error::{InternalError, ParseError};
+use crate::recovery;
 use crate::tokenizer::TokenStream;
+use crate::warning::Warning;
 
 pub fn field<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<Field<'a, S>, TokenStream<'a>>
 where
@@ -16,18 +23,20 @@ where
         position(),
         name::<'a, S>(),
         optional(punct(":").with(name::<'a, S>())),
-        parser(arguments),
-        parser(directives),
-        optional(parser(selection_set)),
+        rule("arguments", arguments),
+        rule("directives", directives),
+        optional(rule("selection_set", selection_set)),
+        end_position(),
     )
         .map(
-            |(position, name_or_alias, opt_name, arguments, directives, sel)| {
+            |(position, name_or_alias, opt_name, arguments, directives, sel, end)| {
                 let (name, alias) = match opt_name {
                     Some(name) => (name, Some(name_or_alias)),
                     None => (name_or_alias, None),
                 };
                 Field {
                     position,
+                    end,
                     name,
                     alias,
                     arguments,
@@ -48,33 +57,51 @@ pub fn selection<'a, S>(
 where
     S: Text<'a>,
 {
-    parser(field)
+    let start = input.position();
+    let (selection, consumed) = rule("field", field)
         .map(Selection::Field)
         .or(punct("...").with(
             (
                 position(),
                 optional(ident("on").with(name::<'a, S>()).map(TypeCondition::On)),
-                parser(directives),
-                parser(selection_set),
+                rule("directives", directives),
+                rule("selection_set", selection_set),
+                end_position(),
             )
                 .map(
-                    |(position, type_condition, directives, selection_set)| InlineFragment {
+                    |(position, type_condition, directives, selection_set, end)| InlineFragment {
                         position,
+                        end,
                         type_condition,
                         selection_set,
                         directives,
                     },
                 )
                 .map(Selection::InlineFragment)
-                .or((position(), name::<'a, S>(), parser(directives))
-                    .map(|(position, fragment_name, directives)| FragmentSpread {
-                        position,
-                        fragment_name,
-                        directives,
-                    })
+                .or((
+                    position(),
+                    name::<'a, S>(),
+                    rule("directives", directives),
+                    end_position(),
+                )
+                    .map(
+                        |(position, fragment_name, directives, end)| FragmentSpread {
+                            position,
+                            end,
+                            fragment_name,
+                            directives,
+                        },
+                    )
                     .map(Selection::FragmentSpread)),
         ))
-        .parse_stream(input)
+        .parse_stream(input)?;
+    // counted once parsed, so that the closing brace isn't counted
+    if let Err(error) = input.count_selection() {
+        let mut errors = Errors::empty(start);
+        errors.add_error(error);
+        return Err(Consumed::Consumed(errors.into()));
+    }
+    Ok((selection, consumed))
 }
 
 pub fn selection_set<'a, S>(
@@ -85,8 +112,8 @@ where
 {
     (
         position().skip(punct("{")),
-        many1(parser(selection)),
-        position().skip(punct("}")),
+        many1(rule("selection", selection)),
+        punct("}").with(end_position()),
     )
         .map(|(start, items, end)| SelectionSet {
             span: (start, end),
@@ -103,10 +130,12 @@ where
 {
     position()
         .skip(ident("query"))
-        .and(parser(operation_common))
+        .and(rule("operation_common", operation_common))
+        .and(end_position())
         .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Query {
+            |((position, (name, variable_definitions, directives, selection_set)), end)| Query {
                 position,
+                end,
                 name,
                 selection_set,
                 variable_definitions,
@@ -139,15 +168,19 @@ where
                         (
                             position(),
                             punct("$").with(name::<'a, T>()).skip(punct(":")),
-                            parser(parse_type),
-                            optional(punct("=").with(parser(default_value))),
+                            rule("parse_type", common::parse_type),
+                            optional(punct("=").with(rule("default_value", default_value))),
+                            end_position(),
                         )
                             .map(
-                                |(position, name, var_type, default_value)| VariableDefinition {
-                                    position,
-                                    name,
-                                    var_type,
-                                    default_value,
+                                |(position, name, var_type, default_value, end)| {
+                                    VariableDefinition {
+                                        position,
+                                        end,
+                                        name,
+                                        var_type,
+                                        default_value,
+                                    }
                                 },
                             ),
                     ))
@@ -155,8 +188,8 @@ where
             )
             .map(|vars| vars.unwrap_or_else(Vec::new)),
         )
-        .and(parser(directives))
-        .and(parser(selection_set))
+        .and(rule("directives", directives))
+        .and(rule("selection_set", selection_set))
         .map(|(((a, b), c), d)| (a, b, c, d))
         .parse_stream(input)
 }
@@ -169,10 +202,12 @@ where
 {
     position()
         .skip(ident("mutation"))
-        .and(parser(operation_common))
+        .and(rule("operation_common", operation_common))
+        .and(end_position())
         .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Mutation {
+            |((position, (name, variable_definitions, directives, selection_set)), end)| Mutation {
                 position,
+                end,
                 name,
                 selection_set,
                 variable_definitions,
@@ -190,14 +225,18 @@ where
 {
     position()
         .skip(ident("subscription"))
-        .and(parser(operation_common))
+        .and(rule("operation_common", operation_common))
+        .and(end_position())
         .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Subscription {
-                position,
-                name,
-                selection_set,
-                variable_definitions,
-                directives,
+            |((position, (name, variable_definitions, directives, selection_set)), end)| {
+                Subscription {
+                    position,
+                    end,
+                    name,
+                    selection_set,
+                    variable_definitions,
+                    directives,
+                }
             },
         )
         .parse_stream(input)
@@ -209,11 +248,11 @@ pub fn operation_definition<'a, S>(
 where
     S: Text<'a>,
 {
-    parser(selection_set)
+    rule("selection_set", selection_set)
         .map(OperationDefinition::SelectionSet)
-        .or(parser(query).map(OperationDefinition::Query))
-        .or(parser(mutation).map(OperationDefinition::Mutation))
-        .or(parser(subscription).map(OperationDefinition::Subscription))
+        .or(rule("query", query).map(OperationDefinition::Query))
+        .or(rule("mutation", mutation).map(OperationDefinition::Mutation))
+        .or(rule("subscription", subscription).map(OperationDefinition::Subscription))
         .parse_stream(input)
 }
 
@@ -227,12 +266,14 @@ where
         position().skip(ident("fragment")),
         name::<'a, T>(),
         ident("on").with(name::<'a, T>()).map(TypeCondition::On),
-        parser(directives),
-        parser(selection_set),
+        rule("directives", directives),
+        rule("selection_set", selection_set),
+        end_position(),
     )
         .map(
-            |(position, name, type_condition, directives, selection_set)| FragmentDefinition {
+            |(position, name, type_condition, directives, selection_set, end)| FragmentDefinition {
                 position,
+                end,
                 name,
                 type_condition,
                 directives,
@@ -248,27 +289,213 @@ pub fn definition<'a, S>(
 where
     S: Text<'a>,
 {
-    parser(operation_definition)
+    rule("operation_definition", operation_definition)
         .map(Definition::Operation)
-        .or(parser(fragment_definition).map(Definition::Fragment))
+        .or(rule("fragment_definition", fragment_definition).map(Definition::Fragment))
         .parse_stream(input)
 }
 
 /// Parses a piece of query language and returns an AST
 pub fn parse_query<'a, S>(s: &'a str) -> Result<Document<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    parse_query_with_warnings(s, 0).map(|(doc, _)| doc)
+}
+
+/// Parses a piece of query language and returns an AST along with
+/// non-fatal warnings encountered while parsing
+///
+/// At most `max_warnings` warnings are returned. If there are more, the
+/// last entry of the list says how many of them were omitted.
+pub fn parse_query_with_warnings<'a, S>(
+    s: &'a str,
+    max_warnings: usize,
+) -> Result<(Document<'a, S>, Vec<Warning>), ParseError>
 where
     S: Text<'a>,
 {
     let mut tokens = TokenStream::new(s);
-    let (doc, _) = many1(parser(definition))
-        .map(|d| Document { definitions: d })
+    tokens.set_warning_limit(max_warnings);
+    let doc = document(&mut tokens)?;
+
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses a piece of query language, failing if the document exceeds
+/// `limits`
+pub fn parse_query_with_limits<'a, S>(
+    s: &'a str,
+    limits: &Limits,
+) -> Result<Document<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    tokens.set_limits(limits);
+    document(&mut tokens)
+}
+
+/// Parses a piece of query language with the given settings and returns
+/// an AST along with non-fatal warnings
+pub fn parse_query_with_options<'a, S>(
+    s: &'a str,
+    options: &ParserOptions,
+) -> Result<(Document<'a, S>, Vec<Warning>), ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = options.tokens(s);
+    let doc = document(&mut tokens)?;
+
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses a piece of query language, skipping the definitions which fail to parse
+///
+/// Returns the definitions which could be parsed along with an error for
+/// each broken one. A broken definition is still part of the document if
+/// it parses after being cut short before the error, which keeps
+/// half-written definitions around in editors.
+pub fn parse_query_with_recovery<'a, S>(s: &'a str) -> (Document<'a, S>, Vec<ParseError>)
+where
+    S: Text<'a>,
+{
+    document_with_recovery(&mut TokenStream::new(s))
+}
+
+/// Parses the whole token stream as a single document
+pub(crate) fn document<'a, S>(tokens: &mut TokenStream<'a>) -> Result<Document<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let start = tokens.checkpoint();
+    let result = many1(rule("definition", definition))
+        .map(|d| Document {
+            definitions: d,
+            comments: Vec::new(),
+        })
         .skip(eof())
-        .parse_stream(&mut tokens)
-        .map_err(|e| e.into_inner().error)?;
+        .parse_stream(tokens);
+    let mut doc = match result {
+        Ok((doc, _)) => doc,
+        Err(e) => {
+            let first = e.into_inner().error;
+            tokens.reset(start);
+            let further = recovery::further_errors(tokens, &first, parse_definition::<S>);
+            return Err(ParseError::with_further(first, further));
+        }
+    };
+    doc.comments = tokens.take_comments();
 
     Ok(doc)
 }
 
+fn parse_definition<'a, S>(
+    tokens: &mut TokenStream<'a>,
+) -> Result<Definition<'a, S>, InternalError<'a>>
+where
+    S: Text<'a>,
+{
+    rule("definition", definition)
+        .parse_stream(tokens)
+        .map(|(definition, _)| definition)
+        .map_err(|e| e.into_inner().error)
+}
+
+/// Parses the whole token stream, skipping broken definitions
+pub(crate) fn document_with_recovery<'a, S>(
+    tokens: &mut TokenStream<'a>,
+) -> (Document<'a, S>, Vec<ParseError>)
+where
+    S: Text<'a>,
+{
+    let (definitions, errors) = recovery::definitions(tokens, parse_definition);
+    let doc = Document {
+        definitions,
+        comments: tokens.take_comments(),
+    };
+    (doc, errors.into_iter().map(ParseError::from).collect())
+}
+
+/// Parses a standalone input value, like a default value or the argument
+/// of a directive
+///
+/// Variables are accepted anywhere in the value.
+pub fn parse_value<'a, S>(s: &'a str) -> Result<Value<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (value, _) = rule("value", value).skip(eof()).parse(tokens)?;
+
+    Ok(value)
+}
+
+/// Parses a standalone type reference, like `[Episode!]!`
+pub fn parse_type<'a, S>(s: &'a str) -> Result<Type<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (typ, _) = rule("parse_type", common::parse_type)
+        .skip(eof())
+        .parse(tokens)?;
+
+    Ok(typ)
+}
+
+/// Parses a standalone selection set, like `{ id name }`
+pub fn parse_selection_set<'a, S>(s: &'a str) -> Result<SelectionSet<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (selection_set, _) = rule("selection_set", selection_set)
+        .skip(eof())
+        .parse(tokens)?;
+
+    Ok(selection_set)
+}
+
+/// Parses a federation `FieldSet`, like `id organization { id }`
+///
+/// This is the value of the `fields` argument of `@key`, `@requires` and
+/// `@provides`: the selections of a selection set without the braces.
+pub fn parse_field_set<'a, S>(s: &'a str) -> Result<SelectionSet<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (selection_set, _) = (
+        position(),
+        many1(rule("selection", selection)),
+        end_position(),
+    )
+        .map(|(start, items, end)| SelectionSet {
+            span: (start, end),
+            items,
+        })
+        .skip(eof())
+        .parse(tokens)?;
+
+    Ok(selection_set)
+}
+
+/// Parses a single fragment definition which is the only definition in
+/// the text
+pub fn parse_fragment<'a, S>(s: &'a str) -> Result<FragmentDefinition<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (fragment, _) = rule("fragment_definition", fragment_definition)
+        .skip(eof())
+        .parse(tokens)?;
+
+    Ok(fragment)
+}
+
 /// Parses a single ExecutableDefinition and returns an AST as well as the
 /// remainder of the input which is unparsed
 pub fn consume_definition<'a, S>(s: &'a str) -> Result<(Definition<'a, S>, &'a str), ParseError>
@@ -276,16 +503,25 @@ where
     S: Text<'a>,
 {
     let tokens = TokenStream::new(s);
-    let (doc, tokens) = parser(definition).parse(tokens)?;
+    let (doc, tokens) = rule("definition", definition).parse(tokens)?;
 
     Ok((doc, &s[tokens.offset()..]))
 }
 
 #[cfg(test)]
 mod test {
-    use super::{consume_definition, parse_query};
+    use super::{
+        consume_definition, parse_field_set, parse_fragment, parse_query, parse_query_with_options,
+        parse_query_with_warnings, parse_selection_set, parse_type, parse_value,
+    };
+    use crate::common::BigNumber;
+    use crate::diagnostic::DEFAULT_LIMIT;
+    use crate::message::{Message, MessageKey};
+    use crate::parser::ParserOptions;
     use crate::position::Pos;
     use crate::query::grammar::*;
+    use crate::syntax_error::ErrorCode;
+    use crate::warning::Warning;
 
     fn ast(s: &str) -> Document<String> {
         parse_query::<String>(&s).unwrap().to_owned()
@@ -298,20 +534,22 @@ mod test {
             Document {
                 definitions: vec![Definition::Operation(OperationDefinition::SelectionSet(
                     SelectionSet {
-                        span: (Pos { line: 1, column: 1 }, Pos { line: 1, column: 5 }),
+                        span: (Pos::new(1, 1, 0), Pos::new(1, 6, 5)),
                         items: vec![Selection::Field(Field {
-                            position: Pos { line: 1, column: 3 },
+                            position: Pos::new(1, 3, 2),
+                            end: Pos::new(1, 4, 3),
                             alias: None,
                             name: "a".into(),
                             arguments: Vec::new(),
                             directives: Vec::new(),
                             selection_set: SelectionSet {
-                                span: (Pos { line: 1, column: 3 }, Pos { line: 1, column: 3 }),
+                                span: (Pos::new(1, 3, 2), Pos::new(1, 3, 2)),
                                 items: Vec::new()
                             },
                         }),],
                     }
                 ))],
+                comments: Vec::new(),
             }
         );
     }
@@ -323,15 +561,10 @@ mod test {
             Document {
                 definitions: vec![Definition::Operation(OperationDefinition::SelectionSet(
                     SelectionSet {
-                        span: (
-                            Pos { line: 1, column: 1 },
-                            Pos {
-                                line: 1,
-                                column: 33
-                            }
-                        ),
+                        span: (Pos::new(1, 1, 0), Pos::new(1, 34, 33)),
                         items: vec![Selection::Field(Field {
-                            position: Pos { line: 1, column: 3 },
+                            position: Pos::new(1, 3, 2),
+                            end: Pos::new(1, 32, 31),
                             alias: None,
                             name: "a".into(),
                             arguments: vec![
@@ -341,27 +574,309 @@ mod test {
                             ],
                             directives: Vec::new(),
                             selection_set: SelectionSet {
-                                span: (Pos { line: 1, column: 3 }, Pos { line: 1, column: 3 }),
+                                span: (Pos::new(1, 3, 2), Pos::new(1, 3, 2)),
                                 items: Vec::new()
                             },
                         }),],
                     }
                 ))],
+                comments: Vec::new(),
             }
         );
     }
 
+    #[test]
+    fn spans() {
+        let doc = ast("query Q($a: Int = 1) @d(x: 1) {
+  f: g(a: $a) { h }
+  ...F @x # comment
+  ... on T { i }
+}
+fragment F on T { j }");
+        let spans: Vec<String> = doc
+            .definitions
+            .iter()
+            .map(|def| def.span().to_string())
+            .collect();
+        assert_eq!(spans, ["1:1-5:2", "6:1-6:22"]);
+        assert_eq!(doc.span().to_string(), "1:1-6:22");
+        let query = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => q,
+            _ => unreachable!(),
+        };
+        assert_eq!(query.variable_definitions[0].span().to_string(), "1:9-1:20");
+        assert_eq!(query.directives[0].span().to_string(), "1:22-1:30");
+        let spans: Vec<String> = query
+            .selection_set
+            .items
+            .iter()
+            .map(|sel| sel.span().to_string())
+            .collect();
+        assert_eq!(spans, ["2:3-2:20", "3:6-3:10", "4:7-4:17"]);
+    }
+
     #[test]
     fn one_field_roundtrip() {
         assert_eq!(ast("{ a }").to_string(), "{\n  a\n}\n");
     }
 
     #[test]
+    #[cfg(not(feature = "num-bigint"))]
     #[should_panic(expected = "number too large")]
     fn large_integer() {
         ast("{ a(x: 340282366920938463463374607431768211456 }");
     }
 
+    #[test]
+    fn negative_integers() {
+        let value = parse_value::<String>("[-5, -0, -9223372036854775808]").unwrap();
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Int(Number(-5)),
+                Value::Int(Number(0)),
+                Value::Int(Number(i64::MIN)),
+            ])
+        );
+        assert_eq!(value.to_string(), "[-5, 0, -9223372036854775808]");
+        let n = Number(-5);
+        assert!(n.is_negative());
+        assert_eq!(n.as_i64(), -5);
+        assert_eq!(n.as_i32(), Some(-5));
+        assert_eq!(n.as_u64(), None);
+        assert_eq!(Number(i64::MIN).as_i32(), None);
+    }
+
+    #[test]
+    fn negative_bigint() {
+        let value = parse_value::<String>("-9223372036854775809").unwrap();
+        assert_eq!(
+            value,
+            Value::BigInt(BigNumber::from(-9223372036854775809i128))
+        );
+        let n = BigNumber::from(-9223372036854775809i128);
+        assert!(n.is_negative());
+        assert_eq!(n.as_i128(), Some(-9223372036854775809));
+        assert_eq!(n.as_u128(), 9223372036854775809);
+        assert_eq!(n.as_i64(), None);
+        let value = parse_value::<String>("-170141183460469231731687303715884105728").unwrap();
+        assert_eq!(value, Value::BigInt(BigNumber::from(i128::MIN)));
+
+        // magnitudes up to u128::MAX are accepted with either sign
+        for text in &[
+            "340282366920938463463374607431768211455",
+            "-340282366920938463463374607431768211455",
+        ] {
+            let value = parse_value::<String>(text).unwrap();
+            assert_eq!(value.to_string(), *text);
+            match value {
+                Value::BigInt(n) => assert_eq!(n.as_u128(), u128::MAX),
+                _ => unreachable!(),
+            }
+        }
+        #[cfg(not(feature = "num-bigint"))]
+        for value in &[
+            "340282366920938463463374607431768211456",
+            "-340282366920938463463374607431768211456",
+        ] {
+            let err = parse_value::<String>(value).unwrap_err();
+            assert_eq!(err.code(), ErrorCode::NumberTooLarge);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "num-bigint")]
+    fn arbitrary_precision() {
+        let text = "-1606938044258990275541962092341162602522202993782792835301376";
+        let value = parse_value::<String>(text).unwrap();
+        let n = match value {
+            Value::BigInt(ref n) => n,
+            _ => unreachable!(),
+        };
+        assert!(n.is_negative());
+        assert_eq!(n.as_i128(), None);
+        assert_eq!(n.as_u128(), u128::MAX);
+        assert_eq!(n.to_bigint(), -(num_bigint::BigInt::from(1) << 200u32));
+        assert_eq!(value.to_string(), text);
+        assert_eq!(
+            BigNumber::from(num_bigint::BigInt::from(5)),
+            BigNumber::from(5i128)
+        );
+    }
+
+    #[test]
+    fn float_lexemes() {
+        let source = "[1.500, 1e10, -0.1000000000000000055511151231257827, 1E+400]";
+        let value = parse_value::<&str>(source).unwrap();
+        assert_eq!(value.to_string(), source);
+        let floats = match value {
+            Value::List(ref items) => items,
+            _ => unreachable!(),
+        };
+        match floats[0] {
+            Value::Float(ref f) => {
+                assert_eq!(f.raw(), Some("1.500"));
+                assert_eq!(f.as_f64(), 1.5);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(floats[0], Value::Float(1.5.into()));
+        assert_eq!(floats[3], Value::Float(f64::INFINITY.into()));
+        assert_eq!(value.into_static().to_string(), source);
+    }
+
+    #[test]
+    fn literals() {
+        let source = r#"[-12, 18446744073709551616, "\u00e9", """b""", true, null, RED]"#;
+        let value = parse_value::<&str>(source).unwrap();
+        let items = match value {
+            Value::List(ref items) => items,
+            _ => unreachable!(),
+        };
+        let literals: Vec<_> = items.iter().map(|v| v.literal().unwrap()).collect();
+        assert_eq!(
+            literals,
+            [
+                "-12",
+                "18446744073709551616",
+                r#""\u00e9""#,
+                r#""""b""""#,
+                "true",
+                "null",
+                "RED"
+            ]
+        );
+        assert_eq!(value.literal(), None);
+        let value = parse_value::<&str>("$v").unwrap();
+        assert_eq!(value.literal(), None);
+        assert_eq!(Value::<&str>::String("a".into()).literal(), None);
+    }
+
+    #[test]
+    fn bigint_disabled() {
+        let options = ParserOptions::new().bigint_extension(false);
+        let query = "{ a(x: 9223372036854775807, y: 9223372036854775808) }";
+        let err = parse_query_with_options::<String>(query, &options).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::NumberTooLarge);
+        assert_eq!(
+            err.to_string(),
+            "query parse error: Parse error at 1:32\n\
+             number too large for Int, the BigInt extension is disabled\n"
+        );
+        let value = parse_value::<String>("[1, 18446744073709551616]");
+        assert!(matches!(value.unwrap(), Value::List(_)));
+    }
+
+    #[test]
+    fn ints_as_bigint() {
+        let options = ParserOptions::new().ints_as_bigint(true);
+        let query = "{ a(x: 0, y: [18446744073709551616], z: 1.5) }";
+        let (doc, warnings) = parse_query_with_options::<String>(query, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        let args = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set)) => match set.items[0]
+            {
+                Selection::Field(ref field) => &field.arguments,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(args[0].1, Value::BigInt(BigNumber::from(0i128)));
+        assert_eq!(
+            args[1].1,
+            Value::List(vec![Value::BigInt(BigNumber::from(
+                18446744073709551616i128
+            ))])
+        );
+        assert_eq!(args[2].1, Value::Float(1.5.into()));
+    }
+
+    #[test]
+    fn bigint_warning() {
+        let (_, warnings) = parse_query_with_warnings::<String>(
+            "{ a(x: 1, y: 18446744073709551616) }",
+            DEFAULT_LIMIT,
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                position: Pos::new(1, 14, 13),
+                message: Message::new(MessageKey::BigIntExtension),
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_object_field_warning() {
+        let (doc, warnings) = parse_query_with_warnings::<String>(
+            "query Q($v: I = {a: 1, a: 2}) { f(x: {b: 1, c: {b: 2}, b: 3, b: 4}) }",
+            DEFAULT_LIMIT,
+        )
+        .unwrap();
+        let warning = |line, column, offset, name: &str, first: &str| Warning {
+            position: Pos::new(line, column, offset),
+            message: Message::with_args(
+                MessageKey::DuplicateObjectField,
+                vec![name.to_string(), first.to_string()],
+            ),
+        };
+        assert_eq!(
+            warnings,
+            vec![
+                warning(1, 24, 23, "a", "1:18"),
+                warning(1, 56, 55, "b", "1:39"),
+                warning(1, 62, 61, "b", "1:39"),
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "warning at 1:24: field `a` of the object is already given at 1:18 \
+             (see §5.6.3 Input Object Field Uniqueness)"
+        );
+        // all fields are kept
+        assert_eq!(
+            doc.to_string(),
+            "query Q($v: I = {a: 1, a: 2}) {\n  f(x: {b: 1, c: {b: 2}, b: 3, b: 4})\n}\n"
+        );
+    }
+
+    #[test]
+    fn duplicate_argument_warning() {
+        let query = "{ a(x: 1, y: 2, x: 3) @d(z: 1, z: 2) b(x: 1) }";
+        let options = ParserOptions::new();
+        let (_, warnings) = parse_query_with_options::<&str>(query, &options).unwrap();
+        assert_eq!(warnings, vec![]);
+
+        let options = options.warn_duplicate_arguments(true);
+        let (doc, warnings) = parse_query_with_options::<&str>(query, &options).unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                Warning {
+                    position: Pos::new(1, 17, 16),
+                    message: Message::with_args(
+                        MessageKey::DuplicateArgument,
+                        vec!["x".to_string(), "1:5".to_string()],
+                    ),
+                },
+                Warning {
+                    position: Pos::new(1, 32, 31),
+                    message: Message::with_args(
+                        MessageKey::DuplicateArgument,
+                        vec!["z".to_string(), "1:26".to_string()],
+                    ),
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "warning at 1:17: argument `x` is already given at 1:5 \
+             (see §5.4.2 Argument Uniqueness)"
+        );
+        assert_eq!(doc, parse_query::<&str>(query).unwrap());
+    }
+
     #[test]
     fn consume_single_query() {
         let (query, remainder) = consume_definition::<String>("query { a } query { b }").unwrap();
@@ -392,6 +907,85 @@ mod test {
         assert_eq!(err, "query parse error: Parse error at 1:1\nUnexpected `where[Name]`\nExpected `{`, `query`, `mutation`, `subscription` or `fragment`\n");
     }
 
+    #[test]
+    fn standalone_value() {
+        let value = parse_value::<String>(r#"{ a: [1, "b", $c], d: ENUM, e: null }"#).unwrap();
+        let Value::Object(fields) = value else {
+            panic!("not an object: {:?}", value);
+        };
+        assert_eq!(
+            fields,
+            [
+                (
+                    "a".into(),
+                    Value::List(vec![
+                        Value::Int(Number(1)),
+                        Value::String("b".into()),
+                        Value::Variable("c".into()),
+                    ])
+                ),
+                ("d".into(), Value::Enum("ENUM".into())),
+                ("e".into(), Value::Null),
+            ]
+        );
+        assert_eq!(
+            parse_value::<&str>("18446744073709551616").unwrap(),
+            Value::BigInt(crate::common::BigNumber::from(18446744073709551616i128))
+        );
+        let err = parse_value::<&str>("1 2").unwrap_err();
+        assert_eq!(err.position(), Pos::new(1, 3, 2));
+        assert!(parse_value::<&str>("").is_err());
+    }
+
+    #[test]
+    fn standalone_type() {
+        assert_eq!(
+            parse_type::<String>(" [Episode!]! ").unwrap(),
+            Type::NonNullType(Box::new(Type::ListType(Box::new(Type::NonNullType(
+                Box::new(Type::NamedType("Episode".into()))
+            )))))
+        );
+        assert_eq!(parse_type::<&str>("Int").unwrap(), Type::NamedType("Int"));
+        assert!(parse_type::<&str>("[Int").is_err());
+        assert!(parse_type::<&str>("Int!!").is_err());
+        assert!(parse_type::<&str>("Int String").is_err());
+    }
+
+    #[test]
+    fn standalone_selection_set() {
+        let set = parse_selection_set::<String>("{ id ... on User { name } ...F }").unwrap();
+        assert_eq!(set.span, (Pos::new(1, 1, 0), Pos::new(1, 33, 32)));
+        assert_eq!(set.items.len(), 3);
+        assert!(matches!(set.items[1], Selection::InlineFragment(_)));
+        assert!(parse_selection_set::<&str>("id name").is_err());
+        assert!(parse_selection_set::<&str>("{ id } { name }").is_err());
+    }
+
+    #[test]
+    fn field_set() {
+        let set = parse_field_set::<String>(" id organization { id } ").unwrap();
+        assert_eq!(set.span, (Pos::new(1, 2, 1), Pos::new(1, 24, 23)));
+        assert_eq!(
+            set.to_string(),
+            "{\n  id\n  organization {\n    id\n  }\n}\n"
+        );
+        assert!(parse_field_set::<&str>("{ id }").is_err());
+        assert!(parse_field_set::<&str>("").is_err());
+        assert!(parse_field_set::<&str>("id }").is_err());
+    }
+
+    #[test]
+    fn standalone_fragment() {
+        let fragment =
+            parse_fragment::<String>("fragment F on User @d { id friends { id } }").unwrap();
+        assert_eq!(fragment.name, "F");
+        assert_eq!(fragment.type_condition, TypeCondition::On("User".into()));
+        assert_eq!(fragment.directives.len(), 1);
+        assert_eq!(fragment.selection_set.items.len(), 2);
+        assert!(parse_fragment::<&str>("{ id }").is_err());
+        assert!(parse_fragment::<&str>("fragment F on U { a } fragment G on U { a }").is_err());
+    }
+
     #[test]
     fn recursion_too_deep() {
         let query = format!(
@@ -1,4 +1,6 @@
+use crate::error::Error;
 use crate::tokenizer::{Kind, Token, TokenStream};
+use combine::error::StreamError;
 use combine::StreamOnce;
 use thiserror::Error;
 
@@ -27,7 +29,7 @@ pub fn minify_query<'a>(source: String) -> Result<String, MinifyError> {
                 bits.push(token.value);
                 prev_was_punctuator = is_non_punctuator;
             }
-            Err(ref e) if e == &combine::easy::Error::end_of_input() => break,
+            Err(ref e) if e == &Error::end_of_input() => break,
             Err(e) => return Err(MinifyError(e.to_string())),
         }
     }
@@ -1,12 +1,40 @@
 //! Query language AST and parsing utilities
 //!
+mod annotate;
 mod ast;
+mod build;
+mod conflicts;
 mod error;
+mod fix;
+pub mod flat;
 mod format;
 mod grammar;
 mod minify;
+mod path;
+mod shrink;
+mod store;
+mod substitute;
+pub mod visit;
 
+pub use self::annotate::Annotations;
 pub use self::ast::*;
+pub use self::build::{FieldBuilder, FragmentBuilder, InlineFragmentBuilder, OperationBuilder};
+#[cfg(feature = "rayon")]
+pub use self::conflicts::par_response_key_conflicts;
+pub use self::conflicts::{response_key_conflicts, Conflict, ConflictReason};
 pub use self::error::ParseError;
-pub use self::grammar::{consume_definition, parse_query};
+pub use self::fix::{apply_fixes, suggest_fixes, suggest_fixes_with_schema, Fix, Patch};
+#[cfg(feature = "rayon")]
+pub use self::fix::{par_suggest_fixes, par_suggest_fixes_with_schema};
+pub use self::grammar::{
+    consume_definition, parse_field_set, parse_fragment, parse_query, parse_query_with_limits,
+    parse_query_with_options, parse_query_with_recovery, parse_query_with_warnings,
+    parse_selection_set, parse_type, parse_value,
+};
+pub(crate) use self::grammar::{definition, document, document_with_recovery};
 pub use self::minify::minify_query;
+pub use self::path::{InvalidPath, NodePath, NodeRef, PathSegment};
+pub use self::shrink::shrink_query;
+pub use self::store::{normalize_query, OperationStore, StoreError};
+pub use self::substitute::{substitute_variables, SubstituteError};
+pub use crate::owned_source::query::OwnedDocument;
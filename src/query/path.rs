@@ -0,0 +1,487 @@
+//! Addressing nodes of a query document by path
+//!
+//! A path lists the children taken from the document down to the node, e.g.
+//! `/definitions/0/selections/2/arguments/id` is the `id` argument of the
+//! third selection of the first definition. Paths only depend on the
+//! structure of the document, so they stay valid for clones of it and can
+//! be stored as strings.
+//!
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::common::{Directive, Text, Value};
+use crate::query::ast::{Definition, Document, OperationDefinition, Selection};
+use crate::query::ast::{SelectionSet, VariableDefinition};
+
+/// One step of a `NodePath`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// Definition of a document, by index
+    Definition(usize),
+    /// Variable definition of an operation, by index
+    Variable(usize),
+    /// Item of a selection set, by index
+    Selection(usize),
+    /// Directive, by index, as directives may be repeated
+    Directive(usize),
+    /// Argument of a field or directive, by name
+    Argument(String),
+}
+
+/// Location of a node in a query document
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NodePath {
+    pub segments: Vec<PathSegment>,
+}
+
+/// Error parsing a `NodePath` from a string
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid node path: {}", _0)]
+pub struct InvalidPath(String);
+
+/// Reference to any addressable node of a query document
+#[derive(Debug)]
+pub enum NodeRef<'d, 'a, T: Text<'a>> {
+    Document(&'d Document<'a, T>),
+    Definition(&'d Definition<'a, T>),
+    Variable(&'d VariableDefinition<'a, T>),
+    Selection(&'d Selection<'a, T>),
+    Directive(&'d Directive<'a, T>),
+    Argument(&'d (T::Value, Value<'a, T>)),
+}
+
+// derives would require `T: Copy`
+impl<'d, 'a, T: Text<'a>> Clone for NodeRef<'d, 'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'d, 'a, T: Text<'a>> Copy for NodeRef<'d, 'a, T> {}
+
+impl<'d, 'a, T: Text<'a>> NodeRef<'d, 'a, T> {
+    /// Returns true if both refer to the very same node, not an equal one
+    pub fn ptr_eq(&self, other: &NodeRef<'d, 'a, T>) -> bool {
+        use self::NodeRef::*;
+        match (*self, *other) {
+            (Document(a), Document(b)) => std::ptr::eq(a, b),
+            (Definition(a), Definition(b)) => std::ptr::eq(a, b),
+            (Variable(a), Variable(b)) => std::ptr::eq(a, b),
+            (Selection(a), Selection(b)) => std::ptr::eq(a, b),
+            (Directive(a), Directive(b)) => std::ptr::eq(a, b),
+            (Argument(a), Argument(b)) => std::ptr::eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Returns the direct children of the node along with their segments
+    pub fn children(&self) -> Vec<(PathSegment, NodeRef<'d, 'a, T>)> {
+        let mut children = Vec::new();
+        match *self {
+            NodeRef::Document(doc) => {
+                for (i, def) in doc.definitions.iter().enumerate() {
+                    children.push((PathSegment::Definition(i), NodeRef::Definition(def)));
+                }
+            }
+            NodeRef::Definition(Definition::Operation(op)) => {
+                let (variables, directives, set) = match op {
+                    OperationDefinition::SelectionSet(set) => (&[][..], &[][..], set),
+                    OperationDefinition::Query(q) => (
+                        &q.variable_definitions[..],
+                        &q.directives[..],
+                        &q.selection_set,
+                    ),
+                    OperationDefinition::Mutation(m) => (
+                        &m.variable_definitions[..],
+                        &m.directives[..],
+                        &m.selection_set,
+                    ),
+                    OperationDefinition::Subscription(s) => (
+                        &s.variable_definitions[..],
+                        &s.directives[..],
+                        &s.selection_set,
+                    ),
+                };
+                for (i, var) in variables.iter().enumerate() {
+                    children.push((PathSegment::Variable(i), NodeRef::Variable(var)));
+                }
+                push_directives(&mut children, directives);
+                push_selections(&mut children, set);
+            }
+            NodeRef::Definition(Definition::Fragment(frag)) => {
+                push_directives(&mut children, &frag.directives);
+                push_selections(&mut children, &frag.selection_set);
+            }
+            NodeRef::Selection(Selection::Field(field)) => {
+                push_arguments(&mut children, &field.arguments);
+                push_directives(&mut children, &field.directives);
+                push_selections(&mut children, &field.selection_set);
+            }
+            NodeRef::Selection(Selection::FragmentSpread(spread)) => {
+                push_directives(&mut children, &spread.directives);
+            }
+            NodeRef::Selection(Selection::InlineFragment(frag)) => {
+                push_directives(&mut children, &frag.directives);
+                push_selections(&mut children, &frag.selection_set);
+            }
+            NodeRef::Directive(dir) => push_arguments(&mut children, &dir.arguments),
+            NodeRef::Variable(_) | NodeRef::Argument(_) => {}
+        }
+        children
+    }
+}
+
+fn push_directives<'d, 'a, T: Text<'a>>(
+    children: &mut Vec<(PathSegment, NodeRef<'d, 'a, T>)>,
+    directives: &'d [Directive<'a, T>],
+) {
+    for (i, dir) in directives.iter().enumerate() {
+        children.push((PathSegment::Directive(i), NodeRef::Directive(dir)));
+    }
+}
+
+fn push_selections<'d, 'a, T: Text<'a>>(
+    children: &mut Vec<(PathSegment, NodeRef<'d, 'a, T>)>,
+    set: &'d SelectionSet<'a, T>,
+) {
+    for (i, item) in set.items.iter().enumerate() {
+        children.push((PathSegment::Selection(i), NodeRef::Selection(item)));
+    }
+}
+
+fn push_arguments<'d, 'a, T: Text<'a>>(
+    children: &mut Vec<(PathSegment, NodeRef<'d, 'a, T>)>,
+    arguments: &'d [(T::Value, Value<'a, T>)],
+) {
+    for arg in arguments {
+        children.push((
+            PathSegment::Argument(arg.0.as_ref().to_string()),
+            NodeRef::Argument(arg),
+        ));
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the node at `path`, if the document has one there
+    pub fn node_at_path<'d>(&'d self, path: &NodePath) -> Option<NodeRef<'d, 'a, T>> {
+        let mut node = NodeRef::Document(self);
+        for segment in &path.segments {
+            node = node
+                .children()
+                .into_iter()
+                .find(|(s, _)| s == segment)
+                .map(|(_, child)| child)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the path of a node of this document
+    ///
+    /// Nodes are compared by identity, so `None` is returned for nodes of
+    /// other documents, even equal ones.
+    pub fn path_of<'d>(&'d self, node: NodeRef<'d, 'a, T>) -> Option<NodePath> {
+        let mut path = NodePath::default();
+        if find(NodeRef::Document(self), &node, &mut path.segments) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Removes the node at `path`, returns false if there is none
+    ///
+    /// Paths of the following siblings of the node change, as they are
+    /// mostly based on indices.
+    pub fn remove_at_path(&mut self, path: &NodePath) -> bool {
+        let (last, parents) = match path.segments.split_last() {
+            Some(split) => split,
+            None => return false,
+        };
+        match node_mut(self, parents) {
+            Some(node) => node.remove(last),
+            None => false,
+        }
+    }
+}
+
+/// Returns the node at the path given by `segments`, if it may have
+/// children
+pub(crate) fn node_mut<'d, 'a, T: Text<'a>>(
+    doc: &'d mut Document<'a, T>,
+    segments: &[PathSegment],
+) -> Option<NodeMut<'d, 'a, T>> {
+    let mut node = NodeMut::Document(doc);
+    for segment in segments {
+        node = node.child(segment)?;
+    }
+    Some(node)
+}
+
+/// Nodes which may have children
+pub(crate) enum NodeMut<'d, 'a, T: Text<'a>> {
+    Document(&'d mut Document<'a, T>),
+    Definition(&'d mut Definition<'a, T>),
+    Selection(&'d mut Selection<'a, T>),
+    Directive(&'d mut Directive<'a, T>),
+}
+
+impl<'d, 'a, T: Text<'a>> NodeMut<'d, 'a, T> {
+    fn child(self, segment: &PathSegment) -> Option<NodeMut<'d, 'a, T>> {
+        match (self, segment) {
+            (NodeMut::Document(doc), PathSegment::Definition(i)) => {
+                doc.definitions.get_mut(*i).map(NodeMut::Definition)
+            }
+            (NodeMut::Definition(def), PathSegment::Directive(i)) => {
+                definition_parts(def).1?.get_mut(*i).map(NodeMut::Directive)
+            }
+            (NodeMut::Definition(def), PathSegment::Selection(i)) => definition_parts(def)
+                .2
+                .items
+                .get_mut(*i)
+                .map(NodeMut::Selection),
+            (NodeMut::Selection(sel), PathSegment::Directive(i)) => {
+                selection_parts(sel).1.get_mut(*i).map(NodeMut::Directive)
+            }
+            (NodeMut::Selection(sel), PathSegment::Selection(i)) => selection_parts(sel)
+                .2?
+                .items
+                .get_mut(*i)
+                .map(NodeMut::Selection),
+            _ => None,
+        }
+    }
+
+    fn remove(self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (NodeMut::Document(doc), PathSegment::Definition(i)) => {
+                remove_index(&mut doc.definitions, *i)
+            }
+            (NodeMut::Definition(def), PathSegment::Variable(i)) => definition_parts(def)
+                .0
+                .is_some_and(|vars| remove_index(vars, *i)),
+            (NodeMut::Definition(def), PathSegment::Directive(i)) => definition_parts(def)
+                .1
+                .is_some_and(|dirs| remove_index(dirs, *i)),
+            (NodeMut::Definition(def), PathSegment::Selection(i)) => {
+                remove_index(&mut definition_parts(def).2.items, *i)
+            }
+            (NodeMut::Selection(sel), PathSegment::Argument(name)) => selection_parts(sel)
+                .0
+                .is_some_and(|args| remove_argument(args, name)),
+            (NodeMut::Selection(sel), PathSegment::Directive(i)) => {
+                remove_index(selection_parts(sel).1, *i)
+            }
+            (NodeMut::Selection(sel), PathSegment::Selection(i)) => selection_parts(sel)
+                .2
+                .is_some_and(|set| remove_index(&mut set.items, *i)),
+            (NodeMut::Directive(dir), PathSegment::Argument(name)) => {
+                remove_argument(&mut dir.arguments, name)
+            }
+            _ => false,
+        }
+    }
+}
+
+pub(crate) type DefinitionParts<'d, 'a, T> = (
+    Option<&'d mut Vec<VariableDefinition<'a, T>>>,
+    Option<&'d mut Vec<Directive<'a, T>>>,
+    &'d mut SelectionSet<'a, T>,
+);
+
+pub(crate) fn definition_parts<'d, 'a, T: Text<'a>>(
+    def: &'d mut Definition<'a, T>,
+) -> DefinitionParts<'d, 'a, T> {
+    match def {
+        Definition::Operation(OperationDefinition::SelectionSet(set)) => (None, None, set),
+        Definition::Operation(OperationDefinition::Query(q)) => (
+            Some(&mut q.variable_definitions),
+            Some(&mut q.directives),
+            &mut q.selection_set,
+        ),
+        Definition::Operation(OperationDefinition::Mutation(m)) => (
+            Some(&mut m.variable_definitions),
+            Some(&mut m.directives),
+            &mut m.selection_set,
+        ),
+        Definition::Operation(OperationDefinition::Subscription(s)) => (
+            Some(&mut s.variable_definitions),
+            Some(&mut s.directives),
+            &mut s.selection_set,
+        ),
+        Definition::Fragment(frag) => (None, Some(&mut frag.directives), &mut frag.selection_set),
+    }
+}
+
+pub(crate) type SelectionParts<'d, 'a, T> = (
+    Option<&'d mut Vec<(<T as Text<'a>>::Value, Value<'a, T>)>>,
+    &'d mut Vec<Directive<'a, T>>,
+    Option<&'d mut SelectionSet<'a, T>>,
+);
+
+pub(crate) fn selection_parts<'d, 'a, T: Text<'a>>(
+    sel: &'d mut Selection<'a, T>,
+) -> SelectionParts<'d, 'a, T> {
+    match sel {
+        Selection::Field(field) => (
+            Some(&mut field.arguments),
+            &mut field.directives,
+            Some(&mut field.selection_set),
+        ),
+        Selection::FragmentSpread(spread) => (None, &mut spread.directives, None),
+        Selection::InlineFragment(frag) => {
+            (None, &mut frag.directives, Some(&mut frag.selection_set))
+        }
+    }
+}
+
+fn remove_index<X>(items: &mut Vec<X>, i: usize) -> bool {
+    if i < items.len() {
+        items.remove(i);
+        true
+    } else {
+        false
+    }
+}
+
+fn remove_argument<'a, T: Text<'a>>(args: &mut Vec<(T::Value, Value<'a, T>)>, name: &str) -> bool {
+    match args.iter().position(|(n, _)| n.as_ref() == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+fn find<'d, 'a, T: Text<'a>>(
+    current: NodeRef<'d, 'a, T>,
+    node: &NodeRef<'d, 'a, T>,
+    segments: &mut Vec<PathSegment>,
+) -> bool {
+    if current.ptr_eq(node) {
+        return true;
+    }
+    for (segment, child) in current.children() {
+        segments.push(segment);
+        if find(child, node, segments) {
+            return true;
+        }
+        segments.pop();
+    }
+    false
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.segments.is_empty() {
+            return f.write_str("/");
+        }
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::Definition(i) => write!(f, "/definitions/{}", i)?,
+                PathSegment::Variable(i) => write!(f, "/variables/{}", i)?,
+                PathSegment::Selection(i) => write!(f, "/selections/{}", i)?,
+                PathSegment::Directive(i) => write!(f, "/directives/{}", i)?,
+                PathSegment::Argument(ref name) => write!(f, "/arguments/{}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NodePath {
+    type Err = InvalidPath;
+
+    fn from_str(s: &str) -> Result<NodePath, InvalidPath> {
+        let invalid = || InvalidPath(s.to_string());
+        let rest = s.strip_prefix('/').ok_or_else(invalid)?;
+        let mut segments = Vec::new();
+        let mut parts = rest.split('/').filter(|part| !part.is_empty());
+        while let Some(kind) = parts.next() {
+            let value = parts.next().ok_or_else(invalid)?;
+            let index = || value.parse().map_err(|_| invalid());
+            segments.push(match kind {
+                "definitions" => PathSegment::Definition(index()?),
+                "variables" => PathSegment::Variable(index()?),
+                "selections" => PathSegment::Selection(index()?),
+                "directives" => PathSegment::Directive(index()?),
+                "arguments" => PathSegment::Argument(value.to_string()),
+                _ => return Err(invalid()),
+            });
+        }
+        Ok(NodePath { segments })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NodePath, NodeRef};
+    use crate::parse_query;
+    use crate::query::{Definition, Selection};
+
+    const QUERY: &str = "query Q($v: Int) @a { user(id: $v) { ...F @b(c: 1) } }
+                         fragment F on User { name }";
+
+    #[test]
+    fn roundtrip() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let paths = [
+            "/",
+            "/definitions/0/variables/0",
+            "/definitions/0/directives/0",
+            "/definitions/0/selections/0/arguments/id",
+            "/definitions/0/selections/0/selections/0/directives/0/arguments/c",
+            "/definitions/1/selections/0",
+        ];
+        for text in &paths {
+            let path: NodePath = text.parse().unwrap();
+            assert_eq!(path.to_string(), *text);
+            let node = doc.node_at_path(&path).unwrap();
+            assert_eq!(doc.path_of(node), Some(path));
+        }
+        for path in &["/definitions/2", "/definitions/0/arguments/id"] {
+            assert!(doc.node_at_path(&path.parse().unwrap()).is_none());
+        }
+        assert!("definitions/0".parse::<NodePath>().is_err());
+        assert!("/definitions/x".parse::<NodePath>().is_err());
+        assert!("/fields/0".parse::<NodePath>().is_err());
+    }
+
+    #[test]
+    fn remove() {
+        let mut doc = parse_query::<&str>(QUERY).unwrap();
+        for path in &[
+            "/definitions/0/selections/0/selections/0/directives/0/arguments/c",
+            "/definitions/0/selections/0/arguments/id",
+            "/definitions/0/variables/0",
+            "/definitions/1",
+        ] {
+            assert!(doc.remove_at_path(&path.parse().unwrap()), "{}", path);
+        }
+        assert!(!doc.remove_at_path(&"/definitions/1".parse().unwrap()));
+        assert!(!doc.remove_at_path(&"/".parse().unwrap()));
+        assert_eq!(
+            doc.to_string(),
+            "query Q @a {\n  user {\n    ...F @b\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn clones() {
+        let doc = parse_query::<String>(QUERY).unwrap();
+        let copy = doc.clone();
+        let name = match copy.definitions[1] {
+            Definition::Fragment(ref frag) => &frag.selection_set.items[0],
+            _ => unreachable!(),
+        };
+        assert!(doc.path_of(NodeRef::Selection(name)).is_none());
+        let path = copy.path_of(NodeRef::Selection(name)).unwrap();
+        assert_eq!(path.to_string(), "/definitions/1/selections/0");
+        match doc.node_at_path(&path) {
+            Some(NodeRef::Selection(Selection::Field(field))) => assert_eq!(field.name, "name"),
+            _ => unreachable!(),
+        }
+    }
+}
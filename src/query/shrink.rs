@@ -0,0 +1,112 @@
+//! Minimizing query documents for bug reports
+//!
+use std::collections::VecDeque;
+
+use crate::common::Text;
+use crate::query::ast::Document;
+use crate::query::path::{NodePath, NodeRef};
+
+/// Removes as much of the document as possible while `predicate` holds
+///
+/// The predicate is typically "still triggers the bug". Definitions,
+/// variable definitions, selections, directives and arguments are removed
+/// one at a time, outermost first, keeping each removal for which the
+/// predicate still returns true. This repeats until nothing else can be
+/// removed, so the predicate fails for the result with any single node
+/// removed.
+///
+/// The predicate should hold for the original document, otherwise it is
+/// returned unchanged.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_query;
+/// use graphql_parser::query::shrink_query;
+///
+/// let doc = parse_query::<String>(
+///     "query Q($id: ID) { user(id: $id) { name friends(first: 10) { name } } }"
+/// ).unwrap();
+/// let small = shrink_query(doc, |doc| doc.to_string().contains("friends(first: 10)"));
+/// assert_eq!(small.to_string(), "query Q {\n  user {\n    friends(first: 10)\n  }\n}\n");
+/// ```
+pub fn shrink_query<'a, T, F>(mut doc: Document<'a, T>, mut predicate: F) -> Document<'a, T>
+where
+    T: Text<'a> + Clone,
+    F: FnMut(&Document<'a, T>) -> bool,
+{
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut paths = node_paths(&doc);
+        let mut idx = 0;
+        while idx < paths.len() {
+            let mut candidate = doc.clone();
+            if candidate.remove_at_path(&paths[idx]) && predicate(&candidate) {
+                doc = candidate;
+                changed = true;
+                // the following nodes moved up, so `idx` is tried again
+                paths = node_paths(&doc);
+            } else {
+                idx += 1;
+            }
+        }
+    }
+    doc
+}
+
+/// Returns paths of all nodes of the document, level by level
+fn node_paths<'a, T: Text<'a>>(doc: &Document<'a, T>) -> Vec<NodePath> {
+    let mut paths = Vec::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((NodePath::default(), NodeRef::Document(doc)));
+    while let Some((path, node)) = queue.pop_front() {
+        for (segment, child) in node.children() {
+            let mut child_path = path.clone();
+            child_path.segments.push(segment);
+            paths.push(child_path.clone());
+            queue.push_back((child_path, child));
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod test {
+    use super::shrink_query;
+    use crate::parse_query;
+    use crate::query::{Definition, Document, Selection};
+
+    fn has_field(doc: &Document<String>, name: &str) -> bool {
+        doc.definitions.iter().any(|def| match *def {
+            Definition::Fragment(ref frag) => frag
+                .selection_set
+                .items
+                .iter()
+                .any(|item| matches!(*item, Selection::Field(ref field) if field.name == name)),
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn minimal() {
+        let doc = parse_query::<String>(
+            "query A @x { a b(x: 1) @y { c } }
+             fragment F on T { bug(a: 1, b: 2) other { ...G } }
+             fragment G on T { d }",
+        )
+        .unwrap();
+        let mut calls = 0;
+        let small = shrink_query(doc, |doc| {
+            calls += 1;
+            has_field(doc, "bug")
+        });
+        assert_eq!(small.to_string(), "fragment F on T {\n  bug\n}\n");
+        assert!(calls < 30, "{} calls", calls);
+    }
+
+    #[test]
+    fn predicate_fails() {
+        let doc = parse_query::<String>("{ a }").unwrap();
+        assert_eq!(shrink_query(doc.clone(), |_| false), doc);
+    }
+}
@@ -0,0 +1,257 @@
+//! Allow-list of persisted operations
+//!
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::position::Pos;
+use crate::query::{parse_query, ParseError};
+
+/// Error checking a document against an `OperationStore`
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("unknown persisted operation `{}`", _0)]
+    UnknownKey(String),
+    /// The document differs from the stored one, see
+    /// `OperationStore::difference` for where
+    #[error("document doesn't match persisted operation `{}`", _0)]
+    Mismatch(String),
+    #[error("{}", _0)]
+    Parse(#[from] ParseError),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    digest: [u8; 32],
+    normalized: String,
+}
+
+/// Set of allowed operations
+///
+/// Documents are normalized before being stored or compared, so formatting,
+/// comments and commas don't matter. Each operation is stored under a key,
+/// by default the hex encoded SHA-256 of its normalized text.
+///
+/// Such keys differ from the ones of automatic persisted queries, which
+/// hash the query text as sent by the client. To look operations up by
+/// those, store them with `insert_with_key` under the hash of their
+/// original text.
+#[derive(Debug, Clone, Default)]
+pub struct OperationStore {
+    entries: HashMap<String, Entry>,
+    /// Keys of each normalized text, in the order of insertion
+    keys: HashMap<[u8; 32], Vec<String>>,
+}
+
+/// Returns the canonical text of a query document
+///
+/// This is the pretty-printed document, which doesn't depend on the
+/// formatting of the source.
+pub fn normalize_query(document: &str) -> Result<String, ParseError> {
+    Ok(parse_query::<&str>(document)?.to_string())
+}
+
+impl OperationStore {
+    pub fn new() -> OperationStore {
+        OperationStore::default()
+    }
+
+    /// Adds a document, returning the hash it is stored under
+    ///
+    /// This is the hash of the normalized text, not of `document`.
+    pub fn insert(&mut self, document: &str) -> Result<String, ParseError> {
+        let normalized = normalize_query(document)?;
+        let digest = digest(&normalized);
+        let mut key = String::with_capacity(64);
+        for byte in &digest {
+            write!(key, "{:02x}", byte).unwrap();
+        }
+        self.add(key.clone(), digest, normalized);
+        Ok(key)
+    }
+
+    /// Adds a document under a key chosen by the caller
+    ///
+    /// Useful for operation manifests which use their own identifiers.
+    pub fn insert_with_key(&mut self, key: &str, document: &str) -> Result<(), ParseError> {
+        let normalized = normalize_query(document)?;
+        let digest = digest(&normalized);
+        self.add(key.to_string(), digest, normalized);
+        Ok(())
+    }
+
+    /// Stores the document under `key`, replacing the previous one
+    fn add(&mut self, key: String, digest: [u8; 32], normalized: String) {
+        if let Some(previous) = self.entries.get(&key) {
+            if let Some(keys) = self.keys.get_mut(&previous.digest) {
+                keys.retain(|k| *k != key);
+                if keys.is_empty() {
+                    self.keys.remove(&previous.digest);
+                }
+            }
+        }
+        self.keys.entry(digest).or_default().push(key.clone());
+        self.entries.insert(key, Entry { digest, normalized });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the key of the stored operation equal to the document
+    ///
+    /// If the operation is stored under several keys, this is the first
+    /// one it was stored under.
+    pub fn find(&self, document: &str) -> Result<Option<&str>, ParseError> {
+        let normalized = normalize_query(document)?;
+        let digest = digest(&normalized);
+        Ok(self
+            .keys
+            .get(&digest)
+            .and_then(|keys| keys.first())
+            .map(|key| key.as_str()))
+    }
+
+    /// Returns true if the document is one of the stored operations
+    pub fn is_allowed(&self, document: &str) -> bool {
+        matches!(self.find(document), Ok(Some(_)))
+    }
+
+    /// Checks that the document is the operation stored under `key`
+    ///
+    /// Hashes are compared in constant time, so neither the time taken nor
+    /// the error reveals how much of a forged document matches.
+    pub fn check(&self, key: &str, document: &str) -> Result<(), StoreError> {
+        let entry = self.entry(key)?;
+        let normalized = normalize_query(document)?;
+        if constant_time_eq(&digest(&normalized), &entry.digest) {
+            return Ok(());
+        }
+        Err(StoreError::Mismatch(key.to_string()))
+    }
+
+    /// Returns the first difference in the normalized text of the document
+    /// from the operation stored under `key`, `None` if they are equal
+    ///
+    /// This is meant for debugging manifests. Don't show it to clients, as
+    /// it tells how much of a forged document matches.
+    pub fn difference(&self, key: &str, document: &str) -> Result<Option<Pos>, StoreError> {
+        let entry = self.entry(key)?;
+        let normalized = normalize_query(document)?;
+        if normalized == entry.normalized {
+            return Ok(None);
+        }
+        Ok(Some(first_difference(&entry.normalized, &normalized)))
+    }
+
+    fn entry(&self, key: &str) -> Result<&Entry, StoreError> {
+        self.entries
+            .get(key)
+            .ok_or_else(|| StoreError::UnknownKey(key.to_string()))
+    }
+}
+
+fn digest(normalized: &str) -> [u8; 32] {
+    Sha256::digest(normalized.as_bytes()).into()
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn first_difference(expected: &str, actual: &str) -> Pos {
+    let mut pos = Pos::new(1, 1, 0);
+    for (e, a) in expected.chars().zip(actual.chars()) {
+        if e != a {
+            break;
+        }
+        if e == '\n' {
+            pos.line += 1;
+            pos.column = 1;
+        } else {
+            pos.column += 1;
+        }
+        pos.offset += e.len_utf8();
+    }
+    pos
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OperationStore, StoreError};
+    use crate::position::Pos;
+
+    #[test]
+    fn allow_list() {
+        let mut store = OperationStore::new();
+        let key = store.insert("query Q { user(id: 1) { name } }").unwrap();
+        assert_eq!(key.len(), 64);
+        store.insert_with_key("users", "{ users { id } }").unwrap();
+        assert_eq!(store.len(), 2);
+
+        let same = "# persisted\nquery Q {\n  user(id: 1) {name,}\n}";
+        assert!(store.is_allowed(same));
+        assert_eq!(store.find(same).unwrap(), Some(key.as_str()));
+        assert!(!store.is_allowed("query Q { user(id: 2) { name } }"));
+        assert!(!store.is_allowed("{"));
+        store.check(&key, same).unwrap();
+        store.check("users", "{users{id}}").unwrap();
+    }
+
+    #[test]
+    fn digests() {
+        let mut store = OperationStore::new();
+        let key = store.insert("{a}").unwrap();
+        assert_eq!(super::normalize_query("{a}").unwrap(), "{\n  a\n}\n");
+        assert_eq!(
+            key,
+            "e0dc5d76b9865226ef70e1b48f8f81f3360f61766327c4b52c389b09f64ddbc9"
+        );
+    }
+
+    #[test]
+    fn replace() {
+        let mut store = OperationStore::new();
+        store.insert_with_key("q", "{ a }").unwrap();
+        store.insert_with_key("q", "{ b }").unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(!store.is_allowed("{ a }"));
+        assert_eq!(store.find("{ a }").unwrap(), None);
+        assert!(store.check("q", "{ a }").is_err());
+        store.check("q", "{ b }").unwrap();
+
+        store.insert_with_key("r", "{ b }").unwrap();
+        store.insert_with_key("q", "{ c }").unwrap();
+        assert_eq!(store.find("{ b }").unwrap(), Some("r"));
+        assert_eq!(store.find("{ c }").unwrap(), Some("q"));
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut store = OperationStore::new();
+        store.insert_with_key("q", "{ user { name } }").unwrap();
+        match store.check("q", "{ user { name email } }") {
+            Err(StoreError::Mismatch(key)) => assert_eq!(key, "q"),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(
+            store.difference("q", "{ user { name email } }").unwrap(),
+            Some(Pos::new(4, 3, 22))
+        );
+        assert_eq!(store.difference("q", "{user{name}}").unwrap(), None);
+        assert_eq!(
+            store.check("x", "{ a }").unwrap_err().to_string(),
+            "unknown persisted operation `x`"
+        );
+        assert!(matches!(
+            store.check("q", "{ user"),
+            Err(StoreError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,359 @@
+//! Replacing variables of an operation by their values
+//!
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::mem;
+use std::ptr;
+
+use thiserror::Error;
+
+use crate::common::{Directive, Text, Type, Value};
+use crate::position::Pos;
+use crate::query::ast::*;
+
+/// Error substituting the variables of an operation
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubstituteError {
+    #[error("document contains no operation")]
+    NoOperation,
+    /// No operation name is given, but the document contains several
+    #[error("operation name is required, the document contains several operations")]
+    AmbiguousOperation,
+    #[error("unknown operation `{}`", _0)]
+    UnknownOperation(String),
+    /// A variable of a non-null type without a default value has no value,
+    /// or its value is null
+    #[error(
+        "variable `${}` of type `{}` at {} is required",
+        name,
+        var_type,
+        position
+    )]
+    MissingVariable {
+        name: String,
+        var_type: String,
+        position: Pos,
+    },
+    /// A variable is used without being defined by the operation
+    #[error("variable `${}` is not defined by the operation", _0)]
+    UndefinedVariable(String),
+}
+
+/// Returns a document without variables, which are replaced by their values
+///
+/// The result contains the selected operation, without its variable
+/// definitions, and the fragments it spreads. `operation_name` may only be
+/// omitted if the document contains a single operation.
+///
+/// A variable takes the value given in `variables`, otherwise its default
+/// value. Variables without either are absent: an argument or object field
+/// set to one is removed, and a list item set to one becomes `null`, as
+/// specified for input coercion. Required variables of a non-null type
+/// which are absent or `null` fail the substitution.
+///
+/// Variables received as JSON are converted with `Value::from_json` when
+/// the `serde` feature is enabled.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use std::collections::BTreeMap;
+/// use graphql_parser::parse_query;
+/// use graphql_parser::query::{substitute_variables, Value};
+///
+/// let doc = parse_query::<&str>(
+///     "query Q($id: ID!, $first: Int = 10, $after: String) {
+///        user(id: $id) { friends(first: $first, after: $after) { name } }
+///      }",
+/// ).unwrap();
+/// let mut variables = BTreeMap::new();
+/// variables.insert("id", Value::from("4"));
+/// let doc = substitute_variables(&doc, None, &variables).unwrap();
+/// assert_eq!(doc.to_string(), "\
+/// query Q {
+///   user(id: \"4\") {
+///     friends(first: 10) {
+///       name
+///     }
+///   }
+/// }
+/// ");
+/// ```
+pub fn substitute_variables<'a, T, K>(
+    doc: &Document<'a, T>,
+    operation_name: Option<&str>,
+    variables: &BTreeMap<K, Value<'a, T>>,
+) -> Result<Document<'a, T>, SubstituteError>
+where
+    T: Text<'a> + Clone,
+    K: Borrow<str> + Ord,
+{
+    let (index, mut operation) = select_operation(doc, operation_name)?;
+    let definitions = match operation {
+        OperationDefinition::SelectionSet(_) => Vec::new(),
+        OperationDefinition::Query(ref mut q) => mem::take(&mut q.variable_definitions),
+        OperationDefinition::Mutation(ref mut m) => mem::take(&mut m.variable_definitions),
+        OperationDefinition::Subscription(ref mut s) => mem::take(&mut s.variable_definitions),
+    };
+    let mut subst = Substitution {
+        values: BTreeMap::new(),
+        absent: BTreeSet::new(),
+        spreads: Vec::new(),
+    };
+    for def in definitions {
+        let name = def.name.as_ref().to_string();
+        let value = match variables.get(name.as_str()) {
+            Some(value) => Some(value.clone()),
+            None => def.default_value,
+        };
+        match value {
+            Some(Value::Null) | None if matches!(def.var_type, Type::NonNullType(_)) => {
+                return Err(SubstituteError::MissingVariable {
+                    name,
+                    var_type: def.var_type.to_string(),
+                    position: def.position,
+                });
+            }
+            Some(value) => {
+                subst.values.insert(name, value);
+            }
+            None => {
+                subst.absent.insert(name);
+            }
+        }
+    }
+
+    match operation {
+        OperationDefinition::SelectionSet(ref mut set) => subst.selection_set(set)?,
+        OperationDefinition::Query(ref mut q) => {
+            subst.directives(&mut q.directives)?;
+            subst.selection_set(&mut q.selection_set)?;
+        }
+        OperationDefinition::Mutation(ref mut m) => {
+            subst.directives(&mut m.directives)?;
+            subst.selection_set(&mut m.selection_set)?;
+        }
+        OperationDefinition::Subscription(ref mut s) => {
+            subst.directives(&mut s.directives)?;
+            subst.selection_set(&mut s.selection_set)?;
+        }
+    }
+
+    let fragments: HashMap<&str, &FragmentDefinition<'a, T>> = doc
+        .fragments()
+        .map(|frag| (frag.name.as_ref(), frag))
+        .collect();
+    let mut reached = HashMap::new();
+    let mut queue: VecDeque<String> = mem::take(&mut subst.spreads).into();
+    while let Some(name) = queue.pop_front() {
+        if reached.contains_key(&name) {
+            continue;
+        }
+        if let Some(&frag) = fragments.get(name.as_str()) {
+            let mut frag = frag.clone();
+            subst.directives(&mut frag.directives)?;
+            subst.selection_set(&mut frag.selection_set)?;
+            queue.extend(subst.spreads.drain(..));
+            reached.insert(name, frag);
+        }
+    }
+
+    let mut operation = Some(operation);
+    let definitions = doc
+        .definitions
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, def)| match *def {
+            Definition::Operation(_) if idx == index => operation.take().map(Definition::Operation),
+            Definition::Operation(_) => None,
+            Definition::Fragment(ref frag) => {
+                reached.remove(frag.name.as_ref()).map(Definition::Fragment)
+            }
+        })
+        .collect();
+    Ok(Document {
+        definitions,
+        comments: Vec::new(),
+    })
+}
+
+/// Returns the index and a copy of the operation to execute
+fn select_operation<'a, T: Text<'a> + Clone>(
+    doc: &Document<'a, T>,
+    name: Option<&str>,
+) -> Result<(usize, OperationDefinition<'a, T>), SubstituteError> {
+    let selected = doc.select_operation(name).map_err(|err| match err {
+        OperationError::NoOperation => SubstituteError::NoOperation,
+        OperationError::Ambiguous => SubstituteError::AmbiguousOperation,
+        OperationError::Unknown(name) => SubstituteError::UnknownOperation(name),
+    })?;
+    let index = doc
+        .definitions
+        .iter()
+        .position(|def| matches!(*def, Definition::Operation(ref op) if ptr::eq(op, selected)))
+        .expect("operation is a definition of the document");
+    Ok((index, selected.clone()))
+}
+
+struct Substitution<'a, T: Text<'a>> {
+    values: BTreeMap<String, Value<'a, T>>,
+    /// Variables which are defined, but have no value
+    absent: BTreeSet<String>,
+    /// Names of fragments spread by the selection sets substituted so far
+    spreads: Vec<String>,
+}
+
+impl<'a, T: Text<'a> + Clone> Substitution<'a, T> {
+    /// Returns the value with variables replaced, `None` if it is an
+    /// absent variable
+    fn value(&self, value: Value<'a, T>) -> Result<Option<Value<'a, T>>, SubstituteError> {
+        Ok(Some(match value {
+            Value::Variable(name) => match self.values.get(name.as_ref()) {
+                Some(value) => value.clone(),
+                None if self.absent.contains(name.as_ref()) => return Ok(None),
+                None => return Err(SubstituteError::UndefinedVariable(name.as_ref().into())),
+            },
+            Value::List(items) => Value::List(
+                items
+                    .into_iter()
+                    .map(|item| Ok(self.value(item)?.unwrap_or(Value::Null)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(mut fields) => {
+                self.fields(&mut fields)?;
+                Value::Object(fields)
+            }
+            value => value,
+        }))
+    }
+
+    /// Substitutes values of arguments or object fields, leaving out the
+    /// absent ones
+    fn fields(&self, fields: &mut Vec<(T::Value, Value<'a, T>)>) -> Result<(), SubstituteError> {
+        for (name, value) in mem::take(fields) {
+            if let Some(value) = self.value(value)? {
+                fields.push((name, value));
+            }
+        }
+        Ok(())
+    }
+
+    fn directives(&self, directives: &mut [Directive<'a, T>]) -> Result<(), SubstituteError> {
+        for dir in directives {
+            self.fields(&mut dir.arguments)?;
+        }
+        Ok(())
+    }
+
+    fn selection_set(&mut self, set: &mut SelectionSet<'a, T>) -> Result<(), SubstituteError> {
+        for item in &mut set.items {
+            match *item {
+                Selection::Field(ref mut field) => {
+                    self.fields(&mut field.arguments)?;
+                    self.directives(&mut field.directives)?;
+                    self.selection_set(&mut field.selection_set)?;
+                }
+                Selection::InlineFragment(ref mut frag) => {
+                    self.directives(&mut frag.directives)?;
+                    self.selection_set(&mut frag.selection_set)?;
+                }
+                Selection::FragmentSpread(ref mut spread) => {
+                    self.directives(&mut spread.directives)?;
+                    self.spreads.push(spread.fragment_name.as_ref().to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{substitute_variables, SubstituteError};
+    use crate::parse_query;
+    use crate::position::Pos;
+    use crate::query::Value;
+
+    const QUERY: &str = "\
+        query A($id: ID!, $tags: [String], $flag: Boolean = true) {
+          node(id: $id) @include(if: $flag) { ...F }
+        }
+        query B($x: Int) { other(x: $x) { ...G } }
+        fragment G on T { g(x: $x) }
+        fragment F on T { f(tags: $tags, list: [$tags, 1], obj: {a: $tags, b: $id}) ...F }";
+
+    #[test]
+    fn substitute() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let mut variables = BTreeMap::new();
+        variables.insert("id".to_string(), Value::from(7));
+        let result = substitute_variables(&doc, Some("A"), &variables).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "query A {\n  \
+               node(id: 7) @include(if: true) {\n    \
+                 ...F\n  \
+               }\n\
+             }\n\
+             \n\
+             fragment F on T {\n  \
+               f(list: [null, 1], obj: {b: 7})\n  \
+               ...F\n\
+             }\n"
+        );
+
+        variables.insert("tags".to_string(), Value::List(vec![Value::from("a")]));
+        variables.insert("flag".to_string(), Value::Null);
+        let result = substitute_variables(&doc, Some("A"), &variables).unwrap();
+        assert!(result
+            .to_string()
+            .contains("f(tags: [\"a\"], list: [[\"a\"], 1], obj: {a: [\"a\"], b: 7})"));
+        assert!(result.to_string().contains("@include(if: null)"));
+    }
+
+    #[test]
+    fn errors() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let variables = BTreeMap::<&str, Value<&str>>::new();
+        assert_eq!(
+            substitute_variables(&doc, None, &variables),
+            Err(SubstituteError::AmbiguousOperation)
+        );
+        assert_eq!(
+            substitute_variables(&doc, Some("C"), &variables),
+            Err(SubstituteError::UnknownOperation("C".into()))
+        );
+        let err = substitute_variables(&doc, Some("A"), &variables).unwrap_err();
+        assert_eq!(
+            err,
+            SubstituteError::MissingVariable {
+                name: "id".into(),
+                var_type: "ID!".into(),
+                position: Pos::new(1, 9, 8),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "variable `$id` of type `ID!` at 1:9 is required"
+        );
+
+        let mut variables = BTreeMap::new();
+        variables.insert("id", Value::Null);
+        assert!(matches!(
+            substitute_variables(&doc, Some("A"), &variables),
+            Err(SubstituteError::MissingVariable { .. })
+        ));
+
+        let doc = parse_query::<&str>("{ a(x: $x) }").unwrap();
+        assert_eq!(
+            substitute_variables(&doc, None, &variables),
+            Err(SubstituteError::UndefinedVariable("x".into()))
+        );
+        let doc = parse_query::<&str>("fragment F on T { a }").unwrap();
+        assert_eq!(
+            substitute_variables(&doc, None, &variables),
+            Err(SubstituteError::NoOperation)
+        );
+    }
+}
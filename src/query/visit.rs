@@ -0,0 +1,581 @@
+//! Traversal of the query AST
+//!
+//! Implement [`Visitor`](trait.Visitor.html) overriding the hooks for the
+//! nodes of interest and pass it to
+//! [`walk_document`](fn.walk_document.html). Every hook does nothing by
+//! default. Hooks get references with the lifetime of the document, so
+//! visitors may keep them.
+//! [`VisitorMut`](trait.VisitorMut.html) and
+//! [`walk_document_mut`](fn.walk_document_mut.html) do the same for
+//! rewriting a document in place.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::query::{parse_query, Field};
+//! use graphql_parser::query::visit::{walk_document, Visitor};
+//!
+//! #[derive(Default)]
+//! struct Fields<'d> {
+//!     names: Vec<&'d str>,
+//! }
+//!
+//! impl<'d, 'a> Visitor<'d, 'a, &'a str> for Fields<'d> {
+//!     fn enter_field(&mut self, field: &'d Field<'a, &'a str>) {
+//!         self.names.push(field.name);
+//!     }
+//! }
+//!
+//! let doc = parse_query::<&str>("{ user { name ... on Admin { role } } }").unwrap();
+//! let mut fields = Fields::default();
+//! walk_document(&mut fields, &doc);
+//! assert_eq!(fields.names, ["user", "name", "role"]);
+//! ```
+use crate::common::{Directive, Text, Value};
+use crate::query::ast::*;
+
+/// Hooks called when entering and leaving nodes of a query document
+///
+/// `enter_*` hooks are called before the children of the node are walked
+/// and `leave_*` hooks after.
+#[allow(unused_variables)]
+pub trait Visitor<'d, 'a, T: Text<'a>> {
+    fn enter_document(&mut self, doc: &'d Document<'a, T>) {}
+    fn leave_document(&mut self, doc: &'d Document<'a, T>) {}
+    fn enter_operation(&mut self, op: &'d OperationDefinition<'a, T>) {}
+    fn leave_operation(&mut self, op: &'d OperationDefinition<'a, T>) {}
+    fn enter_fragment_definition(&mut self, frag: &'d FragmentDefinition<'a, T>) {}
+    fn leave_fragment_definition(&mut self, frag: &'d FragmentDefinition<'a, T>) {}
+    fn enter_variable_definition(&mut self, var: &'d VariableDefinition<'a, T>) {}
+    fn leave_variable_definition(&mut self, var: &'d VariableDefinition<'a, T>) {}
+    fn enter_selection_set(&mut self, set: &'d SelectionSet<'a, T>) {}
+    fn leave_selection_set(&mut self, set: &'d SelectionSet<'a, T>) {}
+    fn enter_field(&mut self, field: &'d Field<'a, T>) {}
+    fn leave_field(&mut self, field: &'d Field<'a, T>) {}
+    fn enter_fragment_spread(&mut self, spread: &'d FragmentSpread<'a, T>) {}
+    fn leave_fragment_spread(&mut self, spread: &'d FragmentSpread<'a, T>) {}
+    fn enter_inline_fragment(&mut self, frag: &'d InlineFragment<'a, T>) {}
+    fn leave_inline_fragment(&mut self, frag: &'d InlineFragment<'a, T>) {}
+    fn enter_directive(&mut self, dir: &'d Directive<'a, T>) {}
+    fn leave_directive(&mut self, dir: &'d Directive<'a, T>) {}
+    /// Called for arguments of fields and directives
+    fn enter_argument(&mut self, name: &'d T::Value, value: &'d Value<'a, T>) {}
+    fn leave_argument(&mut self, name: &'d T::Value, value: &'d Value<'a, T>) {}
+    /// Called for argument values, default values of variables and items
+    /// of lists and objects
+    fn enter_value(&mut self, value: &'d Value<'a, T>) {}
+    fn leave_value(&mut self, value: &'d Value<'a, T>) {}
+}
+
+pub fn walk_document<'d, 'a, T, V>(visitor: &mut V, doc: &'d Document<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_document(doc);
+    for def in &doc.definitions {
+        walk_definition(visitor, def);
+    }
+    visitor.leave_document(doc);
+}
+
+pub fn walk_definition<'d, 'a, T, V>(visitor: &mut V, def: &'d Definition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    match *def {
+        Definition::Operation(ref op) => walk_operation(visitor, op),
+        Definition::Fragment(ref frag) => walk_fragment_definition(visitor, frag),
+    }
+}
+
+pub fn walk_operation<'d, 'a, T, V>(visitor: &mut V, op: &'d OperationDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_operation(op);
+    let (variables, directives, set) = match *op {
+        OperationDefinition::SelectionSet(ref set) => (&[][..], &[][..], set),
+        OperationDefinition::Query(ref q) => (
+            &q.variable_definitions[..],
+            &q.directives[..],
+            &q.selection_set,
+        ),
+        OperationDefinition::Mutation(ref m) => (
+            &m.variable_definitions[..],
+            &m.directives[..],
+            &m.selection_set,
+        ),
+        OperationDefinition::Subscription(ref s) => (
+            &s.variable_definitions[..],
+            &s.directives[..],
+            &s.selection_set,
+        ),
+    };
+    for var in variables {
+        walk_variable_definition(visitor, var);
+    }
+    walk_directives(visitor, directives);
+    walk_selection_set(visitor, set);
+    visitor.leave_operation(op);
+}
+
+pub fn walk_fragment_definition<'d, 'a, T, V>(visitor: &mut V, frag: &'d FragmentDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_fragment_definition(frag);
+    walk_directives(visitor, &frag.directives);
+    walk_selection_set(visitor, &frag.selection_set);
+    visitor.leave_fragment_definition(frag);
+}
+
+pub fn walk_variable_definition<'d, 'a, T, V>(visitor: &mut V, var: &'d VariableDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_variable_definition(var);
+    if let Some(ref value) = var.default_value {
+        walk_value(visitor, value);
+    }
+    visitor.leave_variable_definition(var);
+}
+
+pub fn walk_selection_set<'d, 'a, T, V>(visitor: &mut V, set: &'d SelectionSet<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_selection_set(set);
+    for item in &set.items {
+        walk_selection(visitor, item);
+    }
+    visitor.leave_selection_set(set);
+}
+
+pub fn walk_selection<'d, 'a, T, V>(visitor: &mut V, selection: &'d Selection<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    match *selection {
+        Selection::Field(ref field) => walk_field(visitor, field),
+        Selection::FragmentSpread(ref spread) => {
+            visitor.enter_fragment_spread(spread);
+            walk_directives(visitor, &spread.directives);
+            visitor.leave_fragment_spread(spread);
+        }
+        Selection::InlineFragment(ref frag) => {
+            visitor.enter_inline_fragment(frag);
+            walk_directives(visitor, &frag.directives);
+            walk_selection_set(visitor, &frag.selection_set);
+            visitor.leave_inline_fragment(frag);
+        }
+    }
+}
+
+pub fn walk_field<'d, 'a, T, V>(visitor: &mut V, field: &'d Field<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_field(field);
+    walk_arguments(visitor, &field.arguments);
+    walk_directives(visitor, &field.directives);
+    walk_selection_set(visitor, &field.selection_set);
+    visitor.leave_field(field);
+}
+
+pub fn walk_directive<'d, 'a, T, V>(visitor: &mut V, dir: &'d Directive<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_directive(dir);
+    walk_arguments(visitor, &dir.arguments);
+    visitor.leave_directive(dir);
+}
+
+pub fn walk_value<'d, 'a, T, V>(visitor: &mut V, value: &'d Value<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_value(value);
+    match *value {
+        Value::List(ref items) => {
+            for item in items {
+                walk_value(visitor, item);
+            }
+        }
+        Value::Object(ref fields) => {
+            for (_, item) in fields {
+                walk_value(visitor, item);
+            }
+        }
+        _ => {}
+    }
+    visitor.leave_value(value);
+}
+
+fn walk_directives<'d, 'a, T, V>(visitor: &mut V, directives: &'d [Directive<'a, T>])
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    for dir in directives {
+        walk_directive(visitor, dir);
+    }
+}
+
+fn walk_arguments<'d, 'a, T, V>(visitor: &mut V, arguments: &'d [(T::Value, Value<'a, T>)])
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    for (name, value) in arguments {
+        visitor.enter_argument(name, value);
+        walk_value(visitor, value);
+        visitor.leave_argument(name, value);
+    }
+}
+
+/// Hooks called with mutable nodes of a query document
+///
+/// Hooks may change nodes in place. Children are walked after the
+/// `enter_*` hook returns, so nodes it adds, e.g. directives of a field,
+/// are visited too.
+#[allow(unused_variables)]
+pub trait VisitorMut<'a, T: Text<'a>> {
+    fn enter_document(&mut self, doc: &mut Document<'a, T>) {}
+    fn leave_document(&mut self, doc: &mut Document<'a, T>) {}
+    fn enter_operation(&mut self, op: &mut OperationDefinition<'a, T>) {}
+    fn leave_operation(&mut self, op: &mut OperationDefinition<'a, T>) {}
+    fn enter_fragment_definition(&mut self, frag: &mut FragmentDefinition<'a, T>) {}
+    fn leave_fragment_definition(&mut self, frag: &mut FragmentDefinition<'a, T>) {}
+    fn enter_variable_definition(&mut self, var: &mut VariableDefinition<'a, T>) {}
+    fn leave_variable_definition(&mut self, var: &mut VariableDefinition<'a, T>) {}
+    fn enter_selection_set(&mut self, set: &mut SelectionSet<'a, T>) {}
+    fn leave_selection_set(&mut self, set: &mut SelectionSet<'a, T>) {}
+    fn enter_field(&mut self, field: &mut Field<'a, T>) {}
+    fn leave_field(&mut self, field: &mut Field<'a, T>) {}
+    fn enter_fragment_spread(&mut self, spread: &mut FragmentSpread<'a, T>) {}
+    fn leave_fragment_spread(&mut self, spread: &mut FragmentSpread<'a, T>) {}
+    fn enter_inline_fragment(&mut self, frag: &mut InlineFragment<'a, T>) {}
+    fn leave_inline_fragment(&mut self, frag: &mut InlineFragment<'a, T>) {}
+    fn enter_directive(&mut self, dir: &mut Directive<'a, T>) {}
+    fn leave_directive(&mut self, dir: &mut Directive<'a, T>) {}
+    /// Called for arguments of fields and directives
+    fn enter_argument(&mut self, name: &mut T::Value, value: &mut Value<'a, T>) {}
+    fn leave_argument(&mut self, name: &mut T::Value, value: &mut Value<'a, T>) {}
+    /// Called for argument values, default values of variables and items
+    /// of lists and objects
+    fn enter_value(&mut self, value: &mut Value<'a, T>) {}
+    fn leave_value(&mut self, value: &mut Value<'a, T>) {}
+}
+
+pub fn walk_document_mut<'a, T, V>(visitor: &mut V, doc: &mut Document<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_document(doc);
+    for def in &mut doc.definitions {
+        walk_definition_mut(visitor, def);
+    }
+    visitor.leave_document(doc);
+}
+
+pub fn walk_definition_mut<'a, T, V>(visitor: &mut V, def: &mut Definition<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    match *def {
+        Definition::Operation(ref mut op) => walk_operation_mut(visitor, op),
+        Definition::Fragment(ref mut frag) => walk_fragment_definition_mut(visitor, frag),
+    }
+}
+
+pub fn walk_operation_mut<'a, T, V>(visitor: &mut V, op: &mut OperationDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_operation(op);
+    let (variables, directives, set) = match *op {
+        OperationDefinition::SelectionSet(ref mut set) => (&mut [][..], &mut [][..], set),
+        OperationDefinition::Query(ref mut q) => (
+            &mut q.variable_definitions[..],
+            &mut q.directives[..],
+            &mut q.selection_set,
+        ),
+        OperationDefinition::Mutation(ref mut m) => (
+            &mut m.variable_definitions[..],
+            &mut m.directives[..],
+            &mut m.selection_set,
+        ),
+        OperationDefinition::Subscription(ref mut s) => (
+            &mut s.variable_definitions[..],
+            &mut s.directives[..],
+            &mut s.selection_set,
+        ),
+    };
+    for var in variables {
+        walk_variable_definition_mut(visitor, var);
+    }
+    walk_directives_mut(visitor, directives);
+    walk_selection_set_mut(visitor, set);
+    visitor.leave_operation(op);
+}
+
+pub fn walk_fragment_definition_mut<'a, T, V>(visitor: &mut V, frag: &mut FragmentDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_fragment_definition(frag);
+    walk_directives_mut(visitor, &mut frag.directives);
+    walk_selection_set_mut(visitor, &mut frag.selection_set);
+    visitor.leave_fragment_definition(frag);
+}
+
+pub fn walk_variable_definition_mut<'a, T, V>(visitor: &mut V, var: &mut VariableDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_variable_definition(var);
+    if let Some(ref mut value) = var.default_value {
+        walk_value_mut(visitor, value);
+    }
+    visitor.leave_variable_definition(var);
+}
+
+pub fn walk_selection_set_mut<'a, T, V>(visitor: &mut V, set: &mut SelectionSet<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_selection_set(set);
+    for item in &mut set.items {
+        walk_selection_mut(visitor, item);
+    }
+    visitor.leave_selection_set(set);
+}
+
+pub fn walk_selection_mut<'a, T, V>(visitor: &mut V, selection: &mut Selection<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    match *selection {
+        Selection::Field(ref mut field) => walk_field_mut(visitor, field),
+        Selection::FragmentSpread(ref mut spread) => {
+            visitor.enter_fragment_spread(spread);
+            walk_directives_mut(visitor, &mut spread.directives);
+            visitor.leave_fragment_spread(spread);
+        }
+        Selection::InlineFragment(ref mut frag) => {
+            visitor.enter_inline_fragment(frag);
+            walk_directives_mut(visitor, &mut frag.directives);
+            walk_selection_set_mut(visitor, &mut frag.selection_set);
+            visitor.leave_inline_fragment(frag);
+        }
+    }
+}
+
+pub fn walk_field_mut<'a, T, V>(visitor: &mut V, field: &mut Field<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_field(field);
+    walk_arguments_mut(visitor, &mut field.arguments);
+    walk_directives_mut(visitor, &mut field.directives);
+    walk_selection_set_mut(visitor, &mut field.selection_set);
+    visitor.leave_field(field);
+}
+
+pub fn walk_directive_mut<'a, T, V>(visitor: &mut V, dir: &mut Directive<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_directive(dir);
+    walk_arguments_mut(visitor, &mut dir.arguments);
+    visitor.leave_directive(dir);
+}
+
+pub fn walk_value_mut<'a, T, V>(visitor: &mut V, value: &mut Value<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.enter_value(value);
+    match *value {
+        Value::List(ref mut items) => {
+            for item in items {
+                walk_value_mut(visitor, item);
+            }
+        }
+        Value::Object(ref mut fields) => {
+            for (_, item) in fields {
+                walk_value_mut(visitor, item);
+            }
+        }
+        _ => {}
+    }
+    visitor.leave_value(value);
+}
+
+fn walk_directives_mut<'a, T, V>(visitor: &mut V, directives: &mut [Directive<'a, T>])
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    for dir in directives {
+        walk_directive_mut(visitor, dir);
+    }
+}
+
+fn walk_arguments_mut<'a, T, V>(visitor: &mut V, arguments: &mut [(T::Value, Value<'a, T>)])
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    for (name, value) in arguments {
+        visitor.enter_argument(name, value);
+        walk_value_mut(visitor, value);
+        visitor.leave_argument(name, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{walk_document, walk_document_mut, Visitor, VisitorMut};
+    use crate::common::{Directive, Value};
+    use crate::parse_query;
+    use crate::query::*;
+
+    #[derive(Default)]
+    struct Events {
+        log: Vec<String>,
+    }
+
+    impl<'d, 'a> Visitor<'d, 'a, String> for Events {
+        fn enter_operation(&mut self, _: &'d OperationDefinition<'a, String>) {
+            self.log.push("operation".into());
+        }
+        fn leave_operation(&mut self, _: &'d OperationDefinition<'a, String>) {
+            self.log.push("/operation".into());
+        }
+        fn enter_fragment_definition(&mut self, frag: &'d FragmentDefinition<'a, String>) {
+            self.log.push(format!("fragment {}", frag.name));
+        }
+        fn enter_variable_definition(&mut self, var: &'d VariableDefinition<'a, String>) {
+            self.log.push(format!("${}", var.name));
+        }
+        fn enter_field(&mut self, field: &'d Field<'a, String>) {
+            self.log.push(field.name.clone());
+        }
+        fn leave_field(&mut self, field: &'d Field<'a, String>) {
+            self.log.push(format!("/{}", field.name));
+        }
+        fn enter_fragment_spread(&mut self, spread: &'d FragmentSpread<'a, String>) {
+            self.log.push(format!("...{}", spread.fragment_name));
+        }
+        fn enter_inline_fragment(&mut self, _: &'d InlineFragment<'a, String>) {
+            self.log.push("...".into());
+        }
+        fn enter_directive(&mut self, dir: &'d Directive<'a, String>) {
+            self.log.push(format!("@{}", dir.name));
+        }
+        fn enter_argument(&mut self, name: &'d String, _: &'d Value<'a, String>) {
+            self.log.push(format!("{}:", name));
+        }
+        fn enter_value(&mut self, value: &'d Value<'a, String>) {
+            self.log.push(value.to_string());
+        }
+    }
+
+    #[test]
+    fn order() {
+        let doc = parse_query::<String>(
+            "query Q($a: Int = 1) @x { f(a: [$a]) { g @skip(if: true) } ...F ... { h } }
+             fragment F on T { i }",
+        )
+        .unwrap();
+        let mut events = Events::default();
+        walk_document(&mut events, &doc);
+        assert_eq!(
+            events.log,
+            [
+                "operation",
+                "$a",
+                "1",
+                "@x",
+                "f",
+                "a:",
+                "[$a]",
+                "$a",
+                "g",
+                "@skip",
+                "if:",
+                "true",
+                "/g",
+                "/f",
+                "...F",
+                "...",
+                "h",
+                "/h",
+                "/operation",
+                "fragment F",
+                "i",
+                "/i",
+            ]
+        );
+    }
+
+    struct Rewrite;
+
+    impl<'a> VisitorMut<'a, &'a str> for Rewrite {
+        fn enter_field(&mut self, field: &mut Field<'a, &'a str>) {
+            if field.name == "secret" {
+                field.directives.push(Directive {
+                    position: field.position,
+                    end: field.end,
+                    name: "auth",
+                    arguments: vec![("role", Value::Variable("role"))],
+                });
+            }
+            for arg in &mut field.arguments {
+                if arg.0 == "first" {
+                    arg.0 = "limit";
+                }
+            }
+        }
+        fn enter_value(&mut self, value: &mut Value<'a, &'a str>) {
+            if let Value::Variable(ref mut name) = *value {
+                if *name == "role" {
+                    *name = "viewerRole";
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rewrite() {
+        let mut doc =
+            parse_query::<&str>("{ users(first: 10) { secret list(first: [$role]) } }").unwrap();
+        walk_document_mut(&mut Rewrite, &mut doc);
+        assert_eq!(
+            doc.to_string(),
+            "{\n  users(limit: 10) {\n    secret @auth(role: $viewerRole)\n    \
+             list(limit: [$viewerRole])\n  }\n}\n"
+        );
+    }
+}
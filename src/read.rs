@@ -0,0 +1,251 @@
+//! Tokenizing text read incrementally from an `io::Read`
+//!
+use std::io::{self, Read};
+
+use thiserror::Error;
+
+use crate::lexer::{error_len, LexError};
+use crate::position::Pos;
+use crate::tokenizer::{Kind, Token, TokenStream};
+
+/// Size of the chunks read at once
+const CHUNK_SIZE: usize = 8192;
+
+/// A token which owns its text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OwnedToken {
+    pub kind: Kind,
+    /// Exact source text of the token, strings include their quotes
+    pub value: String,
+    /// Position of the first character of the token
+    pub start: Pos,
+    /// Position just past the last character of the token
+    pub end: Pos,
+}
+
+impl<'a> From<Token<'a>> for OwnedToken {
+    fn from(token: Token<'a>) -> OwnedToken {
+        OwnedToken {
+            kind: token.kind,
+            value: token.value.to_string(),
+            start: token.start,
+            end: token.end,
+        }
+    }
+}
+
+/// Error tokenizing text from a reader
+#[derive(Debug, Error)]
+pub enum ReadError {
+    /// Reading failed, or the text is not valid UTF-8
+    #[error("error reading source: {0}")]
+    Io(#[from] io::Error),
+    /// The text contains an invalid token
+    #[error("{0}")]
+    Lex(#[from] LexError),
+}
+
+/// Iterator over the tokens of text read from a reader, see
+/// [`tokenize_reader`](fn.tokenize_reader.html)
+#[derive(Debug)]
+pub struct ReadTokens<R> {
+    reader: R,
+    /// Decoded text which was not tokenized yet
+    buf: String,
+    /// Start of the text in `buf` which was not tokenized yet
+    pos: usize,
+    /// Position of the byte at `pos` in the whole text
+    base: Pos,
+    /// Bytes of a character which is split between chunks
+    partial: Vec<u8>,
+    eof: bool,
+    done: bool,
+}
+
+/// Splits text from `reader` into tokens, reading it in chunks
+///
+/// Only the text of the current token and the chunk containing it is kept
+/// in memory, so arbitrarily large documents can be tokenized. Offsets of
+/// tokens and errors count bytes from the start of the text. After an
+/// error the iterator returns it and then ends.
+///
+/// An unterminated block string is only detected at the end of the input,
+/// so the rest of the text is buffered in that case.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::lexer::tokenize_reader;
+///
+/// let source: &[u8] = b"type Query { users: [User!]! }";
+/// let names = tokenize_reader(source)
+///     .map(|token| token.map(|t| t.value))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(names[..3], ["type", "Query", "{"]);
+/// ```
+pub fn tokenize_reader<R: Read>(reader: R) -> ReadTokens<R> {
+    ReadTokens {
+        reader,
+        buf: String::new(),
+        pos: 0,
+        base: Pos::new(1, 1, 0),
+        partial: Vec::new(),
+        eof: false,
+        done: false,
+    }
+}
+
+impl<R: Read> ReadTokens<R> {
+    /// Appends the next chunk of the input to the buffer
+    fn fill(&mut self) -> io::Result<()> {
+        // drop the text which was already tokenized
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+
+        let mut chunk = [0; CHUNK_SIZE];
+        let len = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(len) => break len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        if len == 0 {
+            self.eof = true;
+            if !self.partial.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+        self.partial.extend_from_slice(&chunk[..len]);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            // a character continues in the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        // validated above
+        self.buf
+            .push_str(std::str::from_utf8(&self.partial[..valid]).unwrap());
+        self.partial.drain(..valid);
+        Ok(())
+    }
+
+    /// Takes the next token of the buffer, unless more input is needed to
+    /// tell where it ends
+    fn take(&mut self) -> Option<Result<OwnedToken, LexError>> {
+        let rest = &self.buf[self.pos..];
+        let mut stream = TokenStream::starting_at(rest, self.base);
+        let result = stream.advance();
+        match result {
+            // a token reaching the end of the buffer may continue
+            Some(Ok(token)) if self.eof || token.end.offset - self.base.offset < rest.len() => {
+                self.pos += token.end.offset - self.base.offset;
+                self.base = token.end;
+                Some(Ok(token.into()))
+            }
+            Some(Err(mut e))
+                if self.eof || e.offset + error_len(&rest[e.offset..]) < rest.len() =>
+            {
+                e.offset += self.base.offset;
+                Some(Err(e))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+impl<R: Read> Iterator for ReadTokens<R> {
+    type Item = Result<OwnedToken, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(result) = self.take() {
+                self.done = result.is_err();
+                return Some(result.map_err(ReadError::from));
+            }
+            if self.eof {
+                self.done = true;
+            } else if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Read};
+
+    use super::{tokenize_reader, OwnedToken, ReadError};
+    use crate::lexer::tokenize;
+
+    /// Returns at most `size` bytes per read
+    struct Chunks<'a> {
+        data: &'a [u8],
+        size: usize,
+    }
+
+    impl Read for Chunks<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.size.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    fn read(source: &str, size: usize) -> Vec<Result<OwnedToken, String>> {
+        let reader = Chunks {
+            data: source.as_bytes(),
+            size,
+        };
+        tokenize_reader(reader)
+            .map(|t| t.map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn same_as_in_memory() {
+        let source = "\u{feff}query Q($id: ID! = 12345) { # comm\u{e9}nt\r\n  \
+                      user(id: $id, s: \"\u{1f600}\\\"\") { ...F }\n}\n\
+                      \"\"\"\nblock \u{e9}\n\"\"\" scalar S";
+        let expected: Vec<_> = tokenize(source)
+            .map(|t| Ok(OwnedToken::from(t.unwrap())))
+            .collect();
+        for size in 1..10 {
+            assert_eq!(read(source, size), expected, "chunks of {}", size);
+        }
+        assert_eq!(read(source, 4096), expected);
+    }
+
+    #[test]
+    fn errors() {
+        for size in [1, 3, 100] {
+            let tokens = read("a \"bc\nd", size);
+            assert_eq!(tokens.len(), 2);
+            assert_eq!(
+                tokens[1],
+                Err("lexing error at byte 2: unterminated string value".into())
+            );
+            let tokens = read("a \"\"\"bc", size);
+            assert_eq!(
+                tokens[1],
+                Err("lexing error at byte 2: unterminated block string value".into())
+            );
+        }
+        let reader: &[u8] = b"a \xe9 b";
+        let tokens: Vec<_> = tokenize_reader(reader).collect();
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(tokens[0], Err(ReadError::Io(_))));
+    }
+}
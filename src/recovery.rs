@@ -0,0 +1,354 @@
+//! Error recovery shared by the query and schema parsers
+use combine::stream::Resetable;
+use combine::StreamOnce;
+
+use crate::diagnostic::Diagnostics;
+use crate::error::{Error, Errors, Info};
+use crate::green::{starts_definition, DEFINITION_KEYWORDS};
+use crate::message::{Message, MessageKey};
+use crate::position::Pos;
+use crate::syntax_error::ErrorCode;
+use crate::tokenizer::{Checkpoint, Kind, Token, TokenStream};
+
+/// Maximum number of prefixes of a broken definition tried by `salvage`
+const SALVAGE_ATTEMPTS: usize = 16;
+
+/// Parses definitions up to the end of input, skipping the broken ones
+///
+/// After an error, parsing resumes at the next token which looks like the
+/// start of a definition: a keyword or a description on the top level of
+/// the broken definition, or a keyword at the start of a line. The latter
+/// catches definitions following an unclosed bracket.
+///
+/// The broken definition itself is salvaged if possible, see `salvage`.
+/// At most `TokenStream::error_limit` errors are returned, if there are
+/// more, the last one says how many of them were omitted.
+pub(crate) fn definitions<'a, D, P>(
+    tokens: &mut TokenStream<'a>,
+    definition: P,
+) -> (Vec<D>, Vec<Errors<'a>>)
+where
+    P: FnMut(&mut TokenStream<'a>) -> Result<D, Errors<'a>>,
+{
+    let mut errors = Diagnostics::new(tokens.error_limit());
+    let definitions = resume(tokens, definition, None, &mut errors);
+    (definitions, errors.into_vec_with(more_errors))
+}
+
+/// Returns the errors after the `first` one, found by parsing the input
+/// again with recovery
+///
+/// Together with the first one, at most `TokenStream::error_limit` errors
+/// are returned before the summary of the omitted ones.
+pub(crate) fn further_errors<'a, D, P>(
+    tokens: &mut TokenStream<'a>,
+    first: &Errors<'a>,
+    definition: P,
+) -> Vec<Errors<'a>>
+where
+    P: FnMut(&mut TokenStream<'a>) -> Result<D, Errors<'a>>,
+{
+    let mut errors = Diagnostics::new(tokens.error_limit().saturating_sub(1));
+    resume(tokens, definition, Some(first.position), &mut errors);
+    errors.into_vec_with(more_errors)
+}
+
+/// Parses definitions, reporting the errors found after `after`
+fn resume<'a, D, P>(
+    tokens: &mut TokenStream<'a>,
+    mut definition: P,
+    after: Option<Pos>,
+    errors: &mut Diagnostics<Errors<'a>>,
+) -> Vec<D>
+where
+    P: FnMut(&mut TokenStream<'a>) -> Result<D, Errors<'a>>,
+{
+    let mut definitions = Vec::new();
+    let mut failed = false;
+    let mut report = |error: Errors<'a>| {
+        if !matches!(after, Some(after) if error.position <= after) {
+            errors.push(error);
+        }
+    };
+    // an empty document is an error, so the first definition is always
+    // attempted
+    while (definitions.is_empty() && !failed) || !tokens.is_at_end() {
+        let start = tokens.checkpoint();
+        match definition(tokens) {
+            Ok(definition) => definitions.push(definition),
+            // the rest of the document is beyond the limit as well
+            Err(error) if exceeds_limits(&error) => {
+                report(error);
+                break;
+            }
+            Err(error) => {
+                failed = true;
+                tokens.reset(start.clone());
+                let skipped = skip_definition(tokens);
+                let resume = tokens.checkpoint();
+                let error_offset = error.position.offset;
+                definitions.extend(salvage(
+                    tokens,
+                    start,
+                    &skipped,
+                    error_offset,
+                    &mut definition,
+                ));
+                tokens.reset(resume);
+                report(error);
+            }
+        }
+    }
+    definitions
+}
+
+/// Summarizes the errors omitted due to the limit, at the first of them
+fn more_errors(first: Errors, omitted: usize) -> Errors {
+    let message = Message::with_args(MessageKey::MoreDiagnostics, Some(omitted.to_string()));
+    Errors {
+        position: first.position,
+        errors: vec![Error::Message(Info::Raised(ErrorCode::Other, message))],
+    }
+}
+
+/// Checks whether parsing stopped because the document is too large
+fn exceeds_limits(error: &Errors) -> bool {
+    error.errors.iter().any(|error| match *error {
+        Error::Message(Info::Raised(code, _)) => code == ErrorCode::LimitExceeded,
+        _ => false,
+    })
+}
+
+/// Takes the tokens up to the start of the next definition
+fn skip_definition<'a>(tokens: &mut TokenStream<'a>) -> Vec<Token<'a>> {
+    let mut skipped: Vec<Token<'a>> = Vec::new();
+    let mut skipped_invalid = false;
+    let mut depth = 0usize;
+    loop {
+        let checkpoint = tokens.checkpoint();
+        match tokens.uncons() {
+            Ok(token) => {
+                let resync = match skipped.last() {
+                    Some(&prev) => {
+                        (depth == 0 && starts_definition(token, prev))
+                            || (token.start.column == 1
+                                && is_keyword(token)
+                                && !is_description(prev))
+                    }
+                    None => skipped_invalid && (is_keyword(token) || token.value == "{"),
+                };
+                if resync {
+                    tokens.reset(checkpoint);
+                    break;
+                }
+                if token.kind == Kind::Punctuator {
+                    match token.value {
+                        "(" | "[" | "{" => depth += 1,
+                        ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+                skipped.push(token);
+            }
+            Err(_) if tokens.is_at_end() => break,
+            Err(_) => {
+                tokens.skip_invalid();
+                skipped_invalid = true;
+            }
+        }
+    }
+    skipped
+}
+
+/// Checks for a definition keyword or a description
+fn is_keyword(token: Token) -> bool {
+    match token.kind {
+        Kind::Name => DEFINITION_KEYWORDS.contains(&token.value),
+        _ => is_description(token),
+    }
+}
+
+fn is_description(token: Token) -> bool {
+    matches!(token.kind, Kind::StringValue | Kind::BlockString)
+}
+
+/// Parses the longest prefix of a broken definition ending before the
+/// error, with the brackets left open closed
+///
+/// For example `query { user { name` is parsed as `query { user { name } }`.
+fn salvage<'a, D, P>(
+    tokens: &mut TokenStream<'a>,
+    start: Checkpoint,
+    skipped: &[Token<'a>],
+    error_offset: usize,
+    definition: &mut P,
+) -> Option<D>
+where
+    P: FnMut(&mut TokenStream<'a>) -> Result<D, Errors<'a>>,
+{
+    let before_error = skipped
+        .iter()
+        .take_while(|token| token.start.offset < error_offset)
+        .count();
+    for len in (1..=before_error).rev().take(SALVAGE_ATTEMPTS) {
+        let prefix = &skipped[..len];
+        let closers = match closers(prefix) {
+            Some(closers) => closers,
+            None => continue,
+        };
+        tokens.reset(start.clone());
+        tokens.start_repair(prefix[len - 1].end.offset, closers);
+        let result = definition(tokens);
+        let complete = tokens.uncons().is_err();
+        tokens.end_repair();
+        match result {
+            Ok(definition) if complete => return Some(definition),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the brackets closing the ones left open by the tokens
+fn closers(tokens: &[Token]) -> Option<Vec<&'static str>> {
+    let mut stack = Vec::new();
+    for token in tokens.iter().filter(|token| token.kind == Kind::Punctuator) {
+        match token.value {
+            "(" => stack.push(")"),
+            "[" => stack.push("]"),
+            "{" => stack.push("}"),
+            ")" | "]" | "}" if stack.pop() != Some(token.value) => return None,
+            _ => {}
+        }
+    }
+    stack.reverse();
+    Some(stack)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnostic::DEFAULT_LIMIT;
+    use crate::parser::Parser;
+    use crate::position::Pos;
+    use crate::query::{parse_query, parse_query_with_recovery, Definition, OperationDefinition};
+    use crate::schema::{self, parse_schema_with_recovery};
+
+    fn operations(source: &str) -> (Vec<String>, Vec<String>) {
+        let (doc, errors) = parse_query_with_recovery::<String>(source);
+        let operations = doc
+            .definitions
+            .iter()
+            .map(|def| match def {
+                Definition::Operation(OperationDefinition::Query(q)) => {
+                    format!("{} {}", q.name.as_deref().unwrap_or("?"), q.selection_set)
+                }
+                Definition::Operation(op) => op.to_string(),
+                Definition::Fragment(f) => f.to_string(),
+            })
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        let errors = errors
+            .iter()
+            .map(|e| e.to_string().lines().next().unwrap().to_string())
+            .collect();
+        (operations, errors)
+    }
+
+    #[test]
+    fn valid() {
+        let source = "query A { a }\nfragment F on T { f }\n{ b(x: [1]) }";
+        let (doc, errors) = parse_query_with_recovery::<String>(source);
+        assert_eq!(doc, parse_query::<String>(source).unwrap());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn skips_broken_definitions() {
+        let (ops, errors) = operations("query A { a }\nquery B { b(x: ) }\nquery C { c }");
+        assert_eq!(ops, ["A { a }", "B { b }", "C { c }"]);
+        assert_eq!(errors, ["query parse error: Parse error at 2:16"]);
+    }
+
+    #[test]
+    fn unclosed_brackets() {
+        let (ops, errors) = operations("query A { user { name\nquery B { b }\nquery C { c(x: [1");
+        assert_eq!(ops, ["A { user { name } }", "B { b }", "C { c(x: [1]) }"]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn invalid_characters() {
+        let (ops, errors) = operations("query A { a }\n\u{a7}\nquery B { b }");
+        assert_eq!(ops, ["A { a }", "B { b }"]);
+        assert_eq!(errors, ["query parse error: Parse error at 2:1"]);
+    }
+
+    #[test]
+    fn error_limit() {
+        let source = "query A { a( }\n".repeat(5);
+        let (_, errors) = Parser::new()
+            .error_limit(2)
+            .parse_query_with_recovery::<&str>(&source);
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[2],
+            "query parse error: Parse error at 3:14\nand 3 more\n"
+        );
+
+        let (_, errors) = parse_query_with_recovery::<&str>(&"query A { a( }\n".repeat(500));
+        assert_eq!(errors.len(), DEFAULT_LIMIT + 1);
+    }
+
+    #[test]
+    fn further_error_limit() {
+        let source = "query A { a( }\n".repeat(500);
+        let err = parse_query::<&str>(&source).unwrap_err();
+        assert_eq!(err.positions().len(), DEFAULT_LIMIT + 1);
+        assert!(err.to_string().ends_with("\nand 400 more\n"));
+
+        let err = Parser::new()
+            .error_limit(1)
+            .parse_query::<&str>(&source)
+            .unwrap_err();
+        assert_eq!(err.positions(), [Pos::new(1, 14, 13), Pos::new(2, 14, 28)]);
+        assert!(err
+            .to_string()
+            .ends_with("Parse error at 2:14\nand 499 more\n"));
+    }
+
+    #[test]
+    fn empty() {
+        let (ops, errors) = operations("# nothing");
+        assert!(ops.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn all_errors() {
+        let err = parse_query::<String>("{ a }\n{ b(x: ) }\n{ c }\n{ d(: 1) }").unwrap_err();
+        assert_eq!(err.positions(), [Pos::new(2, 8, 13), Pos::new(4, 5, 27)]);
+        let err =
+            schema::parse_schema::<String>("type A {\ntype B { b: Int }\nenum C {}").unwrap_err();
+        assert_eq!(err.position(), Pos::new(2, 6, 14));
+        assert_eq!(err.positions().len(), 2);
+    }
+
+    #[test]
+    fn schema() {
+        let source = "type A { a: Int }\ntype B { b: }\n\"C\"\ntype C implements I { c: Int";
+        let (doc, errors) = parse_schema_with_recovery::<String>(source);
+        let names: Vec<_> = doc
+            .definitions
+            .iter()
+            .map(|def| match def {
+                schema::Definition::TypeDefinition(schema::TypeDefinition::Object(o)) => {
+                    format!("{:?} {} {}", o.description, o.name, o.fields.len())
+                }
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(names, ["None A 1", "None B 0", "Some(\"C\") C 1"]);
+        assert_eq!(errors.len(), 2);
+    }
+}
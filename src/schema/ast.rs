@@ -2,15 +2,32 @@ use std::str::FromStr;
 
 use thiserror::Error;
 
-pub use crate::common::{Directive, Text, Type, Value};
-use crate::position::Pos;
-
-#[derive(Debug, Clone, Default, PartialEq)]
+pub use crate::common::{
+    Comment, ConstValue, Directive, Interner, Names, StringValue, Text, Type, Value,
+    VariableInConstValue,
+};
+use crate::position::{Pos, Span};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Document<'a, T: Text<'a>>
 where
     T: Text<'a>,
 {
     pub definitions: Vec<Definition<'a, T>>,
+    /// Comments of the document, see `Parser::preserve_comments`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<Comment<'a, T>>,
 }
 
 impl<'a> Document<'a, String> {
@@ -32,7 +49,15 @@ impl<'a> Document<'a, String> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum Definition<'a, T: Text<'a>> {
     SchemaDefinition(SchemaDefinition<'a, T>),
     TypeDefinition(TypeDefinition<'a, T>),
@@ -40,16 +65,33 @@ pub enum Definition<'a, T: Text<'a>> {
     DirectiveDefinition(DirectiveDefinition<'a, T>),
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct SchemaDefinition<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub directives: Vec<Directive<'a, T>>,
     pub query: Option<T::Value>,
     pub mutation: Option<T::Value>,
     pub subscription: Option<T::Value>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum TypeDefinition<'a, T: Text<'a>> {
     Scalar(ScalarType<'a, T>),
     Object(ObjectType<'a, T>),
@@ -59,7 +101,15 @@ pub enum TypeDefinition<'a, T: Text<'a>> {
     InputObject(InputObjectType<'a, T>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub enum TypeExtension<'a, T: Text<'a>> {
     Scalar(ScalarTypeExtension<'a, T>),
     Object(ObjectTypeExtension<'a, T>),
@@ -69,9 +119,18 @@ pub enum TypeExtension<'a, T: Text<'a>> {
     InputObject(InputObjectTypeExtension<'a, T>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct ScalarType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
@@ -84,6 +143,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
@@ -91,9 +151,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct ScalarTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
@@ -105,15 +174,25 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             directives: vec![],
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct ObjectType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
@@ -128,6 +207,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             implements_interfaces: vec![],
@@ -137,9 +217,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct ObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
     pub directives: Vec<Directive<'a, T>>,
@@ -153,6 +242,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             implements_interfaces: vec![],
             directives: vec![],
@@ -161,9 +251,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
@@ -171,9 +270,18 @@ pub struct Field<'a, T: Text<'a>> {
     pub directives: Vec<Directive<'a, T>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InputValue<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub value_type: Type<'a, T>,
@@ -181,9 +289,18 @@ pub struct InputValue<'a, T: Text<'a>> {
     pub directives: Vec<Directive<'a, T>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InterfaceType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
@@ -198,6 +315,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             implements_interfaces: vec![],
@@ -207,9 +325,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InterfaceTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
     pub directives: Vec<Directive<'a, T>>,
@@ -223,6 +350,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             implements_interfaces: vec![],
             directives: vec![],
@@ -231,9 +359,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct UnionType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
@@ -247,6 +384,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
@@ -255,9 +393,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct UnionTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub types: Vec<T::Value>,
@@ -270,6 +417,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             directives: vec![],
             types: vec![],
@@ -277,9 +425,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct EnumType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
@@ -293,6 +450,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
@@ -301,9 +459,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct EnumValue<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
@@ -316,6 +483,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
@@ -323,9 +491,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct EnumTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub values: Vec<EnumValue<'a, T>>,
@@ -338,6 +515,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             directives: vec![],
             values: vec![],
@@ -345,9 +523,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InputObjectType<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
@@ -361,6 +548,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
@@ -369,9 +557,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct InputObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub fields: Vec<InputValue<'a, T>>,
@@ -384,6 +581,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             name,
             directives: vec![],
             fields: vec![],
@@ -391,7 +589,8 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DirectiveLocation {
     // executable
     Query,
@@ -417,9 +616,18 @@ pub enum DirectiveLocation {
     VariableDefinition,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct DirectiveDefinition<'a, T: Text<'a>> {
     pub position: Pos,
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
@@ -434,6 +642,7 @@ where
     pub fn new(name: T::Value) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             arguments: vec![],
@@ -488,6 +697,95 @@ impl DirectiveLocation {
     }
 }
 
+impl_span!(
+    SchemaDefinition,
+    ScalarType,
+    ScalarTypeExtension,
+    ObjectType,
+    ObjectTypeExtension,
+    Field,
+    InputValue,
+    InterfaceType,
+    InterfaceTypeExtension,
+    UnionType,
+    UnionTypeExtension,
+    EnumType,
+    EnumValue,
+    EnumTypeExtension,
+    InputObjectType,
+    InputObjectTypeExtension,
+    DirectiveDefinition,
+);
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the source range from the first to the last definition
+    pub fn span(&self) -> Span {
+        match (self.definitions.first(), self.definitions.last()) {
+            (Some(first), Some(last)) => Span::new(first.span().start, last.span().end),
+            _ => Span::default(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Definition<'a, T> {
+    pub fn span(&self) -> Span {
+        match *self {
+            Definition::SchemaDefinition(ref def) => def.span(),
+            Definition::TypeDefinition(ref def) => def.span(),
+            Definition::TypeExtension(ref ext) => ext.span(),
+            Definition::DirectiveDefinition(ref def) => def.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeDefinition<'a, T> {
+    pub fn name(&self) -> &str {
+        match *self {
+            TypeDefinition::Scalar(ref def) => def.name.as_ref(),
+            TypeDefinition::Object(ref def) => def.name.as_ref(),
+            TypeDefinition::Interface(ref def) => def.name.as_ref(),
+            TypeDefinition::Union(ref def) => def.name.as_ref(),
+            TypeDefinition::Enum(ref def) => def.name.as_ref(),
+            TypeDefinition::InputObject(ref def) => def.name.as_ref(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match *self {
+            TypeDefinition::Scalar(ref def) => def.span(),
+            TypeDefinition::Object(ref def) => def.span(),
+            TypeDefinition::Interface(ref def) => def.span(),
+            TypeDefinition::Union(ref def) => def.span(),
+            TypeDefinition::Enum(ref def) => def.span(),
+            TypeDefinition::InputObject(ref def) => def.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeExtension<'a, T> {
+    pub fn name(&self) -> &str {
+        match *self {
+            TypeExtension::Scalar(ref ext) => ext.name.as_ref(),
+            TypeExtension::Object(ref ext) => ext.name.as_ref(),
+            TypeExtension::Interface(ref ext) => ext.name.as_ref(),
+            TypeExtension::Union(ref ext) => ext.name.as_ref(),
+            TypeExtension::Enum(ref ext) => ext.name.as_ref(),
+            TypeExtension::InputObject(ref ext) => ext.name.as_ref(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match *self {
+            TypeExtension::Scalar(ref ext) => ext.span(),
+            TypeExtension::Object(ref ext) => ext.span(),
+            TypeExtension::Interface(ref ext) => ext.span(),
+            TypeExtension::Union(ref ext) => ext.span(),
+            TypeExtension::Enum(ref ext) => ext.span(),
+            TypeExtension::InputObject(ref ext) => ext.span(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("invalid directive location")]
 pub struct InvalidDirectiveLocation;
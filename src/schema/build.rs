@@ -0,0 +1,405 @@
+//! Builders for constructing schema documents in code
+//!
+//! Code generators can emit SDL by building the AST and printing it,
+//! instead of filling string templates. Positions are left at their
+//! defaults and optional parts are empty unless set.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::schema::{
+//!     Definition, Document, EnumTypeBuilder, FieldBuilder, InputValueBuilder,
+//!     ObjectTypeBuilder, Type,
+//! };
+//!
+//! let named = |name: &str| Type::NamedType(name.to_string());
+//! let role = EnumTypeBuilder::<String>::new("Role".into())
+//!     .value("ADMIN".into())
+//!     .value("USER".into())
+//!     .build();
+//! let user = ObjectTypeBuilder::<String>::new("User".into())
+//!     .description("A registered user")
+//!     .field(FieldBuilder::new("role".into(), named("Role")))
+//!     .field(
+//!         FieldBuilder::new("friends".into(), Type::ListType(Box::new(named("User"))))
+//!             .argument(InputValueBuilder::new("first".into(), named("Int"))),
+//!     )
+//!     .build();
+//! let doc = Document::new(vec![Definition::from(role), Definition::from(user)]);
+//! assert_eq!(doc.to_string(), "\
+//! enum Role {
+//!   ADMIN
+//!   USER
+//! }
+//!
+//! \"A registered user\"
+//! type User {
+//!   role: Role
+//!   friends(first: Int): [User]
+//! }
+//! ");
+//! ```
+use crate::common::{Directive, Text, Type, Value};
+use crate::position::Pos;
+use crate::schema::ast::*;
+
+/// Adds `description` and `directive` to builders of nodes having both
+macro_rules! impl_common_methods {
+    ($($name:ident),*) => {
+        $(
+            impl<'a, T: Text<'a>> $name<'a, T> {
+                /// Sets the description printed as a string before the node
+                pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+                    self.node.description = Some(description.into());
+                    self
+                }
+
+                /// Adds a directive with the given arguments
+                pub fn directive<I>(mut self, name: T::Value, arguments: I) -> Self
+                where
+                    I: IntoIterator<Item = (T::Value, Value<'a, T>)>,
+                {
+                    self.node.directives.push(Directive {
+                        position: Pos::default(),
+                        end: Pos::default(),
+                        name,
+                        arguments: arguments.into_iter().collect(),
+                    });
+                    self
+                }
+            }
+        )*
+    };
+}
+
+/// Defines a builder wrapping the node, started with the node's `new`
+macro_rules! define_builder {
+    ($($(#[$attr:meta])* $name:ident => $node:ident),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            #[derive(Debug, Clone)]
+            pub struct $name<'a, T: Text<'a>> {
+                node: $node<'a, T>,
+            }
+
+            impl<'a, T: Text<'a>> $name<'a, T> {
+                pub fn new(name: T::Value) -> $name<'a, T> {
+                    $name {
+                        node: $node::new(name),
+                    }
+                }
+
+                pub fn build(self) -> $node<'a, T> {
+                    self.node
+                }
+            }
+        )*
+    };
+}
+
+define_builder! {
+    /// Builder of a scalar type definition
+    ScalarTypeBuilder => ScalarType,
+    /// Builder of an object type definition
+    ObjectTypeBuilder => ObjectType,
+    /// Builder of an interface type definition
+    InterfaceTypeBuilder => InterfaceType,
+    /// Builder of a union type definition
+    UnionTypeBuilder => UnionType,
+    /// Builder of an enum type definition
+    EnumTypeBuilder => EnumType,
+    /// Builder of an input object type definition
+    InputObjectTypeBuilder => InputObjectType,
+    /// Builder of a directive definition
+    DirectiveDefinitionBuilder => DirectiveDefinition,
+}
+
+impl_common_methods!(
+    ScalarTypeBuilder,
+    ObjectTypeBuilder,
+    InterfaceTypeBuilder,
+    UnionTypeBuilder,
+    EnumTypeBuilder,
+    InputObjectTypeBuilder,
+    FieldBuilder,
+    InputValueBuilder
+);
+
+impl<'a, T: Text<'a>> ObjectTypeBuilder<'a, T> {
+    /// Adds an interface to the `implements` list
+    pub fn implements(mut self, interface: T::Value) -> Self {
+        self.node.implements_interfaces.push(interface);
+        self
+    }
+
+    pub fn field(mut self, field: FieldBuilder<'a, T>) -> Self {
+        self.node.fields.push(field.build());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> InterfaceTypeBuilder<'a, T> {
+    /// Adds an interface to the `implements` list
+    pub fn implements(mut self, interface: T::Value) -> Self {
+        self.node.implements_interfaces.push(interface);
+        self
+    }
+
+    pub fn field(mut self, field: FieldBuilder<'a, T>) -> Self {
+        self.node.fields.push(field.build());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> UnionTypeBuilder<'a, T> {
+    /// Adds a member type
+    pub fn member(mut self, name: T::Value) -> Self {
+        self.node.types.push(name);
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> EnumTypeBuilder<'a, T> {
+    /// Adds a value with no description or directives
+    pub fn value(self, name: T::Value) -> Self {
+        self.enum_value(EnumValue::new(name))
+    }
+
+    pub fn enum_value(mut self, value: EnumValue<'a, T>) -> Self {
+        self.node.values.push(value);
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> InputObjectTypeBuilder<'a, T> {
+    pub fn field(mut self, field: InputValueBuilder<'a, T>) -> Self {
+        self.node.fields.push(field.build());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> DirectiveDefinitionBuilder<'a, T> {
+    /// Sets the description printed as a string before the definition
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.node.description = Some(description.into());
+        self
+    }
+
+    pub fn argument(mut self, argument: InputValueBuilder<'a, T>) -> Self {
+        self.node.arguments.push(argument.build());
+        self
+    }
+
+    pub fn repeatable(mut self, repeatable: bool) -> Self {
+        self.node.repeatable = repeatable;
+        self
+    }
+
+    pub fn location(mut self, location: DirectiveLocation) -> Self {
+        self.node.locations.push(location);
+        self
+    }
+}
+
+/// Builder of a field of an object or interface type
+#[derive(Debug, Clone)]
+pub struct FieldBuilder<'a, T: Text<'a>> {
+    node: Field<'a, T>,
+}
+
+impl<'a, T: Text<'a>> FieldBuilder<'a, T> {
+    pub fn new(name: T::Value, field_type: Type<'a, T>) -> FieldBuilder<'a, T> {
+        FieldBuilder {
+            node: Field {
+                position: Pos::default(),
+                end: Pos::default(),
+                description: None,
+                name,
+                arguments: Vec::new(),
+                field_type,
+                directives: Vec::new(),
+            },
+        }
+    }
+
+    pub fn argument(mut self, argument: InputValueBuilder<'a, T>) -> Self {
+        self.node.arguments.push(argument.build());
+        self
+    }
+
+    pub fn build(self) -> Field<'a, T> {
+        self.node
+    }
+}
+
+/// Builder of an argument or a field of an input object type
+#[derive(Debug, Clone)]
+pub struct InputValueBuilder<'a, T: Text<'a>> {
+    node: InputValue<'a, T>,
+}
+
+impl<'a, T: Text<'a>> InputValueBuilder<'a, T> {
+    pub fn new(name: T::Value, value_type: Type<'a, T>) -> InputValueBuilder<'a, T> {
+        InputValueBuilder {
+            node: InputValue {
+                position: Pos::default(),
+                end: Pos::default(),
+                description: None,
+                name,
+                value_type,
+                default_value: None,
+                directives: Vec::new(),
+            },
+        }
+    }
+
+    pub fn default_value<V: Into<Value<'a, T>>>(mut self, value: V) -> Self {
+        self.node.default_value = Some(value.into());
+        self
+    }
+
+    pub fn build(self) -> InputValue<'a, T> {
+        self.node
+    }
+}
+
+macro_rules! impl_from_type_definition {
+    ($($variant:ident($node:ident)),*) => {
+        $(
+            impl<'a, T: Text<'a>> From<$node<'a, T>> for TypeDefinition<'a, T> {
+                fn from(node: $node<'a, T>) -> TypeDefinition<'a, T> {
+                    TypeDefinition::$variant(node)
+                }
+            }
+
+            impl<'a, T: Text<'a>> From<$node<'a, T>> for Definition<'a, T> {
+                fn from(node: $node<'a, T>) -> Definition<'a, T> {
+                    Definition::TypeDefinition(TypeDefinition::$variant(node))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_type_definition!(
+    Scalar(ScalarType),
+    Object(ObjectType),
+    Interface(InterfaceType),
+    Union(UnionType),
+    Enum(EnumType),
+    InputObject(InputObjectType)
+);
+
+impl<'a, T: Text<'a>> From<DirectiveDefinition<'a, T>> for Definition<'a, T> {
+    fn from(node: DirectiveDefinition<'a, T>) -> Definition<'a, T> {
+        Definition::DirectiveDefinition(node)
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Creates a document of the given definitions, without comments
+    pub fn new<I, D>(definitions: I) -> Document<'a, T>
+    where
+        I: IntoIterator<Item = D>,
+        D: Into<Definition<'a, T>>,
+    {
+        Document {
+            definitions: definitions.into_iter().map(Into::into).collect(),
+            comments: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_schema;
+    use crate::semantic::SemanticEq;
+
+    fn named(name: &str) -> Type<'_, &str> {
+        Type::NamedType(name)
+    }
+
+    #[test]
+    fn build_schema() {
+        let definitions: Vec<Definition<&str>> = vec![
+            ScalarTypeBuilder::new("Date")
+                .directive("specifiedBy", vec![("url", Value::from("https://x"))])
+                .build()
+                .into(),
+            InterfaceTypeBuilder::new("Node")
+                .field(FieldBuilder::new(
+                    "id",
+                    Type::NonNullType(Box::new(named("ID"))),
+                ))
+                .build()
+                .into(),
+            ObjectTypeBuilder::new("User")
+                .description("A user")
+                .implements("Node")
+                .directive("key", vec![("fields", Value::from("id"))])
+                .field(FieldBuilder::new(
+                    "id",
+                    Type::NonNullType(Box::new(named("ID"))),
+                ))
+                .field(
+                    FieldBuilder::new("posts", Type::ListType(Box::new(named("Post"))))
+                        .description("Latest posts")
+                        .argument(InputValueBuilder::new("first", named("Int")).default_value(10))
+                        .directive("deprecated", vec![]),
+                )
+                .build()
+                .into(),
+            UnionTypeBuilder::new("Result")
+                .member("User")
+                .member("Error")
+                .build()
+                .into(),
+            EnumTypeBuilder::new("Role")
+                .value("ADMIN")
+                .enum_value(EnumValue {
+                    directives: vec![Directive {
+                        position: Pos::default(),
+                        end: Pos::default(),
+                        name: "deprecated",
+                        arguments: vec![],
+                    }],
+                    ..EnumValue::new("GUEST")
+                })
+                .build()
+                .into(),
+            InputObjectTypeBuilder::new("Filter")
+                .field(InputValueBuilder::new("role", named("Role")).description("Only this role"))
+                .build()
+                .into(),
+            DirectiveDefinitionBuilder::new("key")
+                .description("Key fields")
+                .argument(InputValueBuilder::new("fields", named("String")))
+                .repeatable(true)
+                .location(DirectiveLocation::Object)
+                .location(DirectiveLocation::Interface)
+                .build()
+                .into(),
+        ];
+        let doc = Document::new(definitions);
+        let expected = parse_schema::<&str>(
+            r#"
+            scalar Date @specifiedBy(url: "https://x")
+            interface Node { id: ID! }
+            "A user"
+            type User implements Node @key(fields: "id") {
+                id: ID!
+                "Latest posts"
+                posts(first: Int = 10): [Post] @deprecated
+            }
+            union Result = User | Error
+            enum Role { ADMIN GUEST @deprecated }
+            input Filter { "Only this role" role: Role }
+            "Key fields"
+            directive @key(fields: String) repeatable on OBJECT | INTERFACE
+            "#,
+        )
+        .unwrap();
+        assert!(doc.semantic_eq(&expected), "{}", doc);
+        assert_eq!(doc.to_string(), expected.to_string());
+    }
+}
@@ -0,0 +1,452 @@
+//! Input coercion of values against the types of a schema
+//!
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::common::{StringValue, Text, Type, Value};
+use crate::message::SpecReference;
+use crate::schema::ast::*;
+use crate::schema::index::SchemaIndex;
+
+/// Error coercing a value to an input type
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub struct CoercionError {
+    /// Location of the offending part of the value, empty for the value
+    /// itself
+    pub path: Vec<InputPathSegment>,
+    pub kind: CoercionErrorKind,
+}
+
+impl CoercionError {
+    /// Returns the section of the specification defining the rule, if any
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        use self::CoercionErrorKind::*;
+        let (section, title) = match self.kind {
+            InvalidValue { .. } | NullValue(_) => ("5.6.1", "Values of Correct Type"),
+            MissingField { .. } => ("5.6.4", "Input Object Required Fields"),
+            UnknownField { .. } => ("5.6.2", "Input Object Field Names"),
+            NotInputType(_) => ("3.4.2", "Input and Output Types"),
+            UnknownType(_) => return None,
+        };
+        Some(SpecReference { section, title })
+    }
+}
+
+/// Item of the path to a part of an input value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputPathSegment {
+    /// Field of an input object
+    Field(String),
+    /// Item of a list
+    Index(usize),
+}
+
+/// Reason why a value can't be coerced
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CoercionErrorKind {
+    /// The value doesn't fit the type, like a string given for an `Int`
+    #[error("expected a value of type `{expected}`, found `{found}`")]
+    InvalidValue { expected: String, found: String },
+    #[error("null is not allowed for non-null type `{0}`")]
+    NullValue(String),
+    #[error("field `{field}` of type `{field_type}` is required by `{type_name}`")]
+    MissingField {
+        type_name: String,
+        field: String,
+        field_type: String,
+    },
+    #[error("input object `{type_name}` has no field `{field}`")]
+    UnknownField { type_name: String, field: String },
+    #[error("unknown type `{0}`")]
+    UnknownType(String),
+    #[error("`{0}` is not an input type")]
+    NotInputType(String),
+}
+
+impl fmt::Display for CoercionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.path.is_empty() {
+            f.write_str(" at ")?;
+            for (i, segment) in self.path.iter().enumerate() {
+                match *segment {
+                    InputPathSegment::Field(ref name) if i == 0 => f.write_str(name)?,
+                    InputPathSegment::Field(ref name) => write!(f, ".{}", name)?,
+                    InputPathSegment::Index(idx) => write!(f, "[{}]", idx)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Coerces a literal value to an input type of the schema
+///
+/// This is the input coercion of the specification:
+///
+/// * `null` is only accepted for nullable types.
+/// * A value which isn't a list is accepted for a list type and wrapped in
+///   a list of one item.
+/// * Built-in scalars only accept values of their kind. An `Int` is also
+///   accepted for `Float` and `ID`, and converted.
+/// * Enum values must be defined by the enum type.
+/// * Fields of input objects must be defined, required fields must be
+///   given, and absent fields with a default value get that value.
+///
+/// Values and fields added by extensions of the types count as defined.
+///
+/// Custom scalars accept any value, which is returned unchanged. So are
+/// variables, which are coerced separately against the type they are
+/// defined with.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{parse_schema, parse_type, parse_value};
+/// use graphql_parser::schema::coerce_value;
+///
+/// let schema = parse_schema::<&str>(
+///     "input Page { first: Int = 10, after: ID }",
+/// ).unwrap();
+/// let page_type = parse_type::<&str>("[Page!]").unwrap();
+/// let value = parse_value::<&str>("{after: 5}").unwrap();
+/// let coerced = coerce_value(&value, &page_type, &schema).unwrap();
+/// assert_eq!(coerced.to_string(), r#"[{first: 10, after: "5"}]"#);
+///
+/// let value = parse_value::<&str>(r#"{first: "10"}"#).unwrap();
+/// let err = coerce_value(&value, &page_type, &schema).unwrap_err();
+/// assert_eq!(err.to_string(), r#"expected a value of type `Int`, found `"10"` at first"#);
+/// ```
+pub fn coerce_value<'a, 't, 's, T, U, S>(
+    value: &Value<'a, T>,
+    value_type: &Type<'t, U>,
+    schema: &Document<'s, S>,
+) -> Result<Value<'static, String>, CoercionError>
+where
+    T: Text<'a>,
+    U: Text<'t>,
+    S: Text<'s>,
+{
+    let mut path = Vec::new();
+    Coercion {
+        index: schema.build_index(),
+    }
+    .coerce(value, value_type, &mut path)
+    .map_err(|kind| CoercionError { path, kind })
+}
+
+struct Coercion<'d, 's, S: Text<'s>> {
+    index: SchemaIndex<'d, 's, S>,
+}
+
+impl<'d, 's, S: Text<'s>> Coercion<'d, 's, S> {
+    /// Coerces the value, leaving `path` pointing at the offending part on
+    /// failure
+    fn coerce<'a, 't, T, U>(
+        &self,
+        value: &Value<'a, T>,
+        value_type: &Type<'t, U>,
+        path: &mut Vec<InputPathSegment>,
+    ) -> Result<Value<'static, String>, CoercionErrorKind>
+    where
+        T: Text<'a>,
+        U: Text<'t>,
+    {
+        match (value, value_type) {
+            (Value::Variable(_), _) => Ok(value.into_static()),
+            (Value::Null, Type::NonNullType(_)) => {
+                Err(CoercionErrorKind::NullValue(value_type.to_string()))
+            }
+            (_, Type::NonNullType(inner)) => self.coerce(value, inner, path),
+            (Value::Null, _) => Ok(Value::Null),
+            (Value::List(items), Type::ListType(inner)) => {
+                let mut result = Vec::with_capacity(items.len());
+                for (idx, item) in items.iter().enumerate() {
+                    path.push(InputPathSegment::Index(idx));
+                    result.push(self.coerce(item, inner, path)?);
+                    path.pop();
+                }
+                Ok(Value::List(result))
+            }
+            (_, Type::ListType(inner)) => Ok(Value::List(vec![self.coerce(value, inner, path)?])),
+            (_, Type::NamedType(ref name)) => self.coerce_named(value, name.as_ref(), path),
+        }
+    }
+
+    fn coerce_named<'a, T: Text<'a>>(
+        &self,
+        value: &Value<'a, T>,
+        type_name: &str,
+        path: &mut Vec<InputPathSegment>,
+    ) -> Result<Value<'static, String>, CoercionErrorKind> {
+        let invalid = || CoercionErrorKind::InvalidValue {
+            expected: type_name.to_string(),
+            found: value.to_string(),
+        };
+        let entry = self.index.type_entry(type_name);
+        let definition = match entry.and_then(|entry| entry.definition) {
+            Some(definition) => definition,
+            None => {
+                return match (type_name, value) {
+                    ("Int", Value::Int(n)) if n.as_i32().is_some() => Ok(value.into_static()),
+                    ("Float", Value::Float(_)) => Ok(value.into_static()),
+                    ("Float", Value::Int(_)) | ("Float", Value::BigInt(_)) => {
+                        Ok(Value::from(value.as_f64().ok_or_else(invalid)?))
+                    }
+                    ("String", Value::String(_))
+                    | ("Boolean", Value::Boolean(_))
+                    | ("ID", Value::String(_)) => Ok(value.into_static()),
+                    ("ID", Value::Int(n)) => Ok(Value::String(StringValue::from(n.to_string()))),
+                    ("ID", Value::BigInt(n)) => Ok(Value::String(StringValue::from(n.to_string()))),
+                    ("Int", _) | ("Float", _) | ("String", _) | ("Boolean", _) | ("ID", _) => {
+                        Err(invalid())
+                    }
+                    _ => Err(CoercionErrorKind::UnknownType(type_name.to_string())),
+                };
+            }
+        };
+        let extensions = entry.map_or(&[][..], |entry| &entry.extensions);
+        match *definition {
+            TypeDefinition::Scalar(_) => Ok(value.into_static()),
+            TypeDefinition::Enum(ref enum_type) => match *value {
+                Value::Enum(ref name)
+                    if enum_values(enum_type, extensions)
+                        .any(|v| v.name.as_ref() == name.as_ref()) =>
+                {
+                    Ok(value.into_static())
+                }
+                _ => Err(invalid()),
+            },
+            TypeDefinition::InputObject(ref input) => {
+                let fields = match *value {
+                    Value::Object(ref fields) => fields,
+                    _ => return Err(invalid()),
+                };
+                for (name, _) in fields {
+                    if !input_fields(input, extensions).any(|f| f.name.as_ref() == name.as_ref()) {
+                        return Err(CoercionErrorKind::UnknownField {
+                            type_name: type_name.to_string(),
+                            field: name.as_ref().to_string(),
+                        });
+                    }
+                }
+                let mut result = Vec::with_capacity(input.fields.len());
+                for field in input_fields(input, extensions) {
+                    let name = field.name.as_ref();
+                    let given = value.get(name);
+                    path.push(InputPathSegment::Field(name.to_string()));
+                    let coerced = match (given, &field.default_value) {
+                        (Some(given), _) => Some(self.coerce(given, &field.value_type, path)?),
+                        (None, Some(default)) => {
+                            Some(self.coerce(default, &field.value_type, path)?)
+                        }
+                        (None, None) if matches!(field.value_type, Type::NonNullType(_)) => {
+                            path.pop();
+                            return Err(CoercionErrorKind::MissingField {
+                                type_name: type_name.to_string(),
+                                field: name.to_string(),
+                                field_type: field.value_type.to_string(),
+                            });
+                        }
+                        (None, None) => None,
+                    };
+                    path.pop();
+                    if let Some(coerced) = coerced {
+                        result.push((name.to_string(), coerced));
+                    }
+                }
+                Ok(Value::Object(result))
+            }
+            TypeDefinition::Object(_) | TypeDefinition::Interface(_) | TypeDefinition::Union(_) => {
+                Err(CoercionErrorKind::NotInputType(type_name.to_string()))
+            }
+        }
+    }
+}
+
+/// Values of the enum, followed by the ones added by its extensions
+fn enum_values<'d, 's, S: Text<'s>>(
+    enum_type: &'d EnumType<'s, S>,
+    extensions: &'d [&'d TypeExtension<'s, S>],
+) -> impl Iterator<Item = &'d EnumValue<'s, S>> {
+    let extended = extensions.iter().flat_map(|extension| match **extension {
+        TypeExtension::Enum(ref extension) => &extension.values[..],
+        _ => &[],
+    });
+    enum_type.values.iter().chain(extended)
+}
+
+/// Fields of the input object, followed by the ones added by its extensions
+fn input_fields<'d, 's, S: Text<'s>>(
+    input: &'d InputObjectType<'s, S>,
+    extensions: &'d [&'d TypeExtension<'s, S>],
+) -> impl Iterator<Item = &'d InputValue<'s, S>> {
+    let extended = extensions.iter().flat_map(|extension| match **extension {
+        TypeExtension::InputObject(ref extension) => &extension.fields[..],
+        _ => &[],
+    });
+    input.fields.iter().chain(extended)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{coerce_value, CoercionError, CoercionErrorKind, InputPathSegment};
+    use crate::{parse_schema, parse_type, parse_value};
+
+    const SCHEMA: &str = "
+        scalar Date
+        enum Color { RED GREEN }
+        input Filter { color: Color!, tags: [String!] = [], since: Date, inner: Filter }
+        type Query { a: Int }
+    ";
+
+    fn coerce(value: &str, value_type: &str) -> Result<String, CoercionError> {
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        let value = parse_value::<&str>(value).unwrap();
+        let value_type = parse_type::<&str>(value_type).unwrap();
+        coerce_value(&value, &value_type, &schema).map(|v| v.to_string())
+    }
+
+    fn kind(value: &str, value_type: &str) -> CoercionErrorKind {
+        coerce(value, value_type).unwrap_err().kind
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(coerce("1", "Int").unwrap(), "1");
+        assert_eq!(coerce("1", "Float").unwrap(), "1.0");
+        assert_eq!(coerce("1.5", "Float").unwrap(), "1.5");
+        assert_eq!(coerce("1", "ID").unwrap(), "\"1\"");
+        assert_eq!(coerce("\"x\"", "ID!").unwrap(), "\"x\"");
+        assert_eq!(coerce("true", "Boolean").unwrap(), "true");
+        assert_eq!(coerce("{any: [1]}", "Date").unwrap(), "{any: [1]}");
+        assert_eq!(coerce("$v", "Int!").unwrap(), "$v");
+        assert_eq!(
+            kind("2147483648", "Int"),
+            CoercionErrorKind::InvalidValue {
+                expected: "Int".into(),
+                found: "2147483648".into(),
+            }
+        );
+        assert!(matches!(
+            kind("1.5", "Int"),
+            CoercionErrorKind::InvalidValue { .. }
+        ));
+        assert!(matches!(
+            kind("\"true\"", "Boolean"),
+            CoercionErrorKind::InvalidValue { .. }
+        ));
+        assert_eq!(
+            kind("1", "Nope"),
+            CoercionErrorKind::UnknownType("Nope".into())
+        );
+        assert_eq!(
+            kind("1", "Query"),
+            CoercionErrorKind::NotInputType("Query".into())
+        );
+    }
+
+    #[test]
+    fn null_and_lists() {
+        assert_eq!(coerce("null", "Int").unwrap(), "null");
+        assert_eq!(
+            kind("null", "[Int]!"),
+            CoercionErrorKind::NullValue("[Int]!".into())
+        );
+        assert_eq!(coerce("1", "[Int]").unwrap(), "[1]");
+        assert_eq!(coerce("1", "[[Int]]").unwrap(), "[[1]]");
+        assert_eq!(coerce("[1, null]", "[Int]").unwrap(), "[1, null]");
+        let err = coerce("[[1], [2, null]]", "[[Int!]]").unwrap_err();
+        assert_eq!(
+            err.path,
+            [InputPathSegment::Index(1), InputPathSegment::Index(1)]
+        );
+        assert_eq!(
+            err.to_string(),
+            "null is not allowed for non-null type `Int!` at [1][1]"
+        );
+    }
+
+    #[test]
+    fn enums_and_input_objects() {
+        assert_eq!(coerce("RED", "Color").unwrap(), "RED");
+        assert!(matches!(
+            kind("BLUE", "Color"),
+            CoercionErrorKind::InvalidValue { .. }
+        ));
+        assert!(matches!(
+            kind("\"RED\"", "Color"),
+            CoercionErrorKind::InvalidValue { .. }
+        ));
+        assert_eq!(
+            coerce("{since: 1, color: GREEN}", "Filter").unwrap(),
+            "{color: GREEN, tags: [], since: 1}"
+        );
+        assert_eq!(
+            coerce(
+                "{color: RED, tags: \"a\", inner: {color: RED, tags: null}}",
+                "Filter"
+            )
+            .unwrap(),
+            "{color: RED, tags: [\"a\"], inner: {color: RED, tags: null}}"
+        );
+        assert_eq!(
+            kind("{tags: []}", "Filter"),
+            CoercionErrorKind::MissingField {
+                type_name: "Filter".into(),
+                field: "color".into(),
+                field_type: "Color!".into(),
+            }
+        );
+        assert_eq!(
+            kind("{color: RED, size: 1}", "Filter"),
+            CoercionErrorKind::UnknownField {
+                type_name: "Filter".into(),
+                field: "size".into(),
+            }
+        );
+        let err = coerce("{color: RED, inner: {color: RED, tags: [null]}}", "Filter").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "null is not allowed for non-null type `String!` at inner.tags[0]"
+        );
+        assert_eq!(coerce("[]", "Filter").map_err(|e| e.path), Err(vec![]));
+        let err = coerce("{color: RED, size: 1}", "Filter").unwrap_err();
+        assert_eq!(
+            err.spec_reference().unwrap().to_string(),
+            "§5.6.2 Input Object Field Names"
+        );
+        assert_eq!(coerce("1", "Nope").unwrap_err().spec_reference(), None);
+    }
+
+    #[test]
+    fn extensions() {
+        let schema = parse_schema::<&str>(
+            "enum Color { RED }
+            extend enum Color { BLUE }
+            input F { a: Int }
+            extend input F { b: Int! }
+            extend scalar Date @specifiedBy(url: \"x\")",
+        )
+        .unwrap();
+        let coerce = |value: &str, value_type: &str| {
+            let value = parse_value::<&str>(value).unwrap();
+            let value_type = parse_type::<&str>(value_type).unwrap();
+            coerce_value(&value, &value_type, &schema).map(|v| v.to_string())
+        };
+        assert_eq!(coerce("BLUE", "Color").unwrap(), "BLUE");
+        assert_eq!(coerce("{b: 1}", "F").unwrap(), "{b: 1}");
+        assert_eq!(
+            coerce("{a: 1}", "F").unwrap_err().kind,
+            CoercionErrorKind::MissingField {
+                type_name: "F".into(),
+                field: "b".into(),
+                field_type: "Int!".into(),
+            }
+        );
+        assert_eq!(
+            coerce("1", "Date").unwrap_err().kind,
+            CoercionErrorKind::UnknownType("Date".into())
+        );
+    }
+}
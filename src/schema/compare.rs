@@ -0,0 +1,253 @@
+//! Semantic comparison of schema documents
+//!
+use std::collections::HashSet;
+
+use crate::common::{Directive, Text};
+use crate::schema::ast::*;
+
+/// Compares schema documents by what they define, not how it is written
+///
+/// Order of definitions, fields, arguments, enum values, implemented
+/// interfaces, union members and directives doesn't matter, neither do
+/// descriptions, comments or formatting. Repeated directives of the same
+/// name are still compared in order.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_schema;
+/// use graphql_parser::schema::Equivalence;
+///
+/// let old = parse_schema::<&str>(r#"
+///     type Query { users: [User] @cost(weight: 2) }
+///     "A user" type User { id: ID! name: String }
+/// "#).unwrap();
+/// let new = parse_schema::<&str>(r#"
+///     type User { name: String, id: ID! }
+///     type Query { users: [User] }
+/// "#).unwrap();
+/// assert!(!Equivalence::new().equal(&old, &new));
+/// assert!(Equivalence::new().ignore_directive("cost").equal(&old, &new));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Equivalence {
+    ignored_directives: HashSet<String>,
+}
+
+impl Equivalence {
+    pub fn new() -> Equivalence {
+        Equivalence::default()
+    }
+
+    /// Ignores uses and the definition of the directive
+    pub fn ignore_directive(&mut self, name: &str) -> &mut Self {
+        self.ignored_directives.insert(name.to_string());
+        self
+    }
+
+    /// Returns true if both documents define the same schema
+    pub fn equal<'a, 'b, T, U>(&self, left: &Document<'a, T>, right: &Document<'b, U>) -> bool
+    where
+        T: Text<'a> + Clone,
+        U: Text<'b> + Clone,
+    {
+        self.canonical(left) == self.canonical(right)
+    }
+
+    /// Returns the canonical text of the document
+    ///
+    /// Documents which are equal under this comparison have the same
+    /// canonical text, so it can be stored or diffed instead of the
+    /// document.
+    pub fn canonical<'a, T>(&self, doc: &Document<'a, T>) -> String
+    where
+        T: Text<'a> + Clone,
+    {
+        let mut definitions: Vec<String> = doc
+            .definitions
+            .iter()
+            .filter(|def| match *def {
+                Definition::DirectiveDefinition(ref dir) => !self.is_ignored(dir.name.as_ref()),
+                _ => true,
+            })
+            .map(|def| {
+                let mut def = def.clone();
+                self.normalize(&mut def);
+                def.to_string()
+            })
+            .collect();
+        definitions.sort();
+        definitions.concat()
+    }
+
+    fn is_ignored(&self, name: &str) -> bool {
+        self.ignored_directives.contains(name)
+    }
+
+    fn normalize<'a, T: Text<'a>>(&self, def: &mut Definition<'a, T>) {
+        match *def {
+            Definition::SchemaDefinition(ref mut schema) => {
+                self.directives(&mut schema.directives);
+            }
+            Definition::TypeDefinition(ref mut def) => match *def {
+                TypeDefinition::Scalar(ref mut scalar) => {
+                    scalar.description = None;
+                    self.directives(&mut scalar.directives);
+                }
+                TypeDefinition::Object(ref mut object) => {
+                    object.description = None;
+                    object.implements_interfaces.sort();
+                    self.directives(&mut object.directives);
+                    self.fields(&mut object.fields);
+                }
+                TypeDefinition::Interface(ref mut iface) => {
+                    iface.description = None;
+                    iface.implements_interfaces.sort();
+                    self.directives(&mut iface.directives);
+                    self.fields(&mut iface.fields);
+                }
+                TypeDefinition::Union(ref mut union) => {
+                    union.description = None;
+                    union.types.sort();
+                    self.directives(&mut union.directives);
+                }
+                TypeDefinition::Enum(ref mut enum_) => {
+                    enum_.description = None;
+                    self.directives(&mut enum_.directives);
+                    self.enum_values(&mut enum_.values);
+                }
+                TypeDefinition::InputObject(ref mut input) => {
+                    input.description = None;
+                    self.directives(&mut input.directives);
+                    self.input_values(&mut input.fields);
+                }
+            },
+            Definition::TypeExtension(ref mut ext) => match *ext {
+                TypeExtension::Scalar(ref mut scalar) => {
+                    self.directives(&mut scalar.directives);
+                }
+                TypeExtension::Object(ref mut object) => {
+                    object.implements_interfaces.sort();
+                    self.directives(&mut object.directives);
+                    self.fields(&mut object.fields);
+                }
+                TypeExtension::Interface(ref mut iface) => {
+                    iface.implements_interfaces.sort();
+                    self.directives(&mut iface.directives);
+                    self.fields(&mut iface.fields);
+                }
+                TypeExtension::Union(ref mut union) => {
+                    union.types.sort();
+                    self.directives(&mut union.directives);
+                }
+                TypeExtension::Enum(ref mut enum_) => {
+                    self.directives(&mut enum_.directives);
+                    self.enum_values(&mut enum_.values);
+                }
+                TypeExtension::InputObject(ref mut input) => {
+                    self.directives(&mut input.directives);
+                    self.input_values(&mut input.fields);
+                }
+            },
+            Definition::DirectiveDefinition(ref mut dir) => {
+                dir.description = None;
+                dir.locations.sort_by_key(|loc| loc.as_str());
+                dir.locations.dedup();
+                self.input_values(&mut dir.arguments);
+            }
+        }
+    }
+
+    fn directives<'a, T: Text<'a>>(&self, directives: &mut Vec<Directive<'a, T>>) {
+        directives.retain(|dir| !self.is_ignored(dir.name.as_ref()));
+        for dir in directives.iter_mut() {
+            dir.arguments.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        directives.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn fields<'a, T: Text<'a>>(&self, fields: &mut [Field<'a, T>]) {
+        for field in fields.iter_mut() {
+            field.description = None;
+            self.directives(&mut field.directives);
+            self.input_values(&mut field.arguments);
+        }
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn input_values<'a, T: Text<'a>>(&self, values: &mut [InputValue<'a, T>]) {
+        for value in values.iter_mut() {
+            value.description = None;
+            self.directives(&mut value.directives);
+        }
+        values.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn enum_values<'a, T: Text<'a>>(&self, values: &mut [EnumValue<'a, T>]) {
+        for value in values.iter_mut() {
+            value.description = None;
+            self.directives(&mut value.directives);
+        }
+        values.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Equivalence;
+    use crate::parse_schema;
+
+    fn equal(left: &str, right: &str) -> bool {
+        let left = parse_schema::<String>(left).unwrap();
+        let right = parse_schema::<&str>(right).unwrap();
+        Equivalence::new().equal(&left, &right)
+    }
+
+    #[test]
+    fn ordering_and_descriptions() {
+        assert!(equal(
+            r#"
+            schema @a(x: 1, y: 2) { query: Q }
+            "Query" type Q implements A & B { f("arg" b: Int, a: Int): E @d }
+            enum E { ONE TWO }
+            union U = A | B
+            directive @d("x" x: Int) on FIELD_DEFINITION | OBJECT
+            "#,
+            r#"
+            directive @d(x: Int) on OBJECT | FIELD_DEFINITION
+            union U = B | A
+            enum E { "second" TWO ONE }
+            type Q implements B & A {
+              "field"
+              f(a: Int, b: Int): E @d
+            }
+            schema @a(y: 2, x: 1) { query: Q }
+            "#
+        ));
+    }
+
+    #[test]
+    fn differences() {
+        assert!(!equal("type Q { f: Int }", "type Q { f: Int! }"));
+        assert!(!equal(
+            "type Q { f(a: Int = 1): Int }",
+            "type Q { f(a: Int = 2): Int }"
+        ));
+        assert!(!equal("type Q { f: Int }", "extend type Q { f: Int }"));
+        assert!(!equal("enum E { A B }", "enum E { A }"));
+        assert!(!equal("type Q { f: Int @a }", "type Q { f: Int }"));
+    }
+
+    #[test]
+    fn ignored_directives() {
+        let left = parse_schema::<&str>(
+            "directive @internal on FIELD_DEFINITION type Q { f: Int @internal @key }",
+        )
+        .unwrap();
+        let right = parse_schema::<&str>("type Q { f: Int @key }").unwrap();
+        let mut eq = Equivalence::new();
+        assert!(!eq.equal(&left, &right));
+        eq.ignore_directive("internal");
+        assert!(eq.equal(&left, &right));
+        assert_eq!(eq.canonical(&left), eq.canonical(&right));
+    }
+}
@@ -0,0 +1,359 @@
+//! Schema coordinates, which name elements of a schema
+//!
+//! Coordinates are defined by the [Schema Coordinates RFC][rfc]:
+//!
+//! * `Type` names a type
+//! * `Type.field` names a field, an input field or an enum value
+//! * `Type.field(arg:)` names an argument of a field
+//! * `@directive` names a directive
+//! * `@directive(arg:)` names an argument of a directive
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::parse_schema;
+//! use graphql_parser::schema::{SchemaCoordinate, SchemaElement};
+//!
+//! let schema = parse_schema::<&str>("type Query { user(id: ID!): String }").unwrap();
+//! let coordinate: SchemaCoordinate = "Query.user(id:)".parse().unwrap();
+//! match coordinate.lookup(&schema) {
+//!     Some(SchemaElement::InputValue(arg)) => assert_eq!(arg.name, "id"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! [rfc]: https://github.com/graphql/graphql-wg/blob/main/rfcs/SchemaCoordinates.md
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::schema::ast::*;
+
+/// Name of a type, field, argument, enum value or directive in a schema
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SchemaCoordinate {
+    /// `Type`
+    Type(String),
+    /// `Type.member`, where the member is a field, an input field or an
+    /// enum value
+    Member { type_name: String, member: String },
+    /// `Type.field(argument:)`
+    Argument {
+        type_name: String,
+        field: String,
+        argument: String,
+    },
+    /// `@directive`
+    Directive(String),
+    /// `@directive(argument:)`
+    DirectiveArgument { directive: String, argument: String },
+}
+
+/// An element of a schema document named by a coordinate
+#[derive(Debug, Clone, Copy)]
+pub enum SchemaElement<'d, 'a, T: Text<'a>> {
+    Type(&'d TypeDefinition<'a, T>),
+    Field(&'d Field<'a, T>),
+    /// An argument or an input field
+    InputValue(&'d InputValue<'a, T>),
+    EnumValue(&'d EnumValue<'a, T>),
+    Directive(&'d DirectiveDefinition<'a, T>),
+}
+
+/// Error parsing a schema coordinate
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("invalid schema coordinate at byte {offset}")]
+pub struct InvalidCoordinate {
+    /// Byte offset of the first character which doesn't fit the grammar
+    pub offset: usize,
+}
+
+impl SchemaCoordinate {
+    /// Finds the definition of the element the coordinate names
+    ///
+    /// Only definitions are searched, not extensions. Returns `None` if
+    /// the element isn't defined in the document.
+    pub fn lookup<'d, 'a, T>(
+        &self,
+        document: &'d Document<'a, T>,
+    ) -> Option<SchemaElement<'d, 'a, T>>
+    where
+        T: Text<'a>,
+    {
+        match *self {
+            SchemaCoordinate::Type(ref name) => find_type(document, name).map(SchemaElement::Type),
+            SchemaCoordinate::Member {
+                ref type_name,
+                ref member,
+            } => match find_type(document, type_name)? {
+                TypeDefinition::Object(ObjectType { ref fields, .. })
+                | TypeDefinition::Interface(InterfaceType { ref fields, .. }) => {
+                    find(fields, |f| &f.name, member).map(SchemaElement::Field)
+                }
+                TypeDefinition::InputObject(ref input) => {
+                    find(&input.fields, |f| &f.name, member).map(SchemaElement::InputValue)
+                }
+                TypeDefinition::Enum(ref enum_type) => {
+                    find(&enum_type.values, |v| &v.name, member).map(SchemaElement::EnumValue)
+                }
+                TypeDefinition::Scalar(_) | TypeDefinition::Union(_) => None,
+            },
+            SchemaCoordinate::Argument {
+                ref type_name,
+                ref field,
+                ref argument,
+            } => {
+                let fields = match find_type(document, type_name)? {
+                    TypeDefinition::Object(ObjectType { ref fields, .. })
+                    | TypeDefinition::Interface(InterfaceType { ref fields, .. }) => fields,
+                    _ => return None,
+                };
+                let field = find(fields, |f| &f.name, field)?;
+                find(&field.arguments, |a| &a.name, argument).map(SchemaElement::InputValue)
+            }
+            SchemaCoordinate::Directive(ref name) => {
+                find_directive(document, name).map(SchemaElement::Directive)
+            }
+            SchemaCoordinate::DirectiveArgument {
+                ref directive,
+                ref argument,
+            } => {
+                let directive = find_directive(document, directive)?;
+                find(&directive.arguments, |a| &a.name, argument).map(SchemaElement::InputValue)
+            }
+        }
+    }
+}
+
+fn find<'d, I, V, F>(items: &'d [I], name: F, wanted: &str) -> Option<&'d I>
+where
+    V: AsRef<str> + 'd,
+    F: Fn(&'d I) -> &'d V,
+{
+    items.iter().find(|item| name(item).as_ref() == wanted)
+}
+
+pub(crate) fn find_type<'d, 'a, T>(
+    document: &'d Document<'a, T>,
+    wanted: &str,
+) -> Option<&'d TypeDefinition<'a, T>>
+where
+    T: Text<'a>,
+{
+    document.definitions.iter().find_map(|d| match *d {
+        Definition::TypeDefinition(ref t) if t.name() == wanted => Some(t),
+        _ => None,
+    })
+}
+
+fn find_directive<'d, 'a, T>(
+    document: &'d Document<'a, T>,
+    wanted: &str,
+) -> Option<&'d DirectiveDefinition<'a, T>>
+where
+    T: Text<'a>,
+{
+    document.definitions.iter().find_map(|d| match *d {
+        Definition::DirectiveDefinition(ref d) if d.name.as_ref() == wanted => Some(d),
+        _ => None,
+    })
+}
+
+/// Cursor over the text of a coordinate
+struct Scanner<'s> {
+    text: &'s str,
+    offset: usize,
+}
+
+impl<'s> Scanner<'s> {
+    fn error(&self) -> InvalidCoordinate {
+        InvalidCoordinate {
+            offset: self.offset,
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.text[self.offset..].starts_with(c) {
+            self.offset += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), InvalidCoordinate> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn name(&mut self) -> Result<String, InvalidCoordinate> {
+        let rest = &self.text[self.offset..];
+        let len = rest
+            .bytes()
+            .enumerate()
+            .take_while(|&(idx, b)| {
+                b == b'_' || b.is_ascii_alphabetic() || (idx > 0 && b.is_ascii_digit())
+            })
+            .count();
+        if len == 0 {
+            return Err(self.error());
+        }
+        self.offset += len;
+        Ok(rest[..len].to_string())
+    }
+
+    /// Parses `(name:)`
+    fn argument(&mut self) -> Result<Option<String>, InvalidCoordinate> {
+        if !self.eat('(') {
+            return Ok(None);
+        }
+        let name = self.name()?;
+        self.expect(':')?;
+        self.expect(')')?;
+        Ok(Some(name))
+    }
+}
+
+impl FromStr for SchemaCoordinate {
+    type Err = InvalidCoordinate;
+    fn from_str(s: &str) -> Result<SchemaCoordinate, InvalidCoordinate> {
+        let mut scanner = Scanner { text: s, offset: 0 };
+        let coordinate = if scanner.eat('@') {
+            let directive = scanner.name()?;
+            match scanner.argument()? {
+                Some(argument) => SchemaCoordinate::DirectiveArgument {
+                    directive,
+                    argument,
+                },
+                None => SchemaCoordinate::Directive(directive),
+            }
+        } else {
+            let type_name = scanner.name()?;
+            if scanner.eat('.') {
+                let member = scanner.name()?;
+                match scanner.argument()? {
+                    Some(argument) => SchemaCoordinate::Argument {
+                        type_name,
+                        field: member,
+                        argument,
+                    },
+                    None => SchemaCoordinate::Member { type_name, member },
+                }
+            } else {
+                SchemaCoordinate::Type(type_name)
+            }
+        };
+        if scanner.offset != s.len() {
+            return Err(scanner.error());
+        }
+        Ok(coordinate)
+    }
+}
+
+impl fmt::Display for SchemaCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaCoordinate::Type(ref name) => f.write_str(name),
+            SchemaCoordinate::Member {
+                ref type_name,
+                ref member,
+            } => write!(f, "{}.{}", type_name, member),
+            SchemaCoordinate::Argument {
+                ref type_name,
+                ref field,
+                ref argument,
+            } => write!(f, "{}.{}({}:)", type_name, field, argument),
+            SchemaCoordinate::Directive(ref name) => write!(f, "@{}", name),
+            SchemaCoordinate::DirectiveArgument {
+                ref directive,
+                ref argument,
+            } => write!(f, "@{}({}:)", directive, argument),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InvalidCoordinate, SchemaCoordinate, SchemaElement};
+    use crate::schema::parse_schema;
+
+    fn parse(s: &str) -> Result<SchemaCoordinate, InvalidCoordinate> {
+        s.parse()
+    }
+
+    #[test]
+    fn roundtrip() {
+        for text in &[
+            "Query",
+            "Query.user",
+            "Query.user(id:)",
+            "@deprecated",
+            "@deprecated(reason:)",
+            "_T1._f2(_a3:)",
+        ] {
+            assert_eq!(parse(text).unwrap().to_string(), *text);
+        }
+        assert_eq!(
+            parse("Query.user(id:)").unwrap(),
+            SchemaCoordinate::Argument {
+                type_name: "Query".into(),
+                field: "user".into(),
+                argument: "id".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse("").unwrap_err().offset, 0);
+        assert_eq!(parse("1Query").unwrap_err().offset, 0);
+        assert_eq!(parse("Query.").unwrap_err().offset, 6);
+        assert_eq!(parse("Query.user(id)").unwrap_err().offset, 13);
+        assert_eq!(parse("Query .user").unwrap_err().offset, 5);
+        assert_eq!(parse("@skip.if").unwrap_err().offset, 5);
+        assert_eq!(parse("Query.user(id:).x").unwrap_err().offset, 15);
+    }
+
+    #[test]
+    fn lookup() {
+        let schema = parse_schema::<String>(
+            "type Query { user(id: ID!): User }
+             interface Node { id: ID! }
+             input Filter { name: String }
+             enum Role { ADMIN USER }
+             scalar Date
+             directive @cost(weight: Int) on FIELD_DEFINITION",
+        )
+        .unwrap();
+        let lookup = |text: &str| parse(text).unwrap().lookup(&schema);
+        assert!(matches!(lookup("Date"), Some(SchemaElement::Type(_))));
+        assert!(matches!(lookup("Node.id"), Some(SchemaElement::Field(f)) if f.name == "id"));
+        assert!(matches!(
+            lookup("Filter.name"),
+            Some(SchemaElement::InputValue(f)) if f.name == "name"
+        ));
+        assert!(matches!(
+            lookup("Role.ADMIN"),
+            Some(SchemaElement::EnumValue(v)) if v.name == "ADMIN"
+        ));
+        assert!(matches!(
+            lookup("Query.user(id:)"),
+            Some(SchemaElement::InputValue(a)) if a.name == "id"
+        ));
+        assert!(matches!(lookup("@cost"), Some(SchemaElement::Directive(_))));
+        assert!(matches!(
+            lookup("@cost(weight:)"),
+            Some(SchemaElement::InputValue(a)) if a.name == "weight"
+        ));
+        assert!(lookup("User").is_none());
+        assert!(lookup("Query.users").is_none());
+        assert!(lookup("Query.user(name:)").is_none());
+        assert!(lookup("Date.x").is_none());
+        assert!(lookup("Filter.name(x:)").is_none());
+        assert!(lookup("@skip").is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Listing of the deprecated elements of a schema
+//!
+use crate::common::Text;
+use crate::directives::HasDirectives;
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::coordinate::SchemaCoordinate;
+
+/// An element of a schema marked with `@deprecated`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Deprecation {
+    pub coordinate: SchemaCoordinate,
+    /// The reason given, or the default reason of the directive
+    pub reason: String,
+    /// Position of the deprecated element
+    pub position: Pos,
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the deprecated fields, arguments, input fields and enum
+    /// values, in the order of the document
+    ///
+    /// Both definitions and extensions are searched, so elements added by
+    /// an extension are reported with the coordinate of the extended type.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_schema;
+    ///
+    /// let schema = parse_schema::<&str>(r#"
+    ///     type Query { user(id: ID, login: String @deprecated): User }
+    ///     enum Role { ADMIN, OWNER @deprecated(reason: "Use ADMIN.") }
+    /// "#).unwrap();
+    /// let deprecated = schema
+    ///     .deprecations()
+    ///     .map(|d| format!("{}: {}", d.coordinate, d.reason))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(deprecated, [
+    ///     "Query.user(login:): No longer supported",
+    ///     "Role.OWNER: Use ADMIN.",
+    /// ]);
+    /// ```
+    pub fn deprecations(&self) -> impl Iterator<Item = Deprecation> {
+        let mut deprecations = Vec::new();
+        for definition in &self.definitions {
+            match *definition {
+                Definition::TypeDefinition(ref definition) => match *definition {
+                    TypeDefinition::Object(ref t) => fields(&t.name, &t.fields, &mut deprecations),
+                    TypeDefinition::Interface(ref t) => {
+                        fields(&t.name, &t.fields, &mut deprecations)
+                    }
+                    TypeDefinition::Enum(ref t) => {
+                        enum_values(&t.name, &t.values, &mut deprecations)
+                    }
+                    TypeDefinition::InputObject(ref t) => {
+                        input_fields(&t.name, &t.fields, &mut deprecations)
+                    }
+                    TypeDefinition::Scalar(_) | TypeDefinition::Union(_) => {}
+                },
+                Definition::TypeExtension(ref extension) => match *extension {
+                    TypeExtension::Object(ref t) => fields(&t.name, &t.fields, &mut deprecations),
+                    TypeExtension::Interface(ref t) => {
+                        fields(&t.name, &t.fields, &mut deprecations)
+                    }
+                    TypeExtension::Enum(ref t) => {
+                        enum_values(&t.name, &t.values, &mut deprecations)
+                    }
+                    TypeExtension::InputObject(ref t) => {
+                        input_fields(&t.name, &t.fields, &mut deprecations)
+                    }
+                    TypeExtension::Scalar(_) | TypeExtension::Union(_) => {}
+                },
+                Definition::DirectiveDefinition(ref directive) => {
+                    for argument in &directive.arguments {
+                        push(&mut deprecations, argument, argument.position, || {
+                            SchemaCoordinate::DirectiveArgument {
+                                directive: directive.name.as_ref().to_string(),
+                                argument: argument.name.as_ref().to_string(),
+                            }
+                        });
+                    }
+                }
+                Definition::SchemaDefinition(_) => {}
+            }
+        }
+        deprecations.into_iter()
+    }
+}
+
+fn push<'a, T, N, F>(deprecations: &mut Vec<Deprecation>, node: &N, position: Pos, coordinate: F)
+where
+    T: Text<'a>,
+    N: HasDirectives<'a, T>,
+    F: FnOnce() -> SchemaCoordinate,
+{
+    if let Some(deprecated) = node.deprecated() {
+        deprecations.push(Deprecation {
+            coordinate: coordinate(),
+            reason: deprecated.reason().to_string(),
+            position,
+        });
+    }
+}
+
+fn member(type_name: &str, member: &str) -> SchemaCoordinate {
+    SchemaCoordinate::Member {
+        type_name: type_name.to_string(),
+        member: member.to_string(),
+    }
+}
+
+fn fields<'a, T: Text<'a>>(
+    type_name: &T::Value,
+    fields: &[Field<'a, T>],
+    deprecations: &mut Vec<Deprecation>,
+) {
+    let type_name = type_name.as_ref();
+    for field in fields {
+        push(deprecations, field, field.position, || {
+            member(type_name, field.name.as_ref())
+        });
+        for argument in &field.arguments {
+            push(deprecations, argument, argument.position, || {
+                SchemaCoordinate::Argument {
+                    type_name: type_name.to_string(),
+                    field: field.name.as_ref().to_string(),
+                    argument: argument.name.as_ref().to_string(),
+                }
+            });
+        }
+    }
+}
+
+fn input_fields<'a, T: Text<'a>>(
+    type_name: &T::Value,
+    fields: &[InputValue<'a, T>],
+    deprecations: &mut Vec<Deprecation>,
+) {
+    for field in fields {
+        push(deprecations, field, field.position, || {
+            member(type_name.as_ref(), field.name.as_ref())
+        });
+    }
+}
+
+fn enum_values<'a, T: Text<'a>>(
+    type_name: &T::Value,
+    values: &[EnumValue<'a, T>],
+    deprecations: &mut Vec<Deprecation>,
+) {
+    for value in values {
+        push(deprecations, value, value.position, || {
+            member(type_name.as_ref(), value.name.as_ref())
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse_schema;
+
+    #[test]
+    fn deprecations() {
+        let schema = parse_schema::<&str>(
+            r#"
+            type A { a: Int @deprecated(reason: "a") b: Int }
+            extend type A { c(x: Int @deprecated): Int }
+            interface I { i: Int @deprecated }
+            input In { f: Int @deprecated(reason: null) }
+            directive @d(arg: Int @deprecated) on FIELD
+            scalar S @deprecated
+            "#,
+        )
+        .unwrap();
+        let deprecations = schema
+            .deprecations()
+            .map(|d| format!("{} {} {}", d.position, d.coordinate, d.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            deprecations,
+            [
+                "2:22 A.a a",
+                "3:31 A.c(x:) No longer supported",
+                "4:27 I.i No longer supported",
+                "5:24 In.f No longer supported",
+                "6:26 @d(arg:) No longer supported",
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::io;
 
 use crate::common::Text;
 use crate::format::{format_directives, Displayable, Formatter, Style};
@@ -11,17 +12,29 @@ where
 {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        crate::format::to_string(self, style)
+    }
+
+    /// Format a document according to style into `out`, without building
+    /// the whole text in memory first
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        crate::format::format_to(self, style, out)
     }
-}
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+    /// Format a document according to style into a file, socket or other
+    /// `io::Write`
+    ///
+    /// The text is written in many small pieces, so unbuffered writers
+    /// should be wrapped in a `BufWriter`.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: W) -> io::Result<()> {
+        crate::format::write_to(self, style, out)
+    }
+
+    /// Format a document on a single line with as little whitespace as
+    /// possible, for persisted queries or sending over the network
+    pub fn format_minified(&self) -> String {
+        crate::format::to_string(self, Style::default().minify(true))
+    }
 }
 
 fn description<'a>(description: &Option<String>, f: &mut Formatter) {
@@ -70,19 +83,22 @@ where
         f.start_block();
         if let Some(ref q) = self.query {
             f.indent();
-            f.write("query: ");
+            f.write("query");
+            f.colon();
             f.write(q.as_ref());
             f.endline();
         }
         if let Some(ref m) = self.mutation {
             f.indent();
-            f.write("mutation: ");
+            f.write("mutation");
+            f.colon();
             f.write(m.as_ref());
             f.endline();
         }
         if let Some(ref s) = self.subscription {
             f.indent();
-            f.write("subscription: ");
+            f.write("subscription");
+            f.colon();
             f.write(s.as_ref());
             f.endline();
         }
@@ -202,7 +218,7 @@ where
             f.write(" ");
         }
         f.write(self.name.as_ref());
-        f.write(": ");
+        f.colon();
         self.value_type.display(f);
         if let Some(ref def) = self.default_value {
             f.write(" = ");
@@ -236,7 +252,7 @@ where
         f.indent();
         f.write(self.name.as_ref());
         format_arguments(&self.arguments, f);
-        f.write(": ");
+        f.colon();
         self.field_type.display(f);
         format_directives(&self.directives, f);
         f.endline();
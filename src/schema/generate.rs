@@ -0,0 +1,579 @@
+//! Generating random operations valid against a schema
+//!
+//! Load tests and resolver fuzzers need many different but valid
+//! operations. `OperationGenerator` walks the resolved schema from a root
+//! type and picks fields, arguments and inline fragments at random, so
+//! every generated operation passes validation against the schema.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::parse_schema;
+//! use graphql_parser::query::OperationKind;
+//! use graphql_parser::schema::{OperationGenerator, Schema};
+//!
+//! let doc = parse_schema::<&str>("
+//!     type Query { user(id: ID!): User }
+//!     type User { name: String friends(first: Int): [User] }
+//! ").unwrap();
+//! let schema = Schema::new(&doc).unwrap();
+//! let mut generator = OperationGenerator::new(&schema).seed(7).max_depth(3);
+//! let op = generator.generate(OperationKind::Query).unwrap();
+//! assert!(op.to_string().starts_with("query {\n  user(id: "));
+//! assert!(generator.generate(OperationKind::Mutation).is_none());
+//! ```
+use std::collections::HashSet;
+
+use crate::common::{StringValue, Text, Type, Value};
+use crate::query::{
+    FieldBuilder, InlineFragmentBuilder, OperationBuilder, OperationDefinition, OperationKind,
+};
+use crate::schema::ast::*;
+use crate::schema::resolve::Schema;
+
+/// Items of a generated list value, at most
+const MAX_LIST_ITEMS: u64 = 3;
+/// Nesting of generated input objects and lists before optional parts are
+/// left out
+const MAX_VALUE_DEPTH: usize = 4;
+
+/// Generator of random operations valid against a schema
+///
+/// Generation is deterministic: the same schema, options and seed always
+/// produce the same sequence of operations. Fields are selected without
+/// fragment definitions or directives, abstract types get inline
+/// fragments on their possible types, and a field selected twice in the
+/// same selection set is aliased, so fields never conflict.
+#[derive(Debug, Clone)]
+pub struct OperationGenerator<'s, 'a, T: Text<'a>> {
+    schema: &'s Schema<'a, T>,
+    max_depth: usize,
+    max_fields: usize,
+    argument_coverage: f64,
+    rng: Rng,
+}
+
+impl<'s, 'a, T: Text<'a>> OperationGenerator<'s, 'a, T> {
+    /// Creates a generator with a depth of 3, up to 4 fields per
+    /// selection set, half of the optional arguments and seed 0
+    pub fn new(schema: &'s Schema<'a, T>) -> OperationGenerator<'s, 'a, T> {
+        OperationGenerator {
+            schema,
+            max_depth: 3,
+            max_fields: 4,
+            argument_coverage: 0.5,
+            rng: Rng(0),
+        }
+    }
+
+    /// Sets the number of nested selection sets below the root, at most
+    ///
+    /// Composite fields aren't selected at the last level. At depth 0 only
+    /// the leaf fields of the root type are selected, except that a
+    /// subscription gets a depth of 1 if its root type has no leaf fields.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the number of fields selected in each selection set, at most
+    pub fn max_fields(mut self, max_fields: usize) -> Self {
+        self.max_fields = max_fields.max(1);
+        self
+    }
+
+    /// Sets the probability of passing each optional argument or input
+    /// object field, between 0 and 1
+    ///
+    /// Required arguments and fields are always passed. With 1 every
+    /// argument is passed, which exercises argument handling the most.
+    pub fn argument_coverage(mut self, coverage: f64) -> Self {
+        self.argument_coverage = coverage.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng(seed);
+        self
+    }
+
+    /// Generates an operation of the given kind
+    ///
+    /// `SelectionSet` generates a query in the shorthand form. Returns
+    /// `None` if the schema has no root type for the operation.
+    pub fn generate(
+        &mut self,
+        kind: OperationKind,
+    ) -> Option<OperationDefinition<'static, String>> {
+        let schema = self.schema;
+        let root = match kind {
+            OperationKind::SelectionSet | OperationKind::Query => schema.query_type()?,
+            OperationKind::Mutation => schema.mutation_type()?,
+            OperationKind::Subscription => schema.subscription_type()?,
+        };
+        let mut builder = OperationBuilder::new(kind);
+        if kind == OperationKind::Subscription {
+            // subscriptions select a single root field, which can't be
+            // an introspection field
+            let mut depth = self.max_depth;
+            let mut fields = self.candidates(&root.fields, depth);
+            if fields.is_empty() {
+                depth = 1;
+                fields = self.candidates(&root.fields, depth);
+            }
+            if let Some(&field) = fields.get(self.rng.below(fields.len() as u64) as usize) {
+                builder = builder.field(self.field(field, None, depth));
+            }
+        } else {
+            let mut names = HashSet::new();
+            for field in self.fields(&root.fields, self.max_depth, &mut names) {
+                builder = builder.field(field);
+            }
+        }
+        Some(builder.build())
+    }
+
+    /// Returns the fields which can be selected with `depth` levels left
+    fn candidates<'f>(&self, fields: &'f [Field<'a, T>], depth: usize) -> Vec<&'f Field<'a, T>> {
+        fields
+            .iter()
+            .filter(|field| depth > 0 || !self.is_composite(&field.field_type))
+            .collect()
+    }
+
+    /// Selects a random subset of `fields`, or `__typename` if none of
+    /// them can be selected
+    fn fields(
+        &mut self,
+        fields: &[Field<'a, T>],
+        depth: usize,
+        names: &mut HashSet<String>,
+    ) -> Vec<FieldBuilder<'static, String>> {
+        let mut candidates = self.candidates(fields, depth);
+        if candidates.is_empty() {
+            return vec![self.typename(names)];
+        }
+        let count = 1 + self.rng.below(self.max_fields.min(candidates.len()) as u64) as usize;
+        let mut result = Vec::with_capacity(count);
+        for idx in 0..count {
+            let pick = idx + self.rng.below((candidates.len() - idx) as u64) as usize;
+            candidates.swap(idx, pick);
+            let field = candidates[idx];
+            let alias = self.response_name(field.name.as_ref(), names);
+            result.push(self.field(field, alias, depth));
+        }
+        result
+    }
+
+    fn field(
+        &mut self,
+        field: &Field<'a, T>,
+        alias: Option<String>,
+        depth: usize,
+    ) -> FieldBuilder<'static, String> {
+        let mut builder = FieldBuilder::new(field.name.as_ref().to_string());
+        if let Some(alias) = alias {
+            builder = builder.alias(alias);
+        }
+        for argument in &field.arguments {
+            if let Some(value) = self.argument(argument, 0) {
+                builder = builder.argument(argument.name.as_ref().to_string(), value);
+            }
+        }
+        if self.is_composite(&field.field_type) {
+            let mut names = HashSet::new();
+            builder = self.selection_set(
+                builder,
+                field.field_type.innermost_name(),
+                depth - 1,
+                &mut names,
+            );
+        }
+        builder
+    }
+
+    /// Fills the selection set of `builder`, selecting from the type
+    /// `type_name`
+    fn selection_set(
+        &mut self,
+        mut builder: FieldBuilder<'static, String>,
+        type_name: &str,
+        depth: usize,
+        names: &mut HashSet<String>,
+    ) -> FieldBuilder<'static, String> {
+        let schema = self.schema;
+        let (fields, possible_types) = match schema.type_by_name(type_name) {
+            Some(TypeDefinition::Object(object)) => (&object.fields[..], Vec::new()),
+            Some(TypeDefinition::Interface(interface)) => (
+                &interface.fields[..],
+                schema
+                    .implementers(type_name)
+                    .filter_map(|definition| match definition {
+                        TypeDefinition::Object(object) => Some(object),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            Some(TypeDefinition::Union(union)) => (
+                &[][..],
+                union
+                    .types
+                    .iter()
+                    .filter_map(|name| match schema.type_by_name(name.as_ref()) {
+                        Some(TypeDefinition::Object(object)) => Some(object),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => (&[][..], Vec::new()),
+        };
+        // unions only have fragments, interfaces have fragments half of
+        // the time
+        let fragments = !possible_types.is_empty() && (fields.is_empty() || self.rng.below(2) == 0);
+        if !fields.is_empty() || !fragments {
+            for field in self.fields(fields, depth, names) {
+                builder = builder.field(field);
+            }
+        }
+        if fragments {
+            let count = 1 + self.rng.below(possible_types.len().min(2) as u64) as usize;
+            for _ in 0..count {
+                let object = possible_types[self.rng.below(possible_types.len() as u64) as usize];
+                let mut fragment =
+                    InlineFragmentBuilder::new(Some(object.name.as_ref().to_string()));
+                for field in self.fields(&object.fields, depth, names) {
+                    fragment = fragment.field(field);
+                }
+                builder = builder.selection(fragment.build());
+            }
+        }
+        builder
+    }
+
+    fn typename(&mut self, names: &mut HashSet<String>) -> FieldBuilder<'static, String> {
+        let alias = self.response_name("__typename", names);
+        let builder = FieldBuilder::new("__typename".to_string());
+        match alias {
+            Some(alias) => builder.alias(alias),
+            None => builder,
+        }
+    }
+
+    /// Records the response name of a field, returning an alias if the
+    /// name is already taken in the selection set
+    fn response_name(&mut self, name: &str, names: &mut HashSet<String>) -> Option<String> {
+        if names.insert(name.to_string()) {
+            return None;
+        }
+        let alias = (2..)
+            .map(|n| format!("{}{}", name.trim_start_matches('_'), n))
+            .find(|alias| !names.contains(alias))
+            .expect("some alias is free");
+        names.insert(alias.clone());
+        Some(alias)
+    }
+
+    fn is_composite(&self, field_type: &Type<'a, T>) -> bool {
+        matches!(
+            self.schema.type_by_name(field_type.innermost_name()),
+            Some(TypeDefinition::Object(_))
+                | Some(TypeDefinition::Interface(_))
+                | Some(TypeDefinition::Union(_))
+        )
+    }
+
+    /// Returns the value to pass for an argument or input object field,
+    /// `None` if it's left out
+    fn argument(
+        &mut self,
+        input: &InputValue<'a, T>,
+        depth: usize,
+    ) -> Option<Value<'static, String>> {
+        let required = input.value_type.is_non_null() && input.default_value.is_none();
+        if !required && (depth >= MAX_VALUE_DEPTH || !self.rng.chance(self.argument_coverage)) {
+            return None;
+        }
+        Some(self.value(&input.value_type, depth))
+    }
+
+    fn value(&mut self, value_type: &Type<'a, T>, depth: usize) -> Value<'static, String> {
+        let inner: &Type<'a, T> = match *value_type {
+            Type::NonNullType(ref inner) => inner,
+            _ if depth >= MAX_VALUE_DEPTH || self.rng.below(8) == 0 => return Value::Null,
+            _ => value_type,
+        };
+        match *inner {
+            Type::ListType(ref item) | Type::NonNullType(ref item) => {
+                let count = if depth >= MAX_VALUE_DEPTH {
+                    0
+                } else {
+                    self.rng.below(MAX_LIST_ITEMS + 1)
+                };
+                Value::List((0..count).map(|_| self.value(item, depth + 1)).collect())
+            }
+            Type::NamedType(ref name) => self.named_value(name.as_ref(), depth),
+        }
+    }
+
+    fn named_value(&mut self, type_name: &str, depth: usize) -> Value<'static, String> {
+        let schema = self.schema;
+        match schema.type_by_name(type_name) {
+            Some(TypeDefinition::Enum(enum_type)) if !enum_type.values.is_empty() => {
+                let idx = self.rng.below(enum_type.values.len() as u64) as usize;
+                Value::Enum(enum_type.values[idx].name.as_ref().to_string())
+            }
+            Some(TypeDefinition::InputObject(input)) => Value::Object(
+                input
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        let value = self.argument(field, depth + 1)?;
+                        Some((field.name.as_ref().to_string(), value))
+                    })
+                    .collect(),
+            ),
+            _ => match type_name {
+                "Int" => Value::from(self.rng.below(2001) as i64 - 1000),
+                "Float" => Value::from((self.rng.below(200_001) as f64 - 100_000.0) / 100.0),
+                "Boolean" => Value::Boolean(self.rng.below(2) == 0),
+                "ID" if self.rng.below(2) == 0 => Value::from(self.rng.below(100_000) as i64),
+                // strings are accepted by ID and the usual custom scalars
+                _ => Value::String(StringValue::from(self.string())),
+            },
+        }
+    }
+
+    fn string(&mut self) -> String {
+        const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        let len = 1 + self.rng.below(8);
+        (0..len)
+            .map(|_| char::from(CHARS[self.rng.below(CHARS.len() as u64) as usize]))
+            .collect()
+    }
+}
+
+/// SplitMix64, which is good enough here and accepts any seed
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number below `n`, or 0 if `n` is 0
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        self.next() % n
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::OperationGenerator;
+    use crate::query::{self, OperationKind, Selection, SelectionSet};
+    use crate::schema::{coerce_value, Document, Schema, TypeDefinition};
+    use crate::{parse_query, parse_schema};
+
+    const SCHEMA: &str = r#"
+        schema { query: Query mutation: Mutation subscription: Subscription }
+        scalar Date
+        enum Role { ADMIN USER }
+        input Filter { role: Role!, since: Date, tags: [String!], inner: Filter }
+        interface Node { id: ID! }
+        interface Named implements Node { id: ID! name(upper: Boolean = false): String }
+        type User implements Named & Node {
+            id: ID!
+            name(upper: Boolean = false): String
+            friends(first: Int, filter: Filter): [User!]!
+            posts(after: ID, first: Int!): [Post]
+            search: [Result]
+        }
+        type Post implements Node { id: ID! title: String author: User score: Float }
+        type Empty { self: Empty }
+        union Result = User | Post
+        type Query {
+            node(id: ID!): Node
+            user(id: ID!): User
+            users(filter: Filter, ids: [[ID!]]): [User]
+            search(text: String!): [Result!]
+            empty: Empty
+            version: String
+        }
+        type Mutation { createPost(title: String!, at: Date): Post }
+        type Subscription { posted(author: ID): Post! }
+    "#;
+
+    /// Checks the selections of a generated operation against the schema,
+    /// returning the depth of the selection set
+    fn check<'a>(
+        doc: &Document<'a, &'a str>,
+        schema: &Schema<'a, &'a str>,
+        type_name: &str,
+        set: &SelectionSet<String>,
+        names: &mut HashSet<String>,
+    ) -> usize {
+        assert!(
+            !set.items.is_empty(),
+            "empty selection set on {}",
+            type_name
+        );
+        let mut depth = 0;
+        for item in &set.items {
+            match item {
+                Selection::Field(field) => {
+                    let name = field.alias.as_ref().unwrap_or(&field.name);
+                    assert!(names.insert(name.clone()), "{} selected twice", name);
+                    if field.name == "__typename" {
+                        continue;
+                    }
+                    let fields = match schema.type_by_name(type_name) {
+                        Some(TypeDefinition::Object(t)) => &t.fields,
+                        Some(TypeDefinition::Interface(t)) => &t.fields,
+                        _ => panic!("fields selected on {}", type_name),
+                    };
+                    let definition = fields.iter().find(|f| f.name == field.name).unwrap();
+                    for argument in &definition.arguments {
+                        let value = field
+                            .arguments
+                            .iter()
+                            .find(|(name, _)| name == argument.name)
+                            .map(|(_, value)| value.clone());
+                        match value {
+                            Some(value) => {
+                                coerce_value(&value, &argument.value_type, doc).unwrap();
+                            }
+                            None => assert!(
+                                !argument.value_type.is_non_null()
+                                    || argument.default_value.is_some(),
+                                "missing {}",
+                                argument.name
+                            ),
+                        }
+                    }
+                    assert!(field.arguments.len() <= definition.arguments.len());
+                    let inner = definition.field_type.innermost_name();
+                    let composite = !matches!(
+                        schema.type_by_name(inner),
+                        Some(TypeDefinition::Scalar(_)) | Some(TypeDefinition::Enum(_))
+                    );
+                    assert_eq!(composite, !field.selection_set.items.is_empty());
+                    if composite {
+                        let mut names = HashSet::new();
+                        let nested = check(doc, schema, inner, &field.selection_set, &mut names);
+                        depth = depth.max(1 + nested);
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let on = match fragment.type_condition {
+                        Some(query::TypeCondition::On(ref on)) => on,
+                        None => panic!("fragment without type condition"),
+                    };
+                    let possible = match schema.type_by_name(type_name) {
+                        Some(TypeDefinition::Union(u)) => u.types.contains(&on.as_str()),
+                        _ => schema.implementers(type_name).any(|t| t.name() == on),
+                    };
+                    assert!(possible, "{} is not a possible type of {}", on, type_name);
+                    depth = depth.max(check(doc, schema, on, &fragment.selection_set, names));
+                }
+                Selection::FragmentSpread(_) => panic!("unexpected fragment spread"),
+            }
+        }
+        depth
+    }
+
+    #[test]
+    fn generated_operations_are_valid() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        for max_depth in 0..4 {
+            let mut generator = OperationGenerator::new(&schema)
+                .seed(max_depth as u64)
+                .max_depth(max_depth)
+                .argument_coverage(0.7);
+            for kind in [
+                OperationKind::Query,
+                OperationKind::Mutation,
+                OperationKind::Subscription,
+            ] {
+                for _ in 0..100 {
+                    let op = generator.generate(kind).unwrap();
+                    let printed = op.to_string();
+                    let parsed = parse_query::<String>(&printed).unwrap();
+                    let op = parsed.operations().next().unwrap();
+                    assert_eq!(op.kind(), kind);
+                    let root = match kind {
+                        OperationKind::Mutation => "Mutation",
+                        OperationKind::Subscription => "Subscription",
+                        _ => "Query",
+                    };
+                    let mut names = HashSet::new();
+                    let depth = check(&doc, &schema, root, op.selection_set(), &mut names);
+                    if kind == OperationKind::Subscription {
+                        assert_eq!(op.selection_set().items.len(), 1);
+                        assert!(depth <= max_depth.max(1), "too deep:\n{}", printed);
+                    } else {
+                        assert!(depth <= max_depth, "too deep:\n{}", printed);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn options() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        let generate = |seed| {
+            let mut generator = OperationGenerator::new(&schema).seed(seed);
+            (0..10)
+                .map(|_| {
+                    generator
+                        .generate(OperationKind::Query)
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+
+        // without coverage only required arguments are passed
+        let mut generator = OperationGenerator::new(&schema)
+            .argument_coverage(0.0)
+            .max_fields(1)
+            .max_depth(0);
+        for _ in 0..20 {
+            let printed = generator
+                .generate(OperationKind::SelectionSet)
+                .unwrap()
+                .to_string();
+            assert!(printed.starts_with("{\n"), "{}", printed);
+            assert!(!printed.contains("filter"), "{}", printed);
+            assert!(
+                ["  version\n", "  __typename\n"]
+                    .iter()
+                    .any(|f| printed.contains(f)),
+                "{}",
+                printed
+            );
+        }
+
+        let doc = parse_schema::<&str>("type Query { a: Int }").unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        let mut generator = OperationGenerator::new(&schema);
+        assert!(generator.generate(OperationKind::Mutation).is_none());
+        assert!(generator.generate(OperationKind::Subscription).is_none());
+    }
+}
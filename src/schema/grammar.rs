@@ -1,14 +1,21 @@
 use combine::combinator::sep_by1;
 use combine::combinator::{choice, eof, many, many1, optional, position};
-use combine::easy::{Error, Errors};
 use combine::error::StreamError;
-use combine::{parser, ParseResult, Parser};
+use combine::stream::Resetable;
+use combine::{ParseResult, Parser, Positioned};
 
 use crate::common::{default_value, directives, parse_type, string, Text};
-use crate::helpers::{ident, kind, name, punct};
+use crate::error::{Error, Errors, Info};
+use crate::helpers::{end_position, ident, kind, name, punct, rule};
+use crate::limits::Limits;
+use crate::message::{Message, MessageKey};
+use crate::parser::ParserOptions;
+use crate::recovery;
 use crate::schema::ast::*;
-use crate::schema::error::ParseError;
+use crate::schema::error::{InternalError, ParseError};
+use crate::syntax_error::ErrorCode;
 use crate::tokenizer::{Kind as T, Token, TokenStream};
+use crate::warning::Warning;
 
 pub fn schema<'a, S>(
     input: &mut TokenStream<'a>,
@@ -16,15 +23,16 @@ pub fn schema<'a, S>(
 where
     S: Text<'a>,
 {
-    (
+    let (schema, consumed) = (
         position().skip(ident("schema")),
-        parser(directives),
+        rule("directives", directives),
         punct("{")
             .with(many((kind(T::Name).skip(punct(":")), name::<'a, S>())))
             .skip(punct("}")),
+        end_position(),
     )
         .flat_map(
-            |(position, directives, operations): (_, _, Vec<(Token, _)>)| {
+            |(position, directives, operations, end): (_, _, Vec<(Token, _)>, _)| {
                 let mut query = None;
                 let mut mutation = None;
                 let mut subscription = None;
@@ -32,25 +40,34 @@ where
                 for (oper, type_name) in operations {
                     match oper.value {
                         "query" if query.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `query` operation",
-                            ));
+                            err.add_error(Error::Unexpected(Info::Raised(
+                                ErrorCode::DuplicateOperation,
+                                Message::with_args(MessageKey::DuplicateOperation, Some("query")),
+                            )));
                         }
                         "query" => {
                             query = Some(type_name);
                         }
                         "mutation" if mutation.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `mutation` operation",
-                            ));
+                            err.add_error(Error::Unexpected(Info::Raised(
+                                ErrorCode::DuplicateOperation,
+                                Message::with_args(
+                                    MessageKey::DuplicateOperation,
+                                    Some("mutation"),
+                                ),
+                            )));
                         }
                         "mutation" => {
                             mutation = Some(type_name);
                         }
                         "subscription" if subscription.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `subscription` operation",
-                            ));
+                            err.add_error(Error::Unexpected(Info::Raised(
+                                ErrorCode::DuplicateOperation,
+                                Message::with_args(
+                                    MessageKey::DuplicateOperation,
+                                    Some("subscription"),
+                                ),
+                            )));
                         }
                         "subscription" => {
                             subscription = Some(type_name);
@@ -68,6 +85,7 @@ where
                 }
                 Ok(SchemaDefinition {
                     position,
+                    end,
                     directives,
                     query,
                     mutation,
@@ -75,7 +93,14 @@ where
                 })
             },
         )
-        .parse_stream(input)
+        .parse_stream(input)?;
+    if schema.query.is_none() && schema.mutation.is_none() && schema.subscription.is_none() {
+        input.warn(
+            schema.position,
+            Message::new(MessageKey::EmptySchemaDefinition),
+        );
+    }
+    Ok((schema, consumed))
 }
 
 pub fn scalar_type<'a, T>(
@@ -87,10 +112,12 @@ where
     (
         position(),
         ident("scalar").with(name::<'a, T>()),
-        parser(directives),
+        rule("directives", directives),
+        end_position(),
     )
-        .map(|(position, name, directives)| ScalarType {
+        .map(|(position, name, directives, end)| ScalarType {
             position,
+            end,
             description: None,
             name,
             directives,
@@ -107,19 +134,21 @@ where
     (
         position(),
         ident("scalar").with(name::<'a, T>()),
-        parser(directives),
+        rule("directives", directives),
+        end_position(),
     )
-        .flat_map(|(position, name, directives)| {
+        .flat_map(|(position, name, directives, end)| {
             if directives.is_empty() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Scalar type extension should contain at least \
-                 one directive.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyScalarExtension,
+                )));
                 return Err(e);
             }
             Ok(ScalarTypeExtension {
                 position,
+                end,
                 name,
                 directives,
             })
@@ -133,13 +162,23 @@ pub fn implements_interfaces<'a, X>(
 where
     X: Text<'a>,
 {
-    optional(
+    let position = input.position();
+    let (interfaces, consumed) = optional(
         ident("implements")
             .skip(optional(punct("&")))
             .with(sep_by1(name::<'a, X>(), punct("&"))),
     )
     .map(|opt| opt.unwrap_or_else(Vec::new))
-    .parse_stream(input)
+    .parse_stream(input)?;
+    for (idx, iface) in interfaces.iter().enumerate() {
+        if interfaces[..idx].contains(iface) {
+            input.warn(
+                position,
+                Message::with_args(MessageKey::DuplicateInterface, Some(iface.as_ref())),
+            );
+        }
+    }
+    Ok((interfaces, consumed))
 }
 
 pub fn input_value<'a, X>(
@@ -150,20 +189,24 @@ where
 {
     (
         position(),
-        optional(parser(string)),
+        optional(rule("string", string)),
         name::<'a, X>(),
-        punct(":").with(parser(parse_type)),
-        optional(punct("=").with(parser(default_value))),
-        parser(directives),
+        punct(":").with(rule("parse_type", parse_type)),
+        optional(punct("=").with(rule("default_value", default_value))),
+        rule("directives", directives),
+        end_position(),
     )
         .map(
-            |(position, description, name, value_type, default_value, directives)| InputValue {
-                position,
-                description,
-                name,
-                value_type,
-                default_value,
-                directives,
+            |(position, description, name, value_type, default_value, directives, end)| {
+                InputValue {
+                    position,
+                    end,
+                    description,
+                    name,
+                    value_type,
+                    default_value,
+                    directives,
+                }
             },
         )
         .parse_stream(input)
@@ -175,9 +218,13 @@ pub fn arguments_definition<'a, T>(
 where
     T: Text<'a>,
 {
-    optional(punct("(").with(many1(parser(input_value))).skip(punct(")")))
-        .map(|v| v.unwrap_or_else(Vec::new))
-        .parse_stream(input)
+    optional(
+        punct("(")
+            .with(many1(rule("input_value", input_value)))
+            .skip(punct(")")),
+    )
+    .map(|v| v.unwrap_or_else(Vec::new))
+    .parse_stream(input)
 }
 
 pub fn field<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<Field<'a, S>, TokenStream<'a>>
@@ -186,15 +233,17 @@ where
 {
     (
         position(),
-        optional(parser(string)),
+        optional(rule("string", string)),
         name::<'a, S>(),
-        parser(arguments_definition),
-        punct(":").with(parser(parse_type)),
-        parser(directives),
+        rule("arguments_definition", arguments_definition),
+        punct(":").with(rule("parse_type", parse_type)),
+        rule("directives", directives),
+        end_position(),
     )
         .map(
-            |(position, description, name, arguments, field_type, directives)| Field {
+            |(position, description, name, arguments, field_type, directives, end)| Field {
                 position,
+                end,
                 description,
                 name,
                 arguments,
@@ -209,9 +258,13 @@ pub fn fields<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<Vec<Field<'a, S
 where
     S: Text<'a>,
 {
-    optional(punct("{").with(many1(parser(field))).skip(punct("}")))
-        .map(|v| v.unwrap_or_else(Vec::new))
-        .parse_stream(input)
+    optional(
+        punct("{")
+            .with(many1(rule("field", field)))
+            .skip(punct("}")),
+    )
+    .map(|v| v.unwrap_or_else(Vec::new))
+    .parse_stream(input)
 }
 
 pub fn object_type<'a, S>(
@@ -223,13 +276,15 @@ where
     (
         position(),
         ident("type").with(name::<'a, S>()),
-        parser(implements_interfaces::<S>),
-        parser(directives),
-        parser(fields),
+        rule("implements_interfaces", implements_interfaces::<S>),
+        rule("directives", directives),
+        rule("fields", fields),
+        end_position(),
     )
-        .map(|(position, name, interfaces, directives, fields)| {
+        .map(|(position, name, interfaces, directives, fields, end)| {
             ObjectType {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -249,21 +304,23 @@ where
     (
         position(),
         ident("type").with(name::<'a, S>()),
-        parser(implements_interfaces::<S>),
-        parser(directives),
-        parser(fields),
+        rule("implements_interfaces", implements_interfaces::<S>),
+        rule("directives", directives),
+        rule("fields", fields),
+        end_position(),
     )
-        .flat_map(|(position, name, interfaces, directives, fields)| {
+        .flat_map(|(position, name, interfaces, directives, fields, end)| {
             if interfaces.is_empty() && directives.is_empty() && fields.is_empty() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Object type extension should contain at least \
-                     one interface, directive or field.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyObjectExtension,
+                )));
                 return Err(e);
             }
             Ok(ObjectTypeExtension {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -282,13 +339,15 @@ where
     (
         position(),
         ident("interface").with(name::<'a, T>()),
-        parser(implements_interfaces::<T>),
-        parser(directives),
-        parser(fields),
+        rule("implements_interfaces", implements_interfaces::<T>),
+        rule("directives", directives),
+        rule("fields", fields),
+        end_position(),
     )
-        .map(|(position, name, interfaces, directives, fields)| {
+        .map(|(position, name, interfaces, directives, fields, end)| {
             InterfaceType {
                 position,
+                end,
                 name,
                 implements_interfaces: interfaces,
                 directives,
@@ -308,21 +367,23 @@ where
     (
         position(),
         ident("interface").with(name::<'a, T>()),
-        parser(implements_interfaces::<T>),
-        parser(directives),
-        parser(fields),
+        rule("implements_interfaces", implements_interfaces::<T>),
+        rule("directives", directives),
+        rule("fields", fields),
+        end_position(),
     )
-        .flat_map(|(position, name, interfaces, directives, fields)| {
+        .flat_map(|(position, name, interfaces, directives, fields, end)| {
             if directives.is_empty() && fields.is_empty() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Interface type extension should contain at least \
-                     one directive or field.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyInterfaceExtension,
+                )));
                 return Err(e);
             }
             Ok(InterfaceTypeExtension {
                 position,
+                end,
                 name,
                 implements_interfaces: interfaces,
                 directives,
@@ -352,12 +413,14 @@ where
     (
         position(),
         ident("union").with(name::<'a, T>()),
-        parser(directives),
-        optional(punct("=").with(parser(union_members::<T>))),
+        rule("directives", directives),
+        optional(punct("=").with(rule("union_members", union_members::<T>))),
+        end_position(),
     )
-        .map(|(position, name, directives, types)| {
+        .map(|(position, name, directives, types, end)| {
             UnionType {
                 position,
+                end,
                 name,
                 directives,
                 types: types.unwrap_or_else(Vec::new),
@@ -376,20 +439,22 @@ where
     (
         position(),
         ident("union").with(name::<'a, T>()),
-        parser(directives),
-        optional(punct("=").with(parser(union_members::<T>))),
+        rule("directives", directives),
+        optional(punct("=").with(rule("union_members", union_members::<T>))),
+        end_position(),
     )
-        .flat_map(|(position, name, directives, types)| {
+        .flat_map(|(position, name, directives, types, end)| {
             if directives.is_empty() && types.is_none() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Union type extension should contain at least \
-                 one directive or type.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyUnionExtension,
+                )));
                 return Err(e);
             }
             Ok(UnionTypeExtension {
                 position,
+                end,
                 name,
                 directives,
                 types: types.unwrap_or_else(Vec::new),
@@ -408,12 +473,14 @@ where
         .with(many1(
             (
                 position(),
-                optional(parser(string)),
+                optional(rule("string", string)),
                 name::<'a, T>(),
-                parser(directives),
+                rule("directives", directives),
+                end_position(),
             )
-                .map(|(position, description, name, directives)| EnumValue {
+                .map(|(position, description, name, directives, end)| EnumValue {
                     position,
+                    end,
                     description,
                     name,
                     directives,
@@ -432,12 +499,14 @@ where
     (
         position(),
         ident("enum").with(name::<'a, T>()),
-        parser(directives),
-        optional(parser(enum_values)),
+        rule("directives", directives),
+        optional(rule("enum_values", enum_values)),
+        end_position(),
     )
-        .map(|(position, name, directives, values)| {
+        .map(|(position, name, directives, values, end)| {
             EnumType {
                 position,
+                end,
                 name,
                 directives,
                 values: values.unwrap_or_else(Vec::new),
@@ -456,20 +525,22 @@ where
     (
         position(),
         ident("enum").with(name::<'a, T>()),
-        parser(directives),
-        optional(parser(enum_values)),
+        rule("directives", directives),
+        optional(rule("enum_values", enum_values)),
+        end_position(),
     )
-        .flat_map(|(position, name, directives, values)| {
+        .flat_map(|(position, name, directives, values, end)| {
             if directives.is_empty() && values.is_none() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Enum type extension should contain at least \
-                 one directive or value.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyEnumExtension,
+                )));
                 return Err(e);
             }
             Ok(EnumTypeExtension {
                 position,
+                end,
                 name,
                 directives,
                 values: values.unwrap_or_else(Vec::new),
@@ -484,9 +555,13 @@ pub fn input_fields<'a, T>(
 where
     T: Text<'a>,
 {
-    optional(punct("{").with(many1(parser(input_value))).skip(punct("}")))
-        .map(|v| v.unwrap_or_else(Vec::new))
-        .parse_stream(input)
+    optional(
+        punct("{")
+            .with(many1(rule("input_value", input_value)))
+            .skip(punct("}")),
+    )
+    .map(|v| v.unwrap_or_else(Vec::new))
+    .parse_stream(input)
 }
 
 pub fn input_object_type<'a, T>(
@@ -498,12 +573,14 @@ where
     (
         position(),
         ident("input").with(name::<'a, T>()),
-        parser(directives),
-        parser(input_fields),
+        rule("directives", directives),
+        rule("input_fields", input_fields),
+        end_position(),
     )
-        .map(|(position, name, directives, fields)| {
+        .map(|(position, name, directives, fields, end)| {
             InputObjectType {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -522,20 +599,22 @@ where
     (
         position(),
         ident("input").with(name::<'a, T>()),
-        parser(directives),
-        parser(input_fields),
+        rule("directives", directives),
+        rule("input_fields", input_fields),
+        end_position(),
     )
-        .flat_map(|(position, name, directives, fields)| {
+        .flat_map(|(position, name, directives, fields, end)| {
             if directives.is_empty() && fields.is_empty() {
                 let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Input object type extension should contain at least \
-                     one directive or field.",
-                ));
+                e.add_error(Error::Expected(Info::raised(
+                    ErrorCode::EmptyExtension,
+                    MessageKey::EmptyInputObjectExtension,
+                )));
                 return Err(e);
             }
             Ok(InputObjectTypeExtension {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -564,13 +643,15 @@ where
     (
         position(),
         ident("directive").and(punct("@")).with(name::<'a, T>()),
-        parser(arguments_definition),
+        rule("arguments_definition", arguments_definition),
         optional(ident("repeatable")),
-        ident("on").with(parser(directive_locations)),
+        ident("on").with(rule("directive_locations", directive_locations)),
+        end_position(),
     )
-        .map(|(position, name, arguments, repeatable, locations)| {
+        .map(|(position, name, arguments, repeatable, locations, end)| {
             DirectiveDefinition {
                 position,
+                end,
                 name,
                 arguments,
                 locations,
@@ -589,18 +670,18 @@ where
 {
     use self::TypeDefinition::*;
     (
-        optional(parser(string)),
+        optional(rule("string", string)),
         choice((
             choice((
-                parser(scalar_type).map(Scalar),
-                parser(object_type).map(Object),
-                parser(interface_type).map(Interface),
-                parser(union_type).map(Union),
-                parser(enum_type).map(Enum),
-                parser(input_object_type).map(InputObject),
+                rule("scalar_type", scalar_type).map(Scalar),
+                rule("object_type", object_type).map(Object),
+                rule("interface_type", interface_type).map(Interface),
+                rule("union_type", union_type).map(Union),
+                rule("enum_type", enum_type).map(Enum),
+                rule("input_object_type", input_object_type).map(InputObject),
             ))
             .map(Definition::TypeDefinition),
-            parser(directive_definition).map(Definition::DirectiveDefinition),
+            rule("directive_definition", directive_definition).map(Definition::DirectiveDefinition),
         )),
     )
         // We can't set description inside type definition parser, because
@@ -634,12 +715,14 @@ where
 {
     ident("extend")
         .with(choice((
-            parser(scalar_type_extension).map(TypeExtension::Scalar),
-            parser(object_type_extension).map(TypeExtension::Object),
-            parser(interface_type_extension).map(TypeExtension::Interface),
-            parser(union_type_extension).map(TypeExtension::Union),
-            parser(enum_type_extension).map(TypeExtension::Enum),
-            parser(input_object_type_extension).map(TypeExtension::InputObject),
+            rule("scalar_type_extension", scalar_type_extension).map(TypeExtension::Scalar),
+            rule("object_type_extension", object_type_extension).map(TypeExtension::Object),
+            rule("interface_type_extension", interface_type_extension)
+                .map(TypeExtension::Interface),
+            rule("union_type_extension", union_type_extension).map(TypeExtension::Union),
+            rule("enum_type_extension", enum_type_extension).map(TypeExtension::Enum),
+            rule("input_object_type_extension", input_object_type_extension)
+                .map(TypeExtension::InputObject),
         )))
         .parse_stream(input)
 }
@@ -651,31 +734,140 @@ where
     T: Text<'a>,
 {
     choice((
-        parser(schema).map(Definition::SchemaDefinition),
-        parser(type_extension).map(Definition::TypeExtension),
-        parser(described_definition),
+        rule("schema", schema).map(Definition::SchemaDefinition),
+        rule("type_extension", type_extension).map(Definition::TypeExtension),
+        rule("described_definition", described_definition),
     ))
     .parse_stream(input)
 }
 
 /// Parses a piece of schema language and returns an AST
 pub fn parse_schema<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    parse_schema_with_warnings(s, 0).map(|(doc, _)| doc)
+}
+
+/// Parses a piece of schema language and returns an AST along with
+/// non-fatal warnings encountered while parsing
+///
+/// At most `max_warnings` warnings are returned. If there are more, the
+/// last entry of the list says how many of them were omitted.
+pub fn parse_schema_with_warnings<'a, T>(
+    s: &'a str,
+    max_warnings: usize,
+) -> Result<(Document<'a, T>, Vec<Warning>), ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    tokens.set_warning_limit(max_warnings);
+    let doc = document(&mut tokens)?;
+
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses a piece of schema language, failing if the document exceeds
+/// `limits`
+pub fn parse_schema_with_limits<'a, T>(
+    s: &'a str,
+    limits: &Limits,
+) -> Result<Document<'a, T>, ParseError>
 where
     T: Text<'a>,
 {
     let mut tokens = TokenStream::new(s);
-    let (doc, _) = many1(parser(definition))
-        .map(|d| Document { definitions: d })
+    tokens.set_limits(limits);
+    document(&mut tokens)
+}
+
+/// Parses a piece of schema language with the given settings and returns
+/// an AST along with non-fatal warnings
+pub fn parse_schema_with_options<'a, T>(
+    s: &'a str,
+    options: &ParserOptions,
+) -> Result<(Document<'a, T>, Vec<Warning>), ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = options.tokens(s);
+    let doc = document(&mut tokens)?;
+
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses a piece of schema language, skipping the definitions which fail to parse
+///
+/// Returns the definitions which could be parsed along with an error for
+/// each broken one. A broken definition is still part of the document if
+/// it parses after being cut short before the error, which keeps
+/// half-written definitions around in editors.
+pub fn parse_schema_with_recovery<'a, T>(s: &'a str) -> (Document<'a, T>, Vec<ParseError>)
+where
+    T: Text<'a>,
+{
+    document_with_recovery(&mut TokenStream::new(s))
+}
+
+/// Parses the whole token stream as a single document
+pub(crate) fn document<'a, T>(tokens: &mut TokenStream<'a>) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let start = tokens.checkpoint();
+    let result = many1(rule("definition", definition))
+        .map(|d| Document {
+            definitions: d,
+            comments: Vec::new(),
+        })
         .skip(eof())
-        .parse_stream(&mut tokens)
-        .map_err(|e| e.into_inner().error)?;
+        .parse_stream(tokens);
+    let mut doc = match result {
+        Ok((doc, _)) => doc,
+        Err(e) => {
+            let first = e.into_inner().error;
+            tokens.reset(start);
+            let further = recovery::further_errors(tokens, &first, parse_definition::<T>);
+            return Err(ParseError::with_further(first, further));
+        }
+    };
+    doc.comments = tokens.take_comments();
 
     Ok(doc)
 }
 
+fn parse_definition<'a, T>(
+    tokens: &mut TokenStream<'a>,
+) -> Result<Definition<'a, T>, InternalError<'a>>
+where
+    T: Text<'a>,
+{
+    rule("definition", definition)
+        .parse_stream(tokens)
+        .map(|(definition, _)| definition)
+        .map_err(|e| e.into_inner().error)
+}
+
+/// Parses the whole token stream, skipping broken definitions
+pub(crate) fn document_with_recovery<'a, T>(
+    tokens: &mut TokenStream<'a>,
+) -> (Document<'a, T>, Vec<ParseError>)
+where
+    T: Text<'a>,
+{
+    let (definitions, errors) = recovery::definitions(tokens, parse_definition);
+    let doc = Document {
+        definitions,
+        comments: tokens.take_comments(),
+    };
+    (doc, errors.into_iter().map(ParseError::from).collect())
+}
+
 #[cfg(test)]
 mod test {
-    use super::parse_schema;
+    use super::{parse_schema, parse_schema_with_warnings};
+    use crate::diagnostic::DEFAULT_LIMIT;
     use crate::position::Pos;
     use crate::schema::grammar::*;
 
@@ -689,13 +881,106 @@ mod test {
             ast("schema { query: Query }"),
             Document {
                 definitions: vec![Definition::SchemaDefinition(SchemaDefinition {
-                    position: Pos { line: 1, column: 1 },
+                    position: Pos::new(1, 1, 0),
+                    end: Pos::new(1, 24, 23),
                     directives: vec![],
                     query: Some("Query".into()),
                     mutation: None,
                     subscription: None
                 })],
+                comments: Vec::new(),
             }
         );
     }
+
+    #[test]
+    fn spans() {
+        let doc = ast(r#"
+"Description"
+type Query implements Node @key(fields: "id") {
+  "field" node(id: ID! = 1 @x): Node
+}
+extend enum E { A @deprecated B }
+directive @x on ARGUMENT_DEFINITION
+"#);
+        let spans: Vec<String> = doc
+            .definitions
+            .iter()
+            .map(|def| def.span().to_string())
+            .collect();
+        assert_eq!(spans, ["3:1-5:2", "6:8-6:34", "7:1-7:36"]);
+        match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref obj)) => {
+                assert_eq!(obj.fields[0].span().to_string(), "4:3-4:37");
+                assert_eq!(obj.fields[0].arguments[0].span().to_string(), "4:16-4:30");
+                assert_eq!(obj.directives[0].span().to_string(), "3:28-3:46");
+            }
+            _ => unreachable!(),
+        }
+        match doc.definitions[1] {
+            Definition::TypeExtension(TypeExtension::Enum(ref ext)) => {
+                assert_eq!(ext.values[0].span().to_string(), "6:17-6:30");
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn no_warnings() {
+        let (_, warnings) = parse_schema_with_warnings::<String>(
+            "type A implements B & C { a: Int }",
+            DEFAULT_LIMIT,
+        )
+        .unwrap();
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn duplicate_interface_warning() {
+        let (_, warnings) = parse_schema_with_warnings::<String>(
+            "type A implements B & B { a: Int }",
+            DEFAULT_LIMIT,
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                position: Pos::new(1, 8, 7),
+                message: Message::with_args(MessageKey::DuplicateInterface, Some("B")),
+            }]
+        );
+    }
+
+    #[test]
+    fn empty_schema_warning() {
+        let (_, warnings) =
+            parse_schema_with_warnings::<String>("schema { }", DEFAULT_LIMIT).unwrap();
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                position: Pos::new(1, 1, 0),
+                message: Message::new(MessageKey::EmptySchemaDefinition),
+            }]
+        );
+    }
+
+    #[test]
+    fn warning_limit() {
+        let (_, warnings) =
+            parse_schema_with_warnings::<String>("type A implements B & B & B & B { a: Int }", 1)
+                .unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                Warning {
+                    position: Pos::new(1, 8, 7),
+                    message: Message::with_args(MessageKey::DuplicateInterface, Some("B")),
+                },
+                Warning {
+                    position: Pos::new(1, 8, 7),
+                    message: Message::with_args(MessageKey::MoreDiagnostics, Some("2")),
+                },
+            ]
+        );
+    }
 }
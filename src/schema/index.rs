@@ -0,0 +1,186 @@
+//! Lookup of types and directives of a schema document by name
+//!
+use std::collections::hash_map::{Entry, HashMap};
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::coordinate::SchemaCoordinate;
+
+/// Types and directives of a schema document, by name
+///
+/// Built with `Document::build_index`. The index borrows the document, so
+/// it is cheap to build but has to be rebuilt after changing the document.
+#[derive(Debug)]
+pub struct SchemaIndex<'d, 'a, T: Text<'a>> {
+    types: HashMap<&'d str, TypeEntry<'d, 'a, T>>,
+    directives: HashMap<&'d str, &'d DirectiveDefinition<'a, T>>,
+    duplicates: Vec<Duplicate>,
+}
+
+/// The definition and extensions of a type
+#[derive(Debug)]
+pub struct TypeEntry<'d, 'a, T: Text<'a>> {
+    /// The first definition of the type, `None` if the type is only
+    /// extended
+    pub definition: Option<&'d TypeDefinition<'a, T>>,
+    /// Extensions of the type in the order of the document
+    pub extensions: Vec<&'d TypeExtension<'a, T>>,
+}
+
+/// A type or directive defined more than once
+///
+/// Only the first definition is indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Duplicate {
+    /// Either `SchemaCoordinate::Type` or `SchemaCoordinate::Directive`
+    pub coordinate: SchemaCoordinate,
+    pub first: Pos,
+    pub second: Pos,
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Builds an index of the types and directives of the document
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_schema;
+    ///
+    /// let schema = parse_schema::<&str>("
+    ///     type User { id: ID }
+    ///     extend type User { name: String }
+    ///     scalar User
+    /// ").unwrap();
+    /// let index = schema.build_index();
+    /// let user = index.type_entry("User").unwrap();
+    /// assert_eq!(user.definition.unwrap().span().start.line, 2);
+    /// assert_eq!(user.extensions.len(), 1);
+    /// assert_eq!(index.duplicates()[0].second.line, 4);
+    /// ```
+    pub fn build_index(&self) -> SchemaIndex<'_, 'a, T> {
+        let mut index = SchemaIndex {
+            types: HashMap::new(),
+            directives: HashMap::new(),
+            duplicates: Vec::new(),
+        };
+        for definition in &self.definitions {
+            match *definition {
+                Definition::TypeDefinition(ref definition) => {
+                    let entry = index.entry(definition.name());
+                    match entry.definition {
+                        Some(first) => {
+                            let duplicate = Duplicate {
+                                coordinate: SchemaCoordinate::Type(definition.name().into()),
+                                first: first.span().start,
+                                second: definition.span().start,
+                            };
+                            index.duplicates.push(duplicate);
+                        }
+                        None => entry.definition = Some(definition),
+                    }
+                }
+                Definition::TypeExtension(ref extension) => {
+                    index.entry(extension.name()).extensions.push(extension);
+                }
+                Definition::DirectiveDefinition(ref directive) => {
+                    match index.directives.entry(directive.name.as_ref()) {
+                        Entry::Occupied(first) => index.duplicates.push(Duplicate {
+                            coordinate: SchemaCoordinate::Directive(directive.name.as_ref().into()),
+                            first: first.get().position,
+                            second: directive.position,
+                        }),
+                        Entry::Vacant(entry) => {
+                            entry.insert(directive);
+                        }
+                    }
+                }
+                Definition::SchemaDefinition(_) => {}
+            }
+        }
+        index
+    }
+}
+
+impl<'d, 'a, T: Text<'a>> SchemaIndex<'d, 'a, T> {
+    fn entry(&mut self, name: &'d str) -> &mut TypeEntry<'d, 'a, T> {
+        self.types.entry(name).or_insert_with(|| TypeEntry {
+            definition: None,
+            extensions: Vec::new(),
+        })
+    }
+
+    /// Returns the definition and extensions of a type
+    pub fn type_entry(&self, name: &str) -> Option<&TypeEntry<'d, 'a, T>> {
+        self.types.get(name)
+    }
+
+    /// Returns the definition of a type
+    pub fn type_definition(&self, name: &str) -> Option<&'d TypeDefinition<'a, T>> {
+        self.types.get(name).and_then(|entry| entry.definition)
+    }
+
+    /// Returns the definition of a directive
+    pub fn directive(&self, name: &str) -> Option<&'d DirectiveDefinition<'a, T>> {
+        self.directives.get(name).copied()
+    }
+
+    /// Returns the names and entries of all types, in no particular order
+    pub fn types(&self) -> impl Iterator<Item = (&'d str, &TypeEntry<'d, 'a, T>)> {
+        self.types.iter().map(|(&name, entry)| (name, entry))
+    }
+
+    /// Returns the directive definitions, in no particular order
+    pub fn directives(&self) -> impl Iterator<Item = &'d DirectiveDefinition<'a, T>> + '_ {
+        self.directives.values().copied()
+    }
+
+    /// Returns the types and directives defined more than once, in the
+    /// order of the document
+    pub fn duplicates(&self) -> &[Duplicate] {
+        &self.duplicates
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Duplicate;
+    use crate::parse_schema;
+    use crate::schema::SchemaCoordinate;
+
+    #[test]
+    fn index() {
+        let schema = parse_schema::<&str>(
+            "extend enum E { B }
+            enum E { A }
+            directive @d on FIELD
+            extend enum E { C }
+            directive @d on QUERY
+            type Query { e: E }",
+        )
+        .unwrap();
+        let index = schema.build_index();
+        let entry = index.type_entry("E").unwrap();
+        assert_eq!(entry.definition.unwrap().name(), "E");
+        assert_eq!(entry.extensions.len(), 2);
+        assert_eq!(entry.extensions[1].span().start.line, 4);
+        assert_eq!(index.type_definition("Query").unwrap().name(), "Query");
+        assert!(index.type_definition("Mutation").is_none());
+        assert_eq!(index.directive("d").unwrap().position.line, 3);
+        let mut names = index.types().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["E", "Query"]);
+        assert_eq!(
+            index.duplicates(),
+            [Duplicate {
+                coordinate: SchemaCoordinate::Directive("d".into()),
+                first: index.directive("d").unwrap().position,
+                second: schema.definitions[4].span().start,
+            }]
+        );
+
+        let schema = parse_schema::<&str>("extend scalar S @a").unwrap();
+        let index = schema.build_index();
+        assert!(index.type_entry("S").unwrap().definition.is_none());
+        assert!(index.type_definition("S").is_none());
+    }
+}
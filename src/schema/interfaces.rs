@@ -0,0 +1,392 @@
+//! Interface implementations of a resolved schema
+//!
+use std::collections::BTreeSet;
+
+use thiserror::Error;
+
+use crate::common::{same_type, Text, Type};
+use crate::message::SpecReference;
+use crate::parallel;
+use crate::schema::ast::*;
+use crate::schema::resolve::Schema;
+
+/// A type which doesn't implement one of its interfaces correctly
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("`{type_name}` doesn't implement `{interface}` correctly: {kind}")]
+pub struct ImplementationError {
+    /// Name of the object or interface type
+    pub type_name: String,
+    pub interface: String,
+    pub kind: ImplementationErrorKind,
+}
+
+impl ImplementationError {
+    /// Returns the section of the specification defining valid
+    /// implementations
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        Some(SpecReference {
+            section: "3.6",
+            title: "Objects",
+        })
+    }
+}
+
+/// Reason why a type doesn't implement an interface
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ImplementationErrorKind {
+    #[error("the interface is not defined")]
+    UndefinedInterface,
+    #[error("the type is not an interface")]
+    NotAnInterface,
+    /// The interface implements an interface which the type doesn't
+    /// declare
+    #[error("interface `{0}` it implements is not declared")]
+    MissingInterface(String),
+    #[error("field `{0}` is missing")]
+    MissingField(String),
+    /// The type of the field is not the type of the interface field, nor
+    /// a subtype of it
+    #[error("field `{field}` has type `{found}`, which is not a subtype of `{expected}`")]
+    FieldType {
+        field: String,
+        expected: String,
+        found: String,
+    },
+    #[error("argument `{argument}` of field `{field}` is missing")]
+    MissingArgument { field: String, argument: String },
+    /// Arguments must have exactly the type of the interface argument
+    #[error("argument `{argument}` of field `{field}` has type `{found}` instead of `{expected}`")]
+    ArgumentType {
+        field: String,
+        argument: String,
+        expected: String,
+        found: String,
+    },
+    /// An argument which the interface doesn't define is required
+    #[error("additional argument `{argument}` of field `{field}` is required")]
+    RequiredArgument { field: String, argument: String },
+}
+
+/// Interfaces declared by a type and its fields
+type Implementation<'s, 'a, T> = (&'s [<T as Text<'a>>::Value], &'s [Field<'a, T>]);
+
+/// Returns the interfaces a type declares and its fields, for objects and
+/// interfaces
+fn implementation<'s, 'a, T: Text<'a>>(
+    definition: &'s TypeDefinition<'a, T>,
+) -> Option<Implementation<'s, 'a, T>> {
+    match *definition {
+        TypeDefinition::Object(ref t) => Some((&t.implements_interfaces, &t.fields)),
+        TypeDefinition::Interface(ref t) => Some((&t.implements_interfaces, &t.fields)),
+        _ => None,
+    }
+}
+
+impl<'a, T: Text<'a>> Schema<'a, T> {
+    /// Returns the object and interface types which declare that they
+    /// implement the interface, in the order of the document
+    pub fn implementers<'s>(
+        &'s self,
+        interface: &'s str,
+    ) -> impl Iterator<Item = &'s TypeDefinition<'a, T>> + 's {
+        self.types().iter().filter(move |definition| {
+            implementation(definition).is_some_and(|(interfaces, _)| {
+                interfaces.iter().any(|name| name.as_ref() == interface)
+            })
+        })
+    }
+
+    /// Returns the interfaces of an object or interface type, including
+    /// the interfaces implemented by those interfaces
+    ///
+    /// Interfaces are listed once, each before the interfaces it
+    /// implements. Undefined interfaces are skipped.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::parse_schema;
+    /// use graphql_parser::schema::Schema;
+    ///
+    /// let doc = parse_schema::<&str>("
+    ///     interface Node { id: ID! }
+    ///     interface Resource implements Node { id: ID! url: String }
+    ///     type Image implements Resource & Node { id: ID! url: String }
+    /// ").unwrap();
+    /// let schema = Schema::new(&doc).unwrap();
+    /// let names = schema.interfaces_of("Image").iter().map(|i| i.name).collect::<Vec<_>>();
+    /// assert_eq!(names, ["Resource", "Node"]);
+    /// assert_eq!(schema.implementers("Node").count(), 2);
+    /// assert!(schema.check_implementations().is_empty());
+    /// ```
+    pub fn interfaces_of(&self, type_name: &str) -> Vec<&InterfaceType<'a, T>> {
+        let mut result = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut queue = match self.type_by_name(type_name).and_then(implementation) {
+            Some((interfaces, _)) => interfaces.iter().collect::<Vec<_>>(),
+            None => return result,
+        };
+        queue.reverse();
+        while let Some(name) = queue.pop() {
+            if !seen.insert(name.as_ref()) {
+                continue;
+            }
+            if let Some(TypeDefinition::Interface(interface)) = self.type_by_name(name.as_ref()) {
+                result.push(interface);
+                queue.extend(interface.implements_interfaces.iter().rev());
+            }
+        }
+        result
+    }
+
+    /// Checks that every object and interface type implements the
+    /// interfaces it declares as specified
+    ///
+    /// Each field of an interface must be defined by the implementing type,
+    /// with the same type or a subtype of it, and with the arguments of the
+    /// interface field. Additional arguments must be optional. A type must
+    /// also declare the interfaces its interfaces implement.
+    pub fn check_implementations(&self) -> Vec<ImplementationError> {
+        parallel::map(self.types(), |_, definition| self.check_type(definition))
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Same as `check_implementations`, checking the types on all threads
+    #[cfg(feature = "rayon")]
+    pub fn par_check_implementations(&self) -> Vec<ImplementationError>
+    where
+        T::Value: Sync,
+    {
+        parallel::par_map(self.types(), |_, definition| self.check_type(definition))
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn check_type(&self, definition: &TypeDefinition<'a, T>) -> Vec<ImplementationError> {
+        let mut errors = Vec::new();
+        let (interfaces, fields) = match implementation(definition) {
+            Some(implementation) => implementation,
+            None => return errors,
+        };
+        for name in interfaces {
+            let mut error = |kind| {
+                errors.push(ImplementationError {
+                    type_name: definition.name().to_string(),
+                    interface: name.as_ref().to_string(),
+                    kind,
+                })
+            };
+            let interface = match self.type_by_name(name.as_ref()) {
+                Some(TypeDefinition::Interface(interface)) => interface,
+                Some(_) => {
+                    error(ImplementationErrorKind::NotAnInterface);
+                    continue;
+                }
+                None => {
+                    error(ImplementationErrorKind::UndefinedInterface);
+                    continue;
+                }
+            };
+            for inherited in &interface.implements_interfaces {
+                if !interfaces.iter().any(|n| n.as_ref() == inherited.as_ref()) {
+                    error(ImplementationErrorKind::MissingInterface(
+                        inherited.as_ref().to_string(),
+                    ));
+                }
+            }
+            for expected in &interface.fields {
+                match fields
+                    .iter()
+                    .find(|f| f.name.as_ref() == expected.name.as_ref())
+                {
+                    Some(field) => self.check_field(expected, field, &mut error),
+                    None => error(ImplementationErrorKind::MissingField(
+                        expected.name.as_ref().to_string(),
+                    )),
+                }
+            }
+        }
+        errors
+    }
+
+    fn check_field<F>(&self, expected: &Field<'a, T>, field: &Field<'a, T>, error: &mut F)
+    where
+        F: FnMut(ImplementationErrorKind),
+    {
+        let field_name = || field.name.as_ref().to_string();
+        if !self.is_subtype(&field.field_type, &expected.field_type) {
+            error(ImplementationErrorKind::FieldType {
+                field: field_name(),
+                expected: expected.field_type.to_string(),
+                found: field.field_type.to_string(),
+            });
+        }
+        for argument in &expected.arguments {
+            match field
+                .arguments
+                .iter()
+                .find(|a| a.name.as_ref() == argument.name.as_ref())
+            {
+                Some(found) if !same_type(&found.value_type, &argument.value_type) => {
+                    error(ImplementationErrorKind::ArgumentType {
+                        field: field_name(),
+                        argument: argument.name.as_ref().to_string(),
+                        expected: argument.value_type.to_string(),
+                        found: found.value_type.to_string(),
+                    })
+                }
+                Some(_) => {}
+                None => error(ImplementationErrorKind::MissingArgument {
+                    field: field_name(),
+                    argument: argument.name.as_ref().to_string(),
+                }),
+            }
+        }
+        for argument in &field.arguments {
+            let additional = !expected
+                .arguments
+                .iter()
+                .any(|a| a.name.as_ref() == argument.name.as_ref());
+            let required = argument.value_type.is_non_null() && argument.default_value.is_none();
+            if additional && required {
+                error(ImplementationErrorKind::RequiredArgument {
+                    field: field_name(),
+                    argument: argument.name.as_ref().to_string(),
+                });
+            }
+        }
+    }
+
+    /// Returns `true` if a field of type `sub` may implement a field of
+    /// type `sup`
+    fn is_subtype(&self, sub: &Type<'a, T>, sup: &Type<'a, T>) -> bool {
+        match (sub, sup) {
+            (Type::NonNullType(sub), Type::NonNullType(sup)) => self.is_subtype(sub, sup),
+            (Type::NonNullType(sub), _) => self.is_subtype(sub, sup),
+            (_, Type::NonNullType(_)) => false,
+            (Type::ListType(sub), Type::ListType(sup)) => self.is_subtype(sub, sup),
+            (Type::NamedType(sub), Type::NamedType(sup)) => {
+                let (sub, sup) = (sub.as_ref(), sup.as_ref());
+                if sub == sup {
+                    return true;
+                }
+                match self.type_by_name(sup) {
+                    Some(TypeDefinition::Interface(_)) => {
+                        self.type_by_name(sub).and_then(implementation).is_some_and(
+                            |(interfaces, _)| interfaces.iter().any(|name| name.as_ref() == sup),
+                        )
+                    }
+                    Some(TypeDefinition::Union(union)) => {
+                        matches!(self.type_by_name(sub), Some(TypeDefinition::Object(_)))
+                            && union.types.iter().any(|name| name.as_ref() == sub)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ImplementationErrorKind::*;
+    use crate::parse_schema;
+    use crate::schema::Schema;
+
+    fn check(source: &str) -> Vec<String> {
+        let doc = parse_schema::<&str>(source).unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        let errors = schema.check_implementations();
+        #[cfg(feature = "rayon")]
+        assert_eq!(schema.par_check_implementations(), errors);
+        errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn implementers() {
+        let doc = parse_schema::<&str>(
+            "interface A { a: Int }
+            interface B implements A { a: Int }
+            type X implements B & A { a: Int }
+            type Y implements A { a: Int }
+            type Z { a: Int }",
+        )
+        .unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        let names = schema
+            .implementers("A")
+            .map(|t| t.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["B", "X", "Y"]);
+        let names = schema
+            .interfaces_of("X")
+            .iter()
+            .map(|i| i.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["B", "A"]);
+        assert!(schema.interfaces_of("Z").is_empty());
+        assert!(schema.interfaces_of("Undefined").is_empty());
+    }
+
+    #[test]
+    fn valid_implementations() {
+        let errors = check(
+            "interface Node { id: ID node(x: Int): Node list: [Node] u: U }
+            union U = A
+            type A implements Node {
+                id: ID!
+                node(x: Int, y: Int, z: Int! = 1): A!
+                list: [A!]!
+                u: A
+            }",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn invalid_implementations() {
+        let errors = check(
+            "interface I { a(x: Int): Int b: [Int] c: Int! }
+            interface J implements I { a(x: Int): Int b: [Int] c: Int! }
+            scalar S
+            type A implements J & S & K { a(x: Int!, y: Int!): Int b: Int c: Int }",
+        );
+        assert_eq!(
+            errors,
+            [
+                "`A` doesn't implement `J` correctly: interface `I` it implements is not declared",
+                "`A` doesn't implement `J` correctly: argument `x` of field `a` \
+                 has type `Int!` instead of `Int`",
+                "`A` doesn't implement `J` correctly: additional argument `y` of field `a` \
+                 is required",
+                "`A` doesn't implement `J` correctly: field `b` has type `Int`, \
+                 which is not a subtype of `[Int]`",
+                "`A` doesn't implement `J` correctly: field `c` has type `Int`, \
+                 which is not a subtype of `Int!`",
+                "`A` doesn't implement `S` correctly: the type is not an interface",
+                "`A` doesn't implement `K` correctly: the interface is not defined",
+            ]
+        );
+        let doc = parse_schema::<&str>(
+            "interface I { a(x: Int): Int b: Int } type A implements I { a: Int }",
+        )
+        .unwrap();
+        let schema = Schema::new(&doc).unwrap();
+        let errors = schema.check_implementations();
+        assert_eq!(errors[0].spec_reference().unwrap().section, "3.6");
+        let kinds = errors.into_iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                MissingArgument {
+                    field: "a".into(),
+                    argument: "x".into()
+                },
+                MissingField("b".into()),
+            ]
+        );
+    }
+}
@@ -1,10 +1,38 @@
 //! Schema definition language AST and utility
 //!
 mod ast;
+mod build;
+mod coerce;
+mod compare;
+mod coordinate;
+mod deprecation;
 mod error;
 mod format;
+mod generate;
 mod grammar;
+mod index;
+mod interfaces;
+mod resolve;
+pub mod visit;
 
 pub use self::ast::*;
+pub use self::build::{
+    DirectiveDefinitionBuilder, EnumTypeBuilder, FieldBuilder, InputObjectTypeBuilder,
+    InputValueBuilder, InterfaceTypeBuilder, ObjectTypeBuilder, ScalarTypeBuilder,
+    UnionTypeBuilder,
+};
+pub use self::coerce::{coerce_value, CoercionError, CoercionErrorKind, InputPathSegment};
+pub use self::compare::Equivalence;
+pub use self::coordinate::{InvalidCoordinate, SchemaCoordinate, SchemaElement};
+pub use self::deprecation::Deprecation;
 pub use self::error::ParseError;
-pub use self::grammar::parse_schema;
+pub use self::generate::OperationGenerator;
+pub(crate) use self::grammar::{definition, document, document_with_recovery};
+pub use self::grammar::{
+    parse_schema, parse_schema_with_limits, parse_schema_with_options, parse_schema_with_recovery,
+    parse_schema_with_warnings,
+};
+pub use self::index::{Duplicate, SchemaIndex, TypeEntry};
+pub use self::interfaces::{ImplementationError, ImplementationErrorKind};
+pub use self::resolve::{Schema, SchemaError};
+pub use crate::owned_source::schema::OwnedDocument;
@@ -0,0 +1,488 @@
+//! Schema resolved from a schema document
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::common::{Text, Type};
+use crate::message::SpecReference;
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::coordinate::SchemaCoordinate;
+
+/// Error resolving a schema from a document
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error("type `{name}` is defined again at {position}")]
+    DuplicateType { name: String, position: Pos },
+    #[error("directive `@{name}` is defined again at {position}")]
+    DuplicateDirective { name: String, position: Pos },
+    #[error("schema is defined again at {0}")]
+    DuplicateSchemaDefinition(Pos),
+    #[error("extension at {position} extends undefined type `{name}`")]
+    UndefinedExtendedType { name: String, position: Pos },
+    /// An extension of a different kind than the type, like
+    /// `extend enum Query`
+    #[error("extension at {position} doesn't match the kind of type `{name}`")]
+    ExtensionKindMismatch { name: String, position: Pos },
+    #[error("{operation} root type `{name}` is not defined")]
+    UndefinedRootType {
+        operation: &'static str,
+        name: String,
+    },
+    #[error("{operation} root type `{name}` is not an object type")]
+    RootTypeNotObject {
+        operation: &'static str,
+        name: String,
+    },
+}
+
+impl SchemaError {
+    /// Returns the section of the specification defining the rule
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        use self::SchemaError::*;
+        let (section, title) = match *self {
+            DuplicateType { .. } | DuplicateDirective { .. } | DuplicateSchemaDefinition(_) => {
+                ("3.3", "Schema")
+            }
+            UndefinedExtendedType { .. } | ExtensionKindMismatch { .. } => {
+                ("3.4.3", "Type Extensions")
+            }
+            UndefinedRootType { .. } | RootTypeNotObject { .. } => {
+                ("3.3.1", "Root Operation Types")
+            }
+        };
+        Some(SpecReference { section, title })
+    }
+}
+
+/// A schema with root operation types resolved and extensions merged
+///
+/// This is the view of a schema document most tools work with:
+///
+/// * Extensions are merged into the types they extend, so every type has a
+///   single definition.
+/// * The root operation types are taken from the `schema` definition, or
+///   are the types named `Query`, `Mutation` and `Subscription` if there is
+///   none.
+/// * The built-in scalars and directives are defined unless the document
+///   defines them itself.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_schema;
+/// use graphql_parser::schema::Schema;
+///
+/// let doc = parse_schema::<&str>("
+///     schema { query: Root }
+///     type Root { version: String }
+///     extend type Root { uptime: Int }
+/// ").unwrap();
+/// let schema = Schema::new(&doc).unwrap();
+/// let query = schema.query_type().unwrap();
+/// assert_eq!(query.name, "Root");
+/// assert_eq!(query.fields.len(), 2);
+/// assert!(schema.type_by_name("Boolean").is_some());
+/// assert!(schema.mutation_type().is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schema<'a, T: Text<'a>> {
+    types: Vec<TypeDefinition<'a, T>>,
+    type_index: HashMap<String, usize>,
+    directives: Vec<DirectiveDefinition<'a, T>>,
+    directive_index: HashMap<String, usize>,
+    query: Option<usize>,
+    mutation: Option<usize>,
+    subscription: Option<usize>,
+}
+
+const BUILTIN_SCALARS: &[&str] = &["Int", "Float", "String", "Boolean", "ID"];
+
+impl<'a, T: Text<'a> + Clone> Schema<'a, T> {
+    /// Resolves the schema defined by a document
+    ///
+    /// Fails on types, directives and schema definitions which are defined
+    /// twice, on extensions of undefined types or of types of another
+    /// kind, and on root operation types which aren't defined object types.
+    /// Nothing else is validated.
+    pub fn new(document: &Document<'a, T>) -> Result<Schema<'a, T>, SchemaError> {
+        let index = document.build_index();
+        if let Some(duplicate) = index.duplicates().first() {
+            return Err(match duplicate.coordinate {
+                SchemaCoordinate::Directive(ref name) => SchemaError::DuplicateDirective {
+                    name: name.clone(),
+                    position: duplicate.second,
+                },
+                ref coordinate => SchemaError::DuplicateType {
+                    name: coordinate.to_string(),
+                    position: duplicate.second,
+                },
+            });
+        }
+
+        let mut schema = Schema {
+            types: Vec::new(),
+            type_index: HashMap::new(),
+            directives: Vec::new(),
+            directive_index: HashMap::new(),
+            query: None,
+            mutation: None,
+            subscription: None,
+        };
+        let mut schema_definition: Option<&SchemaDefinition<'a, T>> = None;
+        for definition in &document.definitions {
+            match *definition {
+                Definition::TypeDefinition(ref definition) => {
+                    let mut definition = definition.clone();
+                    let entry = index
+                        .type_entry(definition.name())
+                        .expect("type is indexed");
+                    for extension in &entry.extensions {
+                        extend(&mut definition, extension)?;
+                    }
+                    schema.add_type(definition);
+                }
+                Definition::TypeExtension(ref extension) => {
+                    if index.type_definition(extension.name()).is_none() {
+                        return Err(SchemaError::UndefinedExtendedType {
+                            name: extension.name().to_string(),
+                            position: extension.span().start,
+                        });
+                    }
+                }
+                Definition::DirectiveDefinition(ref directive) => {
+                    schema.add_directive(directive.clone());
+                }
+                Definition::SchemaDefinition(ref definition) => {
+                    if schema_definition.is_some() {
+                        return Err(SchemaError::DuplicateSchemaDefinition(definition.position));
+                    }
+                    schema_definition = Some(definition);
+                }
+            }
+        }
+        for &name in BUILTIN_SCALARS {
+            if !schema.type_index.contains_key(name) {
+                schema.add_type(TypeDefinition::Scalar(ScalarType::new(name.into())));
+            }
+        }
+        for directive in builtin_directives() {
+            if !schema
+                .directive_index
+                .contains_key(AsRef::<str>::as_ref(&directive.name))
+            {
+                schema.add_directive(directive);
+            }
+        }
+
+        match schema_definition {
+            Some(definition) => {
+                schema.query = schema.root("query", definition.query.as_ref())?;
+                schema.mutation = schema.root("mutation", definition.mutation.as_ref())?;
+                schema.subscription =
+                    schema.root("subscription", definition.subscription.as_ref())?;
+            }
+            None => {
+                schema.query = schema.default_root("Query");
+                schema.mutation = schema.default_root("Mutation");
+                schema.subscription = schema.default_root("Subscription");
+            }
+        }
+        Ok(schema)
+    }
+
+    fn add_type(&mut self, definition: TypeDefinition<'a, T>) {
+        self.type_index
+            .insert(definition.name().to_string(), self.types.len());
+        self.types.push(definition);
+    }
+
+    fn add_directive(&mut self, directive: DirectiveDefinition<'a, T>) {
+        self.directive_index
+            .insert(directive.name.as_ref().to_string(), self.directives.len());
+        self.directives.push(directive);
+    }
+
+    fn root(
+        &self,
+        operation: &'static str,
+        name: Option<&T::Value>,
+    ) -> Result<Option<usize>, SchemaError> {
+        let name = match name {
+            Some(name) => name.as_ref(),
+            None => return Ok(None),
+        };
+        match self.type_index.get(name) {
+            Some(&idx) if matches!(self.types[idx], TypeDefinition::Object(_)) => Ok(Some(idx)),
+            Some(_) => Err(SchemaError::RootTypeNotObject {
+                operation,
+                name: name.to_string(),
+            }),
+            None => Err(SchemaError::UndefinedRootType {
+                operation,
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Returns the type with the conventional name of a root type, if it
+    /// is an object type
+    fn default_root(&self, name: &str) -> Option<usize> {
+        self.type_index
+            .get(name)
+            .copied()
+            .filter(|&idx| matches!(self.types[idx], TypeDefinition::Object(_)))
+    }
+}
+
+impl<'a, T: Text<'a>> Schema<'a, T> {
+    fn object(&self, idx: Option<usize>) -> Option<&ObjectType<'a, T>> {
+        match self.types[idx?] {
+            TypeDefinition::Object(ref object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn query_type(&self) -> Option<&ObjectType<'a, T>> {
+        self.object(self.query)
+    }
+
+    pub fn mutation_type(&self) -> Option<&ObjectType<'a, T>> {
+        self.object(self.mutation)
+    }
+
+    pub fn subscription_type(&self) -> Option<&ObjectType<'a, T>> {
+        self.object(self.subscription)
+    }
+
+    pub fn type_by_name(&self, name: &str) -> Option<&TypeDefinition<'a, T>> {
+        self.type_index.get(name).map(|&idx| &self.types[idx])
+    }
+
+    pub fn directive_by_name(&self, name: &str) -> Option<&DirectiveDefinition<'a, T>> {
+        self.directive_index
+            .get(name)
+            .map(|&idx| &self.directives[idx])
+    }
+
+    /// Returns the types in the order of the document, followed by the
+    /// built-in scalars which the document doesn't define
+    pub fn types(&self) -> &[TypeDefinition<'a, T>] {
+        &self.types
+    }
+
+    /// Returns the directives in the order of the document, followed by
+    /// the built-in directives which the document doesn't define
+    pub fn directives(&self) -> &[DirectiveDefinition<'a, T>] {
+        &self.directives
+    }
+}
+
+/// Merges an extension into the definition of the type
+fn extend<'a, T: Text<'a> + Clone>(
+    definition: &mut TypeDefinition<'a, T>,
+    extension: &TypeExtension<'a, T>,
+) -> Result<(), SchemaError> {
+    match (definition, extension) {
+        (TypeDefinition::Scalar(t), TypeExtension::Scalar(e)) => {
+            t.directives.extend(e.directives.iter().cloned());
+        }
+        (TypeDefinition::Object(t), TypeExtension::Object(e)) => {
+            t.implements_interfaces
+                .extend(e.implements_interfaces.iter().cloned());
+            t.directives.extend(e.directives.iter().cloned());
+            t.fields.extend(e.fields.iter().cloned());
+        }
+        (TypeDefinition::Interface(t), TypeExtension::Interface(e)) => {
+            t.implements_interfaces
+                .extend(e.implements_interfaces.iter().cloned());
+            t.directives.extend(e.directives.iter().cloned());
+            t.fields.extend(e.fields.iter().cloned());
+        }
+        (TypeDefinition::Union(t), TypeExtension::Union(e)) => {
+            t.directives.extend(e.directives.iter().cloned());
+            t.types.extend(e.types.iter().cloned());
+        }
+        (TypeDefinition::Enum(t), TypeExtension::Enum(e)) => {
+            t.directives.extend(e.directives.iter().cloned());
+            t.values.extend(e.values.iter().cloned());
+        }
+        (TypeDefinition::InputObject(t), TypeExtension::InputObject(e)) => {
+            t.directives.extend(e.directives.iter().cloned());
+            t.fields.extend(e.fields.iter().cloned());
+        }
+        (_, extension) => {
+            return Err(SchemaError::ExtensionKindMismatch {
+                name: extension.name().to_string(),
+                position: extension.span().start,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Returns the directives defined by the specification
+fn builtin_directives<'a, T: Text<'a>>() -> Vec<DirectiveDefinition<'a, T>> {
+    use self::DirectiveLocation::*;
+    fn directive<'a, T: Text<'a>>(
+        name: &'static str,
+        argument: InputValue<'a, T>,
+        locations: &[DirectiveLocation],
+    ) -> DirectiveDefinition<'a, T> {
+        let mut directive = DirectiveDefinition::new(name.into());
+        directive.arguments.push(argument);
+        directive.locations.extend_from_slice(locations);
+        directive
+    }
+    fn argument<'a, T: Text<'a>>(name: &'static str, value_type: Type<'a, T>) -> InputValue<'a, T> {
+        InputValue {
+            position: Pos::default(),
+            end: Pos::default(),
+            description: None,
+            name: name.into(),
+            value_type,
+            default_value: None,
+            directives: Vec::new(),
+        }
+    }
+    let non_null = |name: &'static str| Type::NonNullType(Box::new(Type::NamedType(name.into())));
+    let mut reason = argument("reason", Type::NamedType("String".into()));
+    reason.default_value = Some(crate::directives::DEFAULT_DEPRECATION_REASON.into());
+    vec![
+        directive(
+            "skip",
+            argument("if", non_null("Boolean")),
+            &[Field, FragmentSpread, InlineFragment],
+        ),
+        directive(
+            "include",
+            argument("if", non_null("Boolean")),
+            &[Field, FragmentSpread, InlineFragment],
+        ),
+        directive(
+            "deprecated",
+            reason,
+            &[
+                FieldDefinition,
+                ArgumentDefinition,
+                InputFieldDefinition,
+                EnumValue,
+            ],
+        ),
+        directive(
+            "specifiedBy",
+            argument("url", non_null("String")),
+            &[Scalar],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Schema, SchemaError};
+    use crate::parse_schema;
+    use crate::schema::TypeDefinition;
+
+    fn resolve(source: &str) -> Result<Schema<'_, &str>, SchemaError> {
+        Schema::new(&parse_schema::<&str>(source).unwrap())
+    }
+
+    #[test]
+    fn roots() {
+        let schema = resolve("type Query { a: Int } type Mutation { b: Int }").unwrap();
+        assert_eq!(schema.query_type().unwrap().name, "Query");
+        assert_eq!(schema.mutation_type().unwrap().name, "Mutation");
+        assert!(schema.subscription_type().is_none());
+
+        // the conventional names are ignored with a schema definition
+        let schema =
+            resolve("schema { query: Q } type Q { a: Int } type Mutation { b: Int }").unwrap();
+        assert_eq!(schema.query_type().unwrap().name, "Q");
+        assert!(schema.mutation_type().is_none());
+
+        assert_eq!(
+            resolve("schema { query: Q }").unwrap_err(),
+            SchemaError::UndefinedRootType {
+                operation: "query",
+                name: "Q".into()
+            }
+        );
+        assert_eq!(
+            resolve("schema { query: Q } scalar Q")
+                .unwrap_err()
+                .to_string(),
+            "query root type `Q` is not an object type"
+        );
+        assert!(resolve("scalar Query").unwrap().query_type().is_none());
+    }
+
+    #[test]
+    fn extensions_and_builtins() {
+        let schema = resolve(
+            "extend enum E @a { C }
+            enum E { A B }
+            extend enum E { D }
+            scalar String @specifiedBy(url: \"s\")
+            directive @skip on QUERY",
+        )
+        .unwrap();
+        match *schema.type_by_name("E").unwrap() {
+            TypeDefinition::Enum(ref e) => {
+                let values = e.values.iter().map(|v| v.name).collect::<Vec<_>>();
+                assert_eq!(values, ["A", "B", "C", "D"]);
+                assert_eq!(e.directives.len(), 1);
+            }
+            _ => unreachable!(),
+        }
+        let types = schema.types().iter().map(|t| t.name()).collect::<Vec<_>>();
+        assert_eq!(types, ["E", "String", "Int", "Float", "Boolean", "ID"]);
+        assert!(schema
+            .directive_by_name("skip")
+            .unwrap()
+            .arguments
+            .is_empty());
+        let deprecated = schema.directive_by_name("deprecated").unwrap();
+        assert_eq!(
+            deprecated.to_string(),
+            "directive @deprecated(reason: String = \"No longer supported\") \
+             on FIELD_DEFINITION | ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION | ENUM_VALUE\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            resolve("type A { a: Int }\nscalar A")
+                .unwrap_err()
+                .to_string(),
+            "type `A` is defined again at 2:1"
+        );
+        assert_eq!(
+            resolve("directive @a on FIELD\ndirective @a on FIELD")
+                .unwrap_err()
+                .to_string(),
+            "directive `@a` is defined again at 2:1"
+        );
+        assert_eq!(
+            resolve("schema { query: Q }\nschema { query: Q }\ntype Q { a: Int }")
+                .unwrap_err()
+                .to_string(),
+            "schema is defined again at 2:1"
+        );
+        assert_eq!(
+            resolve("extend type A { a: Int }").unwrap_err().to_string(),
+            "extension at 1:8 extends undefined type `A`"
+        );
+        assert_eq!(
+            resolve("type A { a: Int }\nextend enum A { B }")
+                .unwrap_err()
+                .to_string(),
+            "extension at 2:8 doesn't match the kind of type `A`"
+        );
+        let err = resolve("schema { query: Q }").unwrap_err();
+        assert_eq!(
+            err.spec_reference().unwrap().to_string(),
+            "§3.3.1 Root Operation Types"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Traversal of the schema AST
+//!
+//! Works like [the query visitor](../../query/visit/index.html): implement
+//! [`Visitor`](trait.Visitor.html) overriding the hooks for the nodes of
+//! interest and pass it to [`walk_document`](fn.walk_document.html).
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::schema::{parse_schema, Field};
+//! use graphql_parser::schema::visit::{walk_document, Visitor};
+//!
+//! struct Deprecated<'d>(Vec<&'d str>);
+//!
+//! impl<'d, 'a> Visitor<'d, 'a, &'a str> for Deprecated<'d> {
+//!     fn enter_field(&mut self, field: &'d Field<'a, &'a str>) {
+//!         if field.directives.iter().any(|d| d.name == "deprecated") {
+//!             self.0.push(field.name);
+//!         }
+//!     }
+//! }
+//!
+//! let doc = parse_schema::<&str>("
+//!     type User { name: String, login: String @deprecated }
+//!     extend type User { email: String @deprecated }
+//! ").unwrap();
+//! let mut found = Deprecated(Vec::new());
+//! walk_document(&mut found, &doc);
+//! assert_eq!(found.0, ["login", "email"]);
+//! ```
+use crate::common::{Directive, Text, Value};
+use crate::schema::ast::*;
+
+/// Hooks called when entering and leaving nodes of a schema document
+///
+/// `enter_*` hooks are called before the children of the node are walked
+/// and `leave_*` hooks after.
+#[allow(unused_variables)]
+pub trait Visitor<'d, 'a, T: Text<'a>> {
+    fn enter_document(&mut self, doc: &'d Document<'a, T>) {}
+    fn leave_document(&mut self, doc: &'d Document<'a, T>) {}
+    fn enter_schema_definition(&mut self, schema: &'d SchemaDefinition<'a, T>) {}
+    fn leave_schema_definition(&mut self, schema: &'d SchemaDefinition<'a, T>) {}
+    fn enter_type_definition(&mut self, def: &'d TypeDefinition<'a, T>) {}
+    fn leave_type_definition(&mut self, def: &'d TypeDefinition<'a, T>) {}
+    fn enter_type_extension(&mut self, ext: &'d TypeExtension<'a, T>) {}
+    fn leave_type_extension(&mut self, ext: &'d TypeExtension<'a, T>) {}
+    fn enter_directive_definition(&mut self, def: &'d DirectiveDefinition<'a, T>) {}
+    fn leave_directive_definition(&mut self, def: &'d DirectiveDefinition<'a, T>) {}
+    /// Called for fields of objects and interfaces
+    fn enter_field(&mut self, field: &'d Field<'a, T>) {}
+    fn leave_field(&mut self, field: &'d Field<'a, T>) {}
+    /// Called for arguments of fields and directive definitions, and for
+    /// fields of input objects
+    fn enter_input_value(&mut self, value: &'d InputValue<'a, T>) {}
+    fn leave_input_value(&mut self, value: &'d InputValue<'a, T>) {}
+    fn enter_enum_value(&mut self, value: &'d EnumValue<'a, T>) {}
+    fn leave_enum_value(&mut self, value: &'d EnumValue<'a, T>) {}
+    fn enter_directive(&mut self, dir: &'d Directive<'a, T>) {}
+    fn leave_directive(&mut self, dir: &'d Directive<'a, T>) {}
+    /// Called for default values, directive arguments and items of lists
+    /// and objects
+    fn enter_value(&mut self, value: &'d Value<'a, T>) {}
+    fn leave_value(&mut self, value: &'d Value<'a, T>) {}
+}
+
+/// Children of a type definition or extension
+struct Members<'d, 'a, T: Text<'a>> {
+    directives: &'d [Directive<'a, T>],
+    fields: &'d [Field<'a, T>],
+    input_values: &'d [InputValue<'a, T>],
+    enum_values: &'d [EnumValue<'a, T>],
+}
+
+impl<'d, 'a, T: Text<'a>> Members<'d, 'a, T> {
+    fn new(directives: &'d [Directive<'a, T>]) -> Members<'d, 'a, T> {
+        Members {
+            directives,
+            fields: &[],
+            input_values: &[],
+            enum_values: &[],
+        }
+    }
+
+    fn walk<V: Visitor<'d, 'a, T> + ?Sized>(&self, visitor: &mut V) {
+        walk_directives(visitor, self.directives);
+        for field in self.fields {
+            walk_field(visitor, field);
+        }
+        for value in self.input_values {
+            walk_input_value(visitor, value);
+        }
+        for value in self.enum_values {
+            visitor.enter_enum_value(value);
+            walk_directives(visitor, &value.directives);
+            visitor.leave_enum_value(value);
+        }
+    }
+}
+
+pub fn walk_document<'d, 'a, T, V>(visitor: &mut V, doc: &'d Document<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_document(doc);
+    for def in &doc.definitions {
+        walk_definition(visitor, def);
+    }
+    visitor.leave_document(doc);
+}
+
+pub fn walk_definition<'d, 'a, T, V>(visitor: &mut V, def: &'d Definition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    match *def {
+        Definition::SchemaDefinition(ref schema) => {
+            visitor.enter_schema_definition(schema);
+            walk_directives(visitor, &schema.directives);
+            visitor.leave_schema_definition(schema);
+        }
+        Definition::TypeDefinition(ref def) => walk_type_definition(visitor, def),
+        Definition::TypeExtension(ref ext) => walk_type_extension(visitor, ext),
+        Definition::DirectiveDefinition(ref def) => {
+            visitor.enter_directive_definition(def);
+            for arg in &def.arguments {
+                walk_input_value(visitor, arg);
+            }
+            visitor.leave_directive_definition(def);
+        }
+    }
+}
+
+pub fn walk_type_definition<'d, 'a, T, V>(visitor: &mut V, def: &'d TypeDefinition<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_type_definition(def);
+    let members = match *def {
+        TypeDefinition::Scalar(ref scalar) => Members::new(&scalar.directives),
+        TypeDefinition::Object(ref obj) => Members {
+            fields: &obj.fields,
+            ..Members::new(&obj.directives)
+        },
+        TypeDefinition::Interface(ref iface) => Members {
+            fields: &iface.fields,
+            ..Members::new(&iface.directives)
+        },
+        TypeDefinition::Union(ref union) => Members::new(&union.directives),
+        TypeDefinition::Enum(ref enum_) => Members {
+            enum_values: &enum_.values,
+            ..Members::new(&enum_.directives)
+        },
+        TypeDefinition::InputObject(ref input) => Members {
+            input_values: &input.fields,
+            ..Members::new(&input.directives)
+        },
+    };
+    members.walk(visitor);
+    visitor.leave_type_definition(def);
+}
+
+pub fn walk_type_extension<'d, 'a, T, V>(visitor: &mut V, ext: &'d TypeExtension<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_type_extension(ext);
+    let members = match *ext {
+        TypeExtension::Scalar(ref scalar) => Members::new(&scalar.directives),
+        TypeExtension::Object(ref obj) => Members {
+            fields: &obj.fields,
+            ..Members::new(&obj.directives)
+        },
+        TypeExtension::Interface(ref iface) => Members {
+            fields: &iface.fields,
+            ..Members::new(&iface.directives)
+        },
+        TypeExtension::Union(ref union) => Members::new(&union.directives),
+        TypeExtension::Enum(ref enum_) => Members {
+            enum_values: &enum_.values,
+            ..Members::new(&enum_.directives)
+        },
+        TypeExtension::InputObject(ref input) => Members {
+            input_values: &input.fields,
+            ..Members::new(&input.directives)
+        },
+    };
+    members.walk(visitor);
+    visitor.leave_type_extension(ext);
+}
+
+pub fn walk_field<'d, 'a, T, V>(visitor: &mut V, field: &'d Field<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_field(field);
+    for arg in &field.arguments {
+        walk_input_value(visitor, arg);
+    }
+    walk_directives(visitor, &field.directives);
+    visitor.leave_field(field);
+}
+
+pub fn walk_input_value<'d, 'a, T, V>(visitor: &mut V, input: &'d InputValue<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_input_value(input);
+    if let Some(ref value) = input.default_value {
+        walk_value(visitor, value);
+    }
+    walk_directives(visitor, &input.directives);
+    visitor.leave_input_value(input);
+}
+
+pub fn walk_directive<'d, 'a, T, V>(visitor: &mut V, dir: &'d Directive<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_directive(dir);
+    for (_, value) in &dir.arguments {
+        walk_value(visitor, value);
+    }
+    visitor.leave_directive(dir);
+}
+
+pub fn walk_value<'d, 'a, T, V>(visitor: &mut V, value: &'d Value<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    visitor.enter_value(value);
+    match *value {
+        Value::List(ref items) => {
+            for item in items {
+                walk_value(visitor, item);
+            }
+        }
+        Value::Object(ref fields) => {
+            for (_, item) in fields {
+                walk_value(visitor, item);
+            }
+        }
+        _ => {}
+    }
+    visitor.leave_value(value);
+}
+
+fn walk_directives<'d, 'a, T, V>(visitor: &mut V, directives: &'d [Directive<'a, T>])
+where
+    T: Text<'a>,
+    V: Visitor<'d, 'a, T> + ?Sized,
+{
+    for dir in directives {
+        walk_directive(visitor, dir);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{walk_document, Visitor};
+    use crate::common::{Directive, Value};
+    use crate::parse_schema;
+    use crate::schema::*;
+
+    fn type_name(def: &TypeDefinition<String>) -> String {
+        match *def {
+            TypeDefinition::Scalar(ref t) => t.name.clone(),
+            TypeDefinition::Object(ref t) => t.name.clone(),
+            TypeDefinition::Interface(ref t) => t.name.clone(),
+            TypeDefinition::Union(ref t) => t.name.clone(),
+            TypeDefinition::Enum(ref t) => t.name.clone(),
+            TypeDefinition::InputObject(ref t) => t.name.clone(),
+        }
+    }
+
+    #[derive(Default)]
+    struct Events {
+        log: Vec<String>,
+    }
+
+    impl<'d, 'a> Visitor<'d, 'a, String> for Events {
+        fn enter_schema_definition(&mut self, _: &'d SchemaDefinition<'a, String>) {
+            self.log.push("schema".into());
+        }
+        fn enter_type_definition(&mut self, def: &'d TypeDefinition<'a, String>) {
+            self.log.push(format!("type {}", type_name(def)));
+        }
+        fn leave_type_definition(&mut self, def: &'d TypeDefinition<'a, String>) {
+            self.log.push(format!("/type {}", type_name(def)));
+        }
+        fn enter_type_extension(&mut self, _: &'d TypeExtension<'a, String>) {
+            self.log.push("extend".into());
+        }
+        fn enter_directive_definition(&mut self, def: &'d DirectiveDefinition<'a, String>) {
+            self.log.push(format!("directive {}", def.name));
+        }
+        fn enter_field(&mut self, field: &'d Field<'a, String>) {
+            self.log.push(field.name.clone());
+        }
+        fn enter_input_value(&mut self, value: &'d InputValue<'a, String>) {
+            self.log.push(format!("{}:", value.name));
+        }
+        fn enter_enum_value(&mut self, value: &'d EnumValue<'a, String>) {
+            self.log.push(value.name.clone());
+        }
+        fn enter_directive(&mut self, dir: &'d Directive<'a, String>) {
+            self.log.push(format!("@{}", dir.name));
+        }
+        fn enter_value(&mut self, value: &'d Value<'a, String>) {
+            self.log.push(value.to_string());
+        }
+    }
+
+    #[test]
+    fn order() {
+        let doc = parse_schema::<String>(
+            "schema @s { query: Q }
+             type Q @t { f(a: Int = 1 @x): E @y(v: [2]) }
+             enum E { A @z B }
+             input I { i: Int }
+             extend scalar S @w
+             directive @x(r: Int) on ARGUMENT_DEFINITION",
+        )
+        .unwrap();
+        let mut events = Events::default();
+        walk_document(&mut events, &doc);
+        assert_eq!(
+            events.log,
+            [
+                "schema",
+                "@s",
+                "type Q",
+                "@t",
+                "f",
+                "a:",
+                "1",
+                "@x",
+                "@y",
+                "[2]",
+                "2",
+                "/type Q",
+                "type E",
+                "A",
+                "@z",
+                "B",
+                "/type E",
+                "type I",
+                "i:",
+                "/type I",
+                "extend",
+                "@w",
+                "directive x",
+                "r:",
+            ]
+        );
+    }
+}
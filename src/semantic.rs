@@ -0,0 +1,343 @@
+//! Comparison of syntax trees ignoring positions
+//!
+//! The derived `PartialEq` of the AST compares positions too, so a
+//! document never equals the one parsed back from its formatted text.
+//! [`SemanticEq`](trait.SemanticEq.html) compares everything else.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::parse_query;
+//! use graphql_parser::semantic::SemanticEq;
+//!
+//! let doc = parse_query::<&str>("query Q($id: ID) { user(id: $id) { name } }").unwrap();
+//! let printed = doc.to_string();
+//! let reparsed = parse_query::<&str>(&printed).unwrap();
+//! assert_ne!(doc, reparsed);
+//! assert!(doc.semantic_eq(&reparsed));
+//! ```
+use crate::common::{same_type, Directive, Text, Type, Value};
+use crate::{document, query, schema};
+
+/// Equality of syntax trees which ignores positions, spans and comments
+///
+/// Everything else is compared as `PartialEq` would, including the order
+/// of definitions, fields and arguments, and descriptions. Floats compare
+/// by value, so `1.0` equals `1.00`.
+pub trait SemanticEq {
+    fn semantic_eq(&self, other: &Self) -> bool;
+}
+
+impl<N: SemanticEq> SemanticEq for [N] {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.semantic_eq(b))
+    }
+}
+
+impl<N: SemanticEq> SemanticEq for Vec<N> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self[..].semantic_eq(&other[..])
+    }
+}
+
+impl<N: SemanticEq> SemanticEq for Option<N> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.semantic_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<N: SemanticEq + ?Sized> SemanticEq for Box<N> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        (**self).semantic_eq(other)
+    }
+}
+
+impl<'a, T: Text<'a>> SemanticEq for Type<'a, T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        same_type(self, other)
+    }
+}
+
+impl<'a, T: Text<'a>> SemanticEq for Value<'a, T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Variable(a), Value::Variable(b)) => a == b,
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::Enum(a), Value::Enum(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a.semantic_eq(b),
+            (Value::Object(a), Value::Object(b)) => pairs(a, b),
+            _ => false,
+        }
+    }
+}
+
+fn eq<V: PartialEq>(a: &V, b: &V) -> bool {
+    a == b
+}
+
+fn node<N: SemanticEq>(a: &N, b: &N) -> bool {
+    a.semantic_eq(b)
+}
+
+/// Compares arguments and object fields, in order
+fn pairs<'a, T: Text<'a>>(a: &[(T::Value, Value<'a, T>)], b: &[(T::Value, Value<'a, T>)]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|((a_name, a), (b_name, b))| a_name == b_name && a.semantic_eq(b))
+}
+
+/// Implements `SemanticEq` for structs, comparing each field with the
+/// given function and ignoring the fields listed before the `;`
+///
+/// The struct is destructured so that adding a field fails to compile
+/// until it is listed here.
+macro_rules! impl_semantic_eq {
+    ($(
+        $module:ident::$name:ident {
+            $($ignored:ident),*;
+            $($field:ident: $compare:ident),* $(,)?
+        }
+    )*) => {
+        $(
+            impl<'a, T: Text<'a>> SemanticEq for $module::$name<'a, T> {
+                fn semantic_eq(&self, other: &Self) -> bool {
+                    let $module::$name { $($ignored: _,)* $($field,)* } = self;
+                    true $(&& $compare($field, &other.$field))*
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `SemanticEq` for enums whose variants all wrap a single node
+macro_rules! impl_semantic_eq_enum {
+    ($($module:ident::$name:ident { $($variant:ident),* $(,)? })*) => {
+        $(
+            impl<'a, T: Text<'a>> SemanticEq for $module::$name<'a, T> {
+                fn semantic_eq(&self, other: &Self) -> bool {
+                    match (self, other) {
+                        $(
+                            ($module::$name::$variant(a), $module::$name::$variant(b)) => {
+                                a.semantic_eq(b)
+                            }
+                        )*
+                        _ => false,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl<'a, T: Text<'a>> SemanticEq for Directive<'a, T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        let Directive {
+            position: _,
+            end: _,
+            name,
+            arguments,
+        } = self;
+        *name == other.name && pairs(arguments, &other.arguments)
+    }
+}
+
+impl_semantic_eq! {
+    query::Document { comments; definitions: node }
+    query::FragmentDefinition {
+        position, end;
+        name: eq, type_condition: node, directives: node, selection_set: node
+    }
+    query::Operation {
+        position, end;
+        kind: eq, name: eq, variable_definitions: node, directives: node, selection_set: node
+    }
+    query::Query {
+        position, end;
+        name: eq, variable_definitions: node, directives: node, selection_set: node
+    }
+    query::Mutation {
+        position, end;
+        name: eq, variable_definitions: node, directives: node, selection_set: node
+    }
+    query::Subscription {
+        position, end;
+        name: eq, variable_definitions: node, directives: node, selection_set: node
+    }
+    query::SelectionSet { span; items: node }
+    query::VariableDefinition {
+        position, end;
+        name: eq, var_type: node, default_value: node
+    }
+    query::Field {
+        position, end;
+        alias: eq, name: eq, arguments: pairs, directives: node, selection_set: node
+    }
+    query::FragmentSpread { position, end; fragment_name: eq, directives: node }
+    query::InlineFragment {
+        position, end;
+        type_condition: node, directives: node, selection_set: node
+    }
+
+    schema::Document { comments; definitions: node }
+    schema::SchemaDefinition {
+        position, end;
+        directives: node, query: eq, mutation: eq, subscription: eq
+    }
+    schema::ScalarType { position, end; description: eq, name: eq, directives: node }
+    schema::ScalarTypeExtension { position, end; name: eq, directives: node }
+    schema::ObjectType {
+        position, end;
+        description: eq, name: eq, implements_interfaces: eq, directives: node, fields: node
+    }
+    schema::ObjectTypeExtension {
+        position, end;
+        name: eq, implements_interfaces: eq, directives: node, fields: node
+    }
+    schema::Field {
+        position, end;
+        description: eq, name: eq, arguments: node, field_type: node, directives: node
+    }
+    schema::InputValue {
+        position, end;
+        description: eq, name: eq, value_type: node, default_value: node, directives: node
+    }
+    schema::InterfaceType {
+        position, end;
+        description: eq, name: eq, implements_interfaces: eq, directives: node, fields: node
+    }
+    schema::InterfaceTypeExtension {
+        position, end;
+        name: eq, implements_interfaces: eq, directives: node, fields: node
+    }
+    schema::UnionType {
+        position, end;
+        description: eq, name: eq, directives: node, types: eq
+    }
+    schema::UnionTypeExtension { position, end; name: eq, directives: node, types: eq }
+    schema::EnumType {
+        position, end;
+        description: eq, name: eq, directives: node, values: node
+    }
+    schema::EnumValue { position, end; description: eq, name: eq, directives: node }
+    schema::EnumTypeExtension { position, end; name: eq, directives: node, values: node }
+    schema::InputObjectType {
+        position, end;
+        description: eq, name: eq, directives: node, fields: node
+    }
+    schema::InputObjectTypeExtension { position, end; name: eq, directives: node, fields: node }
+    schema::DirectiveDefinition {
+        position, end;
+        description: eq, name: eq, arguments: node, repeatable: eq, locations: eq
+    }
+
+    document::Document { comments; definitions: node }
+}
+
+impl_semantic_eq_enum! {
+    query::Definition { Operation, Fragment }
+    query::OperationDefinition { SelectionSet, Query, Mutation, Subscription }
+    query::Selection { Field, FragmentSpread, InlineFragment }
+    schema::Definition { SchemaDefinition, TypeDefinition, TypeExtension, DirectiveDefinition }
+    schema::TypeDefinition { Scalar, Object, Interface, Union, Enum, InputObject }
+    schema::TypeExtension { Scalar, Object, Interface, Union, Enum, InputObject }
+    document::Definition { Executable, TypeSystem }
+}
+
+impl<'a, T: Text<'a>> SemanticEq for query::TypeCondition<'a, T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        let (query::TypeCondition::On(a), query::TypeCondition::On(b)) = (self, other);
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SemanticEq;
+    use crate::{parse_document, parse_query, parse_schema, Parser};
+
+    #[test]
+    fn query_roundtrip() {
+        let source = r#"
+            query Q($id: ID = 1, $f: [Float!]) @op {
+                alias: user(id: $id, obj: {a: [1.0, "s", E]}) @include(if: true) {
+                    ...F
+                    ... on User { name }
+                    ... @skip(if: $s) { id }
+                }
+            }
+            # comment
+            fragment F on User { id }
+            { shorthand }
+        "#;
+        let doc = Parser::new()
+            .preserve_comments(true)
+            .parse_query::<&str>(source)
+            .unwrap();
+        let printed = doc.to_string();
+        let reparsed = parse_query::<&str>(&printed).unwrap();
+        assert!(doc != reparsed);
+        assert!(doc.semantic_eq(&reparsed));
+        assert!(reparsed.semantic_eq(&doc));
+
+        let floats = parse_query::<&str>("{ a(x: 1.00) }").unwrap();
+        assert!(floats.semantic_eq(&parse_query("{ a(x: 1.0) }").unwrap()));
+
+        for other in [
+            "{ a(x: 1) }",
+            "{ a(y: 1.0) }",
+            "{ b: a(x: 1.0) }",
+            "{ a(x: 1.0) @d }",
+            "{ a(x: 1.0) { b } }",
+            "{ a(x: 1.0) } { a(x: 1.0) }",
+            "query { a(x: 1.0) }",
+        ] {
+            let other = parse_query::<&str>(other).unwrap();
+            assert!(!floats.semantic_eq(&other), "{}", other);
+        }
+    }
+
+    #[test]
+    fn schema_roundtrip() {
+        let source = r#"
+            schema @s { query: Query }
+            "Description"
+            type Query implements I @d(x: [1]) { f(a: Int = 1 @deprecated): [I!]! }
+            extend type Query { g: Int }
+            interface I { f(a: Int): [I!]! }
+            union U = Query
+            enum E { A B @deprecated }
+            input In { a: Int = 2 }
+            scalar S
+            directive @d(x: [Int]) repeatable on OBJECT | FIELD
+        "#;
+        let schema = parse_schema::<String>(source).unwrap().into_static();
+        let reparsed = parse_schema::<String>(&schema.to_string())
+            .unwrap()
+            .into_static();
+        assert!(schema != reparsed);
+        assert!(schema.semantic_eq(&reparsed));
+
+        for other in [
+            source.replace("\"Description\"", ""),
+            source.replace("Int = 2", "Int = 3"),
+            source.replace("repeatable", ""),
+            source.replace("[I!]!", "[I]!"),
+        ] {
+            let other = parse_schema::<String>(&other).unwrap().into_static();
+            assert!(!schema.semantic_eq(&other), "{}", other);
+        }
+
+        let document = parse_document::<&str>("type A { a: Int }\n{ a }").unwrap();
+        let spaced = parse_document::<&str>("type A {\n  a: Int\n}\n\n{\n  a\n}").unwrap();
+        assert!(document.semantic_eq(&spaced));
+    }
+}
@@ -0,0 +1,193 @@
+//! Rendering of diagnostics along with the source they point at
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::parse_query;
+//!
+//! let source = "query {\n  user(id: ) { name }\n}";
+//! let err = parse_query::<&str>(source).unwrap_err();
+//! assert_eq!(err.render_snippets(source, Some("user.graphql")), "\
+//! error[unexpected-token]: Unexpected `)`
+//!  --> user.graphql:2:12
+//!   |
+//! 2 |   user(id: ) { name }
+//!   |            ^ Expected `IntValue`, `FloatValue`, `BigIntValue`, \
+//! `StringValue`, `BlockString`, `true`, `false`, `null` or `Name`
+//! ");
+//! ```
+use std::fmt;
+
+use crate::position::{Pos, Span};
+
+/// Severity of an annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A diagnostic pointing at a range of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub severity: Severity,
+    /// Stable identifier of the diagnostic, shown next to the severity
+    pub code: Option<&'static str>,
+    pub title: String,
+    pub span: Span,
+    /// Text shown next to the underlined range
+    pub label: String,
+}
+
+impl Annotation {
+    /// Renders the annotation with the line of `source` it points at
+    ///
+    /// `origin` names the source, usually by its file path. Only the first
+    /// line of a span covering several lines is underlined. If the span
+    /// doesn't point into `source`, the snippet is left out.
+    pub fn render(&self, source: &str, origin: Option<&str>) -> String {
+        let mut out = self.severity.to_string();
+        if let Some(code) = self.code {
+            out.push('[');
+            out.push_str(code);
+            out.push(']');
+        }
+        out.push_str(": ");
+        out.push_str(&self.title);
+        out.push('\n');
+        let start = self.span.start;
+        let line = match line_at(source, start.offset) {
+            Some(line) => line,
+            None => {
+                out.push_str(&format!(" --> {}\n", location(origin, start)));
+                return out;
+            }
+        };
+        let number = start.line.to_string();
+        let gutter = " ".repeat(number.len());
+        out.push_str(&format!("{}--> {}\n", gutter, location(origin, start)));
+        out.push_str(&format!("{} |\n", gutter));
+        out.push_str(&format!("{} | {}\n", number, expand_tabs(line.text)));
+
+        let before = &line.text[..start.offset - line.offset];
+        let end = self
+            .span
+            .end
+            .offset
+            .clamp(start.offset, line.offset + line.text.len());
+        let marked = &line.text[start.offset - line.offset..end - line.offset];
+        let padding = " ".repeat(width(before));
+        let carets = "^".repeat(width(marked).max(1));
+        out.push_str(&format!("{} | {}{}", gutter, padding, carets));
+        if !self.label.is_empty() {
+            out.push(' ');
+            out.push_str(&self.label);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+fn location(origin: Option<&str>, start: Pos) -> String {
+    match origin {
+        Some(origin) => format!("{}:{}", origin, start),
+        None => start.to_string(),
+    }
+}
+
+struct Line<'a> {
+    /// Byte offset of the start of the line
+    offset: usize,
+    text: &'a str,
+}
+
+fn line_at(source: &str, offset: usize) -> Option<Line<'_>> {
+    if offset > source.len() || !source.is_char_boundary(offset) {
+        return None;
+    }
+    let start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let end = source[offset..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |idx| offset + idx);
+    Some(Line {
+        offset: start,
+        text: &source[start..end],
+    })
+}
+
+const TAB: &str = "    ";
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', TAB)
+}
+
+fn width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB.len() } else { 1 })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Annotation, Severity};
+    use crate::position::{Pos, Span};
+    use crate::schema::parse_schema_with_warnings;
+
+    #[test]
+    fn tabs_and_multibyte() {
+        let source = "{\n\t\u{e9}a(x: \"\u{1f600}\")\n}";
+        let annotation = Annotation {
+            severity: Severity::Error,
+            code: None,
+            title: "bad string".into(),
+            span: Span::new(Pos::new(2, 16, 10), Pos::new(2, 19, 16)),
+            label: String::new(),
+        };
+        assert_eq!(
+            annotation.render(source, None),
+            "error: bad string\n \
+             --> 2:16\n  \
+             |\n\
+             2 |     \u{e9}a(x: \"\u{1f600}\")\n  \
+             |           ^^^\n"
+        );
+    }
+
+    #[test]
+    fn outside_of_source() {
+        let annotation = Annotation {
+            severity: Severity::Error,
+            code: Some("x"),
+            title: "t".into(),
+            span: Span::new(Pos::new(3, 1, 100), Pos::new(3, 1, 100)),
+            label: "l".into(),
+        };
+        assert_eq!(
+            annotation.render("", Some("f")),
+            "error[x]: t\n --> f:3:1\n"
+        );
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let source = "type A {\n  a: Int\n}\nschema {}";
+        let (_, warnings) = parse_schema_with_warnings::<&str>(source, 10).unwrap();
+        assert_eq!(
+            warnings[0].annotation().render(source, None),
+            "warning[empty-schema-definition]: schema definition declares no root \
+             operation types\n \
+             --> 4:1\n  \
+             |\n\
+             4 | schema {}\n  \
+             | ^\n"
+        );
+    }
+}
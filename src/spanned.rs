@@ -0,0 +1,441 @@
+//! Values and type references which know their position in the source
+//!
+//! `Value` and `Type` carry no positions, which keeps them cheap and easy
+//! to build and match. Where errors about a literal need to point into the
+//! source, like when validating the arguments of a field, get them with
+//! [`field_arguments`](fn.field_arguments.html) or
+//! [`directive_arguments`](fn.directive_arguments.html): every nested
+//! value keeps its span, and the plain value is one conversion away.
+//! Standalone values, like the ones of a configuration file, are parsed
+//! with [`parse_value`](fn.parse_value.html).
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::query::{Definition, OperationDefinition, Selection, Value};
+//! use graphql_parser::spanned::{self, SpannedValueKind};
+//! use graphql_parser::{parse_query, ParserOptions};
+//!
+//! let source = "query Q {\n  users(ids: [1, \"two\"]) { name }\n}";
+//! let doc = parse_query::<&str>(source).unwrap();
+//! let field = match doc.definitions[0] {
+//!     Definition::Operation(OperationDefinition::Query(ref q)) => match q.selection_set.items[0] {
+//!         Selection::Field(ref field) => field,
+//!         _ => unreachable!(),
+//!     },
+//!     _ => unreachable!(),
+//! };
+//! let arguments = spanned::field_arguments::<&str, _>(source, field, &ParserOptions::new()).unwrap();
+//! assert_eq!(arguments[0].name_span.to_string(), "2:9-2:12");
+//! if let SpannedValueKind::List(ref items) = arguments[0].value.kind {
+//!     assert_eq!(items[1].span.to_string(), "2:18-2:23");
+//! }
+//! assert_eq!(Value::from(arguments[0].value.clone()), field.arguments[0].1);
+//! ```
+use combine::combinator::{eof, optional};
+use combine::Parser;
+
+use crate::common::{argument_nodes, type_node, value_node, Directive, Text, Type, Value};
+use crate::common::{BigNumber, FloatNumber, Number, StringValue};
+use crate::common::{TypeNode, TypeParts, ValueNode, ValueParts};
+use crate::helpers::{name, punct, rule};
+use crate::parser::ParserOptions;
+use crate::position::{Pos, Span};
+use crate::query::{self, ParseError};
+use crate::tokenizer::TokenStream;
+
+/// A value with the source range of every nested value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct SpannedValue<'a, T: Text<'a>> {
+    pub span: Span,
+    pub kind: SpannedValueKind<'a, T>,
+}
+
+/// The variants of `Value`, with spanned values nested
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum SpannedValueKind<'a, T: Text<'a>> {
+    Variable(T::Value),
+    BigInt(BigNumber),
+    Int(Number),
+    Float(FloatNumber<'a, T>),
+    String(StringValue<'a, T>),
+    Boolean(bool),
+    Null,
+    Enum(T::Value),
+    List(Vec<SpannedValue<'a, T>>),
+    /// Fields of an input object in the order of the source
+    Object(Vec<SpannedField<'a, T>>),
+}
+
+/// A field of an input object value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct SpannedField<'a, T: Text<'a>> {
+    pub name: T::Value,
+    /// Range of the name, without the colon
+    pub name_span: Span,
+    pub value: SpannedValue<'a, T>,
+}
+
+/// An argument of a field or directive, which has the same parts as a
+/// field of an input object
+pub type SpannedArgument<'a, T> = SpannedField<'a, T>;
+
+/// A type reference with the source range of every nested type
+///
+/// The span of a non-null type includes the `!`, the span of the type
+/// inside it doesn't.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct SpannedType<'a, T: Text<'a>> {
+    pub span: Span,
+    pub kind: SpannedTypeKind<'a, T>,
+}
+
+/// The variants of `Type`, with spanned types nested
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T::Value: serde::Serialize",
+        deserialize = "T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub enum SpannedTypeKind<'a, T: Text<'a>> {
+    NamedType(T::Value),
+    ListType(Box<SpannedType<'a, T>>),
+    NonNullType(Box<SpannedType<'a, T>>),
+}
+
+impl<'a, T: Text<'a>> SpannedValue<'a, T> {
+    /// Returns the value of a field of an object
+    ///
+    /// As with `Value::get`, the last field wins if the name is repeated.
+    pub fn get(&self, key: &str) -> Option<&SpannedValue<'a, T>> {
+        match self.kind {
+            SpannedValueKind::Object(ref fields) => fields
+                .iter()
+                .rev()
+                .find(|field| field.name.as_ref() == key)
+                .map(|field| &field.value),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> From<SpannedValue<'a, T>> for Value<'a, T> {
+    fn from(value: SpannedValue<'a, T>) -> Value<'a, T> {
+        use self::SpannedValueKind::*;
+        match value.kind {
+            Variable(name) => Value::Variable(name),
+            BigInt(n) => Value::BigInt(n),
+            Int(n) => Value::Int(n),
+            Float(f) => Value::Float(f),
+            String(s) => Value::String(s),
+            Boolean(b) => Value::Boolean(b),
+            Null => Value::Null,
+            Enum(name) => Value::Enum(name),
+            List(items) => Value::List(items.into_iter().map(Value::from).collect()),
+            Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|field| (field.name, Value::from(field.value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> From<SpannedType<'a, T>> for Type<'a, T> {
+    fn from(typ: SpannedType<'a, T>) -> Type<'a, T> {
+        match typ.kind {
+            SpannedTypeKind::NamedType(name) => Type::NamedType(name),
+            SpannedTypeKind::ListType(inner) => Type::ListType(Box::new(Type::from(*inner))),
+            SpannedTypeKind::NonNullType(inner) => Type::NonNullType(Box::new(Type::from(*inner))),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> ValueNode<'a, T> for SpannedValue<'a, T> {
+    fn build(parts: ValueParts<'a, T, Self>, span: Span) -> Self {
+        let kind = match parts {
+            ValueParts::Plain(value) => match value {
+                Value::Variable(name) => SpannedValueKind::Variable(name),
+                Value::BigInt(n) => SpannedValueKind::BigInt(n),
+                Value::Int(n) => SpannedValueKind::Int(n),
+                Value::Float(f) => SpannedValueKind::Float(f),
+                Value::String(s) => SpannedValueKind::String(s),
+                Value::Boolean(b) => SpannedValueKind::Boolean(b),
+                Value::Null => SpannedValueKind::Null,
+                Value::Enum(name) => SpannedValueKind::Enum(name),
+                Value::List(_) | Value::Object(_) => {
+                    unreachable!("plain values are never lists or objects")
+                }
+            },
+            ValueParts::List(items) => SpannedValueKind::List(items),
+            ValueParts::Object(fields) => SpannedValueKind::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, name_span, value)| SpannedField {
+                        name,
+                        name_span,
+                        value,
+                    })
+                    .collect(),
+            ),
+        };
+        SpannedValue { span, kind }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeNode<'a, T> for SpannedType<'a, T> {
+    fn build(parts: TypeParts<'a, T, Self>, span: Span) -> Self {
+        let kind = match parts {
+            TypeParts::Named(name) => SpannedTypeKind::NamedType(name),
+            TypeParts::List(inner) => SpannedTypeKind::ListType(Box::new(inner)),
+            TypeParts::NonNull(inner) => SpannedTypeKind::NonNullType(Box::new(inner)),
+        };
+        SpannedType { span, kind }
+    }
+}
+
+/// Parses a standalone input value, keeping the span of every nested
+/// value
+///
+/// This accepts the same input as `query::parse_value`.
+pub fn parse_value<'a, T>(s: &'a str) -> Result<SpannedValue<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (value, _) = rule("value", value_node).skip(eof()).parse(tokens)?;
+    Ok(value)
+}
+
+/// Parses a standalone input value with the given settings, keeping the
+/// span of every nested value
+pub fn parse_value_with_options<'a, T>(
+    s: &'a str,
+    options: &ParserOptions,
+) -> Result<SpannedValue<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let tokens = options.tokens(s);
+    let (value, _) = rule("value", value_node).skip(eof()).parse(tokens)?;
+    Ok(value)
+}
+
+/// Parses a standalone type reference, keeping the span of every nested
+/// type
+///
+/// This accepts the same input as `query::parse_type`.
+pub fn parse_type<'a, T>(s: &'a str) -> Result<SpannedType<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let tokens = TokenStream::new(s);
+    let (typ, _) = rule("parse_type", type_node).skip(eof()).parse(tokens)?;
+    Ok(typ)
+}
+
+/// Returns the arguments of a field of a query document, keeping the
+/// span of every nested value
+///
+/// `source` and `options` must be the ones the document was parsed with.
+/// The arguments are read again from the source at the position of the
+/// field, so documents built or modified in code aren't supported.
+pub fn field_arguments<'a, 'b, T, U>(
+    source: &'a str,
+    field: &query::Field<'b, U>,
+    options: &ParserOptions,
+) -> Result<Vec<SpannedArgument<'a, T>>, ParseError>
+where
+    T: Text<'a>,
+    U: Text<'b>,
+{
+    let mut tokens = tokens_at(source, field.position, options);
+    let (arguments, _) = name::<'a, T>()
+        .with(optional(punct(":").with(name::<'a, T>())))
+        .with(rule("arguments", argument_nodes))
+        .parse_stream(&mut tokens)
+        .map_err(|e| e.into_inner().error)?;
+    Ok(arguments.into_iter().map(spanned_argument).collect())
+}
+
+/// Returns the arguments of a directive of a query or schema document,
+/// keeping the span of every nested value
+///
+/// See `field_arguments`.
+pub fn directive_arguments<'a, 'b, T, U>(
+    source: &'a str,
+    directive: &Directive<'b, U>,
+    options: &ParserOptions,
+) -> Result<Vec<SpannedArgument<'a, T>>, ParseError>
+where
+    T: Text<'a>,
+    U: Text<'b>,
+{
+    let mut tokens = tokens_at(source, directive.position, options);
+    let (arguments, _) = punct("@")
+        .with(name::<'a, T>())
+        .with(rule("arguments", argument_nodes))
+        .parse_stream(&mut tokens)
+        .map_err(|e| e.into_inner().error)?;
+    Ok(arguments.into_iter().map(spanned_argument).collect())
+}
+
+fn tokens_at<'a>(source: &'a str, position: Pos, options: &ParserOptions) -> TokenStream<'a> {
+    // a position outside of the source fails to parse as end of input
+    let rest = source.get(position.offset..).unwrap_or("");
+    options.tokens_at(rest, position)
+}
+
+fn spanned_argument<'a, T: Text<'a>>(
+    (name, name_span, value): (T::Value, Span, SpannedValue<'a, T>),
+) -> SpannedArgument<'a, T> {
+    SpannedField {
+        name,
+        name_span,
+        value,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{directive_arguments, field_arguments, parse_value_with_options};
+    use super::{parse_type, parse_value, SpannedTypeKind, SpannedValueKind};
+    use crate::common::{Type, Value};
+    use crate::limits::Limits;
+    use crate::parser::ParserOptions;
+    use crate::position::ColumnUnit;
+    use crate::query::{parse_query_with_options, Definition, OperationDefinition, Selection};
+
+    #[test]
+    fn value_spans() {
+        let source = "{a: [1, $x],\n b: {c: \"d\"}}";
+        let value = parse_value::<&str>(source).unwrap();
+        assert_eq!(value.span.to_string(), "1:1-2:14");
+        let a = value.get("a").unwrap();
+        assert_eq!(a.span.to_string(), "1:5-1:12");
+        match a.kind {
+            SpannedValueKind::List(ref items) => {
+                assert_eq!(items[0].span.to_string(), "1:6-1:7");
+                assert_eq!(items[1].span.to_string(), "1:9-1:11");
+                assert_eq!(items[1].kind, SpannedValueKind::Variable("x"));
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+        let b = value.get("b").unwrap();
+        match b.kind {
+            SpannedValueKind::Object(ref fields) => {
+                assert_eq!(fields[0].name_span.to_string(), "2:6-2:7");
+                assert_eq!(fields[0].value.span.to_string(), "2:9-2:12");
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+        assert_eq!(
+            Value::from(value),
+            crate::parse_value::<&str>(source).unwrap()
+        );
+        assert!(parse_value::<&str>("[1").is_err());
+    }
+
+    #[test]
+    fn type_spans() {
+        let typ = parse_type::<&str>("[ Int! ]!").unwrap();
+        assert_eq!(typ.span.to_string(), "1:1-1:10");
+        let list = match typ.kind {
+            SpannedTypeKind::NonNullType(ref inner) => inner,
+            ref kind => panic!("unexpected {:?}", kind),
+        };
+        assert_eq!(list.span.to_string(), "1:1-1:9");
+        let item = match list.kind {
+            SpannedTypeKind::ListType(ref inner) => inner,
+            ref kind => panic!("unexpected {:?}", kind),
+        };
+        assert_eq!(item.span.to_string(), "1:3-1:7");
+        match item.kind {
+            SpannedTypeKind::NonNullType(ref inner) => {
+                assert_eq!(inner.span.to_string(), "1:3-1:6")
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+        assert_eq!(
+            Type::from(typ),
+            crate::parse_type::<&str>("[Int!]!").unwrap()
+        );
+    }
+
+    #[test]
+    fn document_arguments() {
+        let source =
+            "{\n  a(s: \"\u{1f600}\") user(id: 1, filter: {name: \"a\"}) @skip(if: $x) { name }\n}";
+        let options = ParserOptions::new().column_unit(ColumnUnit::Utf16);
+        let (doc, _) = parse_query_with_options::<&str>(source, &options).unwrap();
+        let field = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set)) => {
+                match set.items[1] {
+                    Selection::Field(ref field) => field,
+                    ref item => panic!("unexpected {:?}", item),
+                }
+            }
+            ref def => panic!("unexpected {:?}", def),
+        };
+        let arguments = field_arguments::<&str, _>(source, field, &options).unwrap();
+        assert_eq!(arguments.len(), 2);
+        assert_eq!(arguments[0].name_span.to_string(), "2:19-2:21");
+        assert_eq!(arguments[0].value.span.to_string(), "2:23-2:24");
+        let name = arguments[1].value.get("name").unwrap();
+        assert_eq!(name.span.to_string(), "2:41-2:44");
+        let values: Vec<Value<&str>> = arguments.into_iter().map(|a| a.value.into()).collect();
+        assert_eq!(
+            values,
+            [field.arguments[0].1.clone(), field.arguments[1].1.clone()]
+        );
+
+        let arguments = directive_arguments::<&str, _>(source, &field.directives[0], &options);
+        let arguments = arguments.unwrap();
+        assert_eq!(arguments[0].value.span.to_string(), "2:57-2:59");
+        assert_eq!(arguments[0].value.kind, SpannedValueKind::Variable("x"));
+
+        assert!(field_arguments::<&str, _>("{ }", field, &options).is_err());
+    }
+
+    #[test]
+    fn options() {
+        let options = ParserOptions::new().limits(Limits::new().max_tokens(3));
+        assert!(parse_value_with_options::<&str>("[1]", &options).is_ok());
+        assert!(parse_value_with_options::<&str>("[1, 2]", &options).is_err());
+    }
+}
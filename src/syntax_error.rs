@@ -0,0 +1,358 @@
+//! Structured view of parse errors
+//!
+//! The rendered text of a parse error is meant for people and changes
+//! whenever the wording is improved. Tools which react to particular
+//! errors should match on the [`ErrorCode`](enum.ErrorCode.html) instead.
+use std::fmt;
+
+use crate::error::{Error, Errors, Info};
+use crate::message::{Catalog, DefaultCatalog, ErrorMessages, Message, MessageKey, SpecReference};
+use crate::position::{Pos, Span};
+use crate::snippet::Annotation;
+use crate::tokenizer::Kind;
+
+/// Stable identifier of a kind of syntax error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// A token which doesn't fit the grammar at this point
+    UnexpectedToken,
+    /// The document ends in the middle of a definition
+    UnexpectedEndOfInput,
+    /// A character which can't start any token
+    InvalidCharacter,
+    /// A string or block string without the closing quotes
+    UnterminatedString,
+    /// An invalid escape sequence in a string
+    InvalidEscape,
+    /// A malformed integer or float literal
+    InvalidNumber,
+    /// An integer literal which doesn't fit into the integer types
+    NumberTooLarge,
+    /// Brackets are nested deeper than the recursion limit
+    RecursionLimitExceeded,
+    /// The document exceeds one of the `Limits` other than the depth
+    LimitExceeded,
+    /// A root operation type is defined twice in a schema definition
+    DuplicateOperation,
+    /// A type extension which doesn't extend anything
+    EmptyExtension,
+    /// Any other error
+    Other,
+}
+
+impl ErrorCode {
+    /// Returns the identifier of the code, which never changes between
+    /// releases
+    pub fn as_str(&self) -> &'static str {
+        use self::ErrorCode::*;
+        match *self {
+            UnexpectedToken => "unexpected-token",
+            UnexpectedEndOfInput => "unexpected-end-of-input",
+            InvalidCharacter => "invalid-character",
+            UnterminatedString => "unterminated-string",
+            InvalidEscape => "invalid-escape",
+            InvalidNumber => "invalid-number",
+            NumberTooLarge => "number-too-large",
+            RecursionLimitExceeded => "recursion-limit-exceeded",
+            LimitExceeded => "limit-exceeded",
+            DuplicateOperation => "duplicate-operation",
+            EmptyExtension => "empty-extension",
+            Other => "syntax-error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Something which would have been accepted at the position of an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expected<'e> {
+    /// A token with exactly this text, like a punctuator or a keyword
+    Token(&'e str),
+    /// Any token of the kind
+    Kind(Kind),
+}
+
+/// A single syntax error
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SyntaxError {
+    pub code: ErrorCode,
+    /// Range of the offending token, empty if there is no such token
+    pub span: Span,
+    /// Source text of the offending token
+    pub token: Option<String>,
+    /// Tokens or kinds of tokens which would have been accepted instead
+    ///
+    /// Kinds of tokens are named as in `Kind`, for example `Name`.
+    pub expected: Vec<String>,
+    /// Kinds of the entries of `expected` which name a kind of token
+    #[cfg_attr(feature = "serde", serde(skip))]
+    expected_kinds: Vec<Option<Kind>>,
+}
+
+impl SyntaxError {
+    /// Returns the source text of the offending token
+    pub fn unexpected_token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Returns what would have been accepted instead of the offending token
+    ///
+    /// This is the set of completions which are valid at the position of
+    /// the error.
+    pub fn expected_tokens(&self) -> impl Iterator<Item = Expected<'_>> {
+        self.expected
+            .iter()
+            .zip(&self.expected_kinds)
+            .map(|(text, kind)| match *kind {
+                Some(kind) => Expected::Kind(kind),
+                None => Expected::Token(text),
+            })
+    }
+
+    pub(crate) fn from_errors(errors: &Errors) -> SyntaxError {
+        let mut result = SyntaxError {
+            code: ErrorCode::Other,
+            span: Span::new(errors.position, errors.position),
+            token: None,
+            expected: Vec::new(),
+            expected_kinds: Vec::new(),
+        };
+        // messages describe the problem better than the token the parser
+        // stopped at, which is better than nothing
+        let mut rank = 0;
+        for error in &errors.errors {
+            let (code, code_rank) = match *error {
+                Error::Unexpected(Info::Token(token)) => {
+                    result.span = Span::new(token.start, token.end);
+                    result.token = Some(token.value.to_string());
+                    (ErrorCode::UnexpectedToken, 2)
+                }
+                Error::Unexpected(Info::Raised(ErrorCode::UnexpectedEndOfInput, _)) => {
+                    (ErrorCode::UnexpectedEndOfInput, 1)
+                }
+                // empty extensions are reported as a missing part
+                Error::Unexpected(Info::Raised(code, _))
+                | Error::Message(Info::Raised(code, _))
+                | Error::Expected(Info::Raised(code, _)) => (code, 3),
+                Error::Expected(ref info) => {
+                    let (text, kind) = match *info {
+                        Info::Token(token) => (token.value.to_string(), None),
+                        Info::Kind(kind) => (info.to_string(), Some(kind)),
+                        ref info => (info.to_string(), None),
+                    };
+                    if !result.expected.contains(&text) {
+                        result.expected.push(text);
+                        result.expected_kinds.push(kind);
+                    }
+                    continue;
+                }
+                Error::Unexpected(_) | Error::Message(_) => continue,
+            };
+            if code_rank > rank {
+                rank = code_rank;
+                result.code = code;
+            }
+        }
+        result
+    }
+}
+
+/// Errors found in a document, shared by the `ParseError` types of the
+/// `query`, `schema` and `document` modules
+///
+/// Each of them dereferences to this type, so these are their accessors.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct ParseErrors {
+    errors: Vec<ErrorMessages>,
+    /// Message wrapping the details of all errors
+    #[cfg_attr(feature = "serde", serde(skip))]
+    title: MessageKey,
+}
+
+impl ParseErrors {
+    /// Combines the first error with the ones found after resuming at the
+    /// following definitions
+    pub(crate) fn new(title: MessageKey, first: &Errors, further: &[Errors]) -> ParseErrors {
+        let mut errors = vec![ErrorMessages::from_errors(first)];
+        errors.extend(further.iter().map(ErrorMessages::from_errors));
+        ParseErrors { errors, title }
+    }
+
+    /// Returns the position where parsing failed
+    pub fn position(&self) -> Pos {
+        self.errors[0].position()
+    }
+
+    /// Returns the code of the first error
+    pub fn code(&self) -> ErrorCode {
+        self.errors[0].error().code
+    }
+
+    /// Returns the source text of the token where parsing failed
+    pub fn unexpected_token(&self) -> Option<&str> {
+        self.errors[0].error().unexpected_token()
+    }
+
+    /// Returns what would have been accepted where parsing failed
+    pub fn expected_tokens(&self) -> impl Iterator<Item = Expected<'_>> {
+        self.errors[0].error().expected_tokens()
+    }
+
+    /// Returns the section of the specification defining the rule which the
+    /// first error breaks, if any
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        self.errors[0].spec_reference()
+    }
+
+    /// Returns the structured contents of all errors found in the document
+    pub fn errors(&self) -> impl Iterator<Item = &SyntaxError> {
+        self.errors.iter().map(ErrorMessages::error)
+    }
+
+    /// Returns the positions of all errors found in the document
+    ///
+    /// After an error, parsing resumes at the next definition, so that one
+    /// pass reports independent errors of all definitions. Errors beyond
+    /// `ParserOptions::error_limit` are summarized by a last one.
+    pub fn positions(&self) -> Vec<Pos> {
+        self.errors.iter().map(ErrorMessages::position).collect()
+    }
+
+    /// Returns annotations of the offending tokens for all errors
+    pub fn annotations(&self, catalog: &dyn Catalog) -> Vec<Annotation> {
+        self.errors
+            .iter()
+            .map(|error| error.annotation(catalog))
+            .collect()
+    }
+
+    /// Renders all errors along with the source lines they point at
+    ///
+    /// `source` must be the text which was parsed, `origin` names it in
+    /// the output, usually by its file path.
+    pub fn render_snippets(&self, source: &str, origin: Option<&str>) -> String {
+        self.annotations(&DefaultCatalog)
+            .iter()
+            .map(|annotation| annotation.render(source, origin))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the errors using message templates from the catalog
+    pub fn localize(&self, catalog: &dyn Catalog) -> String {
+        let details: String = self
+            .errors
+            .iter()
+            .map(|error| error.render(catalog))
+            .collect();
+        Message::with_args(self.title, Some(details)).render(catalog)
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.localize(&DefaultCatalog))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ErrorCode::{self, *};
+    use super::Expected;
+    use crate::query::parse_query;
+    use crate::schema::parse_schema;
+    use crate::tokenizer::Kind;
+
+    fn code(source: &str) -> ErrorCode {
+        parse_query::<&str>(source).unwrap_err().code()
+    }
+
+    #[test]
+    fn codes() {
+        assert_eq!(code("{ a(x: ) }"), UnexpectedToken);
+        assert_eq!(code("{ a"), UnexpectedEndOfInput);
+        assert_eq!(code("{ a(x: \u{e9}) }"), InvalidCharacter);
+        assert_eq!(code("{ a(x: \"b) }"), UnterminatedString);
+        assert_eq!(code("{ a(x: \"\\q\") }"), InvalidEscape);
+        assert_eq!(code("{ a(x: 01) }"), InvalidNumber);
+        #[cfg(not(feature = "num-bigint"))]
+        assert_eq!(
+            code("{ a(x: 340282366920938463463374607431768211456) }"),
+            NumberTooLarge
+        );
+        assert_eq!(
+            code(&format!("{{ a(x: {}", "[".repeat(100))),
+            RecursionLimitExceeded
+        );
+        let err = parse_schema::<&str>("schema { query: Q query: R }").unwrap_err();
+        assert_eq!(err.code(), DuplicateOperation);
+        let err = parse_schema::<&str>("extend scalar S").unwrap_err();
+        assert_eq!(err.code(), EmptyExtension);
+    }
+
+    #[test]
+    fn spec_reference() {
+        let err = parse_schema::<&str>("schema { query: Q query: R }").unwrap_err();
+        assert_eq!(
+            err.spec_reference().unwrap().to_string(),
+            "§3.3.1 Root Operation Types"
+        );
+        let err = parse_schema::<&str>("extend union U").unwrap_err();
+        assert_eq!(err.spec_reference().unwrap().section, "3.8.1");
+        assert_eq!(
+            parse_query::<&str>("{ a").unwrap_err().spec_reference(),
+            None
+        );
+    }
+
+    #[test]
+    fn details() {
+        let err = parse_query::<&str>("{ a(x: 1) @ }").unwrap_err();
+        let errors = err.errors().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code.as_str(), "unexpected-token");
+        assert_eq!(errors[0].token.as_deref(), Some("}"));
+        assert_eq!(errors[0].span.to_string(), "1:13-1:14");
+        assert_eq!(errors[0].expected, ["Name"]);
+        assert_eq!(
+            errors[0].expected_tokens().collect::<Vec<_>>(),
+            [Expected::Kind(Kind::Name)]
+        );
+    }
+
+    #[test]
+    fn expected_tokens() {
+        let err = parse_query::<&str>("query Q($a: Int = ) { a }").unwrap_err();
+        assert_eq!(err.unexpected_token(), Some(")"));
+        let expected = err.expected_tokens().collect::<Vec<_>>();
+        assert!(expected.contains(&Expected::Kind(Kind::IntValue)));
+        assert!(expected.contains(&Expected::Token("true")));
+        assert!(!expected.contains(&Expected::Token("$")));
+
+        let err = parse_schema::<&str>("schema { quer: Q }").unwrap_err();
+        assert_eq!(err.unexpected_token(), Some("quer"));
+        assert_eq!(
+            err.expected_tokens().collect::<Vec<_>>(),
+            [
+                Expected::Token("query"),
+                Expected::Token("mutation"),
+                Expected::Token("subscription"),
+            ]
+        );
+    }
+}
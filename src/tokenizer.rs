@@ -1,71 +1,443 @@
 use std::fmt;
 use std::num::IntErrorKind;
+use std::ops::Range;
 
-use combine::easy::{Error, Errors};
 use combine::error::StreamError;
 use combine::stream::Resetable;
 use combine::{Positioned, StreamOnce};
+use memchr::{memchr2, memchr3, memchr_iter, memmem, memrchr};
 
-use crate::position::Pos;
+use crate::common::{Comment, Text};
+use crate::diagnostic::{Diagnostics, DEFAULT_LIMIT};
+use crate::error::{Error, Errors, Info};
+use crate::lexer;
+use crate::limits::{Limits, DEFAULT_DEPTH};
+use crate::message::{Message, MessageKey};
+use crate::position::{ColumnUnit, Pos};
+use crate::syntax_error::ErrorCode;
+use crate::trace::TraceEvent;
+use crate::warning::Warning;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Start, end and byte range of the text of comments, see
+/// `TokenStream::reuse_comment_buffer`
+pub(crate) type CommentBuffer = Vec<(Pos, Pos, Range<usize>)>;
+
+/// Kind of a token
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Kind {
+    /// One of `! $ & ( ) ... : = @ [ ] { | }`
     Punctuator,
+    /// A name, including keywords like `query` or `true`
     Name,
+    /// Any integer not classified as `BigIntValue`
     IntValue,
+    /// An integer outside the range of `i64`
     BigIntValue,
     FloatValue,
+    /// A string in double quotes
     StringValue,
+    /// A string in triple quotes
     BlockString,
+    /// Invalid input, only produced by lossy lexing
+    Error,
+    /// A run of spaces, tabs, line terminators and byte order marks, only
+    /// produced when lexing trivia
+    Whitespace,
+    /// A comma, only produced when lexing trivia
+    Comma,
+    /// A comment up to the end of the line, only produced when lexing
+    /// trivia
+    Comment,
 }
 
+/// A token of the source text
+///
+/// Whitespace, commas and comments are not tokens, they only separate them.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Token<'a> {
     pub kind: Kind,
+    /// Exact source text of the token, strings include their quotes
     pub value: &'a str,
+    /// Position of the first character of the token
+    pub start: Pos,
+    /// Position just past the last character of the token
+    pub end: Pos,
+}
+
+impl<'a> Token<'a> {
+    /// Returns the exact source text of the token
+    pub fn raw(&self) -> &'a str {
+        self.value
+    }
+
+    /// Returns the source text enclosed by the delimiters of a string
+    ///
+    /// Escape sequences are not processed. For other kinds of tokens this
+    /// is the same as `raw`.
+    pub fn raw_contents(&self) -> &'a str {
+        match self.kind {
+            Kind::BlockString => &self.value[3..self.value.len() - 3],
+            Kind::StringValue => &self.value[1..self.value.len() - 1],
+            _ => self.value,
+        }
+    }
 }
 
+/// Stream of tokens which the parser consumes
+///
+/// The stream implements the stream traits of `combine`, so it can be fed
+/// to custom parsers built with that library. To just split a text into
+/// tokens, use [`tokenize`](../lexer/fn.tokenize.html).
 #[derive(Debug, PartialEq)]
 pub struct TokenStream<'a> {
     buf: &'a str,
     position: Pos,
     off: usize,
-    next_state: Option<(usize, Token<'a>, usize, Pos)>,
+    /// End of the last token taken
+    last_end: Pos,
+    column_unit: ColumnUnit,
+    /// Comments seen so far, `None` unless comments are preserved
+    comments: Option<CommentBuffer>,
+    next_state: Option<(usize, Token<'a>, usize, Pos, usize)>,
     recursion_limit: usize,
+    /// Number of further tokens allowed
+    token_limit: usize,
+    /// Number of further selections allowed
+    selection_limit: usize,
+    input_limit: usize,
+    string_limit: usize,
+    /// Whether integers which don't fit into i64 are accepted
+    bigint: bool,
+    /// Whether all integers are parsed as `Value::BigInt`
+    ints_as_bigint: bool,
+    /// Whether repeated arguments are reported as warnings
+    duplicate_arguments: bool,
+    /// Whether whitespace, commas and comments are returned as tokens
+    trivia: bool,
+    repair: Option<Repair>,
+    warnings: Diagnostics<Warning>,
+    /// Maximum number of errors reported for the document
+    error_limit: usize,
+    trace: Option<Vec<TraceEvent<'a>>>,
+}
+
+impl<'a> TokenStream<'a> {
+    /// Starts recording trace events
+    pub(crate) fn start_trace(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+    pub(crate) fn take_trace(&mut self) -> Vec<TraceEvent<'a>> {
+        self.trace.take().unwrap_or_default()
+    }
+
+    /// Records the event if tracing is enabled, `event` is only called then
+    #[inline]
+    pub(crate) fn trace<F>(&mut self, event: F)
+    where
+        F: FnOnce(Pos) -> TraceEvent<'a>,
+    {
+        if let Some(ref mut trace) = self.trace {
+            trace.push(event(self.position));
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token<'a>, Error<'a>> {
+        if let Some(ref mut repair) = self.repair {
+            if self.position.offset >= repair.limit {
+                let value = *repair
+                    .closers
+                    .get(repair.taken)
+                    .ok_or_else(Error::end_of_input)?;
+                repair.taken += 1;
+                return Ok(Token {
+                    kind: Kind::Punctuator,
+                    value,
+                    start: self.last_end,
+                    end: self.last_end,
+                });
+            }
+        }
+        if self.trivia {
+            let old_pos = self.off;
+            let start = self.position;
+            if let Some(kind) = self.skip_trivia() {
+                return Ok(Token {
+                    kind,
+                    value: &self.buf[old_pos..self.off],
+                    start,
+                    end: self.position,
+                });
+            }
+        }
+        if self.buf.len() > self.input_limit {
+            return Err(Error::Message(Info::raised(
+                ErrorCode::LimitExceeded,
+                MessageKey::InputLimitExceeded,
+            )));
+        }
+        // the end of the input isn't a token
+        if !self.is_at_end() {
+            self.token_limit = self.token_limit.checked_sub(1).ok_or_else(|| {
+                Error::Message(Info::raised(
+                    ErrorCode::LimitExceeded,
+                    MessageKey::TokenLimitExceeded,
+                ))
+            })?;
+        }
+        if let Some((at, tok, off, pos, recursion_limit)) = self.next_state {
+            if at == self.off {
+                self.off = off;
+                self.position = pos;
+                self.last_end = tok.end;
+                self.recursion_limit = recursion_limit;
+                return Ok(tok);
+            }
+        }
+        let old_pos = self.off;
+        let start = self.position;
+        let (kind, len) = self.take_token()?;
+        let value = &self.buf[self.off - len..self.off];
+        let end = self.position;
+        self.last_end = end;
+        self.skip_whitespace();
+        let token = Token {
+            kind,
+            value,
+            start,
+            end,
+        };
+        self.next_state = Some((
+            old_pos,
+            token,
+            self.off,
+            self.position,
+            self.recursion_limit,
+        ));
+        Ok(token)
+    }
+}
+
+impl<'a> TokenStream<'a> {
+    /// Takes the next token
+    ///
+    /// Returns `None` at the end of the input. After an error the stream
+    /// stays at the invalid token.
+    pub fn advance(&mut self) -> Option<Result<Token<'a>, lexer::LexError>> {
+        let checkpoint = self.checkpoint();
+        match self.next_token() {
+            Ok(token) => Some(Ok(token)),
+            Err(_) if self.is_at_end() => None,
+            Err(e) => {
+                self.reset(checkpoint);
+                Some(Err(lexer::LexError {
+                    offset: self.off,
+                    message: e.message(),
+                }))
+            }
+        }
+    }
+
+    /// Returns up to `n` next tokens without taking them
+    ///
+    /// Fewer tokens are returned at the end of the input.
+    pub fn peek_n(&mut self, n: usize) -> Result<Vec<Token<'a>>, lexer::LexError> {
+        let checkpoint = self.checkpoint();
+        let tokens = std::iter::from_fn(|| self.advance()).take(n).collect();
+        self.rewind(checkpoint);
+        tokens
+    }
+
+    /// Saves the state of the stream, so that it can be restored with
+    /// `rewind` to parse the same tokens again
+    pub fn checkpoint(&self) -> Checkpoint {
+        Resetable::checkpoint(self)
+    }
+
+    /// Restores the state saved by `checkpoint`
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.reset(checkpoint)
+    }
 }
 
 impl TokenStream<'_> {
-    pub(crate) fn offset(&self) -> usize {
+    /// Returns the byte offset of the next token, or the end of the input
+    pub fn offset(&self) -> usize {
         self.off
     }
+
+    /// Applies the limits to the rest of the input
+    pub(crate) fn set_limits(&mut self, limits: &Limits) {
+        self.recursion_limit = limits.depth;
+        self.token_limit = limits.tokens;
+        self.selection_limit = limits.selections;
+        self.input_limit = limits.input;
+        self.string_limit = limits.string;
+    }
+
+    /// Rejects integers which don't fit into i64 unless `enabled`
+    pub(crate) fn set_bigint_extension(&mut self, enabled: bool) {
+        self.bigint = enabled;
+    }
+
+    pub(crate) fn bigint_extension(&self) -> bool {
+        self.bigint
+    }
+
+    /// Parses all integers as `Value::BigInt` if `enabled`
+    pub(crate) fn set_ints_as_bigint(&mut self, enabled: bool) {
+        self.ints_as_bigint = enabled;
+    }
+
+    pub(crate) fn ints_as_bigint(&self) -> bool {
+        self.ints_as_bigint
+    }
+
+    /// Reports repeated arguments as warnings if `enabled`
+    pub(crate) fn set_duplicate_arguments(&mut self, enabled: bool) {
+        self.duplicate_arguments = enabled;
+    }
+
+    pub(crate) fn duplicate_arguments(&self) -> bool {
+        self.duplicate_arguments
+    }
+
+    /// Counts a selection against the limit
+    pub(crate) fn count_selection(&mut self) -> Result<(), Error<'static>> {
+        self.selection_limit = self.selection_limit.checked_sub(1).ok_or_else(|| {
+            Error::Message(Info::raised(
+                ErrorCode::LimitExceeded,
+                MessageKey::SelectionLimitExceeded,
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Returns the position just past the last token taken
+    pub(crate) fn last_end(&self) -> Pos {
+        self.last_end
+    }
+
+    /// Checks whether all of the input was taken
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.off == self.buf.len()
+    }
+
+    /// Skips the text which failed to tokenize at the current position
+    pub(crate) fn skip_invalid(&mut self) {
+        let len = lexer::error_len(&self.buf[self.off..]);
+        self.update_position(len);
+        self.last_end = self.position;
+        self.skip_whitespace();
+    }
+
+    /// Ends the input after the token which ends at the `limit` offset and
+    /// appends the `closers` punctuators to it
+    ///
+    /// This is used to salvage a broken definition by parsing a prefix of it
+    /// with the brackets left open closed.
+    pub(crate) fn start_repair(&mut self, limit: usize, closers: Vec<&'static str>) {
+        self.repair = Some(Repair {
+            limit,
+            closers,
+            taken: 0,
+        });
+    }
+
+    pub(crate) fn end_repair(&mut self) {
+        self.repair = None;
+    }
+
+    /// Records a non-fatal warning
+    ///
+    /// Warnings are attached to the stream so that they are discarded
+    /// together with the input when the parser backtracks.
+    pub(crate) fn warn(&mut self, position: Pos, message: Message) {
+        self.warnings.push(Warning::new(position, message));
+    }
+
+    /// Limits the number of warnings kept, further warnings are summarized
+    pub(crate) fn set_warning_limit(&mut self, limit: usize) {
+        self.warnings = Diagnostics::new(limit);
+    }
+
+    /// Limits the number of errors reported when parsing resumes after
+    /// errors, further errors are summarized
+    pub(crate) fn set_error_limit(&mut self, limit: usize) {
+        self.error_limit = limit;
+    }
+
+    pub(crate) fn error_limit(&self) -> usize {
+        self.error_limit
+    }
+
+    /// Stores further warnings in `buffer`, reusing its allocation
+    pub(crate) fn reuse_warning_buffer(&mut self, buffer: Vec<Warning>) {
+        self.warnings = Diagnostics::with_buffer(buffer, self.warnings.limit());
+    }
+
+    /// Collects comments into `buffer`, which keeps its allocation, if
+    /// comments are preserved
+    ///
+    /// Comments before the first token are already collected, so they are
+    /// moved to the buffer.
+    pub(crate) fn reuse_comment_buffer(&mut self, mut buffer: CommentBuffer) {
+        if let Some(ref mut comments) = self.comments {
+            buffer.clear();
+            buffer.append(comments);
+            *comments = buffer;
+        }
+    }
+
+    /// Returns the buffer of comments for reuse by the next document
+    pub(crate) fn take_comment_buffer(&mut self) -> CommentBuffer {
+        self.comments.take().unwrap_or_default()
+    }
+
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings).into_vec_with(|first, omitted| {
+            Warning::new(
+                first.position,
+                Message::with_args(MessageKey::MoreDiagnostics, Some(omitted.to_string())),
+            )
+        })
+    }
 }
 
+/// Saved state of a `TokenStream`, see `TokenStream::checkpoint`
 #[derive(Clone, Debug, PartialEq)]
 pub struct Checkpoint {
     position: Pos,
     off: usize,
+    last_end: Pos,
+    recursion_limit: usize,
+    token_limit: usize,
+    selection_limit: usize,
+    repaired: usize,
+    warnings: (usize, usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Repair {
+    limit: usize,
+    closers: Vec<&'static str>,
+    /// Number of closers already returned
+    taken: usize,
 }
 
 impl<'a> StreamOnce for TokenStream<'a> {
     type Item = Token<'a>;
     type Range = Token<'a>;
     type Position = Pos;
-    type Error = Errors<Token<'a>, Token<'a>, Pos>;
+    type Error = Errors<'a>;
 
-    fn uncons(&mut self) -> Result<Self::Item, Error<Token<'a>, Token<'a>>> {
-        if let Some((at, tok, off, pos)) = self.next_state {
-            if at == self.off {
-                self.off = off;
-                self.position = pos;
-                return Ok(tok);
-            }
-        }
-        let old_pos = self.off;
-        let (kind, len) = self.take_token()?;
-        let value = &self.buf[self.off - len..self.off];
-        self.skip_whitespace();
-        let token = Token { kind, value };
-        self.next_state = Some((old_pos, token, self.off, self.position));
+    fn uncons(&mut self) -> Result<Self::Item, Error<'a>> {
+        let token = self.next_token()?;
+        self.trace(|_| TraceEvent::Token {
+            kind: token.kind,
+            value: token.value,
+            position: token.start,
+        });
         Ok(token)
     }
 }
@@ -82,11 +454,31 @@ impl<'a> Resetable for TokenStream<'a> {
         Checkpoint {
             position: self.position,
             off: self.off,
+            last_end: self.last_end,
+            recursion_limit: self.recursion_limit,
+            token_limit: self.token_limit,
+            selection_limit: self.selection_limit,
+            repaired: self.repair.as_ref().map_or(0, |repair| repair.taken),
+            warnings: self.warnings.mark(),
         }
     }
     fn reset(&mut self, checkpoint: Checkpoint) {
+        if checkpoint.off != self.off {
+            self.trace(|from| TraceEvent::Backtrack {
+                from,
+                to: checkpoint.position,
+            });
+        }
         self.position = checkpoint.position;
         self.off = checkpoint.off;
+        self.last_end = checkpoint.last_end;
+        self.recursion_limit = checkpoint.recursion_limit;
+        self.token_limit = checkpoint.token_limit;
+        self.selection_limit = checkpoint.selection_limit;
+        if let Some(ref mut repair) = self.repair {
+            repair.taken = checkpoint.repaired;
+        }
+        self.warnings.rewind(checkpoint.warnings);
     }
 }
 
@@ -104,12 +496,13 @@ fn check_int(value: &str) -> bool {
 // NOTE: This should only be used after we verify that the string
 // passes the check_int check
 fn check_bigint(value: &str) -> bool {
-    if let Err(parse_error) = value.parse::<u64>() {
-        if let IntErrorKind::PosOverflow = parse_error.kind() {
-            return true;
-        }
+    match value.parse::<i64>() {
+        Err(e) => matches!(
+            e.kind(),
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+        ),
+        Ok(_) => false,
     }
-    false
 }
 
 fn check_dec(value: &str) -> bool {
@@ -121,11 +514,11 @@ fn check_exp(value: &str) -> bool {
         return false;
     }
     let first = value.chars().next().unwrap();
-    if first != '-' && first != '+' && (first <= '0' || first >= '9') {
+    if first != '-' && first != '+' && !first.is_ascii_digit() {
         return false;
     }
 
-    value[1..].chars().all(|x| x >= '0' && x <= '9')
+    value[1..].chars().all(|x| x.is_ascii_digit())
 }
 
 fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>) -> bool {
@@ -141,20 +534,83 @@ fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>) -> boo
 }
 
 impl<'a> TokenStream<'a> {
+    /// Starts a stream at the beginning of `s`
     pub fn new(s: &str) -> TokenStream {
-        Self::with_recursion_limit(s, 50)
+        Self::with_recursion_limit(s, DEFAULT_DEPTH)
     }
 
-    /// Specify a limit to recursive parsing. Note that increasing the limit
-    /// from the default may represent a security issue since a maliciously
-    /// crafted input may cause a stack overflow, crashing the process.
+    /// Specify a limit to recursive parsing. The stack is grown as needed,
+    /// but dropping very deep documents still recurses on the stack of the
+    /// calling thread.
     pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream {
+        Self::create(
+            s,
+            recursion_limit,
+            Pos::new(1, 1, 0),
+            ColumnUnit::Chars,
+            false,
+            false,
+        )
+    }
+
+    /// Returns whitespace, commas and comments as tokens instead of
+    /// skipping them
+    pub(crate) fn with_trivia(s: &str) -> TokenStream<'_> {
+        Self::create(
+            s,
+            usize::MAX,
+            Pos::new(1, 1, 0),
+            ColumnUnit::Chars,
+            false,
+            true,
+        )
+    }
+
+    /// Reports positions as if `s` started at `position`, counting
+    /// columns in `unit`
+    pub(crate) fn with_options(
+        s: &str,
+        position: Pos,
+        unit: ColumnUnit,
+        preserve_comments: bool,
+    ) -> TokenStream<'_> {
+        Self::create(s, DEFAULT_DEPTH, position, unit, preserve_comments, false)
+    }
+
+    /// Reports positions as if `s` started at `position` of a larger text
+    pub fn starting_at(s: &str, position: Pos) -> TokenStream<'_> {
+        Self::create(s, DEFAULT_DEPTH, position, ColumnUnit::Chars, false, false)
+    }
+
+    fn create(
+        s: &str,
+        recursion_limit: usize,
+        position: Pos,
+        column_unit: ColumnUnit,
+        preserve_comments: bool,
+        trivia: bool,
+    ) -> TokenStream<'_> {
         let mut me = TokenStream {
             buf: s,
-            position: Pos { line: 1, column: 1 },
+            position,
             off: 0,
+            last_end: position,
+            column_unit,
+            comments: preserve_comments.then(Vec::new),
             next_state: None,
             recursion_limit,
+            token_limit: usize::MAX,
+            selection_limit: usize::MAX,
+            input_limit: usize::MAX,
+            string_limit: usize::MAX,
+            bigint: true,
+            ints_as_bigint: false,
+            duplicate_arguments: false,
+            trivia,
+            repair: None,
+            warnings: Diagnostics::default(),
+            error_limit: DEFAULT_LIMIT,
+            trace: None,
         };
         me.skip_whitespace();
         me
@@ -165,29 +621,37 @@ impl<'a> TokenStream<'a> {
     #[inline]
     fn advance_token<T>(&mut self, kind: Kind, size: usize) -> Result<(Kind, usize), T> {
         self.position.column += size;
+        self.position.offset += size;
         self.off += size;
         Ok((kind, size))
     }
 
-    fn take_token(&mut self) -> Result<(Kind, usize), Error<Token<'a>, Token<'a>>> {
-        use self::Kind::*;
-        let mut iter = self.buf[self.off..].char_indices();
-        let cur_char = match iter.next() {
-            Some((_, x)) => x,
+    fn take_token(&mut self) -> Result<(Kind, usize), Error<'a>> {
+        use self::Kind::{
+            BigIntValue, BlockString, FloatValue, IntValue, Name, Punctuator, StringValue,
+        };
+        // All significant characters outside of string contents are ASCII,
+        // so scanning operates on bytes. Multi-byte sequences only need to be
+        // decoded to report an unexpected character.
+        let bytes = &self.buf.as_bytes()[self.off..];
+        let cur_byte = match bytes.first() {
+            Some(&x) => x,
             None => return Err(Error::end_of_input()),
         };
 
-        match cur_char {
-            '(' | '[' | '{' => {
+        match cur_byte {
+            b'(' | b'[' | b'{' => {
                 // Check for recursion limit
-                self.recursion_limit = self
-                    .recursion_limit
-                    .checked_sub(1)
-                    .ok_or_else(|| Error::message_static_message("Recursion limit exceeded"))?;
+                self.recursion_limit = self.recursion_limit.checked_sub(1).ok_or_else(|| {
+                    Error::Message(Info::raised(
+                        ErrorCode::RecursionLimitExceeded,
+                        MessageKey::RecursionLimitExceeded,
+                    ))
+                })?;
 
                 self.advance_token(Punctuator, 1)
             }
-            ')' | ']' | '}' => {
+            b')' | b']' | b'}' => {
                 // Notes on exceptional cases:
                 // recursion_limit may exceed the original value specified
                 // when constructing the Tokenizer. It may at first
@@ -201,67 +665,57 @@ impl<'a> TokenStream<'a> {
                 self.recursion_limit = self.recursion_limit.saturating_add(1);
                 self.advance_token(Punctuator, 1)
             }
-            '!' | '$' | ':' | '=' | '@' | '|' | '&' => self.advance_token(Punctuator, 1),
-            '.' => {
-                if iter.as_str().starts_with("..") {
+            b'!' | b'$' | b':' | b'=' | b'@' | b'|' | b'&' => self.advance_token(Punctuator, 1),
+            b'.' => {
+                if bytes[1..].starts_with(b"..") {
                     self.advance_token(Punctuator, 3)
                 } else {
-                    Err(Error::unexpected_message(format_args!(
-                        "bare dot {:?} is not supported, \
-                            only \"...\"",
-                        cur_char
+                    Err(Error::Unexpected(Info::text(
+                        ErrorCode::InvalidCharacter,
+                        format!("bare dot {:?} is not supported, only \"...\"", '.'),
                     )))
                 }
             }
-            '_' | 'a'..='z' | 'A'..='Z' => {
-                while let Some((idx, cur_char)) = iter.next() {
-                    match cur_char {
-                        '_' | 'a'..='z' | 'A'..='Z' | '0'..='9' => continue,
-                        _ => return self.advance_token(Name, idx),
-                    }
-                }
-                let len = self.buf.len() - self.off;
-                self.position.column += len;
-                self.off += len;
-
-                Ok((Name, len))
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                let len = bytes[1..]
+                    .iter()
+                    .position(|&b| !(b == b'_' || b.is_ascii_alphanumeric()))
+                    .map_or(bytes.len(), |idx| idx + 1);
+                self.advance_token(Name, len)
             }
-            '-' | '0'..='9' => {
+            b'-' | b'0'..=b'9' => {
                 let mut exponent = None;
                 let mut real = None;
-                let len = loop {
-                    let (idx, cur_char) = match iter.next() {
-                        Some(pair) => pair,
-                        None => break self.buf.len() - self.off,
-                    };
-                    match cur_char {
+                let mut len = bytes.len();
+                for (idx, &cur_byte) in bytes.iter().enumerate().skip(1) {
+                    match cur_byte {
                         // just scan for now, will validate later on
-                        ' ' | '\n' | '\r' | '\t' | ',' | '#' | '!' | '$' | ':' | '=' | '@'
-                        | '|' | '&' | '(' | ')' | '[' | ']' | '{' | '}' => break idx,
-                        '.' => real = Some(idx),
-                        'e' | 'E' => exponent = Some(idx),
+                        b' ' | b'\n' | b'\r' | b'\t' | b',' | b'#' | b'!' | b'$' | b':' | b'='
+                        | b'@' | b'|' | b'&' | b'(' | b')' | b'[' | b']' | b'{' | b'}' => {
+                            len = idx;
+                            break;
+                        }
+                        b'.' => real = Some(idx),
+                        b'e' | b'E' => exponent = Some(idx),
                         _ => {}
                     }
-                };
+                }
 
                 if exponent.is_some() || real.is_some() {
                     let value = &self.buf[self.off..][..len];
                     if !check_float(value, exponent, real) {
-                        return Err(Error::unexpected_message(format_args!(
-                            "unsupported float {:?}",
-                            value
+                        return Err(Error::Unexpected(Info::text(
+                            ErrorCode::InvalidNumber,
+                            format!("unsupported float {:?}", value),
                         )));
                     }
-                    self.position.column += len;
-                    self.off += len;
-
-                    Ok((FloatValue, len))
+                    self.advance_token(FloatValue, len)
                 } else {
                     let value = &self.buf[self.off..][..len];
                     if !check_int(value) {
-                        return Err(Error::unexpected_message(format_args!(
-                            "unsupported integer {:?}",
-                            value
+                        return Err(Error::Unexpected(Info::text(
+                            ErrorCode::InvalidNumber,
+                            format!("unsupported integer {:?}", value),
                         )));
                     } else if check_bigint(value) {
                         self.advance_token(BigIntValue, len)
@@ -270,97 +724,181 @@ impl<'a> TokenStream<'a> {
                     }
                 }
             }
-            '"' => {
-                if iter.as_str().starts_with("\"\"") {
-                    let tail = &iter.as_str()[2..];
-                    for (end_idx, _) in tail.match_indices("\"\"\"") {
-                        if !tail[..end_idx].ends_with('\\') {
-                            self.update_position(end_idx + 6);
-                            return Ok((BlockString, end_idx + 6));
+            b'"' => {
+                if bytes[1..].starts_with(b"\"\"") {
+                    let tail = &bytes[3..];
+                    for end_idx in memmem::find_iter(tail, b"\"\"\"") {
+                        if !tail[..end_idx].ends_with(b"\\") {
+                            return self.take_string(BlockString, end_idx + 6, end_idx);
                         }
                     }
 
-                    Err(Error::unexpected_message("unterminated block string value"))
+                    Err(Error::Unexpected(Info::raised(
+                        ErrorCode::UnterminatedString,
+                        MessageKey::UnterminatedBlockString,
+                    )))
                 } else {
-                    let mut nchars = 1;
-                    let mut escaped = false;
-                    for (idx, cur_char) in iter {
-                        nchars += 1;
-                        match cur_char {
-                            '"' if escaped => {}
-                            '"' => {
-                                self.position.column += nchars;
-                                self.off += idx + 1;
-                                return Ok((StringValue, idx + 1));
-                            }
-                            '\n' => {
-                                return Err(Error::unexpected_message("unterminated string value"));
+                    let mut idx = 1;
+                    while let Some(found) = bytes
+                        .get(idx..)
+                        .and_then(|rest| memchr3(b'"', b'\\', b'\n', rest))
+                    {
+                        idx += found;
+                        match bytes[idx] {
+                            b'"' => return self.take_string(StringValue, idx + 1, idx - 1),
+                            // an escaped line break still ends the line
+                            b'\\' if bytes.get(idx + 1) == Some(&b'\n') => idx += 1,
+                            b'\\' => idx += 2,
+                            _ => {
+                                return Err(Error::Unexpected(Info::raised(
+                                    ErrorCode::UnterminatedString,
+                                    MessageKey::UnterminatedString,
+                                )));
                             }
-
-                            _ => {}
                         }
-
-                        // if we aren't escaped and the current char is a \, we are now escaped
-                        escaped = !escaped && cur_char == '\\';
                     }
-                    Err(Error::unexpected_message("unterminated string value"))
+                    Err(Error::Unexpected(Info::raised(
+                        ErrorCode::UnterminatedString,
+                        MessageKey::UnterminatedString,
+                    )))
                 }
             }
-            _ => Err(Error::unexpected_message(format_args!(
-                "unexpected character {:?}",
-                cur_char
-            ))),
+            _ => {
+                let cur_char = self.buf[self.off..].chars().next().unwrap_or_default();
+                Err(Error::Unexpected(Info::text(
+                    ErrorCode::InvalidCharacter,
+                    format!("unexpected character {:?}", cur_char),
+                )))
+            }
+        }
+    }
+
+    /// Takes a string token of `len` bytes, unless its `content` is longer
+    /// than allowed
+    fn take_string(
+        &mut self,
+        kind: Kind,
+        len: usize,
+        content: usize,
+    ) -> Result<(Kind, usize), Error<'a>> {
+        if content > self.string_limit {
+            return Err(Error::Message(Info::raised(
+                ErrorCode::LimitExceeded,
+                MessageKey::StringLimitExceeded,
+            )));
         }
+        // string contents may contain multi-byte characters, each one is a
+        // single column
+        self.update_position(len);
+        Ok((kind, len))
     }
 
     fn skip_whitespace(&mut self) {
-        let mut iter = self.buf[self.off..].char_indices();
-        let idx = loop {
-            let (idx, cur_char) = match iter.next() {
-                Some(pair) => pair,
-                None => break self.buf.len() - self.off,
-            };
-            match cur_char {
-                '\u{feff}' | '\r' => continue,
-                '\t' => self.position.column += 8,
-                '\n' => {
-                    self.position.column = 1;
-                    self.position.line += 1;
-                }
-                // comma is also entirely ignored in spec
-                ' ' | ',' => {
-                    self.position.column += 1;
-                    continue;
+        if self.trivia {
+            return;
+        }
+        while self.skip_trivia().is_some() {}
+    }
+
+    /// Skips a run of whitespace, a comma or a comment and returns its kind
+    fn skip_trivia(&mut self) -> Option<Kind> {
+        const BOM: &[u8] = "\u{feff}".as_bytes();
+        let bytes = self.buf.as_bytes();
+        let mut off = self.off;
+        let kind = match bytes.get(off) {
+            // comma is also entirely ignored in spec
+            Some(b',') => {
+                self.position.column += 1;
+                off += 1;
+                Kind::Comma
+            }
+            Some(b'#') => {
+                if self.comments.is_some() {
+                    self.record_comment(off);
                 }
-                //comment
-                '#' => {
-                    while let Some((_, cur_char)) = iter.next() {
-                        // TODO(tailhook) ensure SourceCharacter
-                        if cur_char == '\r' || cur_char == '\n' {
+                // TODO(tailhook) ensure SourceCharacter
+                let len = memchr2(b'\r', b'\n', &bytes[off..]).unwrap_or(bytes.len() - off);
+                self.position.column += self.column_unit.width(&self.buf[off..off + len]);
+                off += len;
+                Kind::Comment
+            }
+            _ => {
+                while let Some(&cur_byte) = bytes.get(off) {
+                    match cur_byte {
+                        // CRLF terminates a single line
+                        b'\r' if bytes.get(off + 1) == Some(&b'\n') => {}
+                        b'\r' | b'\n' => {
                             self.position.column = 1;
                             self.position.line += 1;
-                            break;
                         }
+                        b'\t' => self.position.column += self.column_unit.tab_width(),
+                        b' ' => self.position.column += 1,
+                        0xEF if bytes[off..].starts_with(BOM) => off += BOM.len() - 1,
+                        _ => break,
                     }
-                    continue;
+                    off += 1;
+                }
+                if off == self.off {
+                    return None;
                 }
-                _ => break idx,
+                Kind::Whitespace
             }
         };
-        self.off += idx;
+        self.position.offset += off - self.off;
+        self.off = off;
+        Some(kind)
+    }
+
+    /// Stores the comment starting at byte `off`, which is on the same line
+    /// as the current position
+    fn record_comment(&mut self, off: usize) {
+        let line = &self.buf[off..];
+        let len = memchr2(b'\r', b'\n', line.as_bytes()).unwrap_or(line.len());
+        let start = Pos {
+            column: self.position.column,
+            offset: self.position.offset + (off - self.off),
+            ..self.position
+        };
+        let end = Pos {
+            column: start.column + self.column_unit.width(&line[..len]),
+            offset: start.offset + len,
+            ..start
+        };
+        let comments = self.comments.as_mut().unwrap();
+        // comments are skipped again when the parser backtracks
+        if comments.last().is_none_or(|c| c.0.offset < start.offset) {
+            comments.push((start, end, off + 1..off + len));
+        }
+    }
+
+    /// Returns the comments seen so far, keeping the buffer which collected
+    /// them
+    pub(crate) fn take_comments<T: Text<'a>>(&mut self) -> Vec<Comment<'a, T>> {
+        let buf = self.buf;
+        match self.comments {
+            Some(ref mut comments) => comments
+                .drain(..)
+                .map(|(position, end, text)| Comment {
+                    position,
+                    end,
+                    text: buf[text].into(),
+                })
+                .collect(),
+            None => Vec::new(),
+        }
     }
 
     fn update_position(&mut self, len: usize) {
         let val = &self.buf[self.off..][..len];
         self.off += len;
-        let lines = val.as_bytes().iter().filter(|&&x| x == b'\n').count();
+        self.position.offset += len;
+        let lines = memchr_iter(b'\n', val.as_bytes()).count();
         self.position.line += lines;
-        if lines > 0 {
-            let line_offset = val.rfind('\n').unwrap() + 1;
-            let num = val[line_offset..].chars().count();
+        if let Some(last) = memrchr(b'\n', val.as_bytes()) {
+            let num = self.column_unit.width(&val[last + 1..]);
             self.position.column = num + 1;
         } else {
-            let num = val.chars().count();
+            let num = self.column_unit.width(val);
             self.position.column += num;
         }
     }
@@ -376,7 +914,9 @@ impl<'a> fmt::Display for Token<'a> {
 mod test {
     use super::Kind::*;
     use super::{Kind, TokenStream};
-    use combine::easy::Error;
+    use crate::error::Error;
+    use crate::position::Pos;
+    use combine::error::StreamError;
 
     use combine::{Positioned, StreamOnce};
 
@@ -412,6 +952,66 @@ mod test {
         assert_eq!(tok_str(", ,,  ,,,  # x"), &[] as &[&str]);
     }
 
+    #[test]
+    fn positions_after_multibyte_chars() {
+        let mut s = TokenStream::new("\u{feff}a \"\u{e9}\u{1f600}\" # \u{e9}\n\tb");
+        let mut positions = Vec::new();
+        while let Ok(tok) = s.uncons() {
+            positions.push((tok.value, s.position().line, s.position().column));
+        }
+        assert_eq!(
+            positions,
+            [("a", 1, 3), ("\"\u{e9}\u{1f600}\"", 2, 9), ("b", 2, 10)]
+        );
+        let mut s = TokenStream::new("\u{e9}");
+        assert_eq!(
+            s.uncons().unwrap_err().to_string(),
+            "Unexpected `unexpected character '\u{e9}'`"
+        );
+    }
+
+    #[test]
+    fn token_spans() {
+        let mut s = TokenStream::new("{\n  a(b: \"\u{e9}\") \"\"\"x\"\"\"\n}");
+        let mut spans = Vec::new();
+        while let Ok(tok) = s.uncons() {
+            spans.push((
+                tok.raw_contents(),
+                tok.start.to_string(),
+                tok.end.to_string(),
+            ));
+        }
+        let expected = [
+            ("{", "1:1", "1:2"),
+            ("a", "2:3", "2:4"),
+            ("(", "2:4", "2:5"),
+            ("b", "2:5", "2:6"),
+            (":", "2:6", "2:7"),
+            ("\u{e9}", "2:8", "2:11"),
+            (")", "2:11", "2:12"),
+            ("x", "2:13", "2:20"),
+            ("}", "3:1", "3:2"),
+        ];
+        let spans: Vec<_> = spans
+            .iter()
+            .map(|(text, start, end)| (*text, start.as_str(), end.as_str()))
+            .collect();
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn byte_offsets() {
+        let source = "\u{feff}{\n\ta(b: \"\u{e9}\u{1f600}\") # \u{e9}\n  \"\"\"x\n\u{e9}\"\"\" }";
+        let mut s = TokenStream::new(source);
+        let mut count = 0;
+        while let Ok(tok) = s.uncons() {
+            assert_eq!(&source[tok.start.offset..tok.end.offset], tok.value);
+            count += 1;
+        }
+        assert_eq!(count, 9);
+        assert_eq!(s.position().offset, source.len());
+    }
+
     #[test]
     fn simple() {
         assert_eq!(tok_str("a { b }"), ["a", "{", "b", "}"]);
@@ -520,6 +1120,10 @@ mod test {
         assert_eq!(tok_typ("-132e+0"), [FloatValue]);
         assert_eq!(tok_str("132e+0"), ["132e+0"]);
         assert_eq!(tok_typ("132e+0"), [FloatValue]);
+        for value in &["0e0", "1e9", "1e0", "1.5e9", "1e90", "2E09"] {
+            assert_eq!(tok_str(value), [*value]);
+            assert_eq!(tok_typ(value), [FloatValue]);
+        }
         assert_eq!(
             tok_str("a(x: 10.0) { b }"),
             ["a", "(", "x", ":", "10.0", ")", "{", "b", "}"]
@@ -588,11 +1192,6 @@ mod test {
     }
     #[test]
     #[should_panic]
-    fn no_exp_sign_float() {
-        tok_str("0e0");
-    }
-    #[test]
-    #[should_panic]
     fn unterminated_string() {
         tok_str(r#""hello\""#);
     }
@@ -615,6 +1214,20 @@ mod test {
         assert_eq!(tok_typ(r#""my\"quote""#), [StringValue]);
     }
 
+    #[test]
+    fn string_ends() {
+        assert_eq!(tok_str(r#""a\"b" c"#), [r#""a\"b""#, "c"]);
+        assert_eq!(tok_str(r#""a\\" "b""#), [r#""a\\""#, r#""b""#]);
+        for source in ["\"abc\\", "\"abc\\\"", "\"a\\\nb\"", "\"a\nb\""] {
+            let err = TokenStream::new(source).uncons().unwrap_err();
+            assert_eq!(err.to_string(), "Unexpected `unterminated string value`");
+        }
+        assert_eq!(
+            tok_str("\"\"\"a\\\"\"\"\n\"\"\" b"),
+            ["\"\"\"a\\\"\"\"\n\"\"\"", "b"]
+        );
+    }
+
     #[test]
     fn block_string() {
         assert_eq!(tok_str(r#""""""""#), [r#""""""""#]);
@@ -626,4 +1239,44 @@ mod test {
         assert_eq!(tok_str(r#""""\"""quote" """"#), [r#""""\"""quote" """"#]);
         assert_eq!(tok_typ(r#""""\"""quote" """"#), [BlockString]);
     }
+
+    #[test]
+    fn peek_and_rewind() {
+        let mut s = TokenStream::new("{ a(x: 1) } \u{e9}");
+        let peeked = s.peek_n(3).unwrap();
+        let values: Vec<_> = peeked.iter().map(|t| t.value).collect();
+        assert_eq!(values, ["{", "a", "("]);
+        assert_eq!(s.offset(), 0);
+
+        assert_eq!(s.advance().unwrap().unwrap().value, "{");
+        let checkpoint = s.checkpoint();
+        assert_eq!(s.advance().unwrap().unwrap().value, "a");
+        assert_eq!(s.advance().unwrap().unwrap().value, "(");
+        s.rewind(checkpoint);
+        assert_eq!(s.advance().unwrap().unwrap().value, "a");
+
+        assert_eq!(s.peek_n(10).unwrap_err().offset, 12);
+        for _ in 0..6 {
+            s.advance().unwrap().unwrap();
+        }
+        let err = s.advance().unwrap().unwrap_err();
+        assert_eq!(err.offset, 12);
+        assert_eq!(s.offset(), 12);
+    }
+
+    #[test]
+    fn embedded_in_larger_text() {
+        let text = "const q = gql`{ user }`;";
+        let mut s = TokenStream::starting_at(&text[14..], Pos::new(1, 15, 14));
+        let mut values = Vec::new();
+        while let Some(Ok(token)) = s.peek_n(1).map(|t| t.first().copied()).transpose() {
+            if token.value == "`" {
+                break;
+            }
+            values.push(s.advance().unwrap().unwrap());
+        }
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[1].start, Pos::new(1, 17, 16));
+        assert_eq!(s.offset(), 8);
+    }
 }
@@ -0,0 +1,119 @@
+//! Tracing of the parser internals
+//!
+//! Records every grammar rule tried, every token consumed and every
+//! backtrack. This is meant for debugging the grammar itself and for
+//! finding inputs which make the parser backtrack a lot.
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::trace::{self, TraceEvent};
+//!
+//! let (result, events) = trace::parse_query::<&str>("{ a }");
+//! assert!(result.is_ok());
+//! let rules: Vec<_> = events.iter().filter_map(|event| match *event {
+//!     TraceEvent::Enter { rule, .. } => Some(rule),
+//!     _ => None,
+//! }).collect();
+//! assert_eq!(rules[..5], ["definition", "operation_definition", "selection_set",
+//!                        "selection", "field"]);
+//! ```
+use crate::common::Text;
+use crate::lexer::Kind;
+use crate::position::Pos;
+use crate::tokenizer::TokenStream;
+use crate::{query, schema};
+
+/// Event recorded while parsing in the tracing mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceEvent<'a> {
+    /// The parser starts trying a grammar rule
+    Enter { rule: &'static str, position: Pos },
+    /// The parser is done with a grammar rule
+    Exit {
+        rule: &'static str,
+        position: Pos,
+        success: bool,
+    },
+    /// A token was consumed, possibly again after a backtrack
+    Token {
+        kind: Kind,
+        value: &'a str,
+        position: Pos,
+    },
+    /// The parser went back to an earlier position to try an alternative
+    Backtrack { from: Pos, to: Pos },
+}
+
+/// Parses a piece of query language, also returning the parser trace
+///
+/// The trace is returned even if parsing fails.
+pub fn parse_query<'a, S>(
+    s: &'a str,
+) -> (
+    Result<query::Document<'a, S>, query::ParseError>,
+    Vec<TraceEvent<'a>>,
+)
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    tokens.start_trace();
+    let result = query::document(&mut tokens);
+    (result, tokens.take_trace())
+}
+
+/// Parses a piece of schema language, also returning the parser trace
+///
+/// The trace is returned even if parsing fails.
+pub fn parse_schema<'a, T>(
+    s: &'a str,
+) -> (
+    Result<schema::Document<'a, T>, schema::ParseError>,
+    Vec<TraceEvent<'a>>,
+)
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    tokens.start_trace();
+    let result = schema::document(&mut tokens);
+    (result, tokens.take_trace())
+}
+
+#[cfg(test)]
+mod test {
+    use super::TraceEvent;
+    use crate::position::Pos;
+
+    #[test]
+    fn tokens_and_backtracking() {
+        let (result, events) = super::parse_schema::<String>("type A { b: C }");
+        result.unwrap();
+        let tokens: Vec<_> = events
+            .iter()
+            .filter_map(|e| match *e {
+                TraceEvent::Token { value, .. } => Some(value),
+                _ => None,
+            })
+            .collect();
+        // every alternative definition re-reads the keyword
+        assert!(tokens.starts_with(&["type", "type"]), "{:?}", tokens);
+        assert_eq!(tokens.iter().filter(|&&t| t == "A").count(), 1);
+        assert!(events.contains(&TraceEvent::Backtrack {
+            from: Pos::new(1, 6, 5),
+            to: Pos::new(1, 1, 0),
+        }));
+
+        let depth = events.iter().fold(0i32, |depth, e| match *e {
+            TraceEvent::Enter { .. } => depth + 1,
+            TraceEvent::Exit { .. } => depth - 1,
+            _ => depth,
+        });
+        assert_eq!(depth, 0);
+        assert!(events.contains(&TraceEvent::Exit {
+            rule: "object_type",
+            position: Pos::new(1, 16, 15),
+            success: true,
+        }));
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use crate::message::{Catalog, DefaultCatalog, Message, MessageKey, SpecReference};
+use crate::position::{Pos, Span};
+use crate::snippet::{Annotation, Severity};
+
+/// A non-fatal diagnostic produced while parsing or checking a document
+///
+/// Unlike parse errors, warnings never cause the parse to fail. They point
+/// at non-standard syntax that was accepted anyway or at constructs which
+/// are very likely a mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// Position of the construct the warning refers to
+    pub position: Pos,
+    /// Description of the issue
+    pub message: Message,
+}
+
+impl Warning {
+    pub fn new(position: Pos, message: Message) -> Warning {
+        Warning { position, message }
+    }
+
+    /// Returns the section of the specification the warning refers to
+    pub fn spec_reference(&self) -> Option<SpecReference> {
+        self.message.spec_reference()
+    }
+
+    /// Returns the warning as an annotation of its position
+    pub fn annotation(&self) -> Annotation {
+        Annotation {
+            severity: Severity::Warning,
+            code: Some(self.message.key.as_str()),
+            title: self.message.to_string(),
+            span: Span::new(self.position, self.position),
+            label: String::new(),
+        }
+    }
+
+    /// Renders the warning using message templates from the catalog
+    ///
+    /// A hint pointing to the relevant section of the specification is
+    /// appended when known.
+    pub fn localize(&self, catalog: &dyn Catalog) -> String {
+        let mut out = Message::with_args(
+            MessageKey::WarningAt,
+            vec![self.position.to_string(), self.message.render(catalog)],
+        )
+        .render(catalog);
+        if let Some(spec) = self.spec_reference() {
+            out.push_str(
+                &Message::with_args(MessageKey::SpecHint, Some(spec.to_string())).render(catalog),
+            );
+        }
+        out
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.localize(&DefaultCatalog))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Warning;
+    use crate::message::{Message, MessageKey};
+    use crate::position::{Pos, Span};
+    use crate::snippet::{Annotation, Severity};
+
+    #[test]
+    fn display_with_spec_hint() {
+        let warning = Warning::new(
+            Pos::new(2, 3, 12),
+            Message::new(MessageKey::EmptySchemaDefinition),
+        );
+        assert_eq!(
+            warning.to_string(),
+            "warning at 2:3: schema definition declares no root operation types \
+             (see §3.3.1 Root Operation Types)"
+        );
+    }
+
+    #[test]
+    fn annotation() {
+        let position = Pos::new(2, 3, 12);
+        let warning = Warning::new(position, Message::new(MessageKey::EmptySchemaDefinition));
+        assert_eq!(
+            warning.annotation(),
+            Annotation {
+                severity: Severity::Warning,
+                code: Some("empty-schema-definition"),
+                title: "schema definition declares no root operation types".into(),
+                span: Span::new(position, position),
+                label: String::new(),
+            }
+        );
+    }
+}
@@ -28,3 +28,7 @@ fn invalid_curly_brace() {
 fn bad_args() {
     test_error("bad_args");
 }
+#[test]
+fn multiple_errors() {
+    test_error("multiple_errors");
+}
@@ -23,6 +23,15 @@ fn roundtrip(filename: &str, style: &Style) {
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_query::<String>(&buf).unwrap().to_owned();
     assert_eq!(ast.format(style), buf);
+    check_minified(&ast);
+}
+
+fn check_minified(ast: &graphql_parser::query::Document<String>) {
+    let minified = ast.format_minified();
+    assert!(!minified.contains('\n'), "{}", minified);
+    assert!(minified.len() <= ast.to_string().len());
+    let reparsed = parse_query::<String>(&minified).unwrap();
+    assert_eq!(reparsed.to_string(), ast.to_string());
 }
 
 fn roundtrip2(filename: &str) {
@@ -32,6 +41,7 @@ fn roundtrip2(filename: &str) {
     let mut f = File::open(&source).unwrap();
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_query::<String>(&buf).unwrap().to_owned();
+    check_minified(&ast);
 
     let mut buf = String::with_capacity(1024);
     let mut f = File::open(&target).unwrap();
@@ -112,6 +122,14 @@ fn subscription_directive() {
     roundtrip_default("subscription_directive");
 }
 #[test]
+fn subscription_vars() {
+    roundtrip_default("subscription_vars");
+}
+#[test]
+fn subscription_nameless_vars() {
+    roundtrip_default("subscription_nameless_vars");
+}
+#[test]
 fn string_literal() {
     roundtrip_default("string_literal");
 }
@@ -15,6 +15,15 @@ fn roundtrip(filename: &str) {
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_schema::<String>(&buf).unwrap().to_owned();
     assert_eq!(ast.to_string(), buf);
+    check_minified(&ast);
+}
+
+fn check_minified(ast: &graphql_parser::schema::Document<String>) {
+    let minified = ast.format_minified();
+    assert!(!minified.contains('\n'), "{}", minified);
+    assert!(minified.len() <= ast.to_string().len());
+    let reparsed = parse_schema::<String>(&minified).unwrap();
+    assert_eq!(reparsed.to_string(), ast.to_string());
 }
 
 fn roundtrip2(filename: &str) {
@@ -24,6 +33,7 @@ fn roundtrip2(filename: &str) {
     let mut f = File::open(&source).unwrap();
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_schema::<String>(&buf).unwrap();
+    check_minified(&ast);
 
     let mut buf = String::with_capacity(1024);
     let mut f = File::open(&target).unwrap();
@@ -0,0 +1,103 @@
+#![cfg(feature = "serde")]
+extern crate graphql_parser;
+#[cfg(test)]
+#[macro_use]
+extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema, query, schema};
+
+fn sources(dir: &str) -> Vec<(String, String)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let source = fs::read_to_string(&path).unwrap();
+            (path.display().to_string(), source)
+        })
+        .collect()
+}
+
+#[test]
+fn queries() {
+    for (path, source) in sources("tests/queries") {
+        let ast = parse_query::<String>(&source).unwrap();
+        let json = serde_json::to_string(&ast).unwrap();
+        let back: query::Document<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, ast, "{}", path);
+        assert_eq!(back.to_string(), ast.to_string(), "{}", path);
+    }
+}
+
+#[test]
+fn schemas() {
+    for (path, source) in sources("tests/schemas") {
+        let ast = parse_schema::<String>(&source).unwrap();
+        let json = serde_json::to_string(&ast).unwrap();
+        let back: schema::Document<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, ast, "{}", path);
+    }
+}
+
+#[test]
+fn borrowed() {
+    let ast = parse_query::<&str>("query Q($a: [Int!] = [1]) { f(s: \"x\", e: ONE) @d }").unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let back: query::Document<&str> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, ast);
+}
+
+#[test]
+fn format() {
+    let ast = parse_query::<&str>("{ a(x: $v) }").unwrap();
+    let json = serde_json::to_value(&ast).unwrap();
+    let field = &json["definitions"][0]["Operation"]["SelectionSet"]["items"][0]["Field"];
+    assert_eq!(field["name"], "a");
+    assert_eq!(
+        field["position"],
+        serde_json::json!({"line": 1, "column": 3, "offset": 2})
+    );
+    assert_eq!(
+        field["arguments"],
+        serde_json::json!([["x", {"Variable": "v"}]])
+    );
+}
+
+#[test]
+fn errors() {
+    let err = parse_query::<&str>("{ a(x: ) }\nquery { b(").unwrap_err();
+    let json = serde_json::to_value(&err).unwrap();
+    assert_eq!(
+        json[0],
+        serde_json::json!({
+            "message": "Unexpected `)`",
+            "locations": [{"line": 1, "column": 8}],
+            "extensions": {
+                "code": "unexpected-token",
+                "span": {
+                    "start": {"line": 1, "column": 8, "offset": 7},
+                    "end": {"line": 1, "column": 9, "offset": 8},
+                },
+                "token": ")",
+                "expected": [
+                    "IntValue", "FloatValue", "BigIntValue", "StringValue",
+                    "BlockString", "true", "false", "null", "Name",
+                ],
+            },
+        })
+    );
+    assert_eq!(json[1]["extensions"]["code"], "unexpected-end-of-input");
+    assert_eq!(
+        json[1]["locations"],
+        serde_json::json!([{"line": 2, "column": 11}])
+    );
+
+    let err = parse_schema::<&str>("extend scalar S").unwrap_err();
+    let json = serde_json::to_value(&err).unwrap();
+    assert_eq!(json[0]["extensions"]["code"], "empty-extension");
+}